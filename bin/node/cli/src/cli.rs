@@ -26,6 +26,14 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub run: sc_cli::RunCmd,
+
+	/// Fetch indexed data required for upcoming storage proofs from an HTTP directory.
+	///
+	/// `{hash}` in the URL template is replaced with the hex encoded blake2b-256 hash of the
+	/// data and `{cid}` with its raw CID. Directories are tried in the given order, after
+	/// bitswap if `--ipfs-client` is enabled.
+	#[clap(long, value_name = "URL_TEMPLATE", multiple_values(true))]
+	pub storage_proof_directory: Vec<String>,
 }
 
 /// Possible subcommands of the main binary.
@@ -79,12 +87,24 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export a snapshot of the state of a finalized block.
+	ExportSnapshot(sc_cli::ExportSnapshotCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Initialize an empty database from a state snapshot.
+	ImportSnapshot(sc_cli::ImportSnapshotCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Pin indexed transaction data, so that it is kept when blocks are pruned.
+	PinTransaction(sc_cli::PinTransactionCmd),
+
+	/// Manage the peers banned by the node.
+	Peers(sc_cli::PeersCmd),
 }
@@ -140,7 +140,10 @@ pub fn new_partial(
 				grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
 				sc_consensus_babe::BabeLink<Block>,
 			),
-			grandpa::SharedVoterState,
+			(
+				grandpa::SharedVoterState,
+				sc_transaction_storage_proof::ProofReadiness<Block, FullClient>,
+			),
 			Option<Telemetry>,
 		),
 	>,
@@ -230,13 +233,20 @@ pub fn new_partial(
 
 	let import_setup = (block_import, grandpa_link, babe_link);
 
+	let proof_readiness = sc_transaction_storage_proof::ProofReadiness::new(
+		client.clone(),
+		sc_transaction_storage_proof::DEFAULT_LOOKAHEAD,
+		sc_transaction_storage_proof::DEFAULT_MAX_FETCHED_BYTES,
+		config.prometheus_registry(),
+	)?;
+
 	let (rpc_extensions_builder, rpc_setup) = {
 		let (_, grandpa_link, babe_link) = &import_setup;
 
 		let justification_stream = grandpa_link.justification_stream();
 		let shared_authority_set = grandpa_link.shared_authority_set().clone();
 		let shared_voter_state = grandpa::SharedVoterState::empty();
-		let rpc_setup = shared_voter_state.clone();
+		let rpc_setup = (shared_voter_state.clone(), proof_readiness.clone());
 
 		let finality_proof_provider = grandpa::FinalityProofProvider::new_for_service(
 			backend.clone(),
@@ -271,6 +281,7 @@ pub fn new_partial(
 					subscription_executor,
 					finality_provider: finality_proof_provider.clone(),
 				},
+				proof_readiness: proof_readiness.clone(),
 			};
 
 			node_rpc::create_full(deps).map_err(Into::into)
@@ -306,8 +317,12 @@ pub struct NewFullBase {
 }
 
 /// Creates a full service from the configuration.
+///
+/// Indexed data required for storage proofs is fetched from the URL templates in
+/// `storage_proof_directories` if it can't be fetched over bitswap.
 pub fn new_full_base(
 	mut config: Configuration,
+	storage_proof_directories: Vec<String>,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
@@ -324,8 +339,9 @@ pub fn new_full_base(
 		other: (rpc_extensions_builder, import_setup, rpc_setup, mut telemetry),
 	} = new_partial(&config)?;
 
-	let shared_voter_state = rpc_setup;
+	let (shared_voter_state, proof_readiness) = rpc_setup;
 	let auth_disc_publish_non_global_ips = config.network.allow_non_globals_in_dht;
+	let ipfs_client = config.network.ipfs_client;
 	let grandpa_protocol_name = grandpa::protocol_standard_name(
 		&client.block_hash(0).ok().flatten().expect("Genesis block exists; qed"),
 		&config.chain_spec,
@@ -398,6 +414,32 @@ pub fn new_full_base(
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
+		// Fetch data required for upcoming storage proofs that is missing locally.
+		let mut proof_sources: Vec<
+			Box<dyn sc_transaction_storage_proof::IndexedDataSource<Block>>,
+		> = Vec::new();
+		if ipfs_client {
+			proof_sources.push(Box::new(sc_transaction_storage_proof::BitswapSource::new(
+				network.clone(),
+				std::time::Duration::from_secs(30),
+			)));
+		}
+		for url in storage_proof_directories {
+			proof_sources.push(Box::new(sc_transaction_storage_proof::DirectorySource::new(
+				url,
+				std::time::Duration::from_secs(30),
+			)));
+		}
+		task_manager.spawn_handle().spawn(
+			"storage-proof-readiness",
+			None,
+			sc_transaction_storage_proof::run_readiness_worker(
+				proof_readiness.clone(),
+				proof_sources,
+			),
+		);
+
+		let proof_readiness = proof_readiness.clone();
 		let client_clone = client.clone();
 		let slot_duration = babe_link.config().slot_duration();
 		let babe_config = sc_consensus_babe::BabeParams {
@@ -410,6 +452,7 @@ pub fn new_full_base(
 			justification_sync_link: network.clone(),
 			create_inherent_data_providers: move |parent, ()| {
 				let client_clone = client_clone.clone();
+				let proof_readiness = proof_readiness.clone();
 				async move {
 					let uncles = sc_consensus_uncles::create_uncles_inherent_data_provider(
 						&*client_clone,
@@ -426,7 +469,7 @@ pub fn new_full_base(
 
 					let storage_proof =
 						sp_transaction_storage_proof::registration::new_data_provider(
-							&*client_clone,
+							&proof_readiness,
 							&parent,
 						)?;
 
@@ -529,8 +572,12 @@ pub fn new_full_base(
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, |_, _| ()).map(|NewFullBase { task_manager, .. }| task_manager)
+pub fn new_full(
+	config: Configuration,
+	storage_proof_directories: Vec<String>,
+) -> Result<TaskManager, ServiceError> {
+	new_full_base(config, storage_proof_directories, |_, _| ())
+		.map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
 #[cfg(test)]
@@ -597,6 +644,7 @@ mod tests {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
 					new_full_base(
 						config,
+						Vec::new(),
 						|block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						 babe_link: &sc_consensus_babe::BabeLink<Block>| {
 							setup_handles = Some((block_import.clone(), babe_link.clone()));
@@ -774,7 +822,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-					new_full_base(config, |_, _| ())?;
+					new_full_base(config, Vec::new(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(
 					task_manager,
 					client,
@@ -67,6 +67,7 @@ use sp_runtime::{
 	ApplyExtrinsicResult, FixedPointNumber, Perbill, Percent, Permill, Perquintill,
 };
 use sp_std::prelude::*;
+use sp_transaction_storage_proof::multihash;
 #[cfg(any(feature = "std", test))]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
@@ -119,7 +120,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 268,
+	spec_version: 269,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
@@ -1349,12 +1350,20 @@ impl pallet_uniques::Config for Runtime {
 	type WeightInfo = pallet_uniques::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+	// In addition to the default blake2b-256.
+	pub TransactionStorageHashCodes: Vec<u64> = vec![multihash::SHA2_256, multihash::BLAKE3_256];
+}
+
 impl pallet_transaction_storage::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type Call = Call;
 	type FeeDestination = ();
 	type WeightInfo = pallet_transaction_storage::weights::SubstrateWeight<Runtime>;
+	type ContentHashCodes = TransactionStorageHashCodes;
+	type Pricing = pallet_transaction_storage::FlatFee;
+	type RemoveOrigin = EnsureRoot<AccountId>;
 }
 
 impl pallet_whitelist::Config for Runtime {
@@ -1679,6 +1688,47 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl frame_system_rpc_runtime_api::DryRunApi<Block, Balance> for Runtime {
+		fn dry_run(
+			extrinsic: <Block as BlockT>::Extrinsic,
+		) -> frame_system_rpc_runtime_api::DryRunEffects<Balance> {
+			use frame_support::weights::{GetDispatchInfo, PostDispatchInfo};
+
+			let len = extrinsic.encoded_size() as u32;
+			let info = extrinsic.get_dispatch_info();
+			let events_before = System::event_count() as usize;
+			let result = Executive::apply_extrinsic(extrinsic);
+
+			let mut events = System::read_events_no_consensus();
+			let events = events.split_off(events_before.min(events.len()));
+			let actual_info = events.iter().rev().find_map(|record| match record.event {
+				Event::System(frame_system::Event::ExtrinsicSuccess { dispatch_info }) |
+				Event::System(frame_system::Event::ExtrinsicFailed { dispatch_info, .. }) =>
+					Some(dispatch_info),
+				_ => None,
+			});
+			let (weight, fee) = match actual_info {
+				Some(actual_info) => {
+					let post_info = PostDispatchInfo {
+						actual_weight: Some(actual_info.weight),
+						pays_fee: actual_info.pays_fee,
+					};
+					let fee = TransactionPayment::compute_actual_fee(len, &info, &post_info, 0);
+					(actual_info.weight, fee)
+				},
+				// The extrinsic was not applied.
+				None => (0, 0),
+			};
+
+			frame_system_rpc_runtime_api::DryRunEffects {
+				result,
+				events: events.encode(),
+				weight,
+				fee,
+			}
+		}
+	}
+
 	impl pallet_contracts_rpc_runtime_api::ContractsApi<
 		Block, AccountId, Balance, BlockNumber, Hash,
 	>
@@ -1768,6 +1818,16 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_transaction_storage_proof::TransactionStorageApi<Block> for Runtime {
+		fn storage_period() -> NumberFor<Block> {
+			TransactionStorage::storage_period()
+		}
+
+		fn indexed_transactions(block: NumberFor<Block>) -> Vec<Hash> {
+			TransactionStorage::indexed_transactions(block)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
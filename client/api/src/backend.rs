@@ -32,7 +32,7 @@ use sp_runtime::{
 	Justification, Justifications, StateVersion, Storage,
 };
 use sp_state_machine::{
-	ChildStorageCollection, IndexOperation, OffchainChangesCollection, StorageCollection,
+	ChildStorageCollection, IndexOperation, OffchainChangesCollection, StorageCollection, TrieDiff,
 };
 use sp_storage::{ChildInfo, StorageData, StorageKey};
 use std::collections::{HashMap, HashSet};
@@ -430,6 +430,18 @@ pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 		child_info: &ChildInfo,
 		key: &StorageKey,
 	) -> sp_blockchain::Result<Option<Block::Hash>>;
+
+	/// Given two `BlockId`s, a key prefix, and a start key, return up to `limit` of the matching
+	/// storage keys whose values differ between the two blocks. Child tries are only compared by
+	/// their root.
+	fn storage_diff(
+		&self,
+		from: &BlockId<Block>,
+		to: &BlockId<Block>,
+		key_prefix: &StorageKey,
+		start_key: Option<&StorageKey>,
+		limit: usize,
+	) -> sp_blockchain::Result<TrieDiff>;
 }
 
 /// Client backend.
@@ -520,6 +532,27 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Discard non-best, unfinalized leaf block.
 	fn remove_leaf_block(&self, hash: &Block::Hash) -> sp_blockchain::Result<()>;
 
+	/// Pin indexed transaction data with the given content hash, so that it is kept when the
+	/// blocks that stored or renewed it are pruned. The data must be stored locally.
+	///
+	/// Returns `false` if the data is pinned already.
+	fn pin_indexed_transaction(&self, _hash: &Block::Hash) -> sp_blockchain::Result<bool> {
+		Err(sp_blockchain::Error::Backend("Indexed transactions are not supported".into()))
+	}
+
+	/// Unpin indexed transaction data pinned with [`Self::pin_indexed_transaction`]. The data is
+	/// removed once no block references it any longer.
+	///
+	/// Returns `false` if the data is not pinned.
+	fn unpin_indexed_transaction(&self, _hash: &Block::Hash) -> sp_blockchain::Result<bool> {
+		Ok(false)
+	}
+
+	/// Content hashes of pinned indexed transaction data.
+	fn pinned_indexed_transactions(&self) -> sp_blockchain::Result<Vec<Block::Hash>> {
+		Ok(Vec::new())
+	}
+
 	/// Insert auxiliary data into key-value store.
 	fn insert_aux<
 		'a,
@@ -23,8 +23,10 @@ use sc_executor::{RuntimeVersion, RuntimeVersionOf};
 use sp_core::NativeOrEncoded;
 use sp_externalities::Extensions;
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
-use sp_state_machine::{ExecutionManager, ExecutionStrategy, OverlayedChanges, StorageProof};
-use std::{cell::RefCell, panic::UnwindSafe, result};
+use sp_state_machine::{
+	ExecutionManager, ExecutionStrategy, OverlayedChanges, StorageKey, StorageProof,
+};
+use std::{cell::RefCell, collections::BTreeSet, panic::UnwindSafe, result};
 
 use crate::execution_extensions::ExecutionExtensions;
 use sp_api::{ProofRecorder, StorageTransactionCache};
@@ -41,6 +43,15 @@ pub trait ExecutorProvider<Block: BlockT> {
 	fn execution_extensions(&self) -> &ExecutionExtensions<Block>;
 }
 
+/// Keys of the main trie accessed by a call, see [`CallExecutor::call_with_storage_access`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageAccess {
+	/// Keys read from the state the call was executed on.
+	pub read: BTreeSet<StorageKey>,
+	/// Keys written or removed by the call.
+	pub written: BTreeSet<StorageKey>,
+}
+
 /// Method call executor.
 pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 	/// Externalities error type.
@@ -61,6 +72,19 @@ pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 		extensions: Option<Extensions>,
 	) -> Result<Vec<u8>, sp_blockchain::Error>;
 
+	/// Execute a call on top of state in a block of given hash, recording the storage keys it
+	/// accesses.
+	///
+	/// No changes are made.
+	fn call_with_storage_access(
+		&self,
+		id: &BlockId<B>,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+		extensions: Option<Extensions>,
+	) -> Result<(Vec<u8>, StorageAccess), sp_blockchain::Error>;
+
 	/// Execute a contextual call on top of state in a block of a given hash.
 	///
 	/// No changes are made.
@@ -32,6 +32,7 @@ use crate::{blockchain::Info, notifications::StorageEventStream, FinalizeSummary
 use sc_transaction_pool_api::ChainEvent;
 use sc_utils::mpsc::TracingUnboundedReceiver;
 use sp_blockchain;
+use sp_transaction_storage_proof::multihash::BLAKE2B_256;
 
 /// Type that implements `futures::Stream` of block import events.
 pub type ImportNotifications<Block> = TracingUnboundedReceiver<BlockImportNotification<Block>>;
@@ -123,6 +124,15 @@ pub trait BlockBackend<Block: BlockT> {
 		id: &BlockId<Block>,
 	) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>>;
 
+	/// Get content hashes of all indexed transactions for a block,
+	/// including renewed transactions.
+	///
+	/// Unlike `block_indexed_body`, this doesn't require the indexed data to be present.
+	fn block_indexed_hashes(
+		&self,
+		id: &BlockId<Block>,
+	) -> sp_blockchain::Result<Option<Vec<Block::Hash>>>;
+
 	/// Get full block by id.
 	fn block(&self, id: &BlockId<Block>) -> sp_blockchain::Result<Option<SignedBlock<Block>>>;
 
@@ -146,6 +156,56 @@ pub trait BlockBackend<Block: BlockT> {
 	fn has_indexed_transaction(&self, hash: &Block::Hash) -> sp_blockchain::Result<bool> {
 		Ok(self.indexed_transaction(hash)?.is_some())
 	}
+
+	/// Get the size of an indexed transaction in bytes, without loading its data where possible.
+	fn indexed_transaction_size(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<usize>> {
+		Ok(self.indexed_transaction(hash)?.map(|data| data.len()))
+	}
+
+	/// Get the content hash of an indexed transaction by one of the additional content hashes it
+	/// was indexed with. `code` is the multihash code of `digest`.
+	///
+	/// Note that this will only resolve hashes requested by the runtime with
+	/// `storage_index_transaction_with_hashes`.
+	fn indexed_transaction_hash(
+		&self,
+		code: u64,
+		digest: &[u8],
+	) -> sp_blockchain::Result<Option<Block::Hash>>;
+
+	/// Get a block of the UnixFS DAG of an indexed transaction by its blake2b-256 hash.
+	///
+	/// Only transactions larger than a single DAG leaf have such blocks.
+	fn indexed_dag_block(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>>;
+
+	/// Get an indexed transaction or a block of its UnixFS DAG by the content hash a CID refers
+	/// to. `code` is the multihash code of `digest`.
+	///
+	/// Blake2b-256 digests are looked up as indexed transactions, then as DAG blocks. Digests of
+	/// other codes are looked up among the additional content hashes of indexed transactions.
+	fn indexed_content(&self, code: u64, digest: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		if code == BLAKE2B_256 {
+			let mut hash = Block::Hash::default();
+			if hash.as_ref().len() != digest.len() {
+				return Ok(None)
+			}
+			hash.as_mut().copy_from_slice(digest);
+			return match self.indexed_transaction(&hash)? {
+				Some(transaction) => Ok(Some(transaction)),
+				None => self.indexed_dag_block(&hash),
+			}
+		}
+		match self.indexed_transaction_hash(code, digest)? {
+			Some(hash) => self.indexed_transaction(&hash),
+			None => Ok(None),
+		}
+	}
+
+	/// Get the number of the latest block that stored or renewed an indexed transaction.
+	fn indexed_transaction_block(
+		&self,
+		hash: &Block::Hash,
+	) -> sp_blockchain::Result<Option<NumberFor<Block>>>;
 }
 
 /// Provide a list of potential uncle headers for a given block.
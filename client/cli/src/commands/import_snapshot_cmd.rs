@@ -0,0 +1,93 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{ImportParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::{HeaderBackend, ProofProvider};
+use sc_service::chain_ops::import_snapshot;
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, Read},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// The `import-snapshot` command used to initialize an empty database from a snapshot exported
+/// with `export-snapshot`.
+#[derive(Debug, Parser)]
+pub struct ImportSnapshotCmd {
+	/// Input file or stdin if unspecified.
+	#[clap(parse(from_os_str))]
+	pub input: Option<PathBuf>,
+
+	/// Hash of the finalized block the snapshot is expected to be taken at.
+	///
+	/// The hash must come from a trusted source, as it is the only guarantee that the snapshot
+	/// belongs to the finalized chain. The state is verified against the header of the snapshot.
+	#[clap(long, value_name = "HASH")]
+	pub block_hash: String,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ImportSnapshotCmd {
+	/// Run the `import-snapshot` command
+	pub async fn run<B, C, IQ>(&self, client: Arc<C>, import_queue: IQ) -> error::Result<()>
+	where
+		B: BlockT,
+		C: HeaderBackend<B> + ProofProvider<B>,
+		IQ: sc_service::ImportQueue<B>,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+	{
+		let hash = &self.block_hash;
+		let expected_hash = B::Hash::from_str(hash.strip_prefix("0x").unwrap_or(hash))
+			.map_err(|e| format!("Failed to parse block hash: {:?}", e))?;
+		let input: Box<dyn Read> = match &self.input {
+			Some(filename) => Box::new(io::BufReader::new(fs::File::open(filename)?)),
+			None => Box::new(io::stdin()),
+		};
+
+		import_snapshot(client, import_queue, input, expected_hash)
+			.await
+			.map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ImportSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
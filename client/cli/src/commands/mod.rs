@@ -18,14 +18,18 @@
 mod build_spec_cmd;
 mod check_block_cmd;
 mod export_blocks_cmd;
+mod export_snapshot_cmd;
 mod export_state_cmd;
 mod generate;
 mod generate_node_key;
 mod import_blocks_cmd;
+mod import_snapshot_cmd;
 mod insert_key;
 mod inspect_key;
 mod inspect_node_key;
 mod key;
+mod peers_cmd;
+mod pin_transaction_cmd;
 mod purge_chain_cmd;
 mod revert_cmd;
 mod run_cmd;
@@ -35,10 +39,25 @@ mod vanity;
 mod verify;
 
 pub use self::{
-	build_spec_cmd::BuildSpecCmd, check_block_cmd::CheckBlockCmd,
-	export_blocks_cmd::ExportBlocksCmd, export_state_cmd::ExportStateCmd, generate::GenerateCmd,
-	generate_node_key::GenerateNodeKeyCmd, import_blocks_cmd::ImportBlocksCmd,
-	insert_key::InsertKeyCmd, inspect_key::InspectKeyCmd, inspect_node_key::InspectNodeKeyCmd,
-	key::KeySubcommand, purge_chain_cmd::PurgeChainCmd, revert_cmd::RevertCmd, run_cmd::RunCmd,
-	sign::SignCmd, vanity::VanityCmd, verify::VerifyCmd,
+	build_spec_cmd::BuildSpecCmd,
+	check_block_cmd::CheckBlockCmd,
+	export_blocks_cmd::ExportBlocksCmd,
+	export_snapshot_cmd::ExportSnapshotCmd,
+	export_state_cmd::ExportStateCmd,
+	generate::GenerateCmd,
+	generate_node_key::GenerateNodeKeyCmd,
+	import_blocks_cmd::ImportBlocksCmd,
+	import_snapshot_cmd::ImportSnapshotCmd,
+	insert_key::InsertKeyCmd,
+	inspect_key::InspectKeyCmd,
+	inspect_node_key::InspectNodeKeyCmd,
+	key::KeySubcommand,
+	peers_cmd::{PeersAction, PeersCmd},
+	pin_transaction_cmd::PinTransactionCmd,
+	purge_chain_cmd::PurgeChainCmd,
+	revert_cmd::RevertCmd,
+	run_cmd::RunCmd,
+	sign::SignCmd,
+	vanity::VanityCmd,
+	verify::VerifyCmd,
 };
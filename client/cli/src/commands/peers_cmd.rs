@@ -0,0 +1,149 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2018-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{error, params::SharedParams, CliConfiguration};
+use clap::Parser;
+use sc_network::PeerId;
+use sc_peerset::persistence::{PersistedState, STATE_FILE_NAME};
+use std::{
+	path::PathBuf,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The `peers` command used to manage the peers banned by the node.
+///
+/// It operates on the state persisted by the node, and must not be used while the node is running
+/// as the node overwrites that state.
+#[derive(Debug, Clone, Parser)]
+pub struct PeersCmd {
+	#[allow(missing_docs)]
+	#[clap(subcommand)]
+	pub action: PeersAction,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// Actions of the `peers` command.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum PeersAction {
+	/// List the banned peers.
+	Banned,
+
+	/// Ban a peer.
+	Ban {
+		/// Base58 encoded peer ID.
+		peer_id: String,
+
+		/// Duration of the ban in seconds. The ban is permanent if not given.
+		#[clap(long, value_name = "SECONDS")]
+		duration: Option<u64>,
+	},
+
+	/// Lift the ban of a peer and reset its reputation.
+	Unban {
+		/// Base58 encoded peer ID.
+		peer_id: String,
+	},
+}
+
+impl PeersCmd {
+	/// Run the peers command
+	pub fn run(&self, net_config_path: Option<PathBuf>) -> error::Result<()> {
+		let path = net_config_path
+			.ok_or_else(|| error::Error::Input("No network configuration directory".into()))?
+			.join(STATE_FILE_NAME);
+		let mut state = PersistedState::load(&path)?;
+		let now = SystemTime::now();
+		state.remove_expired_bans(now);
+
+		match &self.action {
+			PeersAction::Banned => {
+				for (peer_id, until) in &state.bans {
+					match until {
+						Some(until) => println!(
+							"{} until {}",
+							peer_id,
+							until.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+						),
+						None => println!("{}", peer_id),
+					}
+				}
+				return Ok(())
+			},
+			PeersAction::Ban { peer_id, duration } => {
+				let peer_id = parse_peer_id(peer_id)?;
+				state.bans.insert(peer_id, duration.map(|secs| now + Duration::from_secs(secs)));
+			},
+			PeersAction::Unban { peer_id } => {
+				let peer_id = parse_peer_id(peer_id)?;
+				state.bans.remove(&peer_id);
+				state.reputations.remove(&peer_id);
+			},
+		}
+
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		state.save(&path)?;
+		Ok(())
+	}
+}
+
+fn parse_peer_id(peer_id: &str) -> error::Result<PeerId> {
+	peer_id
+		.parse()
+		.map_err(|e| error::Error::Input(format!("Invalid peer id {}: {}", peer_id, e)))
+}
+
+impl CliConfiguration for PeersCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(dir: &tempfile::TempDir, args: &[&str]) {
+		let cmd = PeersCmd::parse_from(std::iter::once("peers").chain(args.iter().copied()));
+		cmd.run(Some(dir.path().to_path_buf())).unwrap();
+	}
+
+	#[test]
+	fn ban_and_unban_update_the_persisted_state() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join(STATE_FILE_NAME);
+		let (permanent, temporary) = (PeerId::random(), PeerId::random());
+
+		run(&dir, &["ban", &permanent.to_base58()]);
+		run(&dir, &["ban", &temporary.to_base58(), "--duration", "3600"]);
+
+		let state = PersistedState::load(&path).unwrap();
+		assert_eq!(state.bans.len(), 2);
+		assert_eq!(state.bans[&permanent], None);
+		assert!(state.bans[&temporary].unwrap() > SystemTime::now());
+
+		run(&dir, &["unban", &permanent.to_base58()]);
+
+		let state = PersistedState::load(&path).unwrap();
+		assert_eq!(state.bans.keys().collect::<Vec<_>>(), vec![&temporary]);
+	}
+}
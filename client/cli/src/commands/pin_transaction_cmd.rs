@@ -0,0 +1,101 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2018-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use log::info;
+use sc_client_api::Backend;
+use sp_runtime::traits::Block as BlockT;
+use std::{fmt::Debug, str::FromStr, sync::Arc};
+
+/// The `pin-transaction` command used to keep indexed transaction data when the blocks that
+/// stored it are pruned.
+#[derive(Debug, Parser)]
+pub struct PinTransactionCmd {
+	/// Blake2b-256 content hashes of the data. Lists the pinned data if none are given.
+	#[clap(value_name = "HASH")]
+	pub hashes: Vec<String>,
+
+	/// Unpin the data instead, allowing it to be pruned.
+	#[clap(long)]
+	pub unpin: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl PinTransactionCmd {
+	/// Run the pin-transaction command
+	pub async fn run<B, BA>(&self, backend: Arc<BA>) -> error::Result<()>
+	where
+		B: BlockT,
+		BA: Backend<B>,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+	{
+		if self.hashes.is_empty() {
+			for hash in backend.pinned_indexed_transactions()? {
+				println!("{:?}", hash);
+			}
+			return Ok(())
+		}
+		for hash in &self.hashes {
+			let hash = B::Hash::from_str(hash)
+				.map_err(|e| error::Error::Input(format!("Invalid hash {}: {:?}", hash, e)))?;
+			if self.unpin {
+				if backend.unpin_indexed_transaction(&hash)? {
+					info!("Unpinned {:?}", hash);
+				} else {
+					info!("{:?} is not pinned", hash);
+				}
+			} else if backend.pin_indexed_transaction(&hash)? {
+				info!("Pinned {:?}", hash);
+			} else {
+				info!("{:?} is pinned already", hash);
+			}
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for PinTransactionCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -28,11 +28,17 @@ use crate::{
 use clap::Parser;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, PrometheusConfig, TransactionPoolOptions},
+	config::{
+		BasePath, PrometheusConfig, RpcMethodFilter, RpcMethodFilters, RpcRateLimits,
+		TransactionPoolOptions,
+	},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+	net::{IpAddr, Ipv4Addr, SocketAddr},
+	path::PathBuf,
+};
 
 /// The `run` command used to run a node.
 #[derive(Debug, Clone, Parser)]
@@ -131,6 +137,58 @@ pub struct RunCmd {
 	#[clap(long)]
 	pub ws_max_out_buffer_capacity: Option<usize>,
 
+	/// Limit the total cost of HTTP and WS RPC calls each client may make per second.
+	///
+	/// Calls cost 1, except for expensive methods like `state_queryStorage`, `state_traceBlock`
+	/// and `state_getPairs`, see `--rpc-method-cost`. HTTP clients are told apart by their IP
+	/// address, WS clients by their connection, so `--ws-max-connections` bounds the calls of
+	/// all WS clients together. IPC calls are not limited.
+	#[clap(long, value_name = "COST")]
+	pub rpc_rate_limit: Option<u32>,
+
+	/// Limit the number of calls to an RPC method each client may make per second.
+	///
+	/// Expected format is 'METHOD=CALLS', e.g. `--rpc-method-rate-limit state_getKeys=5`, with
+	/// the method named as in JSON-RPC requests.
+	#[clap(long, value_name = "METHOD=CALLS", parse(try_from_str = parse_method_value))]
+	pub rpc_method_rate_limit: Vec<(String, u32)>,
+
+	/// Set the cost of calls to an RPC method, counted against `--rpc-rate-limit`.
+	///
+	/// Expected format is 'METHOD=COST', e.g. `--rpc-method-cost state_getPairs=50`, with the
+	/// method named as in JSON-RPC requests.
+	#[clap(long, value_name = "METHOD=COST", parse(try_from_str = parse_method_value))]
+	pub rpc_method_cost: Vec<(String, u32)>,
+
+	/// Only expose RPC methods matching these patterns over HTTP, WS and IPC.
+	///
+	/// Patterns may contain `*` and `?` wildcards, e.g. `--rpc-allow-methods 'state_*,chain_*'`.
+	/// All methods are exposed by default, subject to `--rpc-methods`.
+	#[clap(long, value_name = "PATTERNS", use_delimiter = true)]
+	pub rpc_allow_methods: Vec<String>,
+
+	/// Don't expose RPC methods matching these patterns over HTTP, WS and IPC.
+	///
+	/// Patterns may contain `*` and `?` wildcards, e.g. `--rpc-deny-methods 'author_*'`.
+	#[clap(long, value_name = "PATTERNS", use_delimiter = true)]
+	pub rpc_deny_methods: Vec<String>,
+
+	/// Read the RPC methods to expose over each interface from a JSON file.
+	///
+	/// Expected format is `{ "http": { "allow": [PATTERNS], "deny": [PATTERNS] }, "ws": .., "ipc":
+	/// .. }`, all entries being optional. Patterns of `--rpc-allow-methods` and
+	/// `--rpc-deny-methods` are added to every interface.
+	#[clap(long, value_name = "FILE", parse(from_os_str))]
+	pub rpc_methods_config: Option<PathBuf>,
+
+	/// Attribute HTTP RPC calls to the client address in the `X-Real-IP` or `X-Forwarded-For`
+	/// headers for rate limiting.
+	///
+	/// Only use behind a reverse proxy that sets these headers, otherwise clients can evade
+	/// their limits. Without it, HTTP RPC calls are attributed to the address of the peer.
+	#[clap(long)]
+	pub rpc_trust_proxy_headers: bool,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	///
 	/// A comma-separated list of origins (protocol://domain or special `null`
@@ -453,6 +511,32 @@ impl CliConfiguration for RunCmd {
 		Ok(self.ws_max_out_buffer_capacity)
 	}
 
+	fn rpc_method_filters(&self) -> Result<RpcMethodFilters> {
+		let mut filters = match self.rpc_methods_config {
+			Some(ref path) => serde_json::from_slice(&std::fs::read(path)?).map_err(|e| {
+				Error::Input(format!("Invalid RPC methods config {}: {}", path.display(), e))
+			})?,
+			None => RpcMethodFilters::default(),
+		};
+		let cli_filter = RpcMethodFilter {
+			allow: self.rpc_allow_methods.clone(),
+			deny: self.rpc_deny_methods.clone(),
+		};
+		filters.http.extend(&cli_filter);
+		filters.ws.extend(&cli_filter);
+		filters.ipc.extend(&cli_filter);
+		Ok(filters)
+	}
+
+	fn rpc_rate_limits(&self) -> Result<RpcRateLimits> {
+		let mut limits = RpcRateLimits::default();
+		limits.client_cost = self.rpc_rate_limit;
+		limits.method_calls.extend(self.rpc_method_rate_limit.iter().cloned());
+		limits.method_costs.extend(self.rpc_method_cost.iter().cloned());
+		limits.trust_proxy_headers = self.rpc_trust_proxy_headers;
+		Ok(limits)
+	}
+
 	fn transaction_pool(&self) -> Result<TransactionPoolOptions> {
 		Ok(self.pool_config.transaction_pool())
 	}
@@ -560,6 +644,15 @@ fn parse_telemetry_endpoints(s: &str) -> std::result::Result<(String, u8), Telem
 	}
 }
 
+/// Parse a 'METHOD=VALUE' pair of RPC rate limit options.
+fn parse_method_value(s: &str) -> std::result::Result<(String, u32), String> {
+	let (method, value) = s
+		.split_once('=')
+		.ok_or_else(|| format!("Expected 'METHOD=VALUE', got '{}'", s))?;
+	let value = value.parse().map_err(|e| format!("Invalid value for '{}': {}", method, e))?;
+	Ok((method.to_string(), value))
+}
+
 /// CORS setting
 ///
 /// The type is introduced to overcome `Option<Option<T>>` handling of `clap`.
@@ -20,7 +20,8 @@ use crate::{arg_enums::SyncMode, params::node_key_params::NodeKeyParams};
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		ConnectionFilterConfig, IpNetwork, NetworkConfiguration, NodeKeyConfig,
+		NonReservedPeerMode, SetConfig, TransportConfig,
 	},
 	multiaddr::Protocol,
 };
@@ -82,6 +83,25 @@ pub struct NetworkParams {
 	#[clap(long, conflicts_with_all = &["no-private-ipv4"])]
 	pub allow_private_ipv4: bool,
 
+	/// Only accept connections with nodes whose IP address is within one of these networks,
+	/// given in CIDR notation (e.g. `10.0.0.0/8`).
+	///
+	/// Connections with all addresses are accepted if none is given.
+	#[clap(long, value_name = "CIDR", multiple_values(true))]
+	pub allow_ip: Vec<IpNetwork>,
+
+	/// Refuse connections with nodes whose IP address is within one of these networks, given in
+	/// CIDR notation (e.g. `198.51.100.0/24`). Takes precedence over `--allow-ip`.
+	#[clap(long, value_name = "CIDR", multiple_values(true))]
+	pub deny_ip: Vec<IpNetwork>,
+
+	/// Maximum number of inbound connections from the same subnet (`/24` for IPv4, `/64` for
+	/// IPv6).
+	///
+	/// Unlimited by default.
+	#[clap(long, value_name = "COUNT")]
+	pub max_inbound_per_subnet: Option<u32>,
+
 	/// Specify the number of outgoing connections we're trying to maintain.
 	#[clap(long, value_name = "COUNT", default_value = "25")]
 	pub out_peers: u32,
@@ -130,6 +150,10 @@ pub struct NetworkParams {
 	#[clap(long)]
 	pub ipfs_server: bool,
 
+	/// Fetch transactions from IPFS peers over bitswap protocol.
+	#[clap(long)]
+	pub ipfs_client: bool,
+
 	/// Blockchain syncing mode.
 	///
 	/// - `Full`: Download and validate full blockchain history.
@@ -227,12 +251,19 @@ impl NetworkParams {
 				enable_mdns: !is_dev && !self.no_mdns,
 				allow_private_ipv4,
 			},
+			connection_filter: ConnectionFilterConfig {
+				allowed: self.allow_ip.clone(),
+				denied: self.deny_ip.clone(),
+				max_inbound_per_subnet: self.max_inbound_per_subnet,
+				..Default::default()
+			},
 			max_parallel_downloads: self.max_parallel_downloads,
 			enable_dht_random_walk: !self.reserved_only,
 			allow_non_globals_in_dht,
 			kademlia_disjoint_query_paths: self.kademlia_disjoint_query_paths,
 			yamux_window_size: None,
 			ipfs_server: self.ipfs_server,
+			ipfs_client: self.ipfs_client,
 			sync_mode: self.sync.into(),
 		}
 	}
@@ -282,6 +313,35 @@ mod tests {
 		assert_eq!(expected, params.network_params.reserved_nodes);
 	}
 
+	#[test]
+	fn connection_filter_parameters() {
+		let params = Cli::try_parse_from([
+			"",
+			"--allow-ip",
+			"10.0.0.0/8",
+			"2001:db8::/32",
+			"--deny-ip",
+			"10.1.0.0/16",
+			"--max-inbound-per-subnet",
+			"4",
+		])
+		.expect("Parses network params");
+
+		assert_eq!(
+			params.network_params.allow_ip,
+			vec![
+				"10.0.0.0/8".parse::<IpNetwork>().unwrap(),
+				"2001:db8::/32".parse::<IpNetwork>().unwrap(),
+			]
+		);
+		assert_eq!(
+			params.network_params.deny_ip,
+			vec!["10.1.0.0/16".parse::<IpNetwork>().unwrap()]
+		);
+		assert_eq!(params.network_params.max_inbound_per_subnet, Some(4));
+		assert!(Cli::try_parse_from(["", "--deny-ip", "10.1.0.1/16"]).is_err());
+	}
+
 	#[test]
 	fn sync_ingores_case() {
 		let params = Cli::try_parse_from(["", "--sync", "wArP"]).expect("Parses network params");
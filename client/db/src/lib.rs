@@ -57,6 +57,7 @@ use crate::{
 	storage_cache::{new_shared_cache, CachingState, SharedCache, SyncingCachingState},
 	utils::{meta_keys, read_db, read_meta, DatabaseType, Meta},
 };
+use cid::multihash::{Code, MultihashDigest};
 use codec::{Decode, Encode};
 use hash_db::Prefix;
 use sc_client_api::{
@@ -72,7 +73,7 @@ use sp_blockchain::{
 	HeaderMetadata, HeaderMetadataCache, Result as ClientResult,
 };
 use sp_core::{
-	offchain::OffchainOverlayedChange,
+	offchain::{OffchainIndexingOrigin, OffchainOverlayedChange, INDEXING_ORIGIN_PREFIX},
 	storage::{well_known_keys, ChildInfo},
 };
 use sp_database::Transaction;
@@ -88,6 +89,7 @@ use sp_state_machine::{
 	backend::Backend as StateBackend, ChildStorageCollection, DBValue, IndexOperation,
 	OffchainChangesCollection, StateMachineStats, StorageCollection, UsageInfo as StateUsageInfo,
 };
+use sp_transaction_storage_proof::unixfs::{Dag, DAG_CHUNK_SIZE};
 use sp_trie::{prefixed_key, MemoryDB, PrefixedMemoryDB};
 
 // Re-export the Database trait so that one can pass an implementation of it.
@@ -111,6 +113,24 @@ pub const DB_HASH_LEN: usize = 32;
 /// Hash type that this backend uses for the database.
 pub type DbHash = sp_core::H256;
 
+/// Prefix of the keys of entries that list additional content hashes of indexed data.
+const INDEXED_HASHES_PREFIX: &[u8] = b"indexed_hashes";
+/// Prefix of the keys of UnixFS DAG block entries of indexed data.
+const DAG_BLOCK_PREFIX: &[u8] = b"dag_block";
+/// Prefix of the keys of entries that list the UnixFS DAG leaves indexed data is rebuilt from.
+const INDEXED_LEAVES_PREFIX: &[u8] = b"indexed_leaves";
+/// Prefix of the keys of index removals requested by a block, applied once it is finalized.
+const PENDING_REMOVALS_PREFIX: &[u8] = b"index_removals";
+/// Prefix of the keys of positions in the indexed entries of a block whose indexed data has been
+/// removed. See [`indexed_entries`].
+const REMOVED_INDICES_PREFIX: &[u8] = b"index_removed";
+/// Prefix of the keys of indexed data renewed by a block itself rather than its extrinsics.
+const BLOCK_RENEWALS_PREFIX: &[u8] = b"index_block_renewals";
+/// Prefix of the keys of the number of the latest block that stored or renewed indexed data.
+const INDEXED_BLOCK_PREFIX: &[u8] = b"index_block";
+/// Key of the list of content hashes of indexed data pinned by the node operator.
+const PINNED_TRANSACTIONS_KEY: &[u8] = b"pinned_transactions";
+
 /// This is used as block body when storage-chain mode is enabled.
 #[derive(Debug, Encode, Decode)]
 struct ExtrinsicHeader {
@@ -578,7 +598,7 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 							.into_iter()
 							.map(|ExtrinsicHeader { indexed_hash, data }| {
 								let decode_result = if indexed_hash != Default::default() {
-									match self.db.get(columns::TRANSACTION, indexed_hash.as_ref()) {
+									match read_indexed_data(&*self.db, &indexed_hash)? {
 										Some(t) => {
 											let mut input =
 												utils::join_input(data.as_ref(), t.as_ref());
@@ -640,44 +660,95 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 	}
 
 	fn indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<Option<Vec<u8>>> {
-		Ok(self.db.get(columns::TRANSACTION, hash.as_ref()))
+		read_indexed_data(&*self.db, &DbHash::from_slice(hash.as_ref()))
 	}
 
 	fn has_indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<bool> {
 		Ok(self.db.contains(columns::TRANSACTION, hash.as_ref()))
 	}
 
+	fn indexed_transaction_size(&self, hash: &Block::Hash) -> ClientResult<Option<usize>> {
+		read_indexed_size(&*self.db, &DbHash::from_slice(hash.as_ref()))
+	}
+
+	fn indexed_transaction_hash(
+		&self,
+		code: u64,
+		digest: &[u8],
+	) -> ClientResult<Option<Block::Hash>> {
+		match self.db.get(columns::TRANSACTION, indexed_hash_key(code, digest).as_ref()) {
+			Some(hash) => match Decode::decode(&mut &hash[..]) {
+				Ok(hash) => Ok(Some(hash)),
+				Err(err) => Err(sp_blockchain::Error::Backend(format!(
+					"Error decoding indexed transaction hash: {}",
+					err
+				))),
+			},
+			None => Ok(None),
+		}
+	}
+
+	fn indexed_dag_block(&self, hash: &Block::Hash) -> ClientResult<Option<Vec<u8>>> {
+		Ok(self.db.get(columns::TRANSACTION, dag_block_key(hash.as_ref()).as_ref()))
+	}
+
+	fn indexed_transaction_block(
+		&self,
+		hash: &Block::Hash,
+	) -> ClientResult<Option<NumberFor<Block>>> {
+		if !self.db.contains(columns::TRANSACTION, hash.as_ref()) {
+			return Ok(None)
+		}
+		Ok(read_indexed_block(&*self.db, &DbHash::from_slice(hash.as_ref())).map(Into::into))
+	}
+
 	fn block_indexed_body(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<Vec<u8>>>> {
+		let hashes = match self.block_indexed_hashes(id)? {
+			Some(hashes) => hashes,
+			None => return Ok(None),
+		};
+		let mut transactions = Vec::with_capacity(hashes.len());
+		for hash in hashes {
+			match read_indexed_data(&*self.db, &DbHash::from_slice(hash.as_ref()))? {
+				Some(t) => transactions.push(t),
+				None =>
+					return Err(sp_blockchain::Error::Backend(format!(
+						"Missing indexed transaction {:?}",
+						hash
+					))),
+			}
+		}
+		Ok(Some(transactions))
+	}
+
+	fn block_indexed_hashes(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<Block::Hash>>> {
 		match self.transaction_storage {
 			TransactionStorageMode::BlockBody => Ok(None),
 			TransactionStorageMode::StorageChain => {
-				let body = match read_db(&*self.db, columns::KEY_LOOKUP, columns::BODY, id)? {
+				let lookup_key =
+					match utils::block_id_to_lookup_key(&*self.db, columns::KEY_LOOKUP, id)? {
+						Some(key) => key,
+						None => return Ok(None),
+					};
+				let body = match self.db.get(columns::BODY, &lookup_key) {
 					Some(body) => body,
 					None => return Ok(None),
 				};
-				match Vec::<ExtrinsicHeader>::decode(&mut &body[..]) {
-					Ok(index) => {
-						let mut transactions = Vec::new();
-						for ExtrinsicHeader { indexed_hash, .. } in index.into_iter() {
-							if indexed_hash != Default::default() {
-								match self.db.get(columns::TRANSACTION, indexed_hash.as_ref()) {
-									Some(t) => transactions.push(t),
-									None =>
-										return Err(sp_blockchain::Error::Backend(format!(
-											"Missing indexed transaction {:?}",
-											indexed_hash
-										))),
-								}
-							}
-						}
-						Ok(Some(transactions))
-					},
-					Err(err) =>
-						return Err(sp_blockchain::Error::Backend(format!(
-							"Error decoding body list: {}",
-							err
-						))),
-				}
+				let removed = read_removed_indices(&*self.db, &lookup_key);
+				Ok(Some(
+					indexed_entries(&*self.db, &lookup_key, &body)?
+						.into_iter()
+						.enumerate()
+						.filter(|(i, hash)| {
+							*hash != Default::default() && !removed.contains(&(*i as u32))
+						})
+						.map(|(_, indexed_hash)| {
+							let mut hash = Block::Hash::default();
+							hash.as_mut().copy_from_slice(indexed_hash.as_ref());
+							hash
+						})
+						.collect(),
+				))
 			},
 		}
 	}
@@ -737,14 +808,32 @@ pub struct BlockImportOperation<Block: BlockT> {
 
 impl<Block: BlockT> BlockImportOperation<Block> {
 	fn apply_offchain(&mut self, transaction: &mut Transaction<DbHash>) {
+		// Offchain indexing changes are recorded along with the block making them.
+		let origin = self.pending_block.as_ref().map(|pending| {
+			OffchainIndexingOrigin {
+				block_number: (*pending.header.number()).saturated_into(),
+				block_hash: pending.header.hash().as_ref().to_vec(),
+			}
+			.encode()
+		});
 		let mut count = 0;
 		for ((prefix, key), value_operation) in self.offchain_storage_updates.drain(..) {
 			count += 1;
 			let key = crate::offchain::concatenate_prefix_and_key(&prefix, &key);
+			let origin_key =
+				crate::offchain::concatenate_prefix_and_key(INDEXING_ORIGIN_PREFIX, &key);
 			match value_operation {
-				OffchainOverlayedChange::SetValue(val) =>
-					transaction.set_from_vec(columns::OFFCHAIN, &key, val),
-				OffchainOverlayedChange::Remove => transaction.remove(columns::OFFCHAIN, &key),
+				OffchainOverlayedChange::SetValue(val) => {
+					transaction.set_from_vec(columns::OFFCHAIN, &key, val);
+					match &origin {
+						Some(origin) => transaction.set(columns::OFFCHAIN, &origin_key, origin),
+						None => transaction.remove(columns::OFFCHAIN, &origin_key),
+					}
+				},
+				OffchainOverlayedChange::Remove => {
+					transaction.remove(columns::OFFCHAIN, &key);
+					transaction.remove(columns::OFFCHAIN, &origin_key);
+				},
 			}
 		}
 
@@ -1232,7 +1321,7 @@ impl<Block: BlockT> Backend<Block> {
 		self.ensure_sequential_finalization(header, last_finalized)?;
 		let with_state = sc_client_api::Backend::have_state_at(self, &hash, number);
 
-		self.note_finalized(transaction, header, *hash, finalization_displaced, with_state)?;
+		self.note_finalized(transaction, header, *hash, finalization_displaced, with_state, None)?;
 
 		if let Some(justification) = justification {
 			transaction.set_from_vec(
@@ -1333,15 +1422,29 @@ impl<Block: BlockT> Backend<Block> {
 			utils::insert_hash_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash)?;
 
 			transaction.set_from_vec(columns::HEADER, &lookup_key, pending_block.header.encode());
+			let mut index_removals = Vec::new();
 			if let Some(body) = pending_block.body {
 				match self.transaction_storage {
 					TransactionStorageMode::BlockBody => {
 						transaction.set_from_vec(columns::BODY, &lookup_key, body.encode());
 					},
 					TransactionStorageMode::StorageChain => {
-						let body =
-							apply_index_ops::<Block>(&mut transaction, body, operation.index_ops);
+						let (body, removals) = apply_index_ops::<Block>(
+							&mut transaction,
+							&*self.storage.db,
+							&lookup_key,
+							body,
+							operation.index_ops,
+						);
 						transaction.set_from_vec(columns::BODY, &lookup_key, body);
+						if !removals.is_empty() {
+							transaction.set_from_vec(
+								columns::META,
+								&pending_removals_key(&lookup_key),
+								removals.encode(),
+							);
+						}
+						index_removals = removals;
 					},
 				}
 			}
@@ -1483,6 +1586,7 @@ impl<Block: BlockT> Backend<Block> {
 					hash,
 					&mut finalization_displaced_leaves,
 					operation.commit_state,
+					Some(index_removals),
 				)?;
 			} else {
 				// canonicalize blocks which are old enough, regardless of finality.
@@ -1632,6 +1736,8 @@ impl<Block: BlockT> Backend<Block> {
 	// write stuff to a transaction after a new block is finalized.
 	// this canonicalizes finalized blocks. Fails if called with a block which
 	// was not a child of the last finalized block.
+	// `index_removals` are the index removals requested by the block, if it is imported in the
+	// same transaction. Otherwise they are read from the database.
 	fn note_finalized(
 		&self,
 		transaction: &mut Transaction<DbHash>,
@@ -1639,10 +1745,12 @@ impl<Block: BlockT> Backend<Block> {
 		f_hash: Block::Hash,
 		displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>,
 		with_state: bool,
+		index_removals: Option<Vec<(u32, DbHash)>>,
 	) -> ClientResult<()> {
 		let f_num = f_header.number().clone();
 
 		let lookup_key = utils::number_and_hash_to_lookup_key(f_num, f_hash.clone())?;
+		let removals_key = pending_removals_key(&lookup_key);
 		if with_state {
 			transaction.set_from_vec(columns::META, meta_keys::FINALIZED_STATE, lookup_key.clone());
 		}
@@ -1663,6 +1771,16 @@ impl<Block: BlockT> Backend<Block> {
 
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
 		self.prune_blocks(transaction, f_num, &new_displaced)?;
+		if let TransactionStorageMode::StorageChain = self.transaction_storage {
+			let removals = match index_removals {
+				Some(removals) => removals,
+				None => read_pending_removals(&*self.storage.db, &removals_key),
+			};
+			if !removals.is_empty() {
+				self.apply_index_removals(transaction, f_num, removals)?;
+				transaction.remove(columns::META, &removals_key);
+			}
+		}
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
 			&mut Some(ref mut displaced) => displaced.merge(new_displaced),
@@ -1727,18 +1845,35 @@ impl<Block: BlockT> Backend<Block> {
 				match self.transaction_storage {
 					TransactionStorageMode::BlockBody => {},
 					TransactionStorageMode::StorageChain => {
-						match Vec::<ExtrinsicHeader>::decode(&mut &body[..]) {
-							Ok(body) =>
-								for ExtrinsicHeader { indexed_hash, .. } in body {
-									if indexed_hash != Default::default() {
-										transaction.release(columns::TRANSACTION, indexed_hash);
-									}
-								},
-							Err(err) =>
-								return Err(sp_blockchain::Error::Backend(format!(
-									"Error decoding body list: {}",
-									err
-								))),
+						let lookup_key = utils::block_id_to_lookup_key(
+							&*self.storage.db,
+							columns::KEY_LOOKUP,
+							id,
+						)?
+						.unwrap_or_default();
+						let entries = indexed_entries(&*self.storage.db, &lookup_key, &body)?;
+						let removed = read_removed_indices(&*self.storage.db, &lookup_key);
+						transaction.remove(columns::META, &removed_indices_key(&lookup_key));
+						transaction.remove(columns::META, &pending_removals_key(&lookup_key));
+						transaction.remove(columns::META, &block_renewals_key(&lookup_key));
+						let number = lookup_key_number(&lookup_key);
+						for (i, indexed_hash) in entries.into_iter().enumerate() {
+							if indexed_hash != Default::default() && !removed.contains(&(i as u32))
+							{
+								transaction.release(columns::TRANSACTION, indexed_hash);
+								update_indexed_hashes(
+									transaction,
+									&*self.storage.db,
+									&indexed_hash,
+									false,
+								);
+								// No later block references the data.
+								let latest = read_indexed_block(&*self.storage.db, &indexed_hash);
+								if latest.is_some() && latest <= number {
+									transaction
+										.remove(columns::META, &indexed_block_key(&indexed_hash));
+								}
+							}
 						}
 					},
 				}
@@ -1748,6 +1883,72 @@ impl<Block: BlockT> Backend<Block> {
 		Ok(())
 	}
 
+	/// Remove indexed data from the bodies of earlier blocks, as requested by the `finalized`
+	/// block.
+	fn apply_index_removals(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		finalized: NumberFor<Block>,
+		removals: Vec<(u32, DbHash)>,
+	) -> ClientResult<()> {
+		let mut removed_indices = HashMap::new();
+		for (number, hash) in removals {
+			if let KeepBlocks::Some(keep_blocks) = self.keep_blocks {
+				// Bodies of blocks this old are pruned, at the latest in this transaction.
+				let keep = std::cmp::max(keep_blocks, 1);
+				if NumberFor::<Block>::from(number).saturating_add(keep.into()) <= finalized {
+					continue
+				}
+			}
+			let id = BlockId::<Block>::number(number.into());
+			let lookup_key =
+				match utils::block_id_to_lookup_key(&*self.storage.db, columns::KEY_LOOKUP, id)? {
+					Some(key) => key,
+					None => continue,
+				};
+			let body = match self.storage.db.get(columns::BODY, &lookup_key) {
+				Some(body) => body,
+				// The block has been pruned already.
+				None => continue,
+			};
+			let entries = indexed_entries(&*self.storage.db, &lookup_key, &body)?;
+			let removed = removed_indices
+				.entry(lookup_key)
+				.or_insert_with_key(|key| read_removed_indices(&*self.storage.db, key));
+			let index = entries
+				.iter()
+				.enumerate()
+				.position(|(i, entry)| *entry == hash && !removed.contains(&(i as u32)));
+			match index {
+				Some(index) => {
+					debug!(
+						target: "db",
+						"Removing indexed transaction {:?} of block #{}",
+						hash,
+						number,
+					);
+					removed.push(index as u32);
+					transaction.release(columns::TRANSACTION, hash);
+					update_indexed_hashes(transaction, &*self.storage.db, &hash, false);
+				},
+				None => debug!(
+					target: "db",
+					"Removed indexed transaction {:?} not found in block #{}",
+					hash,
+					number,
+				),
+			}
+		}
+		for (lookup_key, removed) in removed_indices {
+			transaction.set_from_vec(
+				columns::META,
+				&removed_indices_key(&lookup_key),
+				removed.encode(),
+			);
+		}
+		Ok(())
+	}
+
 	fn empty_state(&self) -> ClientResult<SyncingCachingState<RefTrackingState<Block>, Block>> {
 		let root = EmptyStorage::<Block>::new().0; // Empty trie
 		let db_state = DbState::<Block>::new(self.storage.clone(), root);
@@ -1780,22 +1981,295 @@ fn apply_state_commit(
 	}
 }
 
+/// Key of the entry that maps an additional content hash of indexed data to its primary hash.
+fn indexed_hash_key(code: u64, digest: &[u8]) -> DbHash {
+	DbHash::from(sp_core::hashing::blake2_256(&(code, digest).encode()))
+}
+
+/// Key of the entry that stores a UnixFS DAG block of indexed data with `hash`.
+fn dag_block_key(hash: &[u8]) -> DbHash {
+	DbHash::from(sp_core::hashing::blake2_256(&(DAG_BLOCK_PREFIX, hash).encode()))
+}
+
+/// Key of the entry that lists the keys of the UnixFS DAG leaves of indexed data with `hash`.
+fn indexed_leaves_key(hash: &DbHash) -> DbHash {
+	DbHash::from(sp_core::hashing::blake2_256(&(INDEXED_LEAVES_PREFIX, hash).encode()))
+}
+
+/// Key of the list of index removals requested by the block with `lookup_key`.
+fn pending_removals_key(lookup_key: &[u8]) -> Vec<u8> {
+	[PENDING_REMOVALS_PREFIX, lookup_key].concat()
+}
+
+/// Key of the list of extrinsic indices of the block with `lookup_key` whose indexed data has
+/// been removed.
+fn removed_indices_key(lookup_key: &[u8]) -> Vec<u8> {
+	[REMOVED_INDICES_PREFIX, lookup_key].concat()
+}
+
+/// Key of the list of indexed data renewed by the block with `lookup_key` itself.
+fn block_renewals_key(lookup_key: &[u8]) -> Vec<u8> {
+	[BLOCK_RENEWALS_PREFIX, lookup_key].concat()
+}
+
+/// Key of the number of the latest block that stored or renewed indexed data with `hash`.
+fn indexed_block_key(hash: &DbHash) -> Vec<u8> {
+	[INDEXED_BLOCK_PREFIX, hash.as_ref()].concat()
+}
+
+/// Number of the block with `lookup_key`.
+fn lookup_key_number(lookup_key: &[u8]) -> Option<u32> {
+	let number = lookup_key.get(..4)?;
+	Some(u32::from_be_bytes([number[0], number[1], number[2], number[3]]))
+}
+
+fn read_indexed_block(db: &dyn Database<DbHash>, hash: &DbHash) -> Option<u32> {
+	db.get(columns::META, &indexed_block_key(hash))
+		.and_then(|number| Decode::decode(&mut &number[..]).ok())
+}
+
+/// Record that the block with `lookup_key` stores or renews indexed data with `hash`.
+fn note_indexed_block(
+	transaction: &mut Transaction<DbHash>,
+	db: &dyn Database<DbHash>,
+	lookup_key: &[u8],
+	hash: &DbHash,
+) {
+	let number = match lookup_key_number(lookup_key) {
+		Some(number) => number,
+		None => return,
+	};
+	if read_indexed_block(db, hash).map_or(true, |latest| latest < number) {
+		transaction.set_from_vec(columns::META, &indexed_block_key(hash), number.encode());
+	}
+}
+
+/// Indexed data hashes of the block with `lookup_key` and `body`: data renewed by the block
+/// itself, followed by the indexed hash of each extrinsic, or the zero hash if it has none.
+fn indexed_entries(
+	db: &dyn Database<DbHash>,
+	lookup_key: &[u8],
+	body: &[u8],
+) -> ClientResult<Vec<DbHash>> {
+	let headers = Vec::<ExtrinsicHeader>::decode(&mut &body[..]).map_err(|err| {
+		sp_blockchain::Error::Backend(format!("Error decoding body list: {}", err))
+	})?;
+	let mut entries: Vec<DbHash> = db
+		.get(columns::META, &block_renewals_key(lookup_key))
+		.and_then(|renewals| Decode::decode(&mut &renewals[..]).ok())
+		.unwrap_or_default();
+	entries.extend(headers.into_iter().map(|header| header.indexed_hash));
+	Ok(entries)
+}
+
+fn read_pinned_transactions(db: &dyn Database<DbHash>) -> Vec<DbHash> {
+	db.get(columns::META, PINNED_TRANSACTIONS_KEY)
+		.and_then(|pinned| Decode::decode(&mut &pinned[..]).ok())
+		.unwrap_or_default()
+}
+
+fn read_pending_removals(db: &dyn Database<DbHash>, key: &[u8]) -> Vec<(u32, DbHash)> {
+	db.get(columns::META, key)
+		.and_then(|removals| Decode::decode(&mut &removals[..]).ok())
+		.unwrap_or_default()
+}
+
+fn read_removed_indices(db: &dyn Database<DbHash>, lookup_key: &[u8]) -> Vec<u32> {
+	db.get(columns::META, &removed_indices_key(lookup_key))
+		.and_then(|removed| Decode::decode(&mut &removed[..]).ok())
+		.unwrap_or_default()
+}
+
+/// Key of the entry that lists additional content hash keys of indexed data.
+fn indexed_hashes_key(hash: &DbHash) -> DbHash {
+	DbHash::from(sp_core::hashing::blake2_256(&(INDEXED_HASHES_PREFIX, hash).encode()))
+}
+
+/// Compute keys of additional content hashes of `data`. Unsupported codes are skipped.
+fn content_hash_keys(data: &[u8], hash_codes: &[u64]) -> Vec<DbHash> {
+	let mut keys = Vec::with_capacity(hash_codes.len());
+	for code in hash_codes {
+		match Code::try_from(*code) {
+			Ok(hasher) => {
+				let key = indexed_hash_key(*code, hasher.digest(data).digest());
+				if !keys.contains(&key) {
+					keys.push(key);
+				}
+			},
+			Err(_) => debug!(target: "db", "Ignoring unsupported content hash code {:#x}", code),
+		}
+	}
+	keys
+}
+
+/// Compute keys and contents of the UnixFS DAG blocks of indexed `data`, along with the keys of
+/// its leaves in data order. Data that fits into a single leaf is served as is and has no DAG
+/// entries.
+///
+/// Leaves are stored by value rather than as slices of the indexed data, because the same leaf
+/// may be shared by several transactions that are pruned independently. The indexed data itself
+/// is rebuilt from its leaves, see [`read_indexed_data`].
+fn dag_blocks(data: &[u8]) -> (Vec<(DbHash, Vec<u8>)>, Vec<DbHash>) {
+	if data.len() <= DAG_CHUNK_SIZE {
+		return (Vec::new(), Vec::new())
+	}
+	let dag = Dag::build(data, sp_core::hashing::blake2_256);
+	let mut blocks = Vec::with_capacity(dag.leaves.len() + 1);
+	if let Some(node) = dag.root {
+		blocks.push((dag_block_key(&node.hash), node.data));
+	}
+	let leaves = dag.leaves.iter().map(|leaf| dag_block_key(leaf)).collect::<Vec<_>>();
+	for (key, chunk) in leaves.iter().zip(data.chunks(DAG_CHUNK_SIZE)) {
+		blocks.push((*key, chunk.to_vec()));
+	}
+	(blocks, leaves)
+}
+
+/// Read indexed data with `hash`. Data made of several UnixFS DAG leaves is stored as an empty
+/// entry and rebuilt from the leaves listed under [`indexed_leaves_key`].
+fn read_indexed_data(db: &dyn Database<DbHash>, hash: &DbHash) -> ClientResult<Option<Vec<u8>>> {
+	let data = match db.get(columns::TRANSACTION, hash.as_ref()) {
+		Some(data) if data.is_empty() => data,
+		data => return Ok(data),
+	};
+	let leaves = match db.get(columns::TRANSACTION, indexed_leaves_key(hash).as_ref()) {
+		Some(leaves) => Vec::<DbHash>::decode(&mut &leaves[..]).map_err(|err| {
+			sp_blockchain::Error::Backend(format!("Error decoding DAG leaves of {:?}: {}", hash, err))
+		})?,
+		None => return Ok(Some(data)),
+	};
+	let mut data = Vec::with_capacity(leaves.len() * DAG_CHUNK_SIZE);
+	for leaf in leaves {
+		match db.get(columns::TRANSACTION, leaf.as_ref()) {
+			Some(chunk) => data.extend_from_slice(&chunk),
+			None =>
+				return Err(sp_blockchain::Error::Backend(format!(
+					"Missing DAG leaf {:?} of indexed transaction {:?}",
+					leaf, hash
+				))),
+		}
+	}
+	Ok(Some(data))
+}
+
+/// Size of indexed data with `hash`, computed from the stored value sizes without reading the
+/// data. All DAG leaves but the last one are full chunks.
+fn read_indexed_size(db: &dyn Database<DbHash>, hash: &DbHash) -> ClientResult<Option<usize>> {
+	match db.value_size(columns::TRANSACTION, hash.as_ref()) {
+		Some(0) => (),
+		size => return Ok(size),
+	}
+	let leaves = match db.get(columns::TRANSACTION, indexed_leaves_key(hash).as_ref()) {
+		Some(leaves) => Vec::<DbHash>::decode(&mut &leaves[..]).map_err(|err| {
+			sp_blockchain::Error::Backend(format!("Error decoding DAG leaves of {:?}: {}", hash, err))
+		})?,
+		None => return Ok(Some(0)),
+	};
+	let last = match leaves.last() {
+		Some(last) => last,
+		None => return Ok(Some(0)),
+	};
+	match db.value_size(columns::TRANSACTION, last.as_ref()) {
+		Some(size) => Ok(Some((leaves.len() - 1) * DAG_CHUNK_SIZE + size)),
+		None => Err(sp_blockchain::Error::Backend(format!(
+			"Missing DAG leaf {:?} of indexed transaction {:?}",
+			last, hash
+		))),
+	}
+}
+
+/// Store indexed `data` with `hash`, along with the entries of its additional content hashes and
+/// UnixFS DAG blocks.
+fn store_indexed_data(
+	transaction: &mut Transaction<DbHash>,
+	hash: &DbHash,
+	data: &[u8],
+	hash_codes: &[u64],
+) {
+	let mut keys = Vec::new();
+	for key in content_hash_keys(data, hash_codes) {
+		transaction.store(columns::TRANSACTION, key, hash.encode());
+		keys.push(key);
+	}
+	let (blocks, leaves) = dag_blocks(data);
+	for (key, block) in blocks {
+		if !keys.contains(&key) {
+			transaction.store(columns::TRANSACTION, key, block);
+			keys.push(key);
+		}
+	}
+	// Data made of several leaves is only stored as its leaves.
+	let data = if leaves.is_empty() {
+		data.to_vec()
+	} else {
+		let key = indexed_leaves_key(hash);
+		transaction.store(columns::TRANSACTION, key, leaves.encode());
+		keys.push(key);
+		Vec::new()
+	};
+	if !keys.is_empty() {
+		transaction.store(columns::TRANSACTION, indexed_hashes_key(hash), keys.encode());
+	}
+	transaction.store(columns::TRANSACTION, *hash, data);
+}
+
+/// Reference or release the additional content hash entries of indexed data with `hash`.
+fn update_indexed_hashes(
+	transaction: &mut Transaction<DbHash>,
+	db: &dyn Database<DbHash>,
+	hash: &DbHash,
+	reference: bool,
+) {
+	let list_key = indexed_hashes_key(hash);
+	let keys = match db.get(columns::TRANSACTION, list_key.as_ref()) {
+		Some(keys) => match Vec::<DbHash>::decode(&mut &keys[..]) {
+			Ok(keys) => keys,
+			Err(err) => {
+				warn!(target: "db", "Error decoding content hashes of {:?}: {}", hash, err);
+				return
+			},
+		},
+		None => return,
+	};
+	for key in keys.into_iter().chain(std::iter::once(list_key)) {
+		if reference {
+			transaction.reference(columns::TRANSACTION, key);
+		} else {
+			transaction.release(columns::TRANSACTION, key);
+		}
+	}
+}
+
 fn apply_index_ops<Block: BlockT>(
 	transaction: &mut Transaction<DbHash>,
+	db: &dyn Database<DbHash>,
+	lookup_key: &[u8],
 	body: Vec<Block::Extrinsic>,
 	ops: Vec<IndexOperation>,
-) -> Vec<u8> {
+) -> (Vec<u8>, Vec<(u32, DbHash)>) {
 	let mut extrinsic_headers: Vec<ExtrinsicHeader> = Vec::with_capacity(body.len());
 	let mut index_map = HashMap::new();
 	let mut renewed_map = HashMap::new();
+	let mut block_renewals = Vec::new();
+	let mut removals = Vec::new();
 	for op in ops {
 		match op {
-			IndexOperation::Insert { extrinsic, hash, size } => {
-				index_map.insert(extrinsic, (hash, size));
+			IndexOperation::Insert { extrinsic, hash, size, hash_codes } => {
+				index_map.insert(extrinsic, (hash, size, hash_codes));
 			},
 			IndexOperation::Renew { extrinsic, hash } => {
 				renewed_map.insert(extrinsic, DbHash::from_slice(hash.as_ref()));
 			},
+			IndexOperation::BlockRenew { hash } => {
+				let hash = DbHash::from_slice(hash.as_ref());
+				transaction.reference(columns::TRANSACTION, hash);
+				update_indexed_hashes(transaction, db, &hash, true);
+				note_indexed_block(transaction, db, lookup_key, &hash);
+				block_renewals.push(hash);
+			},
+			IndexOperation::Remove { block, hash } => {
+				removals.push((block, DbHash::from_slice(hash.as_ref())));
+			},
 		}
 	}
 	for (index, extrinsic) in body.into_iter().enumerate() {
@@ -1803,20 +2277,17 @@ fn apply_index_ops<Block: BlockT>(
 		let extrinsic_header = if let Some(hash) = renewed_map.get(&(index as u32)) {
 			// Bump ref counter
 			transaction.reference(columns::TRANSACTION, DbHash::from_slice(hash.as_ref()));
+			update_indexed_hashes(transaction, db, hash, true);
+			note_indexed_block(transaction, db, lookup_key, hash);
 			ExtrinsicHeader { indexed_hash: hash.clone(), data: extrinsic }
 		} else {
 			match index_map.get(&(index as u32)) {
-				Some((hash, size)) if *size as usize <= extrinsic.len() => {
+				Some((hash, size, hash_codes)) if *size as usize <= extrinsic.len() => {
 					let offset = extrinsic.len() - *size as usize;
-					transaction.store(
-						columns::TRANSACTION,
-						DbHash::from_slice(hash.as_ref()),
-						extrinsic[offset..].to_vec(),
-					);
-					ExtrinsicHeader {
-						indexed_hash: DbHash::from_slice(hash.as_ref()),
-						data: extrinsic[..offset].to_vec(),
-					}
+					let hash = DbHash::from_slice(hash.as_ref());
+					store_indexed_data(transaction, &hash, &extrinsic[offset..], hash_codes);
+					note_indexed_block(transaction, db, lookup_key, &hash);
+					ExtrinsicHeader { indexed_hash: hash, data: extrinsic[..offset].to_vec() }
 				},
 				_ => ExtrinsicHeader { indexed_hash: Default::default(), data: extrinsic },
 			}
@@ -1825,11 +2296,20 @@ fn apply_index_ops<Block: BlockT>(
 	}
 	debug!(
 		target: "db",
-		"DB transaction index: {} inserted, {} renewed",
+		"DB transaction index: {} inserted, {} renewed, {} renewed by block, {} removed",
 		index_map.len(),
-		renewed_map.len()
+		renewed_map.len(),
+		block_renewals.len(),
+		removals.len(),
 	);
-	extrinsic_headers.encode()
+	if !block_renewals.is_empty() {
+		transaction.set_from_vec(
+			columns::META,
+			&block_renewals_key(lookup_key),
+			block_renewals.encode(),
+		);
+	}
+	(extrinsic_headers.encode(), removals)
 }
 
 fn apply_indexed_body<Block: BlockT>(transaction: &mut Transaction<DbHash>, body: Vec<Vec<u8>>) {
@@ -2187,6 +2667,64 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		Ok(())
 	}
 
+	fn pin_indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<bool> {
+		if let TransactionStorageMode::BlockBody = self.transaction_storage {
+			return Err(sp_blockchain::Error::Backend(
+				"Indexed transactions are only stored in storage chain mode".into(),
+			))
+		}
+		// Keep block import from updating the reference counts concurrently.
+		let _lock = self.import_lock.write();
+		let hash = DbHash::from_slice(hash.as_ref());
+		let mut pinned = read_pinned_transactions(&*self.storage.db);
+		if pinned.contains(&hash) {
+			return Ok(false)
+		}
+		if !self.storage.db.contains(columns::TRANSACTION, hash.as_ref()) {
+			return Err(sp_blockchain::Error::Backend(format!(
+				"Missing indexed transaction {:?}",
+				hash
+			)))
+		}
+		let mut transaction = Transaction::new();
+		transaction.reference(columns::TRANSACTION, hash);
+		update_indexed_hashes(&mut transaction, &*self.storage.db, &hash, true);
+		pinned.push(hash);
+		transaction.set_from_vec(columns::META, PINNED_TRANSACTIONS_KEY, pinned.encode());
+		self.storage.db.commit(transaction)?;
+		debug!(target: "db", "Pinned indexed transaction {:?}", hash);
+		Ok(true)
+	}
+
+	fn unpin_indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<bool> {
+		let _lock = self.import_lock.write();
+		let hash = DbHash::from_slice(hash.as_ref());
+		let mut pinned = read_pinned_transactions(&*self.storage.db);
+		let index = match pinned.iter().position(|h| *h == hash) {
+			Some(index) => index,
+			None => return Ok(false),
+		};
+		pinned.swap_remove(index);
+		let mut transaction = Transaction::new();
+		transaction.release(columns::TRANSACTION, hash);
+		update_indexed_hashes(&mut transaction, &*self.storage.db, &hash, false);
+		transaction.set_from_vec(columns::META, PINNED_TRANSACTIONS_KEY, pinned.encode());
+		self.storage.db.commit(transaction)?;
+		debug!(target: "db", "Unpinned indexed transaction {:?}", hash);
+		Ok(true)
+	}
+
+	fn pinned_indexed_transactions(&self) -> ClientResult<Vec<Block::Hash>> {
+		Ok(read_pinned_transactions(&*self.storage.db)
+			.into_iter()
+			.map(|pinned| {
+				let mut hash = Block::Hash::default();
+				hash.as_mut().copy_from_slice(pinned.as_ref());
+				hash
+			})
+			.collect())
+	}
+
 	fn blockchain(&self) -> &BlockchainDb<Block> {
 		&self.blockchain
 	}
@@ -3200,6 +3738,7 @@ pub(crate) mod tests {
 					extrinsic: 0,
 					hash: x1_hash.as_ref().to_vec(),
 					size: (x1.len() - 1) as u32,
+					hash_codes: Vec::new(),
 				});
 			} else if i < 5 {
 				// keep renewing 1st
@@ -3233,6 +3772,280 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn block_renew_transaction_storage() {
+		let backend =
+			Backend::<Block>::new_test_with_tx_storage(2, 10, TransactionStorageMode::StorageChain);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		let x1 = ExtrinsicWrapper::from(0u64).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		for i in 0..4 {
+			let index = match i {
+				0 => vec![IndexOperation::Insert {
+					extrinsic: 0,
+					hash: x1_hash.as_ref().to_vec(),
+					size: (x1.len() - 1) as u32,
+					hash_codes: Vec::new(),
+				}],
+				1 => vec![IndexOperation::BlockRenew { hash: x1_hash.as_ref().to_vec() }],
+				_ => Vec::new(),
+			};
+			let hash = insert_block(
+				&backend,
+				i,
+				prev_hash,
+				None,
+				Default::default(),
+				vec![i.into()],
+				Some(index),
+			)
+			.unwrap();
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		let bc = backend.blockchain();
+		assert_eq!(bc.block_indexed_hashes(BlockId::Number(1)).unwrap(), Some(vec![x1_hash]));
+		for i in 1..4 {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(blocks[3])).unwrap();
+			op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+			backend.commit_operation(op).unwrap();
+			let bc = backend.blockchain();
+			// Block #1 keeps the data after block #0 is pruned.
+			if i < 3 {
+				assert!(bc.indexed_transaction(&x1_hash).unwrap().is_some());
+			} else {
+				assert!(bc.indexed_transaction(&x1_hash).unwrap().is_none());
+			}
+		}
+	}
+
+	#[test]
+	fn pin_transaction_storage() {
+		let backend =
+			Backend::<Block>::new_test_with_tx_storage(2, 10, TransactionStorageMode::StorageChain);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		let x1 = ExtrinsicWrapper::from(0u64).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		for i in 0..4 {
+			let mut index = Vec::new();
+			if i == 0 {
+				index.push(IndexOperation::Insert {
+					extrinsic: 0,
+					hash: x1_hash.as_ref().to_vec(),
+					size: (x1.len() - 1) as u32,
+					hash_codes: Vec::new(),
+				});
+			}
+			let hash = insert_block(
+				&backend,
+				i,
+				prev_hash,
+				None,
+				Default::default(),
+				vec![i.into()],
+				Some(index),
+			)
+			.unwrap();
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		assert!(backend.pin_indexed_transaction(&Default::default()).is_err());
+		assert!(backend.pin_indexed_transaction(&x1_hash).unwrap());
+		assert!(!backend.pin_indexed_transaction(&x1_hash).unwrap());
+		assert_eq!(backend.pinned_indexed_transactions().unwrap(), vec![x1_hash]);
+		for i in 1..4 {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(blocks[3])).unwrap();
+			op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+			backend.commit_operation(op).unwrap();
+		}
+		// Block #0 is pruned, but the data is kept.
+		let bc = backend.blockchain();
+		assert!(bc.body(BlockId::Number(0)).unwrap().is_none());
+		assert!(bc.indexed_transaction(&x1_hash).unwrap().is_some());
+
+		assert!(backend.unpin_indexed_transaction(&x1_hash).unwrap());
+		assert!(!backend.unpin_indexed_transaction(&x1_hash).unwrap());
+		assert!(backend.pinned_indexed_transactions().unwrap().is_empty());
+		assert!(bc.indexed_transaction(&x1_hash).unwrap().is_none());
+	}
+
+	#[test]
+	fn remove_transaction_storage() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(
+			10,
+			10,
+			TransactionStorageMode::StorageChain,
+		);
+		let x1 = ExtrinsicWrapper::from(1u64 << 32).encode();
+		let x2 = ExtrinsicWrapper::from(2u64 << 32).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		let x2_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x2[1..]);
+		let index = vec![
+			IndexOperation::Insert {
+				extrinsic: 0,
+				hash: x1_hash.as_ref().to_vec(),
+				size: (x1.len() - 1) as u32,
+				hash_codes: Vec::new(),
+			},
+			IndexOperation::Insert {
+				extrinsic: 1,
+				hash: x2_hash.as_ref().to_vec(),
+				size: (x2.len() - 1) as u32,
+				hash_codes: Vec::new(),
+			},
+		];
+		let block0 = insert_block(
+			&backend,
+			0,
+			Default::default(),
+			None,
+			Default::default(),
+			vec![(1u64 << 32).into(), (2u64 << 32).into()],
+			Some(index),
+		)
+		.unwrap();
+		let index = vec![IndexOperation::Remove { block: 0, hash: x1_hash.as_ref().to_vec() }];
+		let block1 = insert_block(
+			&backend,
+			1,
+			block0,
+			None,
+			Default::default(),
+			vec![3.into()],
+			Some(index),
+		)
+		.unwrap();
+
+		// Removal takes effect once the removing block is finalized.
+		let bc = backend.blockchain();
+		assert_eq!(
+			bc.block_indexed_hashes(BlockId::Number(0)).unwrap(),
+			Some(vec![x1_hash, x2_hash])
+		);
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(block1)).unwrap();
+		op.mark_finalized(BlockId::Hash(block1), None).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		let bc = backend.blockchain();
+		assert_eq!(bc.block_indexed_hashes(BlockId::Number(0)).unwrap(), Some(vec![x2_hash]));
+		assert_eq!(
+			bc.block_indexed_body(BlockId::Number(0)).unwrap(),
+			Some(vec![x2[1..].to_vec()])
+		);
+		assert!(bc.indexed_transaction(&x1_hash).unwrap().is_none());
+		assert!(bc.indexed_transaction(&x2_hash).unwrap().is_some());
+	}
+
+	#[test]
+	fn indexed_dag_blocks() {
+		let backend =
+			Backend::<Block>::new_test_with_tx_storage(2, 10, TransactionStorageMode::StorageChain);
+		assert_eq!(dag_blocks(&[1u8; 100]), (Vec::new(), Vec::new()));
+
+		// Two transactions sharing their first leaf.
+		let shared: Vec<u8> = (0..DAG_CHUNK_SIZE).map(|i| i as u8).collect();
+		let first = [&shared[..], &[1]].concat();
+		let second = [&shared[..], &[2; 10]].concat();
+		let store = |data: &[u8]| {
+			let hash = DbHash::from(sp_core::hashing::blake2_256(data));
+			let mut transaction = Transaction::new();
+			store_indexed_data(&mut transaction, &hash, data, &[]);
+			backend.storage.db.commit(transaction).unwrap();
+			hash
+		};
+		let first_hash = store(&first);
+		let second_hash = store(&second);
+
+		let bc = backend.blockchain();
+		let dag = Dag::build(&first, sp_core::hashing::blake2_256);
+		let root = dag.root.unwrap();
+		assert_eq!(bc.indexed_dag_block(&root.hash.into()).unwrap(), Some(root.data));
+		for (leaf, chunk) in dag.leaves.iter().zip(first.chunks(DAG_CHUNK_SIZE)) {
+			assert_eq!(bc.indexed_dag_block(&(*leaf).into()).unwrap(), Some(chunk.to_vec()));
+		}
+		// The data is only stored as its leaves, and rebuilt from them.
+		let db = &backend.storage.db;
+		assert_eq!(db.get(columns::TRANSACTION, first_hash.as_ref()), Some(Vec::new()));
+		assert_eq!(bc.indexed_transaction_size(&first_hash).unwrap(), Some(first.len()));
+		assert_eq!(bc.indexed_transaction_size(&second_hash).unwrap(), Some(second.len()));
+		assert_eq!(bc.indexed_transaction(&first_hash).unwrap(), Some(first));
+
+		// Pruning the first transaction keeps the leaf shared with the second one.
+		let mut transaction = Transaction::new();
+		transaction.release(columns::TRANSACTION, first_hash);
+		update_indexed_hashes(&mut transaction, &**db, &first_hash, false);
+		backend.storage.db.commit(transaction).unwrap();
+		assert_eq!(bc.indexed_transaction(&first_hash).unwrap(), None);
+		assert_eq!(bc.indexed_dag_block(&root.hash.into()).unwrap(), None);
+		assert_eq!(bc.indexed_dag_block(&dag.leaves[1].into()).unwrap(), None);
+		assert_eq!(bc.indexed_dag_block(&dag.leaves[0].into()).unwrap(), Some(shared));
+		assert_eq!(bc.indexed_transaction(&second_hash).unwrap(), Some(second));
+	}
+
+	#[test]
+	fn indexed_transaction_content_hashes() {
+		const SHA2_256: u64 = 0x12;
+		const UNSUPPORTED: u64 = 0x4242;
+		let backend =
+			Backend::<Block>::new_test_with_tx_storage(2, 10, TransactionStorageMode::StorageChain);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		let x1 = ExtrinsicWrapper::from(0u64).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		let x1_sha2 = sp_core::hashing::sha2_256(&x1[1..]);
+		for i in 0..5 {
+			let mut index = Vec::new();
+			if i == 0 {
+				index.push(IndexOperation::Insert {
+					extrinsic: 0,
+					hash: x1_hash.as_ref().to_vec(),
+					size: (x1.len() - 1) as u32,
+					hash_codes: vec![SHA2_256, UNSUPPORTED],
+				});
+			} else if i < 3 {
+				index.push(IndexOperation::Renew { extrinsic: 0, hash: x1_hash.as_ref().to_vec() });
+			}
+			let hash = insert_block(
+				&backend,
+				i,
+				prev_hash,
+				None,
+				Default::default(),
+				vec![i.into()],
+				Some(index),
+			)
+			.unwrap();
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		let bc = backend.blockchain();
+		assert_eq!(bc.indexed_transaction_hash(SHA2_256, &x1_sha2).unwrap(), Some(x1_hash));
+		assert_eq!(bc.indexed_transaction_hash(UNSUPPORTED, &x1_sha2).unwrap(), None);
+
+		for i in 1..5 {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(blocks[4])).unwrap();
+			op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+			backend.commit_operation(op).unwrap();
+			let bc = backend.blockchain();
+			let resolved = bc.indexed_transaction_hash(SHA2_256, &x1_sha2).unwrap();
+			if i < 4 {
+				assert_eq!(resolved, Some(x1_hash));
+			} else {
+				assert_eq!(resolved, None);
+				assert!(bc.indexed_transaction(&x1_hash).unwrap().is_none());
+			}
+		}
+	}
+
 	#[test]
 	fn remove_leaf_block_works() {
 		let backend =
@@ -3366,4 +4179,53 @@ pub(crate) mod tests {
 		insert_header_no_head(&backend, 1, block0, [1; 32].into());
 		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block2_a]);
 	}
+
+	#[test]
+	fn offchain_indexing_records_origin() {
+		use sp_core::offchain::{OffchainStorage, STORAGE_PREFIX};
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: BlakeTwo256::trie_root(Vec::new(), StateVersion::V1),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.hash();
+		let change = |value: OffchainOverlayedChange| {
+			vec![((STORAGE_PREFIX.to_vec(), b"key".to_vec()), value)]
+		};
+
+		let mut op = backend.begin_operation().unwrap();
+		backend
+			.begin_state_operation(&mut op, BlockId::Hash(Default::default()))
+			.unwrap();
+		op.set_block_data(header.clone(), None, None, None, NewBlockState::Best)
+			.unwrap();
+		op.update_offchain_storage(change(OffchainOverlayedChange::SetValue(b"value".to_vec())))
+			.unwrap();
+		backend.commit_operation(op).unwrap();
+
+		let storage = backend.offchain_storage().unwrap();
+		let origin_key = [STORAGE_PREFIX, b"key"].concat();
+		let origin = storage.get(INDEXING_ORIGIN_PREFIX, &origin_key).unwrap();
+		assert_eq!(
+			OffchainIndexingOrigin::decode(&mut &origin[..]).unwrap(),
+			OffchainIndexingOrigin { block_number: 0, block_hash: hash.as_ref().to_vec() },
+		);
+		assert_eq!(
+			storage.iter_prefix(STORAGE_PREFIX).unwrap().collect::<Vec<_>>(),
+			vec![(origin_key.clone(), b"value".to_vec())],
+		);
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(hash)).unwrap();
+		let header = Header { number: 1, parent_hash: hash, ..header };
+		op.set_block_data(header, None, None, None, NewBlockState::Best).unwrap();
+		op.update_offchain_storage(change(OffchainOverlayedChange::Remove)).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		assert_eq!(storage.get(INDEXING_ORIGIN_PREFIX, &origin_key), None);
+	}
 }
@@ -75,6 +75,13 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 		self.db.get(columns::OFFCHAIN, &concatenate_prefix_and_key(prefix, key))
 	}
 
+	fn iter_prefix<'a>(
+		&'a self,
+		prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		self.db.iter_prefix(columns::OFFCHAIN, prefix)
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
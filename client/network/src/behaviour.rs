@@ -17,9 +17,10 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-	bitswap::Bitswap,
+	bitswap::{Bitswap, BitswapRequestError, Cid},
 	config::ProtocolId,
 	discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
+	network_state::BitswapLedger,
 	peer_info,
 	protocol::{message::Roles, CustomMessageOutcome, NotificationsSink, Protocol},
 	request_responses, DhtEvent, ObservedRole,
@@ -49,7 +50,7 @@ use sp_runtime::{
 };
 use std::{
 	borrow::Cow,
-	collections::{HashSet, VecDeque},
+	collections::{HashMap, HashSet, VecDeque},
 	iter,
 	task::{Context, Poll},
 	time::Duration,
@@ -70,7 +71,7 @@ pub struct Behaviour<B: BlockT> {
 	peer_info: peer_info::PeerInfoBehaviour,
 	/// Discovers nodes of the network.
 	discovery: DiscoveryBehaviour,
-	/// Bitswap server for blockchain data.
+	/// Bitswap server and client for blockchain data.
 	bitswap: Toggle<Bitswap<B>>,
 	/// Generic request-reponse protocols.
 	request_responses: request_responses::RequestResponsesBehaviour,
@@ -292,6 +293,29 @@ impl<B: BlockT> Behaviour<B> {
 			.send_request(target, protocol, request, pending_response, connect)
 	}
 
+	/// Initiates fetching a block by CID over bitswap.
+	pub fn bitswap_request(
+		&mut self,
+		cid: Cid,
+		timeout: Duration,
+		pending_response: oneshot::Sender<Result<Vec<u8>, BitswapRequestError>>,
+	) {
+		match self.bitswap.as_mut() {
+			Some(bitswap) => bitswap.request(cid, timeout, pending_response),
+			None => {
+				let _ = pending_response.send(Err(BitswapRequestError::Disabled));
+			},
+		}
+	}
+
+	/// Returns the bitswap ledgers of the connected peers, by peer.
+	pub fn bitswap_ledgers(&self) -> HashMap<PeerId, BitswapLedger> {
+		match self.bitswap.as_ref() {
+			Some(bitswap) => bitswap.ledgers().map(|(peer, ledger)| (*peer, ledger)).collect(),
+			None => HashMap::new(),
+		}
+	}
+
 	/// Returns a shared reference to the user protocol.
 	pub fn user_protocol(&self) -> &Protocol<B> {
 		&self.substrate
@@ -473,6 +497,10 @@ impl<B: BlockT> NetworkBehaviourEventProcess<peer_info::PeerInfoEvent> for Behav
 			listen_addrs.truncate(30);
 		}
 
+		if let Some(bitswap) = self.bitswap.as_mut() {
+			bitswap.on_peer_identified(&peer_id, &protocols);
+		}
+
 		for addr in listen_addrs {
 			self.discovery.add_self_reported_address(&peer_id, protocols.iter(), addr);
 		}
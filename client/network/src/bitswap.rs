@@ -14,44 +14,69 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Bitswap server for substrate.
+//! Bitswap server and client for substrate.
 //!
 //! Allows querying transactions by hash over standard bitswap protocol
 //! Only supports bitswap 1.2.0.
-//! CID is expected to reference 256-bit Blake2b transaction hash.
+//! CID is expected to reference 256-bit Blake2b transaction hash, or one of the additional
+//! content hashes the transaction was indexed with. Transactions larger than a single UnixFS DAG
+//! leaf are also served block by block, with CIDs of the DAG root and leaves.
+//!
+//! Wantlists of remote peers persist across messages in a per-peer ledger, until the wanted
+//! blocks are sent or the entries are cancelled. Ledgers are served in turns, a limited number of
+//! bytes at a time, so that a single peer can't starve the others.
+//!
+//! In client mode, blocks can be requested by CID from connected peers that advertise the
+//! bitswap protocol. Peers are queried one at a time until one of them returns a block matching
+//! the CID, or the request times out.
 
 use crate::{
 	chain::Client,
+	network_state::BitswapLedger,
 	schema::bitswap::{
-		message::{wantlist::WantType, Block as MessageBlock, BlockPresence, BlockPresenceType},
+		message::{
+			wantlist::{Entry as WantlistEntry, WantType},
+			Block as MessageBlock, BlockPresence, BlockPresenceType, Wantlist,
+		},
 		Message as BitswapMessage,
 	},
+	utils::interval,
+};
+use cid::{
+	multihash::{Code, MultihashDigest},
+	Version,
 };
-use cid::Version;
 use core::pin::Pin;
 use futures::{
+	channel::oneshot,
 	io::{AsyncRead, AsyncWrite},
-	Future,
+	Future, Stream, StreamExt,
 };
 use libp2p::{
 	core::{
-		connection::ConnectionId, upgrade, InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId,
-		UpgradeInfo,
+		connection::ConnectionId, upgrade, ConnectedPoint, InboundUpgrade, Multiaddr,
+		OutboundUpgrade, PeerId, UpgradeInfo,
 	},
 	swarm::{
-		NetworkBehaviour, NetworkBehaviourAction, NotifyHandler, OneShotHandler, PollParameters,
+		IntoProtocolsHandler, NetworkBehaviour, NetworkBehaviourAction, NotifyHandler,
+		OneShotHandler, PollParameters,
 	},
 };
 use log::{debug, error, trace};
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
 use prost::Message;
 use sp_runtime::traits::Block as BlockT;
 use std::{
-	collections::VecDeque,
+	collections::{HashMap, HashSet, VecDeque},
+	convert::TryFrom,
 	io,
 	sync::Arc,
 	task::{Context, Poll},
+	time::{Duration, Instant},
 };
-use unsigned_varint::encode as varint_encode;
+use unsigned_varint::{decode as varint_decode, encode as varint_encode};
+
+pub use cid::Cid;
 
 const LOG_TARGET: &str = "bitswap";
 
@@ -61,10 +86,28 @@ const LOG_TARGET: &str = "bitswap";
 // We set it to the same value as max substrate protocol message
 const MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
 
-// Max number of queued responses before denying requests.
+// Max number of messages in flight to all peers before we stop serving wantlists.
 const MAX_RESPONSE_QUEUE: usize = 20;
-// Max number of blocks per wantlist
-const MAX_WANTED_BLOCKS: usize = 16;
+// Max number of messages in flight to a single peer before we stop serving its wantlist.
+const MAX_PEER_RESPONSE_QUEUE: usize = 4;
+// Max number of entries in the wantlist of a peer.
+const MAX_WANTED_BLOCKS: usize = 1024;
+// Max number of block bytes sent to a peer in one turn, unless a single block is larger.
+const MAX_RESPONSE_BYTES: usize = 512 * 1024;
+// Max number of wanted blocks looked up in the database each time the behaviour is polled, as
+// lookups block the network task.
+const MAX_LOOKUPS_PER_POLL: usize = 32;
+
+// Max number of outbound requests in progress.
+const MAX_PENDING_REQUESTS: usize = 64;
+// Max number of peers asked for the same block before the request fails.
+const MAX_PEERS_PER_REQUEST: usize = 5;
+// Time a peer is given to deliver a block before the next peer is asked.
+const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Interval at which outbound request timeouts are checked.
+const REQUEST_TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Interval at which blocks wanted by remote peers that were missing are looked up again.
+const MISSING_BLOCKS_CHECK_INTERVAL: Duration = Duration::from_secs(6);
 
 const PROTOCOL_NAME: &'static [u8] = b"/ipfs/bitswap/1.2.0";
 
@@ -178,65 +221,218 @@ impl Prefix {
 		res.extend_from_slice(mh_len);
 		res
 	}
+
+	/// Decode the prefix from bytes.
+	pub fn from_bytes(data: &[u8]) -> Result<Prefix, BitswapError> {
+		let (raw_version, remain) = varint_decode::u64(data)?;
+		let version = Version::try_from(raw_version)?;
+		let (codec, remain) = varint_decode::u64(remain)?;
+		let (mh_type, remain) = varint_decode::u64(remain)?;
+		let (mh_len, _remain) = varint_decode::u8(remain)?;
+		Ok(Prefix { version, codec, mh_type, mh_len })
+	}
+
+	/// Compute the CID of `data` using this prefix.
+	pub fn to_cid(&self, data: &[u8]) -> Result<Cid, BitswapError> {
+		let code = Code::try_from(self.mh_type)?;
+		let hash = code.digest(data);
+		if hash.size() != self.mh_len {
+			return Err(BitswapError::BadCid(cid::Error::ParsingError))
+		}
+		Ok(Cid::new(self.version, self.codec, hash)?)
+	}
 }
 
-/// Network behaviour that handles sending and receiving IPFS blocks.
-pub struct Bitswap<B> {
-	client: Arc<dyn Client<B>>,
-	ready_blocks: VecDeque<(PeerId, BitswapMessage)>,
+type ResponseSender = oneshot::Sender<Result<Vec<u8>, BitswapRequestError>>;
+
+/// Outbound request for a single block.
+struct PendingRequest {
+	/// Channels waiting for the block, with the time each of them stops waiting.
+	pending_responses: Vec<(ResponseSender, Instant)>,
+	/// Peers that have already been asked for the block.
+	queried: HashSet<PeerId>,
+	/// Peer that is currently asked for the block, and the time when we stop waiting for it.
+	current: Option<(PeerId, Instant)>,
 }
 
-impl<B: BlockT> Bitswap<B> {
-	/// Create a new instance of the bitswap protocol handler.
-	pub fn new(client: Arc<dyn Client<B>>) -> Self {
-		Self { client, ready_blocks: Default::default() }
+impl PendingRequest {
+	fn respond(self, result: Result<Vec<u8>, BitswapRequestError>) {
+		for (pending_response, _) in self.pending_responses {
+			let _ = pending_response.send(result.clone());
+		}
+	}
+
+	/// Fail the channels whose deadline passed at `now`. Returns `true` if none is left.
+	fn expire(&mut self, now: Instant) -> bool {
+		let (expired, waiting): (Vec<_>, Vec<_>) =
+			self.pending_responses.drain(..).partition(|(_, deadline)| *deadline <= now);
+		self.pending_responses = waiting;
+		for (pending_response, _) in expired {
+			let _ = pending_response.send(Err(BitswapRequestError::Timeout));
+		}
+		self.pending_responses.is_empty()
 	}
 }
 
-impl<B: BlockT> NetworkBehaviour for Bitswap<B> {
-	type ProtocolsHandler = OneShotHandler<BitswapConfig, BitswapMessage, HandlerEvent>;
-	type OutEvent = void::Void;
+/// Outbound requests in progress.
+///
+/// Concurrent requests for the same CID share the queried peers, but each of them fails on its
+/// own timeout.
+#[derive(Default)]
+struct Requests {
+	/// Requests by CID.
+	pending: HashMap<Cid, PendingRequest>,
+	/// Outbound wantlists waiting to be sent.
+	ready_wants: VecDeque<(PeerId, BitswapMessage)>,
+}
 
-	fn new_handler(&mut self) -> Self::ProtocolsHandler {
-		Default::default()
+impl Requests {
+	/// Add a request for `cid` that fails at `deadline`.
+	fn add(&mut self, cid: Cid, deadline: Instant, pending_response: ResponseSender) {
+		if let Some(request) = self.pending.get_mut(&cid) {
+			request.pending_responses.push((pending_response, deadline));
+			return
+		}
+		if self.pending.len() >= MAX_PENDING_REQUESTS {
+			debug!(target: LOG_TARGET, "Rejected request for {}: too many pending requests", cid);
+			let _ = pending_response.send(Err(BitswapRequestError::TooManyRequests));
+			return
+		}
+		trace!(target: LOG_TARGET, "Requesting CID {}", cid);
+		self.pending.insert(
+			cid,
+			PendingRequest {
+				pending_responses: vec![(pending_response, deadline)],
+				queried: Default::default(),
+				current: None,
+			},
+		);
 	}
 
-	fn addresses_of_peer(&mut self, _peer: &PeerId) -> Vec<Multiaddr> {
-		Vec::new()
+	/// Handle a block matching `cid` sent by `peer`.
+	fn on_block(&mut self, peer: &PeerId, cid: &Cid, data: &[u8]) {
+		match self.pending.remove(cid) {
+			Some(request) => {
+				trace!(target: LOG_TARGET, "Received CID {} from {}", cid, peer);
+				if let Some((current, _)) = request.current {
+					if current != *peer {
+						self.ready_wants.push_back((current, want_message(cid, true)));
+					}
+				}
+				request.respond(Ok(data.to_vec()));
+			},
+			None => trace!(target: LOG_TARGET, "Unexpected CID {} from {}", cid, peer),
+		}
 	}
 
-	fn inject_connected(&mut self, _peer: &PeerId) {}
+	/// Handle `peer` telling it doesn't have `cid`.
+	fn on_dont_have(&mut self, peer: &PeerId, cid: &Cid) {
+		if let Some(request) = self.pending.get_mut(cid) {
+			if request.current.as_ref().map_or(false, |(current, _)| current == peer) {
+				trace!(target: LOG_TARGET, "Peer {} doesn't have CID {}", peer, cid);
+				request.current = None;
+			}
+		}
+	}
 
-	fn inject_disconnected(&mut self, _peer: &PeerId) {}
+	/// Fail the requests that expired at `now` and move on from peers that didn't answer in time.
+	fn check_timeouts(&mut self, now: Instant) {
+		let mut expired = Vec::new();
+		for (cid, request) in self.pending.iter_mut() {
+			if request.expire(now) {
+				expired.push(*cid);
+				continue
+			}
+			if let Some((current, peer_deadline)) = request.current {
+				if peer_deadline <= now {
+					trace!(target: LOG_TARGET, "Peer {} didn't send CID {} in time", current, cid);
+					self.ready_wants.push_back((current, want_message(cid, true)));
+					request.current = None;
+				}
+			}
+		}
+		for cid in expired {
+			if let Some(request) = self.pending.remove(&cid) {
+				debug!(target: LOG_TARGET, "Request for CID {} timed out", cid);
+				if let Some((current, _)) = request.current {
+					self.ready_wants.push_back((current, want_message(&cid, true)));
+				}
+			}
+		}
+	}
 
-	fn inject_event(&mut self, peer: PeerId, _connection: ConnectionId, message: HandlerEvent) {
-		let request = match message {
-			HandlerEvent::ResponseSent => return,
-			HandlerEvent::Request(msg) => msg,
-		};
-		trace!(target: LOG_TARGET, "Received request: {:?} from {}", request, peer);
-		if self.ready_blocks.len() > MAX_RESPONSE_QUEUE {
-			debug!(target: LOG_TARGET, "Ignored request: queue is full");
-			return
+	/// Ask the next peer among `peers` for every request that is not waiting on any peer.
+	fn schedule(&mut self, peers: &HashSet<PeerId>, now: Instant) {
+		let mut not_found = Vec::new();
+		for (cid, request) in self.pending.iter_mut() {
+			if request.current.is_some() {
+				continue
+			}
+			let next = if request.queried.len() < MAX_PEERS_PER_REQUEST {
+				peers.iter().find(|peer| !request.queried.contains(peer)).copied()
+			} else {
+				None
+			};
+			match next {
+				Some(peer) => {
+					trace!(target: LOG_TARGET, "Asking {} for CID {}", peer, cid);
+					request.queried.insert(peer);
+					request.current = Some((peer, now + PEER_REQUEST_TIMEOUT));
+					self.ready_wants.push_back((peer, want_message(cid, false)));
+				},
+				// Wait for more peers to connect if nobody has been asked yet.
+				None if request.queried.is_empty() => {},
+				None => not_found.push(*cid),
+			}
 		}
-		let mut response = BitswapMessage {
-			wantlist: None,
-			blocks: Default::default(),
-			payload: Default::default(),
-			block_presences: Default::default(),
-			pending_bytes: 0,
-		};
-		let wantlist = match request.wantlist {
-			Some(wantlist) => wantlist,
-			None => {
-				debug!(target: LOG_TARGET, "Unexpected bitswap message from {}", peer);
-				return
-			},
-		};
-		if wantlist.entries.len() > MAX_WANTED_BLOCKS {
-			trace!(target: LOG_TARGET, "Ignored request: too many entries");
-			return
+		for cid in not_found {
+			if let Some(request) = self.pending.remove(&cid) {
+				debug!(target: LOG_TARGET, "No peer has CID {}", cid);
+				request.respond(Err(BitswapRequestError::NotFound));
+			}
+		}
+	}
+
+	/// Stop waiting on `peer`, which disconnected.
+	fn peer_disconnected(&mut self, peer: &PeerId) {
+		for request in self.pending.values_mut() {
+			if request.current.as_ref().map_or(false, |(current, _)| current == peer) {
+				request.current = None;
+			}
 		}
+	}
+}
+
+/// Wantlist entry of a remote peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WantEntry {
+	priority: i32,
+	/// Send the block itself, rather than only its presence.
+	want_block: bool,
+	send_dont_have: bool,
+	/// The block was missing when it was last looked up.
+	missing: bool,
+}
+
+/// Decision engine state of a remote peer.
+#[derive(Debug, Default)]
+struct Ledger {
+	/// Blocks wanted by the peer. Entries are kept until the peer cancels them or the block is
+	/// sent.
+	wantlist: HashMap<Cid, WantEntry>,
+	/// Block bytes sent to the peer.
+	bytes_sent: u64,
+	/// Block bytes received from the peer.
+	bytes_received: u64,
+	/// Messages sent to the peer that have not been delivered yet, by connection.
+	in_flight: HashMap<ConnectionId, usize>,
+}
+
+impl Ledger {
+	/// Apply a wantlist received from the peer.
+	fn update_wantlist(&mut self, peer: &PeerId, wantlist: Wantlist) {
+		let previous =
+			if wantlist.full { std::mem::take(&mut self.wantlist) } else { Default::default() };
 		for entry in wantlist.entries {
 			let cid = match cid::Cid::read_bytes(entry.block.as_slice()) {
 				Ok(cid) => cid,
@@ -245,32 +441,103 @@ impl<B: BlockT> NetworkBehaviour for Bitswap<B> {
 					continue
 				},
 			};
+			if entry.cancel {
+				trace!(target: LOG_TARGET, "Peer {} cancelled CID {}", peer, cid);
+				self.wantlist.remove(&cid);
+				continue
+			}
 			if cid.version() != cid::Version::V1 ||
-				cid.hash().code() != u64::from(cid::multihash::Code::Blake2b256) ||
-				cid.hash().size() != 32
+				(cid.hash().code() == u64::from(Code::Blake2b256) && cid.hash().size() != 32)
 			{
 				debug!(target: LOG_TARGET, "Ignoring unsupported CID {}: {}", peer, cid);
 				continue
 			}
-			let mut hash = B::Hash::default();
-			hash.as_mut().copy_from_slice(&cid.hash().digest()[0..32]);
-			let transaction = match self.client.indexed_transaction(&hash) {
-				Ok(ex) => ex,
-				Err(e) => {
-					error!(target: LOG_TARGET, "Error retrieving transaction {}: {}", hash, e);
-					None
+			if !self.wantlist.contains_key(&cid) && self.wantlist.len() >= MAX_WANTED_BLOCKS {
+				trace!(target: LOG_TARGET, "Ignoring CID {}: wantlist of {} is full", cid, peer);
+				continue
+			}
+			let want_block = entry.want_type == WantType::Block as i32;
+			match self.wantlist.get(&cid).or_else(|| previous.get(&cid)) {
+				// Missing blocks are not looked up or reported again until the next check.
+				Some(want) if want.missing => {
+					let want = WantEntry { priority: entry.priority, want_block, ..want.clone() };
+					self.wantlist.insert(cid, want);
 				},
-			};
-			match transaction {
+				_ => {
+					let want = WantEntry {
+						priority: entry.priority,
+						want_block,
+						send_dont_have: entry.send_dont_have,
+						missing: false,
+					};
+					self.wantlist.insert(cid, want);
+				},
+			}
+		}
+	}
+
+	/// Wanted CIDs that are due to be looked up, highest priority first.
+	fn pending_wants(&self) -> Vec<(Cid, WantEntry)> {
+		let mut wants = self
+			.wantlist
+			.iter()
+			.filter(|(_, entry)| !entry.missing)
+			.map(|(cid, entry)| (*cid, entry.clone()))
+			.collect::<Vec<_>>();
+		wants.sort_by(|(_, a), (_, b)| b.priority.cmp(&a.priority));
+		wants
+	}
+
+	/// `true` if some wanted CIDs are due to be looked up.
+	fn has_pending_wants(&self) -> bool {
+		self.wantlist.values().any(|entry| !entry.missing)
+	}
+
+	/// Look up missing blocks again on the next turn. Returns `true` if there were any.
+	fn check_missing(&mut self) -> bool {
+		let mut any = false;
+		for entry in self.wantlist.values_mut().filter(|entry| entry.missing) {
+			entry.missing = false;
+			any = true;
+		}
+		any
+	}
+
+	/// Build the next response to the peer from the blocks returned by `lookup`, removing the
+	/// sent blocks from the wantlist. Missing blocks are kept, and DontHave is sent for them only
+	/// once.
+	///
+	/// At most `lookups` entries are looked up, and the budget is decreased accordingly.
+	fn next_response(
+		&mut self,
+		lookups: &mut usize,
+		lookup: impl Fn(&Cid) -> Option<Vec<u8>>,
+	) -> Option<BitswapMessage> {
+		let mut response = BitswapMessage {
+			wantlist: None,
+			blocks: Default::default(),
+			payload: Default::default(),
+			block_presences: Default::default(),
+			pending_bytes: 0,
+		};
+		let mut size = 0;
+		// Entries that are not looked up are answered on the next turn.
+		for (cid, entry) in self.pending_wants() {
+			if *lookups == 0 {
+				break
+			}
+			*lookups -= 1;
+			match lookup(&cid) {
 				Some(transaction) => {
-					trace!(target: LOG_TARGET, "Found CID {:?}, hash {:?}", cid, hash);
-					if entry.want_type == WantType::Block as i32 {
+					trace!(target: LOG_TARGET, "Found CID {:?}", cid);
+					if entry.want_block {
 						let prefix = Prefix {
 							version: cid.version(),
 							codec: cid.codec(),
 							mh_type: cid.hash().code(),
 							mh_len: cid.hash().size(),
 						};
+						size += transaction.len();
 						response
 							.payload
 							.push(MessageBlock { prefix: prefix.to_bytes(), data: transaction });
@@ -280,33 +547,424 @@ impl<B: BlockT> NetworkBehaviour for Bitswap<B> {
 							cid: cid.to_bytes(),
 						});
 					}
+					self.wantlist.remove(&cid);
 				},
 				None => {
-					trace!(target: LOG_TARGET, "Missing CID {:?}, hash {:?}", cid, hash);
+					trace!(target: LOG_TARGET, "Missing CID {:?}", cid);
 					if entry.send_dont_have {
 						response.block_presences.push(BlockPresence {
 							r#type: BlockPresenceType::DontHave as i32,
 							cid: cid.to_bytes(),
 						});
 					}
+					if let Some(entry) = self.wantlist.get_mut(&cid) {
+						entry.missing = true;
+						entry.send_dont_have = false;
+					}
 				},
 			}
+			if size >= MAX_RESPONSE_BYTES {
+				break
+			}
+		}
+		if response.payload.is_empty() && response.block_presences.is_empty() {
+			return None
+		}
+		Some(response)
+	}
+
+	/// Number of messages sent to the peer that have not been delivered yet.
+	fn in_flight(&self) -> usize {
+		self.in_flight.values().sum()
+	}
+
+	/// Record a message sent to the peer on `connection`.
+	fn message_sent(&mut self, connection: ConnectionId) {
+		*self.in_flight.entry(connection).or_default() += 1;
+	}
+
+	/// Record the delivery of a message sent on `connection`.
+	fn message_delivered(&mut self, connection: ConnectionId) {
+		if let Some(in_flight) = self.in_flight.get_mut(&connection) {
+			*in_flight = in_flight.saturating_sub(1);
+		}
+	}
+
+	/// Forget the messages sent on `connection`, which was closed. They are lost and will never
+	/// be acknowledged.
+	fn connection_closed(&mut self, connection: ConnectionId) {
+		self.in_flight.remove(&connection);
+	}
+}
+
+struct Metrics {
+	blocks_sent: Counter<U64>,
+	bytes_received: Counter<U64>,
+	bytes_sent: Counter<U64>,
+	wantlist_entries: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(r: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			blocks_sent: register(
+				Counter::new(
+					"substrate_sub_libp2p_bitswap_blocks_sent_total",
+					"Total number of blocks sent over bitswap",
+				)?,
+				r,
+			)?,
+			bytes_received: register(
+				Counter::new(
+					"substrate_sub_libp2p_bitswap_bytes_received_total",
+					"Total number of block bytes received over bitswap",
+				)?,
+				r,
+			)?,
+			bytes_sent: register(
+				Counter::new(
+					"substrate_sub_libp2p_bitswap_bytes_sent_total",
+					"Total number of block bytes sent over bitswap",
+				)?,
+				r,
+			)?,
+			wantlist_entries: register(
+				Gauge::new(
+					"substrate_sub_libp2p_bitswap_wantlist_entries",
+					"Number of entries in the wantlists of remote peers",
+				)?,
+				r,
+			)?,
+		})
+	}
+}
+
+/// Build a bitswap message with a single wantlist entry for `cid`.
+fn want_message(cid: &Cid, cancel: bool) -> BitswapMessage {
+	BitswapMessage {
+		wantlist: Some(Wantlist {
+			entries: vec![WantlistEntry {
+				block: cid.to_bytes(),
+				priority: 1,
+				cancel,
+				want_type: WantType::Block as i32,
+				send_dont_have: true,
+			}],
+			full: false,
+		}),
+		blocks: Default::default(),
+		payload: Default::default(),
+		block_presences: Default::default(),
+		pending_bytes: 0,
+	}
+}
+
+/// Network behaviour that handles sending and receiving IPFS blocks.
+pub struct Bitswap<B> {
+	client: Arc<dyn Client<B>>,
+	/// Answer wantlists of remote peers.
+	serve: bool,
+	ready_blocks: VecDeque<(PeerId, BitswapMessage)>,
+	/// Decision engine state of remote peers.
+	ledgers: HashMap<PeerId, Ledger>,
+	/// Peers with a non-empty wantlist, in the order they are served.
+	wanting_peers: VecDeque<PeerId>,
+	/// Connected peers known to support bitswap.
+	peers: HashSet<PeerId>,
+	/// Open connections of connected peers.
+	connections: HashMap<PeerId, Vec<ConnectionId>>,
+	/// Outbound requests in progress.
+	requests: Requests,
+	/// Stream that fires when outbound request timeouts must be checked.
+	timeout_check: Pin<Box<dyn Stream<Item = ()> + Send>>,
+	/// Stream that fires when missing blocks wanted by remote peers must be looked up again.
+	missing_blocks_check: Pin<Box<dyn Stream<Item = ()> + Send>>,
+	/// Prometheus metrics.
+	metrics: Option<Metrics>,
+}
+
+impl<B: BlockT> Bitswap<B> {
+	/// Create a new instance of the bitswap protocol handler.
+	///
+	/// If `serve` is `false`, wantlists of remote peers are ignored and the behaviour may only be
+	/// used to request blocks.
+	pub fn new(
+		client: Arc<dyn Client<B>>,
+		serve: bool,
+		metrics_registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			client,
+			serve,
+			ready_blocks: Default::default(),
+			ledgers: Default::default(),
+			wanting_peers: Default::default(),
+			peers: Default::default(),
+			connections: Default::default(),
+			requests: Default::default(),
+			timeout_check: Box::pin(interval(REQUEST_TIMEOUT_CHECK_INTERVAL)),
+			missing_blocks_check: Box::pin(interval(MISSING_BLOCKS_CHECK_INTERVAL)),
+			metrics: metrics_registry.map(Metrics::register).transpose()?,
+		})
+	}
+
+	/// Start requesting the block with the given CID from connected peers.
+	///
+	/// The block data, once verified against the CID, is sent on `pending_response`.
+	pub fn request(
+		&mut self,
+		cid: Cid,
+		timeout: Duration,
+		pending_response: oneshot::Sender<Result<Vec<u8>, BitswapRequestError>>,
+	) {
+		if Code::try_from(cid.hash().code()).is_err() {
+			let _ = pending_response.send(Err(BitswapRequestError::UnsupportedCid));
+			return
+		}
+		self.requests.add(cid, Instant::now() + timeout, pending_response);
+	}
+
+	/// Ledgers of the connected peers, for debugging.
+	pub fn ledgers(&self) -> impl Iterator<Item = (&PeerId, BitswapLedger)> {
+		self.ledgers.iter().map(|(peer, ledger)| {
+			let ledger = BitswapLedger {
+				bytes_sent: ledger.bytes_sent,
+				bytes_received: ledger.bytes_received,
+				wantlist_entries: ledger.wantlist.len(),
+			};
+			(peer, ledger)
+		})
+	}
+
+	/// Get indexed transaction data or a block of its UnixFS DAG referenced by the CID.
+	fn transaction(&self, cid: &Cid) -> Result<Option<Vec<u8>>, sp_blockchain::Error> {
+		self.client.indexed_content(cid.hash().code(), cid.hash().digest())
+	}
+
+	/// Notify the behaviour about the protocols supported by a peer.
+	pub fn on_peer_identified(&mut self, peer: &PeerId, protocols: &[String]) {
+		if protocols.iter().any(|p| p.as_bytes() == PROTOCOL_NAME) {
+			self.peers.insert(*peer);
+		}
+	}
+
+	/// Handle blocks and block presences sent by a peer in response to our wantlist.
+	fn on_response(&mut self, peer: &PeerId, message: &BitswapMessage) {
+		let received = message.payload.iter().map(|block| block.data.len() as u64).sum::<u64>();
+		self.ledgers.entry(*peer).or_default().bytes_received += received;
+		if let Some(metrics) = &self.metrics {
+			metrics.bytes_received.inc_by(received);
+		}
+		for block in &message.payload {
+			let cid = match Prefix::from_bytes(&block.prefix).and_then(|p| p.to_cid(&block.data)) {
+				Ok(cid) => cid,
+				Err(e) => {
+					debug!(target: LOG_TARGET, "Bad block prefix from {}: {:?}", peer, e);
+					continue
+				},
+			};
+			self.requests.on_block(peer, &cid, &block.data);
+		}
+		for presence in &message.block_presences {
+			if presence.r#type != BlockPresenceType::DontHave as i32 {
+				continue
+			}
+			let cid = match cid::Cid::read_bytes(presence.cid.as_slice()) {
+				Ok(cid) => cid,
+				Err(_) => continue,
+			};
+			self.requests.on_dont_have(peer, &cid);
+		}
+	}
+
+	/// Apply a wantlist received from a peer.
+	fn on_wantlist(&mut self, peer: PeerId, wantlist: Wantlist) {
+		let ledger = self.ledgers.entry(peer).or_default();
+		ledger.update_wantlist(&peer, wantlist);
+		if ledger.has_pending_wants() && !self.wanting_peers.contains(&peer) {
+			self.wanting_peers.push_back(peer);
+		}
+	}
+
+	/// Look up the missing blocks wanted by remote peers again, as they may have been imported
+	/// since.
+	fn check_missing_blocks(&mut self) {
+		for (peer, ledger) in self.ledgers.iter_mut() {
+			if ledger.check_missing() && !self.wanting_peers.contains(peer) {
+				self.wanting_peers.push_back(*peer);
+			}
+		}
+	}
+
+	/// Serve wantlists of remote peers, one turn per peer.
+	///
+	/// Returns `false` if the lookup budget ran out before every peer got its turn.
+	fn serve_wantlists(&mut self) -> bool {
+		let mut in_flight = self.ledgers.values().map(|ledger| ledger.in_flight()).sum::<usize>();
+		let mut lookups = MAX_LOOKUPS_PER_POLL;
+		for _ in 0..self.wanting_peers.len() {
+			if in_flight >= MAX_RESPONSE_QUEUE {
+				break
+			}
+			if lookups == 0 {
+				return false
+			}
+			let peer = match self.wanting_peers.pop_front() {
+				Some(peer) => peer,
+				None => break,
+			};
+			let mut ledger = match self.ledgers.remove(&peer) {
+				Some(ledger) => ledger,
+				None => continue,
+			};
+			if ledger.in_flight() < MAX_PEER_RESPONSE_QUEUE {
+				let response = ledger.next_response(&mut lookups, |cid| {
+					self.transaction(cid).unwrap_or_else(|e| {
+						error!(target: LOG_TARGET, "Error retrieving transaction {}: {}", cid, e);
+						None
+					})
+				});
+				if let Some(response) = response {
+					let sent = response.payload.iter().map(|b| b.data.len() as u64).sum::<u64>();
+					ledger.bytes_sent += sent;
+					if let Some(metrics) = &self.metrics {
+						metrics.bytes_sent.inc_by(sent);
+						metrics.blocks_sent.inc_by(response.payload.len() as u64);
+					}
+					trace!(target: LOG_TARGET, "Response: {:?}", response);
+					self.ready_blocks.push_back((peer, response));
+					in_flight += 1;
+				}
+			}
+			if ledger.has_pending_wants() {
+				self.wanting_peers.push_back(peer);
+			}
+			self.ledgers.insert(peer, ledger);
+		}
+		if let Some(metrics) = &self.metrics {
+			let entries = self.ledgers.values().map(|ledger| ledger.wantlist.len()).sum::<usize>();
+			metrics.wantlist_entries.set(entries as u64);
+		}
+		true
+	}
+
+	/// Send `message` to `peer_id` on one of its connections, and count it as in flight there.
+	fn send(
+		&mut self,
+		peer_id: PeerId,
+		message: BitswapMessage,
+	) -> NetworkBehaviourAction<void::Void, <Self as NetworkBehaviour>::ProtocolsHandler> {
+		let handler = match self.connections.get(&peer_id).and_then(|c| c.first()) {
+			Some(connection) => {
+				self.ledgers.entry(peer_id).or_default().message_sent(*connection);
+				NotifyHandler::One(*connection)
+			},
+			None => NotifyHandler::Any,
+		};
+		NetworkBehaviourAction::NotifyHandler { peer_id, handler, event: message }
+	}
+}
+
+impl<B: BlockT> NetworkBehaviour for Bitswap<B> {
+	type ProtocolsHandler = OneShotHandler<BitswapConfig, BitswapMessage, HandlerEvent>;
+	type OutEvent = void::Void;
+
+	fn new_handler(&mut self) -> Self::ProtocolsHandler {
+		Default::default()
+	}
+
+	fn addresses_of_peer(&mut self, _peer: &PeerId) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn inject_connected(&mut self, _peer: &PeerId) {}
+
+	fn inject_connection_established(
+		&mut self,
+		peer: &PeerId,
+		connection: &ConnectionId,
+		_: &ConnectedPoint,
+		_: Option<&Vec<Multiaddr>>,
+	) {
+		self.connections.entry(*peer).or_default().push(*connection);
+	}
+
+	fn inject_disconnected(&mut self, peer: &PeerId) {
+		self.peers.remove(peer);
+		self.connections.remove(peer);
+		if let Some(ledger) = self.ledgers.remove(peer) {
+			debug!(
+				target: LOG_TARGET,
+				"Disconnected from {}: sent {} bytes, received {} bytes",
+				peer,
+				ledger.bytes_sent,
+				ledger.bytes_received,
+			);
+		}
+		self.wanting_peers.retain(|p| p != peer);
+		self.requests.peer_disconnected(peer);
+	}
+
+	fn inject_connection_closed(
+		&mut self,
+		peer: &PeerId,
+		connection: &ConnectionId,
+		_: &ConnectedPoint,
+		_: <Self::ProtocolsHandler as IntoProtocolsHandler>::Handler,
+	) {
+		if let Some(connections) = self.connections.get_mut(peer) {
+			connections.retain(|c| c != connection);
+		}
+		if let Some(ledger) = self.ledgers.get_mut(peer) {
+			ledger.connection_closed(*connection);
+		}
+	}
+
+	fn inject_event(&mut self, peer: PeerId, connection: ConnectionId, message: HandlerEvent) {
+		let request = match message {
+			HandlerEvent::ResponseSent => {
+				if let Some(ledger) = self.ledgers.get_mut(&peer) {
+					ledger.message_delivered(connection);
+				}
+				return
+			},
+			HandlerEvent::Request(msg) => msg,
+		};
+		trace!(target: LOG_TARGET, "Received request: {:?} from {}", request, peer);
+		self.peers.insert(peer);
+		if !request.payload.is_empty() || !request.block_presences.is_empty() {
+			self.on_response(&peer, &request);
+		}
+		match request.wantlist {
+			Some(wantlist) if self.serve => self.on_wantlist(peer, wantlist),
+			Some(_) => trace!(target: LOG_TARGET, "Ignored request: not serving blocks"),
+			None if request.payload.is_empty() && request.block_presences.is_empty() =>
+				debug!(target: LOG_TARGET, "Unexpected bitswap message from {}", peer),
+			None => {},
 		}
-		trace!(target: LOG_TARGET, "Response: {:?}", response);
-		self.ready_blocks.push_back((peer, response));
 	}
 
 	fn poll(
 		&mut self,
-		_ctx: &mut Context,
+		cx: &mut Context,
 		_: &mut impl PollParameters,
 	) -> Poll<NetworkBehaviourAction<Self::OutEvent, Self::ProtocolsHandler>> {
+		while let Poll::Ready(Some(())) = self.missing_blocks_check.poll_next_unpin(cx) {
+			self.check_missing_blocks();
+		}
+		if self.ready_blocks.is_empty() && !self.serve_wantlists() {
+			// Continue serving on the next poll, after other tasks had a chance to run.
+			cx.waker().wake_by_ref();
+		}
 		if let Some((peer_id, message)) = self.ready_blocks.pop_front() {
-			return Poll::Ready(NetworkBehaviourAction::NotifyHandler {
-				peer_id,
-				handler: NotifyHandler::Any,
-				event: message,
-			})
+			return Poll::Ready(self.send(peer_id, message))
+		}
+		while let Poll::Ready(Some(())) = self.timeout_check.poll_next_unpin(cx) {
+			self.requests.check_timeouts(Instant::now());
+		}
+		self.requests.schedule(&self.peers, Instant::now());
+		if let Some((peer_id, message)) = self.requests.ready_wants.pop_front() {
+			return Poll::Ready(self.send(peer_id, message))
 		}
 		Poll::Pending
 	}
@@ -331,6 +989,14 @@ pub enum BitswapError {
 	#[error(transparent)]
 	BadCid(#[from] cid::Error),
 
+	/// Error parsing a CID prefix.
+	#[error(transparent)]
+	BadPrefix(#[from] varint_decode::Error),
+
+	/// Unsupported multihash.
+	#[error(transparent)]
+	Multihash(#[from] cid::multihash::Error),
+
 	/// Packet read error.
 	#[error(transparent)]
 	Read(#[from] io::Error),
@@ -339,3 +1005,263 @@ pub enum BitswapError {
 	#[error("Failed to send response.")]
 	SendResponse,
 }
+
+/// Outbound bitswap request error.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BitswapRequestError {
+	/// Bitswap is not enabled on this node.
+	#[error("Bitswap is not enabled.")]
+	Disabled,
+
+	/// The multihash of the CID is not supported.
+	#[error("Unsupported CID.")]
+	UnsupportedCid,
+
+	/// Too many requests are in progress.
+	#[error("Too many pending requests.")]
+	TooManyRequests,
+
+	/// None of the queried peers has the block.
+	#[error("Block not found.")]
+	NotFound,
+
+	/// No peer delivered the block in time.
+	#[error("Request timed out.")]
+	Timeout,
+
+	/// The network worker has shut down.
+	#[error("Network worker is not running.")]
+	Terminated,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn prefix_roundtrip() {
+		let prefix = Prefix { version: Version::V1, codec: 0x55, mh_type: 0xb220, mh_len: 32 };
+		assert_eq!(Prefix::from_bytes(&prefix.to_bytes()).unwrap(), prefix);
+	}
+
+	#[test]
+	fn prefix_builds_matching_cid() {
+		let data = b"hello world".to_vec();
+		let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(&data));
+		let prefix = Prefix {
+			version: cid.version(),
+			codec: cid.codec(),
+			mh_type: cid.hash().code(),
+			mh_len: cid.hash().size(),
+		};
+		assert_eq!(prefix.to_cid(&data).unwrap(), cid);
+		assert_ne!(prefix.to_cid(b"hello").unwrap(), cid);
+	}
+
+	#[test]
+	fn prefix_rejects_unknown_multihash() {
+		let prefix = Prefix { version: Version::V1, codec: 0x55, mh_type: 0x4242, mh_len: 32 };
+		assert!(prefix.to_cid(b"data").is_err());
+	}
+
+	fn cid(data: &[u8]) -> Cid {
+		Cid::new_v1(0x55, Code::Blake2b256.digest(data))
+	}
+
+	fn wantlist(entries: Vec<(&Cid, i32, bool)>, full: bool) -> Wantlist {
+		Wantlist {
+			entries: entries
+				.into_iter()
+				.map(|(cid, priority, cancel)| WantlistEntry {
+					block: cid.to_bytes(),
+					priority,
+					cancel,
+					want_type: WantType::Block as i32,
+					send_dont_have: false,
+				})
+				.collect(),
+			full,
+		}
+	}
+
+	#[test]
+	fn wantlist_persists_until_cancelled() {
+		let peer = PeerId::random();
+		let (a, b) = (cid(b"a"), cid(b"b"));
+		let mut ledger = Ledger::default();
+		ledger.update_wantlist(&peer, wantlist(vec![(&a, 1, false)], false));
+		ledger.update_wantlist(&peer, wantlist(vec![(&b, 2, false)], false));
+		assert_eq!(ledger.wantlist.len(), 2);
+
+		ledger.update_wantlist(&peer, wantlist(vec![(&a, 0, true)], false));
+		assert_eq!(ledger.wantlist.keys().collect::<Vec<_>>(), vec![&b]);
+	}
+
+	#[test]
+	fn full_wantlist_replaces_previous() {
+		let peer = PeerId::random();
+		let (a, b) = (cid(b"a"), cid(b"b"));
+		let mut ledger = Ledger::default();
+		ledger.update_wantlist(&peer, wantlist(vec![(&a, 1, false)], false));
+		ledger.update_wantlist(&peer, wantlist(vec![(&b, 1, false)], true));
+		assert_eq!(ledger.wantlist.keys().collect::<Vec<_>>(), vec![&b]);
+	}
+
+	#[test]
+	fn wantlist_size_is_limited() {
+		let peer = PeerId::random();
+		let cids = (0..MAX_WANTED_BLOCKS + 1)
+			.map(|i| cid(&(i as u64).to_le_bytes()))
+			.collect::<Vec<_>>();
+		let mut ledger = Ledger::default();
+		ledger
+			.update_wantlist(&peer, wantlist(cids.iter().map(|c| (c, 1, false)).collect(), false));
+		assert_eq!(ledger.wantlist.len(), MAX_WANTED_BLOCKS);
+		assert!(!ledger.wantlist.contains_key(&cids[MAX_WANTED_BLOCKS]));
+	}
+
+	#[test]
+	fn wants_are_served_by_priority() {
+		let peer = PeerId::random();
+		let (a, b, c) = (cid(b"a"), cid(b"b"), cid(b"c"));
+		let mut ledger = Ledger::default();
+		ledger.update_wantlist(
+			&peer,
+			wantlist(vec![(&a, 1, false), (&b, 3, false), (&c, 2, false)], false),
+		);
+		let wants = ledger.pending_wants().into_iter().map(|(cid, _)| cid).collect::<Vec<_>>();
+		assert_eq!(wants, vec![b, c, a]);
+	}
+
+	#[test]
+	fn missing_blocks_stay_wanted() {
+		let peer = PeerId::random();
+		let (a, b) = (cid(b"a"), cid(b"b"));
+		let mut ledger = Ledger::default();
+		let mut wants = wantlist(vec![(&a, 1, false), (&b, 1, false)], false);
+		wants.entries.iter_mut().for_each(|entry| entry.send_dont_have = true);
+		ledger.update_wantlist(&peer, wants);
+		let lookup = |cid: &Cid| (*cid == b).then(|| b"b".to_vec());
+		let mut lookups = MAX_LOOKUPS_PER_POLL;
+
+		let response = ledger.next_response(&mut lookups, lookup).unwrap();
+		assert_eq!(response.payload.iter().map(|b| &b.data[..]).collect::<Vec<_>>(), vec![b"b"]);
+		let dont_have =
+			BlockPresence { r#type: BlockPresenceType::DontHave as i32, cid: a.to_bytes() };
+		assert_eq!(response.block_presences, vec![dont_have]);
+		// The missing block stays wanted, but isn't looked up again until the next check.
+		assert_eq!(ledger.wantlist.keys().collect::<Vec<_>>(), vec![&a]);
+		assert!(!ledger.has_pending_wants());
+		assert!(ledger.next_response(&mut lookups, lookup).is_none());
+
+		// DontHave is sent only once.
+		assert!(ledger.check_missing());
+		assert!(ledger.next_response(&mut lookups, lookup).is_none());
+		assert_eq!(ledger.wantlist.keys().collect::<Vec<_>>(), vec![&a]);
+
+		// The block is sent once it's available.
+		assert!(ledger.check_missing());
+		let response = ledger.next_response(&mut lookups, |_| Some(b"a".to_vec())).unwrap();
+		assert_eq!(response.payload.iter().map(|b| &b.data[..]).collect::<Vec<_>>(), vec![b"a"]);
+		assert!(ledger.wantlist.is_empty());
+	}
+
+	#[test]
+	fn in_flight_messages_are_tracked_per_connection() {
+		let (first, second) = (ConnectionId::new(1), ConnectionId::new(2));
+		let mut ledger = Ledger::default();
+		ledger.message_sent(first);
+		ledger.message_sent(first);
+		ledger.message_sent(second);
+		ledger.message_delivered(first);
+		assert_eq!(ledger.in_flight(), 2);
+
+		// Messages sent on the connection that remains open are still in flight.
+		ledger.connection_closed(first);
+		assert_eq!(ledger.in_flight(), 1);
+		ledger.message_delivered(second);
+		assert_eq!(ledger.in_flight(), 0);
+	}
+
+	fn wanted(requests: &mut Requests) -> Vec<(PeerId, Cid, bool)> {
+		requests
+			.ready_wants
+			.drain(..)
+			.map(|(peer, message)| {
+				let entry = &message.wantlist.unwrap().entries[0];
+				(peer, Cid::read_bytes(entry.block.as_slice()).unwrap(), entry.cancel)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn request_is_retried_on_another_peer() {
+		let now = Instant::now();
+		let (a, b) = (PeerId::random(), PeerId::random());
+		let block = cid(b"block");
+		let mut requests = Requests::default();
+		let (tx, mut rx) = oneshot::channel();
+		requests.add(block, now + Duration::from_secs(60), tx);
+
+		requests.schedule(&vec![a].into_iter().collect(), now);
+		assert_eq!(wanted(&mut requests), vec![(a, block, false)]);
+
+		// `a` doesn't have the block, `b` is asked next.
+		requests.on_dont_have(&a, &block);
+		requests.schedule(&vec![a, b].into_iter().collect(), now);
+		assert_eq!(wanted(&mut requests), vec![(b, block, false)]);
+
+		// `b` doesn't answer in time, and no other peer is left.
+		requests.check_timeouts(now + PEER_REQUEST_TIMEOUT);
+		assert_eq!(wanted(&mut requests), vec![(b, block, true)]);
+		requests.schedule(&vec![a, b].into_iter().collect(), now + PEER_REQUEST_TIMEOUT);
+		assert_eq!(rx.try_recv().unwrap(), Some(Err(BitswapRequestError::NotFound)));
+		assert!(requests.pending.is_empty());
+	}
+
+	#[test]
+	fn request_times_out() {
+		let now = Instant::now();
+		let peer = PeerId::random();
+		let block = cid(b"block");
+		let mut requests = Requests::default();
+		let (tx, mut rx) = oneshot::channel();
+		requests.add(block, now + Duration::from_secs(5), tx);
+		requests.schedule(&vec![peer].into_iter().collect(), now);
+		wanted(&mut requests);
+
+		requests.check_timeouts(now + Duration::from_secs(4));
+		assert_eq!(rx.try_recv().unwrap(), None);
+
+		requests.check_timeouts(now + Duration::from_secs(5));
+		assert_eq!(rx.try_recv().unwrap(), Some(Err(BitswapRequestError::Timeout)));
+		assert_eq!(wanted(&mut requests), vec![(peer, block, true)]);
+		assert!(requests.pending.is_empty());
+	}
+
+	#[test]
+	fn duplicate_requests_are_merged_and_keep_their_timeout() {
+		let now = Instant::now();
+		let peer = PeerId::random();
+		let block = cid(b"block");
+		let mut requests = Requests::default();
+		let (first_tx, mut first_rx) = oneshot::channel();
+		let (second_tx, mut second_rx) = oneshot::channel();
+		let (third_tx, mut third_rx) = oneshot::channel();
+		requests.add(block, now + Duration::from_secs(2), first_tx);
+		requests.add(block, now + Duration::from_secs(8), second_tx);
+		requests.add(block, now + Duration::from_secs(8), third_tx);
+		requests.schedule(&vec![peer].into_iter().collect(), now);
+		assert_eq!(wanted(&mut requests), vec![(peer, block, false)]);
+
+		// The first request fails on its own timeout, without cancelling the others.
+		requests.check_timeouts(now + Duration::from_secs(2));
+		assert_eq!(first_rx.try_recv().unwrap(), Some(Err(BitswapRequestError::Timeout)));
+		assert!(wanted(&mut requests).is_empty());
+
+		requests.on_block(&peer, &block, b"block");
+		assert_eq!(second_rx.try_recv().unwrap(), Some(Ok(b"block".to_vec())));
+		assert_eq!(third_rx.try_recv().unwrap(), Some(Ok(b"block".to_vec())));
+		assert!(requests.pending.is_empty());
+	}
+}
@@ -28,6 +28,7 @@ pub use crate::{
 	},
 	warp_request_handler::WarpSyncProvider,
 };
+pub use ip_network::IpNetwork;
 pub use libp2p::{build_multiaddr, core::PublicKey, identity};
 
 // Note: this re-export shouldn't be part of the public API of the crate and will be removed in
@@ -429,6 +430,8 @@ pub struct NetworkConfiguration {
 	pub node_name: String,
 	/// Configuration for the transport layer.
 	pub transport: TransportConfig,
+	/// Restrictions on the IP addresses of the nodes we are connected to.
+	pub connection_filter: ConnectionFilterConfig,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
 	/// Initial syncing mode.
@@ -447,6 +450,8 @@ pub struct NetworkConfiguration {
 	pub kademlia_disjoint_query_paths: bool,
 	/// Enable serving block data over IPFS bitswap.
 	pub ipfs_server: bool,
+	/// Enable fetching block data from peers over IPFS bitswap.
+	pub ipfs_client: bool,
 
 	/// Size of Yamux receive window of all substreams. `None` for the default (256kiB).
 	/// Any value less than 256kiB is invalid.
@@ -492,6 +497,7 @@ impl NetworkConfiguration {
 			client_version: client_version.into(),
 			node_name: node_name.into(),
 			transport: TransportConfig::Normal { enable_mdns: false, allow_private_ipv4: true },
+			connection_filter: ConnectionFilterConfig::default(),
 			max_parallel_downloads: 5,
 			sync_mode: SyncMode::Full,
 			enable_dht_random_walk: true,
@@ -499,6 +505,7 @@ impl NetworkConfiguration {
 			kademlia_disjoint_query_paths: false,
 			yamux_window_size: None,
 			ipfs_server: false,
+			ipfs_client: false,
 		}
 	}
 
@@ -639,6 +646,39 @@ pub enum TransportConfig {
 	MemoryOnly,
 }
 
+/// Restrictions on the IP addresses of the nodes we are connected to.
+///
+/// The restrictions are enforced by the TCP transport: inbound connections that don't satisfy
+/// them are closed as soon as they are accepted, before any handshake, and addresses that aren't
+/// allowed are not dialed, including the ones a DNS name resolves to. Connections over other
+/// transports, such as the in-memory one, are not filtered.
+#[derive(Clone, Debug)]
+pub struct ConnectionFilterConfig {
+	/// If not empty, only connections with addresses within one of these networks are allowed.
+	pub allowed: Vec<IpNetwork>,
+	/// Connections with addresses within one of these networks are refused, even if they are
+	/// within an allowed network.
+	pub denied: Vec<IpNetwork>,
+	/// Maximum number of inbound connections from the same subnet. `None` for no limit.
+	pub max_inbound_per_subnet: Option<u32>,
+	/// Prefix length of the IPv4 subnets inbound connections are counted by.
+	pub ipv4_subnet_prefix: u8,
+	/// Prefix length of the IPv6 subnets inbound connections are counted by.
+	pub ipv6_subnet_prefix: u8,
+}
+
+impl Default for ConnectionFilterConfig {
+	fn default() -> Self {
+		Self {
+			allowed: Vec::new(),
+			denied: Vec::new(),
+			max_inbound_per_subnet: None,
+			ipv4_subnet_prefix: 24,
+			ipv6_subnet_prefix: 64,
+		}
+	}
+}
+
 /// The policy for connections to non-reserved peers.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NonReservedPeerMode {
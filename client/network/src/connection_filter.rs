@@ -0,0 +1,400 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2019-2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Filtering of the connections based on the IP address of the remote.
+//!
+//! [`FilterTransport`] wraps the TCP transport, below the DNS resolution and the handshakes, so
+//! that inbound connections are dropped as soon as they are accepted, and dials of addresses
+//! that aren't allowed, including the ones a DNS name resolves to, fail before anything is sent.
+
+use crate::config::ConnectionFilterConfig;
+
+use futures::{
+	io::{AsyncRead, AsyncWrite},
+	prelude::*,
+	stream::BoxStream,
+};
+use ip_network::IpNetwork;
+use libp2p::{
+	core::{
+		multiaddr::Protocol,
+		transport::{ListenerEvent, TransportError},
+	},
+	Multiaddr, Transport,
+};
+use log::debug;
+use parking_lot::Mutex;
+use std::{
+	collections::HashMap,
+	io,
+	net::IpAddr,
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+};
+
+/// Reason a connection has been rejected for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+	/// The address of the remote is denied, or not allowed.
+	Denied,
+	/// There are too many inbound connections from the subnet of the remote already.
+	SubnetLimit,
+}
+
+impl RejectReason {
+	/// Label of the reason in the metrics.
+	fn label(&self) -> &'static str {
+		match self {
+			Self::Denied => "denied",
+			Self::SubnetLimit => "subnet-limit",
+		}
+	}
+}
+
+/// Enforces the restrictions of a [`ConnectionFilterConfig`].
+///
+/// Clones share the counts of connections.
+#[derive(Clone)]
+pub struct ConnectionFilter {
+	inner: Arc<Inner>,
+}
+
+struct Inner {
+	/// Restrictions to enforce.
+	config: ConnectionFilterConfig,
+	/// Number of inbound connections by subnet, for the subnets with a limit.
+	inbound_per_subnet: Mutex<HashMap<IpNetwork, u32>>,
+	/// Number of connections rejected, by direction and reason.
+	rejected: Mutex<HashMap<(&'static str, RejectReason), u64>>,
+}
+
+impl ConnectionFilter {
+	/// Builds a new `ConnectionFilter`.
+	pub fn new(config: ConnectionFilterConfig) -> Self {
+		Self {
+			inner: Arc::new(Inner {
+				config,
+				inbound_per_subnet: Default::default(),
+				rejected: Default::default(),
+			}),
+		}
+	}
+
+	/// Number of connections rejected so far, by direction (`"in"` or `"out"`) and reason.
+	pub fn rejected(&self) -> Vec<(&'static str, &'static str, u64)> {
+		self.inner
+			.rejected
+			.lock()
+			.iter()
+			.map(|((direction, reason), count)| (*direction, reason.label(), *count))
+			.collect()
+	}
+
+	/// Checks whether the inbound connection from `address` is allowed.
+	///
+	/// The connection is counted against the limit of its subnet until the returned guard is
+	/// dropped.
+	fn accept(&self, address: &Multiaddr) -> Result<Option<SubnetGuard>, RejectReason> {
+		let ip = match ip_of(address) {
+			Some(ip) => ip,
+			None => return Ok(None),
+		};
+		self.check_allowed(ip).map_err(|reason| self.reject(address, "in", reason))?;
+
+		let limit = match self.inner.config.max_inbound_per_subnet {
+			Some(limit) => limit,
+			None => return Ok(None),
+		};
+		let prefix = match ip {
+			IpAddr::V4(_) => self.inner.config.ipv4_subnet_prefix.min(32),
+			IpAddr::V6(_) => self.inner.config.ipv6_subnet_prefix.min(128),
+		};
+		let subnet =
+			IpNetwork::new_truncate(ip, prefix).expect("Prefix length is capped above; qed");
+		let mut inbound_per_subnet = self.inner.inbound_per_subnet.lock();
+		let count = inbound_per_subnet.entry(subnet).or_default();
+		if *count >= limit {
+			drop(inbound_per_subnet);
+			return Err(self.reject(address, "in", RejectReason::SubnetLimit))
+		}
+		*count += 1;
+		Ok(Some(SubnetGuard { filter: self.inner.clone(), subnet }))
+	}
+
+	/// Checks whether `address` may be dialed.
+	fn dial(&self, address: &Multiaddr) -> Result<(), RejectReason> {
+		match ip_of(address) {
+			Some(ip) =>
+				self.check_allowed(ip).map_err(|reason| self.reject(address, "out", reason)),
+			None => Ok(()),
+		}
+	}
+
+	fn check_allowed(&self, ip: IpAddr) -> Result<(), RejectReason> {
+		let config = &self.inner.config;
+		let allowed =
+			config.allowed.is_empty() || config.allowed.iter().any(|network| network.contains(ip));
+		if !allowed || config.denied.iter().any(|network| network.contains(ip)) {
+			return Err(RejectReason::Denied)
+		}
+		Ok(())
+	}
+
+	fn reject(
+		&self,
+		address: &Multiaddr,
+		direction: &'static str,
+		reason: RejectReason,
+	) -> RejectReason {
+		debug!(
+			target: "sub-libp2p",
+			"Rejecting {} connection with {}: {:?}",
+			direction, address, reason,
+		);
+		*self.inner.rejected.lock().entry((direction, reason)).or_default() += 1;
+		reason
+	}
+}
+
+/// Returns the IP address of `address`, if it starts with one.
+fn ip_of(address: &Multiaddr) -> Option<IpAddr> {
+	match address.iter().next()? {
+		Protocol::Ip4(ip) => Some(ip.into()),
+		Protocol::Ip6(ip) => Some(ip.into()),
+		_ => None,
+	}
+}
+
+/// Counts an inbound connection against the limit of its subnet until dropped.
+pub struct SubnetGuard {
+	filter: Arc<Inner>,
+	subnet: IpNetwork,
+}
+
+impl Drop for SubnetGuard {
+	fn drop(&mut self) {
+		let mut inbound_per_subnet = self.filter.inbound_per_subnet.lock();
+		if let Some(count) = inbound_per_subnet.get_mut(&self.subnet) {
+			*count -= 1;
+			if *count == 0 {
+				inbound_per_subnet.remove(&self.subnet);
+			}
+		}
+	}
+}
+
+/// Transport refusing the connections not satisfying the restrictions of a
+/// [`ConnectionFilter`].
+///
+/// Dials of addresses that aren't allowed fail with [`TransportError::MultiaddrNotSupported`].
+#[derive(Clone)]
+pub struct FilterTransport<T> {
+	inner: T,
+	filter: ConnectionFilter,
+}
+
+impl<T> FilterTransport<T> {
+	/// Wraps `inner`.
+	pub fn new(inner: T, filter: ConnectionFilter) -> Self {
+		Self { inner, filter }
+	}
+}
+
+impl<T> Transport for FilterTransport<T>
+where
+	T: Transport,
+	T::Listener: Send + 'static,
+	T::ListenerUpgrade: Send + 'static,
+	T::Error: Send + 'static,
+	T::Output: 'static,
+{
+	type Output = FilteredStream<T::Output>;
+	type Error = T::Error;
+	type Listener =
+		BoxStream<'static, Result<ListenerEvent<Self::ListenerUpgrade, Self::Error>, Self::Error>>;
+	type ListenerUpgrade = future::BoxFuture<'static, Result<Self::Output, Self::Error>>;
+	type Dial = future::MapOk<T::Dial, fn(T::Output) -> Self::Output>;
+
+	fn listen_on(self, addr: Multiaddr) -> Result<Self::Listener, TransportError<Self::Error>> {
+		let filter = self.filter;
+		let listener = self.inner.listen_on(addr)?.try_filter_map(move |event| {
+			let event = match event {
+				ListenerEvent::Upgrade { upgrade, local_addr, remote_addr } =>
+					match filter.accept(&remote_addr) {
+						Ok(guard) => Some(ListenerEvent::Upgrade {
+							upgrade: upgrade
+								.map_ok(move |inner| FilteredStream { inner, _guard: guard })
+								.boxed(),
+							local_addr,
+							remote_addr,
+						}),
+						// Dropping the upgrade closes the connection.
+						Err(_) => None,
+					},
+				ListenerEvent::NewAddress(addr) => Some(ListenerEvent::NewAddress(addr)),
+				ListenerEvent::AddressExpired(addr) => Some(ListenerEvent::AddressExpired(addr)),
+				ListenerEvent::Error(error) => Some(ListenerEvent::Error(error)),
+			};
+			future::ready(Ok(event))
+		});
+		Ok(listener.boxed())
+	}
+
+	fn dial(self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+		if self.filter.dial(&addr).is_err() {
+			return Err(TransportError::MultiaddrNotSupported(addr))
+		}
+		let outbound: fn(T::Output) -> Self::Output =
+			|inner| FilteredStream { inner, _guard: None };
+		Ok(self.inner.dial(addr)?.map_ok(outbound))
+	}
+
+	fn address_translation(&self, listen: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+		self.inner.address_translation(listen, observed)
+	}
+}
+
+/// Connection established through a [`FilterTransport`].
+#[pin_project::pin_project]
+pub struct FilteredStream<S> {
+	#[pin]
+	inner: S,
+	_guard: Option<SubnetGuard>,
+}
+
+impl<S: AsyncRead> AsyncRead for FilteredStream<S> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		self.project().inner.poll_read(cx, buf)
+	}
+}
+
+impl<S: AsyncWrite> AsyncWrite for FilteredStream<S> {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8],
+	) -> Poll<io::Result<usize>> {
+		self.project().inner.poll_write(cx, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.project().inner.poll_flush(cx)
+	}
+
+	fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.project().inner.poll_close(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use libp2p::tcp::TcpConfig;
+
+	fn address(address: &str) -> Multiaddr {
+		address.parse().unwrap()
+	}
+
+	#[test]
+	fn denied_and_not_allowed_addresses_are_rejected() {
+		let filter = ConnectionFilter::new(ConnectionFilterConfig {
+			allowed: vec!["10.0.0.0/8".parse().unwrap(), "2001:db8::/32".parse().unwrap()],
+			denied: vec!["10.1.0.0/16".parse().unwrap()],
+			..Default::default()
+		});
+
+		assert!(filter.accept(&address("/ip4/10.0.0.1/tcp/1")).is_ok());
+		assert!(filter.dial(&address("/ip6/2001:db8::1/tcp/1")).is_ok());
+		assert!(filter.dial(&address("/dns/example.com/tcp/1")).is_ok());
+		assert!(filter.rejected().is_empty());
+
+		assert_eq!(
+			filter.accept(&address("/ip4/10.1.0.1/tcp/1")).err(),
+			Some(RejectReason::Denied)
+		);
+		assert_eq!(filter.dial(&address("/ip4/192.168.0.1/tcp/1")), Err(RejectReason::Denied));
+		let mut rejected = filter.rejected();
+		rejected.sort();
+		assert_eq!(rejected, vec![("in", "denied", 1), ("out", "denied", 1)]);
+	}
+
+	#[test]
+	fn inbound_connections_are_limited_per_subnet() {
+		let filter = ConnectionFilter::new(ConnectionFilterConfig {
+			max_inbound_per_subnet: Some(2),
+			..Default::default()
+		});
+
+		let first = filter.accept(&address("/ip4/198.51.100.1/tcp/1")).unwrap();
+		let _second = filter.accept(&address("/ip4/198.51.100.2/tcp/1")).unwrap();
+		// Outbound connections and other subnets aren't affected.
+		assert!(filter.dial(&address("/ip4/198.51.100.3/tcp/1")).is_ok());
+		let _other = filter.accept(&address("/ip4/198.51.101.1/tcp/1")).unwrap();
+
+		assert_eq!(
+			filter.accept(&address("/ip4/198.51.100.4/tcp/1")).err(),
+			Some(RejectReason::SubnetLimit)
+		);
+		assert_eq!(filter.rejected(), vec![("in", "subnet-limit", 1)]);
+
+		// Closing a connection frees a slot.
+		drop(first);
+		assert!(filter.accept(&address("/ip4/198.51.100.4/tcp/1")).unwrap().is_some());
+	}
+
+	#[test]
+	fn transport_drops_denied_connections() {
+		let filter = ConnectionFilter::new(ConnectionFilterConfig {
+			denied: vec!["127.0.0.0/8".parse().unwrap()],
+			..Default::default()
+		});
+		let transport = FilterTransport::new(TcpConfig::new(), filter.clone());
+
+		assert!(matches!(
+			transport.clone().dial(address("/ip4/127.0.0.1/tcp/1")),
+			Err(TransportError::MultiaddrNotSupported(_))
+		));
+
+		let mut listener = transport.listen_on(address("/ip4/127.0.0.1/tcp/0")).unwrap();
+		async_std::task::block_on(async {
+			let listen_addr = match listener.next().await {
+				Some(Ok(ListenerEvent::NewAddress(addr))) => addr,
+				_ => panic!("Expected a listen address"),
+			};
+			let port = match listen_addr.iter().nth(1) {
+				Some(Protocol::Tcp(port)) => port,
+				_ => panic!("Expected a TCP address"),
+			};
+			let _connection = async_std::net::TcpStream::connect(("127.0.0.1", port)).await;
+
+			// The connection is dropped without being reported.
+			while filter.rejected().len() < 2 {
+				assert!(futures::poll!(listener.next()).is_pending());
+				async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+			}
+		});
+		let mut rejected = filter.rejected();
+		rejected.sort();
+		assert_eq!(rejected, vec![("in", "denied", 1), ("out", "denied", 1)]);
+	}
+}
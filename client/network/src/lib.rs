@@ -246,6 +246,7 @@
 
 mod behaviour;
 mod chain;
+mod connection_filter;
 mod discovery;
 mod peer_info;
 mod protocol;
@@ -278,7 +279,7 @@ pub use service::{
 	Signature, SigningError,
 };
 
-pub use sc_peerset::ReputationChange;
+pub use sc_peerset::{BannedPeer, ReputationChange};
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 /// The maximum allowed number of established connections per peer.
@@ -45,6 +45,9 @@ pub struct NetworkState {
 	pub connected_peers: HashMap<String, Peer>,
 	/// List of node that we know of but that we're not connected to.
 	pub not_connected_peers: HashMap<String, NotConnectedPeer>,
+	/// Bitswap ledgers of the connected peers that exchanged blocks with us or sent us a
+	/// wantlist, including peers that don't use the Substrate protocols.
+	pub bitswap_ledgers: HashMap<String, BitswapLedger>,
 	/// State of the peerset manager.
 	pub peerset: serde_json::Value,
 }
@@ -76,6 +79,17 @@ pub struct NotConnectedPeer {
 }
 
 /// Part of the `NetworkState` struct. Unstable.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitswapLedger {
+	/// Block bytes sent to the peer.
+	pub bytes_sent: u64,
+	/// Block bytes received from the peer.
+	pub bytes_received: u64,
+	/// Number of blocks in the wantlist of the peer.
+	pub wantlist_entries: usize,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum PeerEndpoint {
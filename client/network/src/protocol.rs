@@ -47,7 +47,9 @@ use message::{
 	BlockAnnounce, Message,
 };
 use notifications::{Notifications, NotificationsOut};
-use prometheus_endpoint::{register, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64};
+use prometheus_endpoint::{
+	register, Counter, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64,
+};
 use prost::Message as _;
 use sc_consensus::import_queue::{BlockImportError, BlockImportStatus, IncomingBlock, Origin};
 use sp_arithmetic::traits::SaturatedConversion;
@@ -126,6 +128,7 @@ struct Metrics {
 	queued_blocks: Gauge<U64>,
 	fork_targets: Gauge<U64>,
 	justifications: GaugeVec<U64>,
+	state_response_bytes: Counter<U64>,
 }
 
 impl Metrics {
@@ -154,6 +157,13 @@ impl Metrics {
 				)?;
 				register(g, r)?
 			},
+			state_response_bytes: {
+				let c = Counter::new(
+					"substrate_sync_state_response_bytes_total",
+					"Total size of the state responses received during state sync",
+				)?;
+				register(c, r)?
+			},
 		})
 	}
 }
@@ -378,7 +388,13 @@ impl<B: BlockT> Protocol<B> {
 				});
 			}
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
+				sets,
+				state_path: network_config
+					.net_config_path
+					.as_ref()
+					.map(|path| path.join(sc_peerset::persistence::STATE_FILE_NAME)),
+			})
 		};
 
 		let block_announces_protocol: Cow<'static, str> = Cow::from({
@@ -488,6 +504,11 @@ impl<B: BlockT> Protocol<B> {
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns the list of banned peers.
+	pub fn banned_peers(&mut self) -> Vec<sc_peerset::BannedPeer> {
+		self.behaviour.banned_peers()
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.peers.len()
@@ -1474,6 +1495,9 @@ impl<B: BlockT> NetworkBehaviour for Protocol<B> {
 										},
 									};
 
+								if let Some(metrics) = &self.metrics {
+									metrics.state_response_bytes.inc_by(resp.len() as u64);
+								}
 								finished_state_requests.push((*id, protobuf_response));
 							},
 							PeerRequest::WarpProof => {
@@ -1554,7 +1578,7 @@ impl<B: BlockT> NetworkBehaviour for Protocol<B> {
 			let event = prepare_block_request(&mut self.peers, id.clone(), request);
 			self.pending_messages.push_back(event);
 		}
-		if let Some((id, request)) = self.sync.state_request() {
+		for (id, request) in self.sync.state_requests() {
 			let event = prepare_state_request(&mut self.peers, id, request);
 			self.pending_messages.push_back(event);
 		}
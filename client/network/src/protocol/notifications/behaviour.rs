@@ -605,6 +605,11 @@ impl Notifications {
 		self.peerset.debug_info()
 	}
 
+	/// Returns the list of banned peers.
+	pub fn banned_peers(&mut self) -> Vec<sc_peerset::BannedPeer> {
+		self.peerset.banned_peers()
+	}
+
 	/// Function that is called when the peerset wants us to connect to a peer.
 	fn peerset_report_connect(&mut self, peer_id: PeerId, set_id: sc_peerset::SetId) {
 		// If `PeerId` is unknown to us, insert an entry, start dialing, and return early.
@@ -75,6 +75,7 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		});
 
 		let behaviour = CustomProtoWithAddr {
@@ -54,7 +54,7 @@ use sp_runtime::{
 	},
 	EncodedJustification, Justifications,
 };
-use state::StateSync;
+use state::{StateSync, StateSyncMode};
 use std::{
 	collections::{hash_map::Entry, HashMap, HashSet},
 	fmt,
@@ -996,50 +996,53 @@ impl<B: BlockT> ChainSync<B> {
 		Either::Right(iter)
 	}
 
-	/// Get a state request, if any.
-	pub fn state_request(&mut self) -> Option<(PeerId, StateRequest)> {
+	/// Get the state requests to send, if any.
+	///
+	/// The key ranges of the state are requested from different peers in parallel.
+	pub fn state_requests(&mut self) -> Vec<(PeerId, StateRequest)> {
+		let mut requests = Vec::new();
 		if self.allowed_requests.is_empty() {
-			return None
-		}
-		if (self.state_sync.is_some() || self.warp_sync.is_some()) &&
-			self.peers.iter().any(|(_, peer)| peer.state == PeerSyncState::DownloadingState)
-		{
-			// Only one pending state request is allowed.
-			return None
+			return requests
 		}
-		if let Some(sync) = &self.state_sync {
+		if let Some(sync) = &mut self.state_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
 
 			for (id, peer) in self.peers.iter_mut() {
 				if peer.state.is_available() && peer.common_number >= sync.target_block_num() {
+					let request = match sync.next_request(*id) {
+						Some(request) => request,
+						None => break,
+					};
 					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
 					trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
-					self.allowed_requests.clear();
-					return Some((*id, request))
+					requests.push((*id, request));
 				}
 			}
 		}
-		if let Some(sync) = &self.warp_sync {
+		if let Some(sync) = &mut self.warp_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
-			if let (Some(request), Some(target)) =
-				(sync.next_state_request(), sync.target_block_number())
-			{
+			if let Some(target) = sync.target_block_number() {
 				for (id, peer) in self.peers.iter_mut() {
 					if peer.state.is_available() && peer.best_number >= target {
+						let request = match sync.next_state_request(*id) {
+							Some(request) => request,
+							None => break,
+						};
 						trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
 						peer.state = PeerSyncState::DownloadingState;
-						self.allowed_requests.clear();
-						return Some((*id, request))
+						requests.push((*id, request));
 					}
 				}
 			}
 		}
-		None
+		if !requests.is_empty() {
+			self.allowed_requests.clear();
+		}
+		requests
 	}
 
 	/// Get a warp sync request, if any.
@@ -1328,7 +1331,7 @@ impl<B: BlockT> ChainSync<B> {
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(response)
+			sync.import(*who, response)
 		} else if let Some(sync) = &mut self.warp_sync {
 			debug!(
 				target: "sync",
@@ -1337,7 +1340,7 @@ impl<B: BlockT> ChainSync<B> {
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import_state(response)
+			sync.import_state(*who, response)
 		} else {
 			debug!(target: "sync", "Ignored obsolete state response from {}", who);
 			return Err(BadPeer(*who, rep::NOT_REQUESTED))
@@ -1366,6 +1369,7 @@ impl<B: BlockT> ChainSync<B> {
 				debug!(target: "sync", "Bad state data received from {}", who);
 				Err(BadPeer(*who, rep::BAD_BLOCK))
 			},
+			state::ImportResult::NotRequested => Err(BadPeer(*who, rep::NOT_REQUESTED)),
 		}
 	}
 
@@ -1577,6 +1581,7 @@ impl<B: BlockT> ChainSync<B> {
 							self.state_sync.as_ref().map_or(0, |s| s.progress().size / (1024 * 1024)),
 						);
 						self.state_sync = None;
+						state::clear_checkpoint(&*self.client);
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -1591,6 +1596,7 @@ impl<B: BlockT> ChainSync<B> {
 							self.warp_sync.as_ref().map_or(0, |s| s.progress().total_bytes / (1024 * 1024)),
 						);
 						self.warp_sync = None;
+						state::clear_checkpoint(&*self.client);
 						self.mode = SyncMode::Full;
 						output.extend(self.restart());
 					}
@@ -1643,6 +1649,10 @@ impl<B: BlockT> ChainSync<B> {
 				},
 				e @ Err(BlockImportError::UnknownParent) | e @ Err(BlockImportError::Other(_)) => {
 					warn!(target: "sync", "💔 Error importing block {:?}: {}", hash, e.unwrap_err());
+					if self.state_sync.is_some() || self.warp_sync.is_some() {
+						// The downloaded state may be the cause, don't resume from it.
+						state::clear_checkpoint(&*self.client);
+					}
 					self.state_sync = None;
 					self.warp_sync = None;
 					output.extend(self.restart());
@@ -1680,14 +1690,19 @@ impl<B: BlockT> ChainSync<B> {
 				let median = heads[heads.len() / 2];
 				if number + STATE_SYNC_FINALITY_THRESHOLD.saturated_into() >= median {
 					if let Ok(Some(header)) = self.client.header(BlockId::hash(hash.clone())) {
-						log::debug!(
-							target: "sync",
-							"Starting state sync for #{} ({})",
-							number,
-							hash,
-						);
-						self.state_sync =
-							Some(StateSync::new(self.client.clone(), header, *skip_proofs));
+						let client = self.client.clone();
+						let mode = StateSyncMode::Fast { skip_proofs: *skip_proofs };
+						let state_sync = StateSync::resume(client.clone(), mode, number)
+							.unwrap_or_else(|| {
+								log::debug!(
+									target: "sync",
+									"Starting state sync for #{} ({})",
+									number,
+									hash,
+								);
+								StateSync::new(client, header, mode)
+							});
+						self.state_sync = Some(state_sync);
 						self.allowed_requests.set_all();
 					}
 				}
@@ -2073,6 +2088,12 @@ impl<B: BlockT> ChainSync<B> {
 			gap_sync.blocks.clear_peer_download(who)
 		}
 		self.peers.remove(who);
+		if let Some(state_sync) = &mut self.state_sync {
+			state_sync.peer_disconnected(who);
+		}
+		if let Some(warp_sync) = &mut self.warp_sync {
+			warp_sync.peer_disconnected(who);
+		}
 		self.extra_justifications.peer_disconnected(who);
 		self.allowed_requests.set_all();
 		self.fork_targets.retain(|_, target| {
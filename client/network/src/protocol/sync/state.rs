@@ -22,27 +22,211 @@ use crate::{
 	schema::v1::{StateEntry, StateRequest, StateResponse},
 };
 use codec::{Decode, Encode};
-use log::debug;
+use libp2p::PeerId;
+use log::{debug, info, warn};
 use sc_client_api::CompactProof;
 use smallvec::SmallVec;
 use sp_core::storage::well_known_keys;
-use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
-use std::{collections::HashMap, sync::Arc};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, Saturating};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
 
 /// State sync support.
 
+/// Number of ranges the key space of the top trie is split into, to download them from different
+/// peers in parallel.
+const STATE_RANGES: usize = 16;
+/// Number of values of the first byte of the keys covered by each range. Ranges are split on the
+/// first byte only, so that the child trie root keys, which all start with `:`, are in the same
+/// range.
+const RANGE_WIDTH: usize = 256 / STATE_RANGES;
+
+/// Auxiliary storage key of the target of the state sync in progress.
+const CHECKPOINT_TARGET_KEY: &[u8] = b"state_sync_target";
+/// Auxiliary storage key of the progress of the state sync in progress.
+const CHECKPOINT_PROGRESS_KEY: &[u8] = b"state_sync_progress";
+/// Prefix of the auxiliary storage keys of the downloaded state chunks, followed by their index.
+const CHECKPOINT_CHUNK_PREFIX: &[u8] = b"state_sync_chunk";
+/// Maximal number of blocks the target of a checkpoint may be behind the block a new state sync
+/// would target, for the checkpoint to be resumed. Peers don't keep the state of older blocks
+/// with the default pruning settings.
+const CHECKPOINT_MAX_AGE: u32 = 256;
+/// Maximal number of times a checkpoint is resumed. A download that still fails to complete is
+/// started over.
+const CHECKPOINT_MAX_RESUMES: u32 = 3;
+
+/// Sync mode a state sync is part of.
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateSyncMode {
+	/// Fast sync, downloading the state of a recent finalized block.
+	Fast {
+		/// Download the state without proofs.
+		skip_proofs: bool,
+	},
+	/// Warp sync, downloading the state of the block a warp proof has been verified up to.
+	Warp,
+}
+
+/// Target of a checkpointed state sync.
+#[derive(Encode, Decode)]
+struct CheckpointTarget<H> {
+	header: H,
+	mode: StateSyncMode,
+	/// Number of times the checkpoint has been resumed.
+	resumes: u32,
+}
+
+/// Progress of a checkpointed state sync, updated along with each downloaded chunk.
+#[derive(Encode, Decode, Default)]
+struct CheckpointProgress {
+	/// Key cursor of each range, and whether it is complete.
+	ranges: Vec<(Vec<Vec<u8>>, bool)>,
+	imported_bytes: u64,
+	chunks: u32,
+}
+
+/// Key values of the tries, by trie root, received in a state response. The root of the top trie
+/// is empty.
+type Chunk = Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>;
+
+fn chunk_key(index: u32) -> Vec<u8> {
+	(CHECKPOINT_CHUNK_PREFIX, index).encode()
+}
+
+fn read_checkpoint<T: Decode, B: BlockT>(client: &dyn Client<B>, key: &[u8]) -> Option<T> {
+	match client.read_aux(key) {
+		Ok(Some(data)) => match T::decode(&mut data.as_slice()) {
+			Ok(value) => Some(value),
+			Err(e) => {
+				warn!(target: "sync", "Error decoding state sync checkpoint: {:?}", e);
+				None
+			},
+		},
+		Ok(None) => None,
+		Err(e) => {
+			warn!(target: "sync", "Error reading state sync checkpoint: {:?}", e);
+			None
+		},
+	}
+}
+
+/// Remove the checkpoint of the state sync in progress, if any.
+///
+/// Must be called once the downloaded state has been imported, or discarded.
+pub fn clear_checkpoint<B: BlockT>(client: &dyn Client<B>) {
+	let progress: CheckpointProgress =
+		read_checkpoint(client, CHECKPOINT_PROGRESS_KEY).unwrap_or_default();
+	let chunk_keys: Vec<_> = (0..progress.chunks).map(chunk_key).collect();
+	let delete: Vec<&[u8]> = [CHECKPOINT_TARGET_KEY, CHECKPOINT_PROGRESS_KEY]
+		.into_iter()
+		.chain(chunk_keys.iter().map(|key| key.as_slice()))
+		.collect();
+	if let Err(e) = client.write_aux(&[], &delete) {
+		warn!(target: "sync", "Error clearing state sync checkpoint: {:?}", e);
+	}
+}
+
+/// Remove the key values past `end` from the top trie of a chunk, along with the child tries whose
+/// root keys are removed.
+///
+/// Returns `true` if any key value was removed, in which case the range ending at `end` has been
+/// fully downloaded.
+fn truncate_chunk(chunk: &mut Chunk, end: &[u8]) -> bool {
+	let top = match chunk.iter_mut().find(|(state_root, _)| state_root.is_empty()) {
+		Some((_, key_values)) => key_values,
+		None => return false,
+	};
+	// Keys are ordered.
+	let kept = top.iter().take_while(|(key, _)| key.as_slice() <= end).count();
+	if kept == top.len() {
+		return false
+	}
+	let child_roots = |key_values: &[(Vec<u8>, Vec<u8>)]| -> HashSet<Vec<u8>> {
+		key_values
+			.iter()
+			.filter(|(key, _)| well_known_keys::is_child_storage_key(key.as_slice()))
+			.map(|(_, root)| root.clone())
+			.collect()
+	};
+	let removed = child_roots(&top[kept..]);
+	top.truncate(kept);
+	let removed: HashSet<_> = removed.difference(&child_roots(top)).cloned().collect();
+	chunk.retain(|(state_root, _)| !removed.contains(state_root));
+	true
+}
+
+/// A range of the key space of the top trie, downloaded independently of the others.
+struct KeyRange {
+	/// Key the range starts after, empty for the first range.
+	start: Vec<u8>,
+	/// Last key of the range, `None` for the last range.
+	end: Option<Vec<u8>>,
+	/// Keys of the last downloaded entries, in the top trie and in the child trie being
+	/// downloaded.
+	last_key: SmallVec<[Vec<u8>; 2]>,
+	complete: bool,
+	/// Peer the range is being downloaded from.
+	peer: Option<PeerId>,
+}
+
+impl KeyRange {
+	/// Split the key space into [`STATE_RANGES`] ranges.
+	fn split() -> Vec<Self> {
+		let bound = |index: usize| vec![(index * RANGE_WIDTH) as u8];
+		(0..STATE_RANGES)
+			.map(|index| KeyRange {
+				start: if index == 0 { Vec::new() } else { bound(index) },
+				end: if index + 1 == STATE_RANGES { None } else { Some(bound(index + 1)) },
+				last_key: SmallVec::default(),
+				complete: false,
+				peer: None,
+			})
+			.collect()
+	}
+
+	/// Start of the next request for the range.
+	fn next_start(&self) -> Vec<Vec<u8>> {
+		if self.last_key.is_empty() && !self.start.is_empty() {
+			vec![self.start.clone()]
+		} else {
+			self.last_key.clone().into_vec()
+		}
+	}
+
+	/// Number of values of the first byte of the keys downloaded so far.
+	fn downloaded(&self) -> usize {
+		let first = |key: &[u8]| key.first().map_or(0, |byte| *byte as usize);
+		let start = first(&self.start);
+		if self.complete {
+			self.end.as_deref().map_or(256, first) - start
+		} else {
+			self.last_key.first().map_or(start, |key| first(key)).saturating_sub(start)
+		}
+	}
+}
+
 /// State sync state machine. Accumulates partial state data until it
 /// is ready to be imported.
+///
+/// The key space is split into ranges that are downloaded from different peers in parallel, each
+/// one verified against the state root on its own.
+///
+/// Every chunk of state is persisted in the auxiliary storage of the client as it is received, so
+/// that the download can be resumed with [`StateSync::resume`] after a restart.
 pub struct StateSync<B: BlockT> {
 	target_block: B::Hash,
 	target_header: B::Header,
 	target_root: B::Hash,
-	last_key: SmallVec<[Vec<u8>; 2]>,
+	ranges: Vec<KeyRange>,
 	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
 	complete: bool,
 	client: Arc<dyn Client<B>>,
 	imported_bytes: u64,
 	skip_proof: bool,
+	/// Number of chunks persisted in the checkpoint, `None` if checkpointing failed.
+	chunks: Option<u32>,
 }
 
 /// Import state chunk result.
@@ -53,26 +237,128 @@ pub enum ImportResult<B: BlockT> {
 	Continue,
 	/// Bad state chunk.
 	BadResponse,
+	/// State chunk received from a peer it was not requested from.
+	NotRequested,
 }
 
 impl<B: BlockT> StateSync<B> {
-	///  Create a new instance.
-	pub fn new(client: Arc<dyn Client<B>>, target: B::Header, skip_proof: bool) -> Self {
+	///  Create a new instance, replacing any previous checkpoint.
+	pub fn new(client: Arc<dyn Client<B>>, target: B::Header, mode: StateSyncMode) -> Self {
+		clear_checkpoint(&*client);
+		let mut sync = Self::with_target(client, target, mode);
+		let checkpoint =
+			CheckpointTarget { header: sync.target_header.clone(), mode, resumes: 0 }.encode();
+		let progress = sync.checkpoint_progress(0).encode();
+		let written = sync.client.write_aux(
+			&[(CHECKPOINT_TARGET_KEY, &checkpoint), (CHECKPOINT_PROGRESS_KEY, &progress)],
+			&[],
+		);
+		if let Err(e) = written {
+			warn!(target: "sync", "Error writing state sync checkpoint: {:?}", e);
+			sync.chunks = None;
+		}
+		sync
+	}
+
+	/// Resume the state sync whose checkpoint is in the auxiliary storage of the client, if any.
+	///
+	/// The checkpoint is only used if it was downloaded in the same `mode`. It is discarded if
+	/// its target is more than [`CHECKPOINT_MAX_AGE`] blocks behind `target_number`, the block a
+	/// new state sync would target, or if it has been resumed [`CHECKPOINT_MAX_RESUMES`] times
+	/// already.
+	pub fn resume(
+		client: Arc<dyn Client<B>>,
+		mode: StateSyncMode,
+		target_number: NumberFor<B>,
+	) -> Option<Self> {
+		let mut target: CheckpointTarget<B::Header> =
+			read_checkpoint(&*client, CHECKPOINT_TARGET_KEY)?;
+		let progress: CheckpointProgress = read_checkpoint(&*client, CHECKPOINT_PROGRESS_KEY)?;
+		if target.mode != mode {
+			debug!(target: "sync", "Ignoring state sync checkpoint of {:?}", target.mode);
+			return None
+		}
+		if target_number.saturating_sub(*target.header.number()) > CHECKPOINT_MAX_AGE.into() {
+			debug!(
+				target: "sync",
+				"Discarding state sync checkpoint for #{}, too far behind #{}",
+				target.header.number(),
+				target_number,
+			);
+			clear_checkpoint(&*client);
+			return None
+		}
+		if target.resumes >= CHECKPOINT_MAX_RESUMES {
+			debug!(
+				target: "sync",
+				"Discarding state sync checkpoint resumed {} times already",
+				target.resumes,
+			);
+			clear_checkpoint(&*client);
+			return None
+		}
+		target.resumes += 1;
+		if let Err(e) = client.write_aux(&[(CHECKPOINT_TARGET_KEY, &target.encode())], &[]) {
+			warn!(target: "sync", "Error writing state sync checkpoint: {:?}", e);
+			return None
+		}
+
+		let mut sync = Self::with_target(client, target.header, mode);
+		if progress.ranges.len() != sync.ranges.len() {
+			warn!(
+				target: "sync",
+				"Ignoring state sync checkpoint with {} key ranges",
+				progress.ranges.len(),
+			);
+			return None
+		}
+		for index in 0..progress.chunks {
+			let chunk: Chunk = read_checkpoint(&*sync.client, &chunk_key(index))?;
+			sync.apply(chunk);
+		}
+		for (range, (last_key, complete)) in sync.ranges.iter_mut().zip(progress.ranges) {
+			range.last_key = last_key.into();
+			range.complete = complete;
+		}
+		sync.imported_bytes = progress.imported_bytes;
+		sync.chunks = Some(progress.chunks);
+		info!(
+			target: "sync",
+			"Resuming state sync for #{} ({}) from checkpoint ({} MiB)",
+			sync.target_block_num(),
+			sync.target_block,
+			sync.imported_bytes / (1024 * 1024),
+		);
+		Some(sync)
+	}
+
+	fn with_target(client: Arc<dyn Client<B>>, target: B::Header, mode: StateSyncMode) -> Self {
 		Self {
 			client,
 			target_block: target.hash(),
 			target_root: target.state_root().clone(),
 			target_header: target,
-			last_key: SmallVec::default(),
+			ranges: KeyRange::split(),
 			state: HashMap::default(),
 			complete: false,
 			imported_bytes: 0,
-			skip_proof,
+			skip_proof: mode == StateSyncMode::Fast { skip_proofs: true },
+			chunks: Some(0),
 		}
 	}
 
-	///  Validate and import a state reponse.
-	pub fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state reponse from `who`.
+	pub fn import(&mut self, who: PeerId, response: StateResponse) -> ImportResult<B> {
+		let index = match self.ranges.iter().position(|range| range.peer == Some(who)) {
+			Some(index) => index,
+			None => {
+				debug!(target: "sync", "Ignored state response from {} without request", who);
+				return ImportResult::NotRequested
+			},
+		};
+		let range = &mut self.ranges[index];
+		range.peer = None;
+
 		if response.entries.is_empty() && response.proof.is_empty() {
 			debug!(target: "sync", "Bad state response");
 			return ImportResult::BadResponse
@@ -81,7 +367,7 @@ impl<B: BlockT> StateSync<B> {
 			debug!(target: "sync", "Missing proof");
 			return ImportResult::BadResponse
 		}
-		let complete = if !self.skip_proof {
+		let (chunk, complete) = if !self.skip_proof {
 			debug!(target: "sync", "Importing state from {} trie nodes", response.proof.len());
 			let proof_size = response.proof.len() as u64;
 			let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
@@ -94,7 +380,7 @@ impl<B: BlockT> StateSync<B> {
 			let (values, completed) = match self.client.verify_range_proof(
 				self.target_root,
 				proof,
-				self.last_key.as_slice(),
+				range.next_start().as_slice(),
 			) {
 				Err(e) => {
 					debug!(
@@ -108,66 +394,34 @@ impl<B: BlockT> StateSync<B> {
 			};
 			debug!(target: "sync", "Imported with {} keys", values.len());
 
-			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.last_key) {
+			let mut complete = completed == 0;
+			if !complete && !values.update_last_key(completed, &mut range.last_key) {
 				debug!(target: "sync", "Error updating key cursor, depth: {}", completed);
 			};
 
-			for values in values.0 {
-				let key_values = if values.state_root.is_empty() {
-					// Read child trie roots.
-					values
-						.key_values
-						.into_iter()
-						.filter(|key_value| {
-							if well_known_keys::is_child_storage_key(key_value.0.as_slice()) {
-								self.state
-									.entry(key_value.1.clone())
-									.or_default()
-									.1
-									.push(key_value.0.clone());
-								false
-							} else {
-								true
-							}
-						})
-						.collect()
-				} else {
-					values.key_values
-				};
-				let mut entry = self.state.entry(values.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child_trie with same root.
-					// Warning this will not work with parallel download.
-				} else {
-					if entry.0.is_empty() {
-						for (key, _value) in key_values.iter() {
-							self.imported_bytes += key.len() as u64;
-						}
-
-						entry.0 = key_values;
-					} else {
-						for (key, value) in key_values {
-							self.imported_bytes += key.len() as u64;
-							entry.0.push((key, value))
-						}
-					}
-				}
-			}
 			self.imported_bytes += proof_size;
-			complete
+			let mut chunk = values
+				.0
+				.into_iter()
+				.map(|values| (values.state_root, values.key_values))
+				.collect();
+			if range.end.as_ref().map_or(false, |end| truncate_chunk(&mut chunk, end)) {
+				complete = true;
+			}
+			(chunk, complete)
 		} else {
 			let mut complete = true;
 			// if the trie is a child trie and one of its parent trie is empty,
 			// the parent cursor stays valid.
 			// Empty parent trie content only happens when all the response content
 			// is part of a single child trie.
-			if self.last_key.len() == 2 && response.entries[0].entries.len() == 0 {
+			if range.last_key.len() == 2 && response.entries[0].entries.len() == 0 {
 				// Do not remove the parent trie position.
-				self.last_key.pop();
+				range.last_key.pop();
 			} else {
-				self.last_key.clear();
+				range.last_key.clear();
 			}
+			let mut chunk = Vec::with_capacity(response.entries.len());
 			for state in response.entries {
 				debug!(
 					target: "sync",
@@ -178,32 +432,33 @@ impl<B: BlockT> StateSync<B> {
 
 				if !state.complete {
 					if let Some(e) = state.entries.last() {
-						self.last_key.push(e.key.clone());
+						range.last_key.push(e.key.clone());
 					}
 					complete = false;
 				}
-				let is_top = state.state_root.is_empty();
-				let entry = self.state.entry(state.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child trie with same root.
-				} else {
-					let mut child_roots = Vec::new();
-					for StateEntry { key, value } in state.entries {
-						// Skip all child key root (will be recalculated on import).
-						if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
-							child_roots.push((value, key));
-						} else {
-							self.imported_bytes += key.len() as u64;
-							entry.0.push((key, value))
-						}
-					}
-					for (root, storage_key) in child_roots {
-						self.state.entry(root).or_default().1.push(storage_key);
-					}
-				}
+				let key_values = state
+					.entries
+					.into_iter()
+					.map(|StateEntry { key, value }| (key, value))
+					.collect();
+				chunk.push((state.state_root, key_values));
 			}
-			complete
+			if range.end.as_ref().map_or(false, |end| truncate_chunk(&mut chunk, end)) {
+				complete = true;
+			}
+			(chunk, complete)
 		};
+		if complete {
+			debug!(target: "sync", "State range {} is complete", index);
+			range.complete = true;
+		}
+
+		let complete = self.ranges.iter().all(|range| range.complete);
+		let encoded = if complete { None } else { Some(chunk.encode()) };
+		self.apply(chunk);
+		if let Some(encoded) = encoded {
+			self.persist(encoded);
+		}
 		if complete {
 			self.complete = true;
 			ImportResult::Import(
@@ -219,12 +474,87 @@ impl<B: BlockT> StateSync<B> {
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_request(&self) -> StateRequest {
-		StateRequest {
+	/// Add the key values of a chunk to the accumulated state.
+	fn apply(&mut self, chunk: Chunk) {
+		for (state_root, key_values) in chunk {
+			let is_top = state_root.is_empty();
+			let entry = self.state.entry(state_root).or_default();
+			if entry.0.len() > 0 && entry.1.len() > 1 {
+				// Already imported child trie with same root.
+				// Child tries are all in the same range, so they are never downloaded in parallel.
+				continue
+			}
+			let mut child_roots = Vec::new();
+			for (key, value) in key_values {
+				// Skip all child key root (will be recalculated on import).
+				if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
+					child_roots.push((value, key));
+				} else {
+					self.imported_bytes += key.len() as u64;
+					entry.0.push((key, value))
+				}
+			}
+			for (root, storage_key) in child_roots {
+				self.state.entry(root).or_default().1.push(storage_key);
+			}
+		}
+	}
+
+	fn checkpoint_progress(&self, chunks: u32) -> CheckpointProgress {
+		CheckpointProgress {
+			ranges: self
+				.ranges
+				.iter()
+				.map(|range| (range.last_key.to_vec(), range.complete))
+				.collect(),
+			imported_bytes: self.imported_bytes,
+			chunks,
+		}
+	}
+
+	/// Persist an encoded chunk in the checkpoint, along with the current progress.
+	///
+	/// Checkpointing stops if writing fails, as later chunks can't be resumed from without it.
+	fn persist(&mut self, chunk: Vec<u8>) {
+		let chunks = match self.chunks {
+			Some(chunks) => chunks,
+			None => return,
+		};
+		let progress = self.checkpoint_progress(chunks + 1).encode();
+		let chunk_key = chunk_key(chunks);
+		match self
+			.client
+			.write_aux(&[(&chunk_key, &chunk), (CHECKPOINT_PROGRESS_KEY, &progress)], &[])
+		{
+			Ok(()) => self.chunks = Some(chunks + 1),
+			Err(e) => {
+				warn!(target: "sync", "Error writing state sync checkpoint: {:?}", e);
+				self.chunks = None;
+				clear_checkpoint(&*self.client);
+			},
+		}
+	}
+
+	/// Produce the next state request to send to `who`, if there is a range left that is not
+	/// being downloaded.
+	///
+	/// The range previously requested from `who`, if any, is released, as the new request
+	/// supersedes it.
+	pub fn next_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		self.peer_disconnected(&who);
+		let range = self.ranges.iter_mut().find(|range| !range.complete && range.peer.is_none())?;
+		range.peer = Some(who);
+		Some(StateRequest {
 			block: self.target_block.encode(),
-			start: self.last_key.clone().into_vec(),
+			start: range.next_start(),
 			no_proof: self.skip_proof,
+		})
+	}
+
+	/// Release the range being downloaded from a peer, so that it is requested from another one.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		for range in self.ranges.iter_mut().filter(|range| range.peer.as_ref() == Some(who)) {
+			range.peer = None;
 		}
 	}
 
@@ -245,8 +575,203 @@ impl<B: BlockT> StateSync<B> {
 
 	/// Returns state sync estimated progress.
 	pub fn progress(&self) -> StateDownloadProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
+		let downloaded: usize = self.ranges.iter().map(KeyRange::downloaded).sum();
+		let percent_done = (downloaded * 100 / 256) as u32;
 		StateDownloadProgress { percentage: percent_done, size: self.imported_bytes }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::v1::KeyValueStateEntry;
+	use sc_client_api::{ProofProvider, StorageProvider};
+	use sp_core::storage::StorageKey;
+	use sp_runtime::generic::BlockId;
+	use substrate_test_runtime_client::{
+		runtime::Block, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	fn response(entries: &[(&[u8], &[u8])], complete: bool) -> StateResponse {
+		StateResponse {
+			entries: vec![KeyValueStateEntry {
+				state_root: Vec::new(),
+				entries: entries
+					.iter()
+					.map(|(key, value)| StateEntry { key: key.to_vec(), value: value.to_vec() })
+					.collect(),
+				complete,
+			}],
+			proof: Vec::new(),
+		}
+	}
+
+	/// Download the ranges left from `who` with empty responses.
+	fn complete(sync: &mut StateSync<Block>, who: PeerId) -> ImportResult<Block> {
+		let mut result = ImportResult::Continue;
+		while sync.next_request(who).is_some() {
+			result = sync.import(who, response(&[], true));
+		}
+		result
+	}
+
+	fn top_key_values(result: ImportResult<Block>) -> Vec<(Vec<u8>, Vec<u8>)> {
+		match result {
+			ImportResult::Import(_, _, imported) => {
+				assert_eq!(imported.state.0.len(), 1);
+				let mut key_values = imported.state.0[0].key_values.clone();
+				key_values.sort();
+				key_values
+			},
+			_ => panic!("State is complete"),
+		}
+	}
+
+	#[test]
+	fn state_is_downloaded_in_parallel() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let mut sync =
+			StateSync::<Block>::new(client, target, StateSyncMode::Fast { skip_proofs: true });
+
+		let peers: Vec<_> = (0..=STATE_RANGES).map(|_| PeerId::random()).collect();
+		let requests: Vec<_> = peers.iter().map(|peer| sync.next_request(*peer)).collect();
+		assert_eq!(requests[0].as_ref().unwrap().start, Vec::<Vec<u8>>::new());
+		assert_eq!(requests[1].as_ref().unwrap().start, vec![vec![0x10]]);
+		assert_eq!(requests[STATE_RANGES - 1].as_ref().unwrap().start, vec![vec![0xf0]]);
+		assert!(requests[STATE_RANGES].is_none());
+
+		// Keys past the end of a range are ignored, and complete it.
+		let result = sync.import(
+			peers[1],
+			response(&[(b"\x11", b"1"), (b"\x20", b"2"), (b"\x21", b"3")], false),
+		);
+		assert!(matches!(result, ImportResult::Continue));
+		assert!(sync.next_request(peers[1]).is_none());
+
+		// The range of a disconnected peer is requested from another one.
+		sync.peer_disconnected(&peers[0]);
+		assert!(matches!(sync.import(peers[0], response(&[], true)), ImportResult::NotRequested));
+		let request = sync.next_request(peers[STATE_RANGES]).unwrap();
+		assert_eq!(request.start, Vec::<Vec<u8>>::new());
+		let result = sync.import(peers[STATE_RANGES], response(&[(b"\x01", b"0")], true));
+		assert!(matches!(result, ImportResult::Continue));
+
+		for peer in &peers[2..STATE_RANGES - 1] {
+			assert!(matches!(sync.import(*peer, response(&[], true)), ImportResult::Continue));
+		}
+		assert_eq!(
+			top_key_values(sync.import(peers[STATE_RANGES - 1], response(&[], true))),
+			vec![
+				(b"\x01".to_vec(), b"0".to_vec()),
+				(b"\x11".to_vec(), b"1".to_vec()),
+				(b"\x20".to_vec(), b"2".to_vec()),
+			],
+		);
+	}
+
+	#[test]
+	fn ranges_are_verified_against_state_root() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let id = BlockId::Number(0);
+		let target = client.header(&id).unwrap().unwrap();
+		let mut sync = StateSync::<Block>::new(
+			client.clone(),
+			target,
+			StateSyncMode::Fast { skip_proofs: false },
+		);
+
+		// The range of the keys starting with `0x3`, which holds the well known keys.
+		let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+		let request = peers.iter().map(|peer| sync.next_request(*peer).unwrap()).last().unwrap();
+		assert_eq!(request.start, vec![vec![0x30]]);
+		let (proof, _) = client.read_proof_collection(&id, &request.start, usize::MAX).unwrap();
+		let mut response = StateResponse { entries: Vec::new(), proof: proof.encode() };
+
+		// A tampered proof is rejected, and the range is requested again.
+		let mut tampered = proof.clone();
+		tampered.encoded_nodes[0].push(0);
+		response.proof = tampered.encode();
+		assert!(matches!(sync.import(peers[3], response.clone()), ImportResult::BadResponse));
+		assert_eq!(sync.next_request(peers[3]).unwrap().start, vec![vec![0x30]]);
+
+		// The proof covers all the keys after the start of the range, the ones past its end are
+		// ignored.
+		response.proof = proof.encode();
+		assert!(matches!(sync.import(peers[3], response), ImportResult::Continue));
+		assert!(sync.ranges[3].complete);
+		let mut expected: Vec<_> = client
+			.storage_pairs(&id, &StorageKey(Vec::new()))
+			.unwrap()
+			.into_iter()
+			.map(|(key, value)| (key.0, value.0))
+			.filter(|(key, _)| key.as_slice() > &[0x30][..] && key.as_slice() <= &[0x40][..])
+			.filter(|(key, _)| !well_known_keys::is_child_storage_key(key))
+			.collect();
+		expected.sort();
+		assert!(expected.iter().any(|(key, _)| key.as_slice() == well_known_keys::CODE));
+		let mut key_values = sync.state[&Vec::new()].0.clone();
+		key_values.sort();
+		assert_eq!(key_values, expected);
+	}
+
+	#[test]
+	fn state_sync_is_resumed_from_checkpoint() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let peer = PeerId::random();
+		let mode = StateSyncMode::Fast { skip_proofs: true };
+
+		let mut sync = StateSync::<Block>::new(client.clone(), target.clone(), mode);
+		sync.next_request(peer).unwrap();
+		assert!(matches!(
+			sync.import(peer, response(&[(b"\x01", b"1")], false)),
+			ImportResult::Continue
+		));
+
+		// Checkpoints downloaded in a different mode are ignored.
+		let fast_with_proofs = StateSyncMode::Fast { skip_proofs: false };
+		assert!(StateSync::<Block>::resume(client.clone(), fast_with_proofs, 0).is_none());
+		assert!(StateSync::<Block>::resume(client.clone(), StateSyncMode::Warp, 0).is_none());
+
+		let mut resumed =
+			StateSync::<Block>::resume(client.clone(), mode, CHECKPOINT_MAX_AGE.into()).unwrap();
+		assert_eq!(resumed.target(), target.hash());
+		assert_eq!(resumed.progress(), sync.progress());
+		assert_eq!(resumed.next_request(peer), sync.next_request(peer));
+
+		assert!(matches!(
+			resumed.import(peer, response(&[(b"\x02", b"2")], true)),
+			ImportResult::Continue
+		));
+		assert_eq!(
+			top_key_values(complete(&mut resumed, peer)),
+			vec![(b"\x01".to_vec(), b"1".to_vec()), (b"\x02".to_vec(), b"2".to_vec())],
+		);
+
+		clear_checkpoint::<Block>(&*client);
+		assert!(StateSync::<Block>::resume(client, mode, 0).is_none());
+	}
+
+	#[test]
+	fn stale_checkpoints_are_discarded() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target = client.header(&BlockId::Number(0)).unwrap().unwrap();
+		let mode = StateSyncMode::Warp;
+
+		// Too far behind the block a new state sync would target.
+		StateSync::<Block>::new(client.clone(), target.clone(), mode);
+		assert!(StateSync::<Block>::resume(client.clone(), mode, (CHECKPOINT_MAX_AGE + 1).into())
+			.is_none());
+		assert!(StateSync::<Block>::resume(client.clone(), mode, 0).is_none());
+
+		// Resumed too many times.
+		StateSync::<Block>::new(client.clone(), target, mode);
+		for _ in 0..CHECKPOINT_MAX_RESUMES {
+			assert!(StateSync::<Block>::resume(client.clone(), mode, 0).is_some());
+		}
+		assert!(StateSync::<Block>::resume(client.clone(), mode, 0).is_none());
+		assert!(read_checkpoint::<CheckpointProgress, Block>(&*client, CHECKPOINT_PROGRESS_KEY)
+			.is_none());
+	}
+}
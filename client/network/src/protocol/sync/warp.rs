@@ -18,7 +18,7 @@
 
 ///! Warp sync support.
 pub use super::state::ImportResult;
-use super::state::StateSync;
+use super::state::{StateSync, StateSyncMode};
 pub use crate::warp_request_handler::{
 	EncodedProof, Request as WarpProofRequest, VerificationResult, WarpSyncProvider,
 };
@@ -27,8 +27,9 @@ use crate::{
 	schema::v1::{StateRequest, StateResponse},
 	WarpSyncPhase, WarpSyncProgress,
 };
+use libp2p::PeerId;
 use sp_finality_grandpa::{AuthorityList, SetId};
-use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, Zero};
 use std::sync::Arc;
 
 enum Phase<B: BlockT> {
@@ -67,14 +68,14 @@ impl<B: BlockT> WarpSync<B> {
 		Self { client, warp_sync_provider, phase, total_proof_bytes: 0 }
 	}
 
-	///  Validate and import a state response.
-	pub fn import_state(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from `who`.
+	pub fn import_state(&mut self, who: PeerId, response: StateResponse) -> ImportResult<B> {
 		match &mut self.phase {
 			Phase::WarpProof { .. } => {
 				log::debug!(target: "sync", "Unexpected state response");
 				return ImportResult::BadResponse
 			},
-			Phase::State(sync) => sync.import(response),
+			Phase::State(sync) => sync.import(who, response),
 		}
 	}
 
@@ -102,7 +103,17 @@ impl<B: BlockT> WarpSync<B> {
 					Ok(VerificationResult::Complete(new_set_id, _, header)) => {
 						log::debug!(target: "sync", "Verified complete proof, set_id={:?}", new_set_id);
 						self.total_proof_bytes += response.0.len() as u64;
-						let state_sync = StateSync::new(self.client.clone(), header, false);
+						// A state download interrupted by a restart is resumed once the warp proof
+						// has been verified again. Its target was verified by the warp proof of
+						// the run that started it, and must not be too far behind the new one.
+						let state_sync = StateSync::resume(
+							self.client.clone(),
+							StateSyncMode::Warp,
+							*header.number(),
+						)
+						.unwrap_or_else(|| {
+							StateSync::new(self.client.clone(), header, StateSyncMode::Warp)
+						});
 						self.phase = Phase::State(state_sync);
 						WarpProofImportResult::Success
 					},
@@ -111,11 +122,18 @@ impl<B: BlockT> WarpSync<B> {
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_state_request(&self) -> Option<StateRequest> {
-		match &self.phase {
+	/// Produce next state request to send to `who`.
+	pub fn next_state_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		match &mut self.phase {
 			Phase::WarpProof { .. } => None,
-			Phase::State(sync) => Some(sync.next_request()),
+			Phase::State(sync) => sync.next_request(who),
+		}
+	}
+
+	/// Release the state range being downloaded from a peer.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		if let Phase::State(sync) = &mut self.phase {
+			sync.peer_disconnected(who);
 		}
 	}
 
@@ -1085,6 +1085,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
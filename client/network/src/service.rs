@@ -29,8 +29,9 @@
 
 use crate::{
 	behaviour::{self, Behaviour, BehaviourOut},
-	bitswap::Bitswap,
+	bitswap::{Bitswap, BitswapRequestError, Cid},
 	config::{parse_str_addr, Params, TransportConfig},
+	connection_filter::ConnectionFilter,
 	discovery::DiscoveryConfig,
 	error::Error,
 	network_state::{
@@ -66,7 +67,7 @@ use log::{debug, error, info, trace, warn};
 use metrics::{Histogram, HistogramVec, MetricSources, Metrics};
 use parking_lot::Mutex;
 use sc_consensus::{BlockImportError, BlockImportStatus, ImportQueue, Link};
-use sc_peerset::PeersetHandle;
+use sc_peerset::{BannedPeer, PeersetHandle};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 use std::{
@@ -84,6 +85,7 @@ use std::{
 		Arc,
 	},
 	task::Poll,
+	time::Duration,
 };
 
 pub use behaviour::{
@@ -252,6 +254,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 
 		// Build the swarm.
 		let client = params.chain.clone();
+		let connection_filter =
+			ConnectionFilter::new(params.network_config.connection_filter.clone());
 		let (mut swarm, bandwidth): (Swarm<B>, _) = {
 			let user_agent = format!(
 				"{} ({})",
@@ -333,11 +337,17 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 					config_mem,
 					params.network_config.yamux_window_size,
 					yamux_maximum_buffer_size,
+					connection_filter.clone(),
 				)
 			};
 
 			let behaviour = {
-				let bitswap = params.network_config.ipfs_server.then(|| Bitswap::new(client));
+				let ipfs_server = params.network_config.ipfs_server;
+				let bitswap = if ipfs_server || params.network_config.ipfs_client {
+					Some(Bitswap::new(client, ipfs_server, params.metrics_registry.as_ref())?)
+				} else {
+					None
+				};
 				let result = Behaviour::new(
 					protocol,
 					user_agent,
@@ -388,6 +398,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 				registry,
 				MetricSources {
 					bandwidth: bandwidth.clone(),
+					connection_filter,
 					major_syncing: is_major_syncing.clone(),
 					connected_peers: num_connected.clone(),
 				},
@@ -632,6 +643,13 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 				.collect()
 		};
 
+		let bitswap_ledgers = swarm
+			.behaviour()
+			.bitswap_ledgers()
+			.into_iter()
+			.map(|(peer_id, ledger)| (peer_id.to_base58(), ledger))
+			.collect();
+
 		let peer_id = Swarm::<B>::local_peer_id(&swarm).to_base58();
 		let listened_addresses = swarm.listeners().cloned().collect();
 		let external_addresses = swarm.external_addresses().map(|r| &r.addr).cloned().collect();
@@ -642,6 +660,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			external_addresses,
 			connected_peers,
 			not_connected_peers,
+			bitswap_ledgers,
 			peerset: swarm.behaviour_mut().user_protocol_mut().peerset_debug_info(),
 		}
 	}
@@ -671,6 +690,11 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 	pub fn reserved_peers(&self) -> impl Iterator<Item = &PeerId> {
 		self.network_service.behaviour().user_protocol().reserved_peers()
 	}
+
+	/// Returns the list of banned peers.
+	pub fn banned_peers(&mut self) -> Vec<BannedPeer> {
+		self.network_service.behaviour_mut().user_protocol_mut().banned_peers()
+	}
 }
 
 impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
@@ -940,6 +964,35 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		});
 	}
 
+	/// Fetches the block with the given CID from connected peers over bitswap.
+	///
+	/// Peers that support bitswap are asked one after another until one of them delivers data
+	/// matching the CID, a maximum number of peers has been tried, or `timeout` elapses.
+	///
+	/// Requires [`NetworkConfiguration::ipfs_client`](
+	/// crate::config::NetworkConfiguration::ipfs_client) or
+	/// [`NetworkConfiguration::ipfs_server`](crate::config::NetworkConfiguration::ipfs_server)
+	/// to be enabled.
+	pub async fn bitswap_request(
+		&self,
+		cid: Cid,
+		timeout: Duration,
+	) -> Result<Vec<u8>, BitswapRequestError> {
+		let (tx, rx) = oneshot::channel();
+
+		let _ = self.to_worker.unbounded_send(ServiceToWorkerMsg::BitswapRequest {
+			cid,
+			timeout,
+			pending_response: tx,
+		});
+
+		match rx.await {
+			Ok(v) => v,
+			// The channel can only be closed if the network worker no longer exists.
+			Err(_) => Err(BitswapRequestError::Terminated),
+		}
+	}
+
 	/// High-level network status information.
 	///
 	/// Returns an error if the `NetworkWorker` is no longer running.
@@ -1007,6 +1060,20 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		self.peerset.report_peer(who, cost_benefit);
 	}
 
+	/// Ban a peer for the given duration, or permanently if `None`.
+	///
+	/// The peer is disconnected and no connection to it is accepted or attempted while it is
+	/// banned. Bans are persisted across restarts if
+	/// [`NetworkConfiguration::net_config_path`](crate::config::NetworkConfiguration) is set.
+	pub fn ban_peer(&self, who: PeerId, duration: Option<Duration>) {
+		self.peerset.ban_peer(who, duration);
+	}
+
+	/// Lift the ban of a peer.
+	pub fn unban_peer(&self, who: PeerId) {
+		self.peerset.unban_peer(who);
+	}
+
 	/// Disconnect from a node as soon as possible.
 	///
 	/// This triggers the same effects as if the connection had closed itself spontaneously.
@@ -1436,6 +1503,11 @@ enum ServiceToWorkerMsg<B: BlockT, H: ExHashT> {
 		pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
 		connect: IfDisconnected,
 	},
+	BitswapRequest {
+		cid: Cid,
+		timeout: Duration,
+		pending_response: oneshot::Sender<Result<Vec<u8>, BitswapRequestError>>,
+	},
 	NetworkStatus {
 		pending_response: oneshot::Sender<Result<NetworkStatus<B>, RequestFailure>>,
 	},
@@ -1603,6 +1675,10 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 						connect,
 					);
 				},
+				ServiceToWorkerMsg::BitswapRequest { cid, timeout, pending_response } => this
+					.network_service
+					.behaviour_mut()
+					.bitswap_request(cid, timeout, pending_response),
 				ServiceToWorkerMsg::NetworkStatus { pending_response } => {
 					let _ = pending_response.send(Ok(this.status()));
 				},
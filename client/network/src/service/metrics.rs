@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::transport::BandwidthSinks;
+use crate::{connection_filter::ConnectionFilter, transport::BandwidthSinks};
 use prometheus_endpoint::{
 	self as prometheus, Counter, CounterVec, Gauge, GaugeVec, HistogramOpts, MetricSource, Opts,
 	PrometheusError, Registry, SourcedCounter, SourcedGauge, U64,
@@ -34,6 +34,7 @@ pub use prometheus_endpoint::{Histogram, HistogramVec};
 /// Registers all networking metrics with the given registry.
 pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics, PrometheusError> {
 	BandwidthCounters::register(registry, sources.bandwidth)?;
+	ConnectionsRejectedCounters::register(registry, sources.connection_filter)?;
 	MajorSyncingGauge::register(registry, sources.major_syncing)?;
 	NumConnectedGauge::register(registry, sources.connected_peers)?;
 	Metrics::register(registry)
@@ -42,6 +43,7 @@ pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics,
 /// Predefined metric sources that are fed directly into prometheus.
 pub struct MetricSources {
 	pub bandwidth: Arc<BandwidthSinks>,
+	pub connection_filter: ConnectionFilter,
 	pub major_syncing: Arc<AtomicBool>,
 	pub connected_peers: Arc<AtomicUsize>,
 }
@@ -285,6 +287,42 @@ impl MetricSource for BandwidthCounters {
 	}
 }
 
+/// The rejected connections counter metric.
+#[derive(Clone)]
+pub struct ConnectionsRejectedCounters(ConnectionFilter);
+
+impl ConnectionsRejectedCounters {
+	/// Registers the `ConnectionsRejectedCounters` metric whose values are
+	/// obtained from the given filter.
+	fn register(registry: &Registry, filter: ConnectionFilter) -> Result<(), PrometheusError> {
+		prometheus::register(
+			SourcedCounter::new(
+				&Opts::new(
+					"substrate_sub_libp2p_connections_rejected_total",
+					"Total number of connections rejected because of the IP address of the remote, \
+					by direction and reason",
+				)
+				.variable_label("direction")
+				.variable_label("reason"),
+				ConnectionsRejectedCounters(filter),
+			)?,
+			registry,
+		)?;
+
+		Ok(())
+	}
+}
+
+impl MetricSource for ConnectionsRejectedCounters {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (direction, reason, count) in self.0.rejected() {
+			set(&[direction, reason], count);
+		}
+	}
+}
+
 /// The "major syncing" metric.
 #[derive(Clone)]
 pub struct MajorSyncingGauge(Arc<AtomicBool>);
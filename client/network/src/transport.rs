@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::connection_filter::{ConnectionFilter, FilterTransport};
 use libp2p::{
 	bandwidth,
 	core::{
@@ -44,6 +45,9 @@ pub use self::bandwidth::BandwidthSinks;
 /// high-level protocols combined, or to some generously high value if you are sure that a maximum
 /// size is enforced on all high-level protocols.
 ///
+/// Connections over TCP, including the ones dialed through a DNS name, are subject to the
+/// restrictions of `connection_filter`.
+///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
 /// the connections spawned with this transport.
 pub fn build_transport(
@@ -51,10 +55,12 @@ pub fn build_transport(
 	memory_only: bool,
 	yamux_window_size: Option<u32>,
 	yamux_maximum_buffer_size: usize,
+	connection_filter: ConnectionFilter,
 ) -> (Boxed<(PeerId, StreamMuxerBox)>, Arc<BandwidthSinks>) {
 	// Build the base layer of the transport.
 	let transport = if !memory_only {
-		let desktop_trans = tcp::TcpConfig::new().nodelay(true);
+		let desktop_trans =
+			FilterTransport::new(tcp::TcpConfig::new().nodelay(true), connection_filter);
 		let desktop_trans =
 			websocket::WsConfig::new(desktop_trans.clone()).or_transport(desktop_trans);
 		let dns_init = futures::executor::block_on(dns::DnsConfig::system(desktop_trans.clone()));
@@ -22,11 +22,13 @@ use crate::NetworkProvider;
 use codec::{Decode, Encode};
 use futures::Future;
 pub use http::SharedClient;
+pub use http_client::{HttpClientIdentity, HttpConfig, HttpHostPattern, HttpProxy};
+pub use ipfs::IndexedData;
 use sc_network::{Multiaddr, PeerId};
 use sp_core::{
 	offchain::{
-		self, HttpError, HttpRequestId, HttpRequestStatus, OffchainStorage, OpaqueMultiaddr,
-		OpaqueNetworkState, StorageKind, Timestamp,
+		self, HttpError, HttpRequestId, HttpRequestStatus, IpfsError, OffchainStorage,
+		OpaqueMultiaddr, OpaqueNetworkState, StorageKind, Timestamp,
 	},
 	OpaquePeerId,
 };
@@ -34,6 +36,10 @@ pub use sp_offchain::STORAGE_PREFIX;
 
 mod http;
 
+mod http_client;
+
+mod ipfs;
+
 mod timestamp;
 
 fn unavailable_yet<R: Default>(name: &str) -> R {
@@ -151,6 +157,49 @@ pub(crate) struct Api {
 	is_validator: bool,
 	/// Everything HTTP-related is handled by a different struct.
 	http: http::HttpApi,
+	/// Fetching of content by CID.
+	ipfs: ipfs::IpfsApi,
+	/// End of the wall-clock budget of the run, blocking calls don't wait beyond it.
+	deadline: Option<Timestamp>,
+	/// Metrics of the HTTP requests of the run.
+	metrics: Option<crate::metrics::Metrics>,
+}
+
+impl Api {
+	/// Don't block for longer than `timeout` from now in any call, and refuse to start HTTP
+	/// requests afterwards.
+	pub fn set_run_timeout(&mut self, timeout: std::time::Duration) {
+		let timeout = offchain::Duration::from_millis(timeout.as_millis() as u64);
+		self.deadline = Some(timestamp::now().add(timeout));
+	}
+
+	/// Report started and failed HTTP requests to `metrics`.
+	pub fn set_metrics(&mut self, metrics: crate::metrics::Metrics) {
+		self.metrics = Some(metrics);
+	}
+
+	/// Count a failure of an HTTP request.
+	fn http_failed(&self, reason: &str) {
+		if let Some(metrics) = &self.metrics {
+			metrics.http_failures.with_label_values(&[reason]).inc();
+		}
+	}
+
+	/// Count the HTTP requests that failed with an I/O error in `result`.
+	fn count_io_error<T>(&self, result: Result<T, HttpError>) -> Result<T, HttpError> {
+		if let Err(HttpError::IoError) = result {
+			self.http_failed("io");
+		}
+		result
+	}
+
+	/// The earlier of `deadline` and the end of the run budget.
+	fn clamp(&self, deadline: Option<Timestamp>) -> Option<Timestamp> {
+		match (deadline, self.deadline) {
+			(Some(deadline), Some(budget)) => Some(deadline.min(budget)),
+			(deadline, budget) => deadline.or(budget),
+		}
+	}
 }
 
 impl offchain::Externalities for Api {
@@ -170,6 +219,7 @@ impl offchain::Externalities for Api {
 	}
 
 	fn sleep_until(&mut self, deadline: Timestamp) {
+		let deadline = self.clamp(Some(deadline)).unwrap_or(deadline);
 		sleep(timestamp::timestamp_from_now(deadline));
 	}
 
@@ -183,7 +233,18 @@ impl offchain::Externalities for Api {
 		uri: &str,
 		_meta: &[u8],
 	) -> Result<HttpRequestId, ()> {
-		self.http.request_start(method, uri)
+		if self.deadline.map_or(false, |deadline| timestamp::now() >= deadline) {
+			self.http_failed("timeout");
+			return Err(())
+		}
+		let id = self
+			.http
+			.request_start(method, uri)
+			.map_err(|()| self.http_failed("rejected"))?;
+		if let Some(metrics) = &self.metrics {
+			metrics.http_requests.inc();
+		}
+		Ok(id)
 	}
 
 	fn http_request_add_header(
@@ -201,7 +262,8 @@ impl offchain::Externalities for Api {
 		chunk: &[u8],
 		deadline: Option<Timestamp>,
 	) -> Result<(), HttpError> {
-		self.http.request_write_body(request_id, chunk, deadline)
+		let result = self.http.request_write_body(request_id, chunk, self.clamp(deadline));
+		self.count_io_error(result)
 	}
 
 	fn http_response_wait(
@@ -209,7 +271,13 @@ impl offchain::Externalities for Api {
 		ids: &[HttpRequestId],
 		deadline: Option<Timestamp>,
 	) -> Vec<HttpRequestStatus> {
-		self.http.response_wait(ids, deadline)
+		let statuses = self.http.response_wait(ids, self.clamp(deadline));
+		for status in &statuses {
+			if let HttpRequestStatus::IoError = status {
+				self.http_failed("io");
+			}
+		}
+		statuses
 	}
 
 	fn http_response_headers(&mut self, request_id: HttpRequestId) -> Vec<(Vec<u8>, Vec<u8>)> {
@@ -222,7 +290,8 @@ impl offchain::Externalities for Api {
 		buffer: &mut [u8],
 		deadline: Option<Timestamp>,
 	) -> Result<usize, HttpError> {
-		self.http.response_read_body(request_id, buffer, deadline)
+		let result = self.http.response_read_body(request_id, buffer, self.clamp(deadline));
+		self.count_io_error(result)
 	}
 
 	fn set_authorized_nodes(&mut self, nodes: Vec<OpaquePeerId>, authorized_only: bool) {
@@ -232,6 +301,14 @@ impl offchain::Externalities for Api {
 		self.network_provider.set_authorized_peers(peer_ids);
 		self.network_provider.set_authorized_only(authorized_only);
 	}
+
+	fn ipfs_fetch(
+		&mut self,
+		cid: &[u8],
+		deadline: Option<Timestamp>,
+	) -> Result<Vec<u8>, IpfsError> {
+		self.ipfs.fetch(cid, self.clamp(deadline))
+	}
 }
 
 /// Information about the local node's network state.
@@ -305,10 +382,19 @@ impl AsyncApi {
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
 		shared_http_client: SharedClient,
+		indexed_data: IndexedData,
 	) -> (Api, Self) {
 		let (http_api, http_worker) = http::http(shared_http_client);
-
-		let api = Api { network_provider, is_validator, http: http_api };
+		let ipfs_api = ipfs::IpfsApi::new(network_provider.clone(), indexed_data);
+
+		let api = Api {
+			network_provider,
+			is_validator,
+			http: http_api,
+			ipfs: ipfs_api,
+			deadline: None,
+			metrics: None,
+		};
 
 		let async_api = Self { http: Some(http_worker) };
 
@@ -324,12 +410,16 @@ impl AsyncApi {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use futures::future::BoxFuture;
 	use sc_client_db::offchain::LocalStorage;
-	use sc_network::{NetworkStateInfo, PeerId};
+	use sc_network::{
+		bitswap::{BitswapRequestError, Cid},
+		NetworkStateInfo, PeerId,
+	};
 	use sp_core::offchain::{DbExternalities, Externalities};
 	use std::{
 		convert::{TryFrom, TryInto},
-		time::SystemTime,
+		time::{Duration, SystemTime},
 	};
 
 	pub(super) struct TestNetwork();
@@ -342,6 +432,18 @@ mod tests {
 		fn set_authorized_only(&self, _reserved_only: bool) {
 			unimplemented!()
 		}
+
+		fn is_major_syncing(&self) -> bool {
+			false
+		}
+
+		fn bitswap_request(
+			&self,
+			_cid: Cid,
+			_timeout: Duration,
+		) -> BoxFuture<'_, Result<Vec<u8>, BitswapRequestError>> {
+			Box::pin(futures::future::ready(Err(BitswapRequestError::NotFound)))
+		}
 	}
 
 	impl NetworkStateInfo for TestNetwork {
@@ -357,9 +459,9 @@ mod tests {
 	fn offchain_api() -> (Api, AsyncApi) {
 		sp_tracing::try_init_simple();
 		let mock = Arc::new(TestNetwork());
-		let shared_client = SharedClient::new();
+		let shared_client = SharedClient::new(Default::default());
 
-		AsyncApi::new(mock, false, shared_client)
+		AsyncApi::new(mock, false, shared_client, Arc::new(|_, _| None))
 	}
 
 	fn offchain_db() -> Db<LocalStorage> {
@@ -405,6 +507,22 @@ mod tests {
 		assert!(new_now.unix_millis() - 100 >= now.unix_millis());
 	}
 
+	#[test]
+	fn should_refuse_requests_after_run_timeout() {
+		let registry = prometheus_endpoint::Registry::new();
+		let metrics = crate::metrics::Metrics::register(&registry).unwrap();
+		let mut api = offchain_api().0;
+		api.set_metrics(metrics.clone());
+		api.set_run_timeout(std::time::Duration::from_millis(50));
+
+		assert!(api.http_request_start("GET", "http://localhost:1", &[]).is_ok());
+		let later = api.timestamp().add(sp_core::offchain::Duration::from_millis(100));
+		api.sleep_until(later);
+		assert!(api.http_request_start("GET", "http://localhost:1", &[]).is_err());
+		assert_eq!(metrics.http_requests.get(), 1);
+		assert_eq!(metrics.http_failures.with_label_values(&["timeout"]).get(), 1);
+	}
+
 	#[test]
 	fn should_set_and_get_local_storage() {
 		// given
@@ -479,4 +597,37 @@ mod tests {
 		// then
 		assert_ne!(seed, [0; 32]);
 	}
+
+	#[test]
+	fn should_fetch_indexed_content() {
+		const SHA2_256: u64 = 0x12;
+		const BLAKE2B_256: u64 = 0xb220;
+		let content = b"indexed content".to_vec();
+		let hash = sp_core::hashing::blake2_256(&content);
+		let sha2_hash = sp_core::hashing::sha2_256(&content);
+		let cid = |hash: &[u8; 32]| [&[0x01, 0x55, 0xa0, 0xe4, 0x02, 0x20][..], hash].concat();
+		let sha2_cid = [&[0x01, 0x55, 0x12, 0x20][..], &sha2_hash].concat();
+		let (mut api, _) = AsyncApi::new(
+			Arc::new(TestNetwork()),
+			false,
+			SharedClient::new(Default::default()),
+			Arc::new({
+				let content = content.clone();
+				move |code: u64, digest: &[u8]| match code {
+					BLAKE2B_256 if digest == hash => Some(content.clone()),
+					SHA2_256 if digest == sha2_hash => Some(content.clone()),
+					_ => None,
+				}
+			}),
+		);
+
+		assert_eq!(api.ipfs_fetch(&cid(&hash), None), Ok(content.clone()));
+		assert_eq!(api.ipfs_fetch(&sha2_cid, None), Ok(content));
+		assert_eq!(api.ipfs_fetch(&cid(&[0; 32]), None), Err(IpfsError::NotFound));
+		assert_eq!(api.ipfs_fetch(&cid(&hash)[..10], None), Err(IpfsError::InvalidCid));
+		assert_eq!(
+			api.ipfs_fetch(&cid(&[0; 32]), Some(timestamp::now())),
+			Err(IpfsError::DeadlineReached),
+		);
+	}
 }
@@ -0,0 +1,79 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Fetching of content by CID for offchain workers.
+//!
+//! Content is looked up among the indexed transactions stored by the local node first, then
+//! requested from connected peers over bitswap.
+
+use super::timestamp;
+use crate::NetworkProvider;
+use sc_network::bitswap::{BitswapRequestError, Cid};
+use sp_core::offchain::{IpfsError, Timestamp};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+const LOG_TARGET: &str = "offchain-worker::ipfs";
+
+/// Time to wait for content from peers if no deadline is given.
+const NO_DEADLINE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Lookup of the indexed transactions and UnixFS DAG blocks stored by the local node, by the
+/// multihash code and digest of a CID. See [`sc_client_api::BlockBackend::indexed_content`].
+pub type IndexedData = Arc<dyn Fn(u64, &[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Fetches content for the offchain worker API.
+pub struct IpfsApi {
+	network_provider: Arc<dyn NetworkProvider + Send + Sync>,
+	indexed_data: IndexedData,
+}
+
+impl IpfsApi {
+	/// Create a new instance.
+	pub fn new(
+		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
+		indexed_data: IndexedData,
+	) -> Self {
+		Self { network_provider, indexed_data }
+	}
+
+	/// Fetch the content with the given CID, see [`sp_core::offchain::Externalities::ipfs_fetch`].
+	pub fn fetch(&self, cid: &[u8], deadline: Option<Timestamp>) -> Result<Vec<u8>, IpfsError> {
+		let cid = Cid::try_from(cid).map_err(|_| IpfsError::InvalidCid)?;
+		if let Some(data) = self.local(&cid) {
+			return Ok(data)
+		}
+
+		let timeout = deadline.map_or(NO_DEADLINE_TIMEOUT, timestamp::timestamp_from_now);
+		if timeout.is_zero() {
+			return Err(IpfsError::DeadlineReached)
+		}
+		let request = self.network_provider.bitswap_request(cid, timeout);
+		futures::executor::block_on(request).map_err(|e| {
+			tracing::debug!(target: LOG_TARGET, "IPFS fetch failed: {}", e);
+			match e {
+				BitswapRequestError::Timeout => IpfsError::DeadlineReached,
+				BitswapRequestError::UnsupportedCid => IpfsError::InvalidCid,
+				_ => IpfsError::NotFound,
+			}
+		})
+	}
+
+	fn local(&self, cid: &Cid) -> Option<Vec<u8>> {
+		(self.indexed_data)(cid.hash().code(), cid.hash().digest())
+	}
+}
@@ -35,14 +35,30 @@
 
 #![warn(missing_docs)]
 
-use std::{collections::HashSet, fmt, marker::PhantomData, sync::Arc};
+use std::{
+	collections::HashSet,
+	fmt,
+	marker::PhantomData,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant},
+};
 
 use futures::{
-	future::{ready, Future},
+	channel::oneshot,
+	future::{self, ready, BoxFuture, Future},
 	prelude::*,
 };
+use futures_timer::Delay;
 use parking_lot::Mutex;
-use sc_network::{ExHashT, NetworkService, NetworkStateInfo, PeerId};
+use prometheus_endpoint::Registry;
+use sc_client_api::BlockBackend;
+use sc_network::{
+	bitswap::{BitswapRequestError, Cid},
+	ExHashT, NetworkService, NetworkStateInfo, PeerId,
+};
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_core::{offchain, traits::SpawnNamed, ExecutionContext};
 use sp_runtime::{
@@ -52,8 +68,9 @@ use sp_runtime::{
 use threadpool::ThreadPool;
 
 mod api;
+mod metrics;
 
-pub use api::Db as OffchainDb;
+pub use api::{Db as OffchainDb, HttpClientIdentity, HttpConfig, HttpHostPattern, HttpProxy};
 pub use sp_offchain::{OffchainWorkerApi, STORAGE_PREFIX};
 
 const LOG_TARGET: &str = "offchain-worker";
@@ -66,6 +83,16 @@ pub trait NetworkProvider: NetworkStateInfo {
 
 	/// Set the authorized only flag.
 	fn set_authorized_only(&self, reserved_only: bool);
+
+	/// Returns `true` if the node is performing a major sync.
+	fn is_major_syncing(&self) -> bool;
+
+	/// Fetch the block with the given CID from connected peers over bitswap.
+	fn bitswap_request(
+		&self,
+		cid: Cid,
+		timeout: Duration,
+	) -> BoxFuture<'_, Result<Vec<u8>, BitswapRequestError>>;
 }
 
 impl<B, H> NetworkProvider for NetworkService<B, H>
@@ -80,6 +107,18 @@ where
 	fn set_authorized_only(&self, reserved_only: bool) {
 		self.set_authorized_only(reserved_only)
 	}
+
+	fn is_major_syncing(&self) -> bool {
+		NetworkService::is_major_syncing(self)
+	}
+
+	fn bitswap_request(
+		&self,
+		cid: Cid,
+		timeout: Duration,
+	) -> BoxFuture<'_, Result<Vec<u8>, BitswapRequestError>> {
+		Box::pin(NetworkService::bitswap_request(self, cid, timeout))
+	}
 }
 
 /// Options for [`OffchainWorkers`]
@@ -88,6 +127,34 @@ pub struct OffchainWorkerOptions {
 	///
 	/// If not enabled, any http request will panic.
 	pub enable_http_requests: bool,
+	/// Maximum number of offchain workers running at the same time.
+	///
+	/// Blocks imported while this many workers are running are skipped. Defaults to the number
+	/// of CPUs, with further blocks queued.
+	pub max_concurrent_workers: Option<usize>,
+	/// Wall-clock time a run may take, counted from the start of its execution.
+	///
+	/// Once it passed, pending HTTP requests of the run are dropped, new ones are refused and
+	/// blocking calls, like waiting for HTTP responses, return as if their deadline was reached.
+	/// The runtime code itself can't be interrupted: a run that keeps computing without calling
+	/// into the node keeps its slot of `max_concurrent_workers` until it returns.
+	pub run_timeout: Option<Duration>,
+	/// Don't run offchain workers while the node is performing a major sync.
+	pub skip_during_major_sync: bool,
+	/// Configuration of the HTTP client.
+	pub http: HttpConfig,
+}
+
+impl Default for OffchainWorkerOptions {
+	fn default() -> Self {
+		Self {
+			enable_http_requests: true,
+			max_concurrent_workers: None,
+			run_timeout: None,
+			skip_during_major_sync: false,
+			http: Default::default(),
+		}
+	}
 }
 
 /// An offchain workers manager.
@@ -97,25 +164,77 @@ pub struct OffchainWorkers<Client, Block: traits::Block> {
 	thread_pool: Mutex<ThreadPool>,
 	shared_http_client: api::SharedClient,
 	enable_http: bool,
+	max_concurrent_workers: Option<usize>,
+	run_timeout: Option<Duration>,
+	skip_during_major_sync: bool,
+	/// Number of offchain workers currently running.
+	running: Arc<AtomicUsize>,
+	metrics: Option<metrics::Metrics>,
 }
 
 impl<Client, Block: traits::Block> OffchainWorkers<Client, Block> {
 	/// Creates new [`OffchainWorkers`].
 	pub fn new(client: Arc<Client>) -> Self {
-		Self::new_with_options(client, OffchainWorkerOptions { enable_http_requests: true })
+		Self::new_with_options(client, Default::default(), None)
 	}
 
-	/// Creates new [`OffchainWorkers`] using the given `options`.
-	pub fn new_with_options(client: Arc<Client>, options: OffchainWorkerOptions) -> Self {
+	/// Creates new [`OffchainWorkers`] using the given `options`, reporting metrics to
+	/// `prometheus_registry`.
+	pub fn new_with_options(
+		client: Arc<Client>,
+		options: OffchainWorkerOptions,
+		prometheus_registry: Option<&Registry>,
+	) -> Self {
+		let metrics = prometheus_registry.and_then(|r| {
+			metrics::Metrics::register(r)
+				.map_err(|e| {
+					tracing::warn!(
+						target: LOG_TARGET,
+						"Failed to register offchain worker metrics: {}",
+						e
+					)
+				})
+				.ok()
+		});
 		Self {
 			client,
 			_block: PhantomData,
 			thread_pool: Mutex::new(ThreadPool::with_name(
 				"offchain-worker".into(),
-				num_cpus::get(),
+				options.max_concurrent_workers.unwrap_or_else(num_cpus::get).max(1),
 			)),
-			shared_http_client: api::SharedClient::new(),
+			shared_http_client: api::SharedClient::new(options.http),
 			enable_http: options.enable_http_requests,
+			max_concurrent_workers: options.max_concurrent_workers,
+			run_timeout: options.run_timeout,
+			skip_during_major_sync: options.skip_during_major_sync,
+			running: Arc::new(AtomicUsize::new(0)),
+			metrics,
+		}
+	}
+
+	/// Reserve a slot for running offchain workers, unless they should be skipped.
+	///
+	/// The slot is released once the returned guard is dropped.
+	fn reserve_worker(&self, network_provider: &dyn NetworkProvider) -> Option<RunningGuard> {
+		let skip = |reason| {
+			tracing::debug!(target: LOG_TARGET, "Skipping offchain workers: {}", reason);
+			if let Some(metrics) = &self.metrics {
+				metrics.skipped.with_label_values(&[reason]).inc();
+			}
+			None
+		};
+
+		if self.skip_during_major_sync && network_provider.is_major_syncing() {
+			return skip("major_sync")
+		}
+		let max = self.max_concurrent_workers.unwrap_or(usize::MAX);
+		match self
+			.running
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then(|| n + 1))
+		{
+			Ok(_) => Some(RunningGuard(self.running.clone())),
+			Err(_) => skip("concurrency"),
 		}
 	}
 }
@@ -129,7 +248,7 @@ impl<Client, Block: traits::Block> fmt::Debug for OffchainWorkers<Client, Block>
 impl<Client, Block> OffchainWorkers<Client, Block>
 where
 	Block: traits::Block,
-	Client: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + Send + Sync + 'static,
 	Client::Api: OffchainWorkerApi<Block>,
 {
 	/// Start the offchain workers after given block.
@@ -165,9 +284,14 @@ where
 			at,
 			version
 		);
-		let process = (version > 0).then(|| {
-			let (api, runner) =
-				api::AsyncApi::new(network_provider, is_validator, self.shared_http_client.clone());
+		let guard = if version > 0 { self.reserve_worker(&*network_provider) } else { None };
+		let process = guard.map(|guard| {
+			let (mut api, runner) = api::AsyncApi::new(
+				network_provider,
+				is_validator,
+				self.shared_http_client.clone(),
+				self.indexed_data(),
+			);
 			tracing::debug!(target: LOG_TARGET, "Spawning offchain workers at {:?}", at);
 			let header = header.clone();
 			let client = self.client.clone();
@@ -175,7 +299,20 @@ where
 			let mut capabilities = offchain::Capabilities::all();
 
 			capabilities.set(offchain::Capabilities::HTTP, self.enable_http);
+			if let Some(metrics) = &self.metrics {
+				api.set_metrics(metrics.clone());
+			}
+			let run_timeout = self.run_timeout;
+			let metrics = self.metrics.clone();
+			let (started_sender, started_receiver) = oneshot::channel();
 			self.spawn_worker(move || {
+				let _guard = guard;
+				// The budget of the run starts once the thread pool gets to it.
+				let started = Instant::now();
+				if let Some(timeout) = run_timeout {
+					api.set_run_timeout(timeout);
+				}
+				let _ = started_sender.send(());
 				let runtime = client.runtime_api();
 				let api = Box::new(api);
 				tracing::debug!(target: LOG_TARGET, "Running offchain workers at {:?}", at);
@@ -191,7 +328,7 @@ where
 						*header.number(),
 					)
 				};
-				if let Err(e) = run {
+				if let Err(e) = &run {
 					tracing::error!(
 						target: LOG_TARGET,
 						"Error running offchain workers at {:?}: {}",
@@ -199,9 +336,43 @@ where
 						e
 					);
 				}
+
+				let elapsed = started.elapsed();
+				let timed_out = run_timeout.map_or(false, |timeout| elapsed > timeout);
+				if timed_out {
+					tracing::warn!(
+						target: LOG_TARGET,
+						"Offchain workers at {:?} exceeded their run time budget: {:?}",
+						at,
+						elapsed,
+					);
+				}
+				if let Some(metrics) = metrics {
+					metrics.run_time.observe(elapsed.as_secs_f64());
+					let result = match (run, timed_out) {
+						(Err(_), _) => "error",
+						(Ok(_), true) => "timeout",
+						(Ok(_), false) => "success",
+					};
+					metrics.runs.with_label_values(&[result]).inc();
+				}
 			});
 
-			runner.process()
+			let process = runner.process();
+			async move {
+				match run_timeout {
+					// Drops the pending HTTP requests of the run once the budget is spent.
+					Some(timeout) => {
+						let budget = async move {
+							if started_receiver.await.is_ok() {
+								Delay::new(timeout).await
+							}
+						};
+						let _ = future::select(Box::pin(process), Box::pin(budget)).await;
+					},
+					None => process.await,
+				}
+			}
 		});
 
 		async move {
@@ -209,6 +380,17 @@ where
 		}
 	}
 
+	/// Lookup of the indexed transactions stored by the local node.
+	fn indexed_data(&self) -> api::IndexedData {
+		let client = self.client.clone();
+		Arc::new(move |code: u64, digest: &[u8]| {
+			client.indexed_content(code, digest).unwrap_or_else(|e| {
+				tracing::debug!(target: LOG_TARGET, "Error reading indexed transaction: {}", e);
+				None
+			})
+		})
+	}
+
 	/// Spawns a new offchain worker.
 	///
 	/// We spawn offchain workers for each block in a separate thread,
@@ -222,6 +404,15 @@ where
 	}
 }
 
+/// Marks an offchain worker run as running until dropped.
+struct RunningGuard(Arc<AtomicUsize>);
+
+impl Drop for RunningGuard {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
 /// Inform the offchain worker about new imported blocks
 pub async fn notification_future<Client, Block, Spawner>(
 	is_validator: bool,
@@ -231,8 +422,12 @@ pub async fn notification_future<Client, Block, Spawner>(
 	network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 ) where
 	Block: traits::Block,
-	Client:
-		ProvideRuntimeApi<Block> + sc_client_api::BlockchainEvents<Block> + Send + Sync + 'static,
+	Client: ProvideRuntimeApi<Block>
+		+ BlockBackend<Block>
+		+ sc_client_api::BlockchainEvents<Block>
+		+ Send
+		+ Sync
+		+ 'static,
 	Client::Api: OffchainWorkerApi<Block>,
 	Spawner: SpawnNamed,
 {
@@ -296,6 +491,18 @@ mod tests {
 		fn set_authorized_only(&self, _reserved_only: bool) {
 			unimplemented!()
 		}
+
+		fn is_major_syncing(&self) -> bool {
+			false
+		}
+
+		fn bitswap_request(
+			&self,
+			_cid: Cid,
+			_timeout: Duration,
+		) -> BoxFuture<'_, Result<Vec<u8>, BitswapRequestError>> {
+			unimplemented!()
+		}
 	}
 
 	struct TestPool(Arc<BasicPool<FullChainApi<TestClient, Block>, Block>>);
@@ -338,6 +545,26 @@ mod tests {
 		assert_eq!(pool.0.ready().next().unwrap().is_propagable(), false);
 	}
 
+	#[test]
+	fn should_skip_blocks_beyond_concurrency_limit() {
+		let registry = Registry::new();
+		let options =
+			OffchainWorkerOptions { max_concurrent_workers: Some(1), ..Default::default() };
+		let offchain =
+			OffchainWorkers::<_, Block>::new_with_options(Arc::new(()), options, Some(&registry));
+		let network = TestNetwork();
+
+		let running = offchain.reserve_worker(&network);
+		assert!(running.is_some());
+		assert!(offchain.reserve_worker(&network).is_none());
+
+		drop(running);
+		assert!(offchain.reserve_worker(&network).is_some());
+		let skipped =
+			offchain.metrics.as_ref().unwrap().skipped.with_label_values(&["concurrency"]);
+		assert_eq!(skipped.get(), 1);
+	}
+
 	#[test]
 	fn offchain_index_set_and_clear_works() {
 		use sp_core::offchain::OffchainStorage;
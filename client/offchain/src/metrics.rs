@@ -0,0 +1,92 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offchain workers metrics.
+
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Histogram, HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
+
+/// Offchain workers metrics.
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	/// Wall-clock time of offchain worker runs.
+	pub run_time: Histogram,
+	/// Finished offchain worker runs, by result.
+	pub runs: CounterVec<U64>,
+	/// Blocks offchain workers were not run for, by reason.
+	pub skipped: CounterVec<U64>,
+	/// HTTP requests started by offchain workers.
+	pub http_requests: Counter<U64>,
+	/// HTTP requests of offchain workers that failed or were refused, by reason.
+	pub http_failures: CounterVec<U64>,
+}
+
+impl Metrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			run_time: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_offchain_worker_run_time",
+						"Wall-clock time of offchain worker runs in seconds",
+					)
+					.buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0]),
+				)?,
+				registry,
+			)?,
+			runs: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_offchain_worker_runs",
+						"Number of finished offchain worker runs",
+					),
+					&["result"],
+				)?,
+				registry,
+			)?,
+			skipped: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_offchain_worker_skipped",
+						"Number of blocks offchain workers were not run for",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			http_requests: register(
+				Counter::new(
+					"substrate_offchain_worker_http_requests",
+					"Number of HTTP requests started by offchain workers",
+				)?,
+				registry,
+			)?,
+			http_failures: register(
+				CounterVec::new(
+					Opts::new(
+						"substrate_offchain_worker_http_failures",
+						"Number of HTTP requests of offchain workers that failed or were refused",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
@@ -31,18 +31,23 @@
 //!
 //! In addition, for each, set, the peerset also holds a list of reserved nodes towards which it
 //! will at all time try to maintain a connection with.
+//!
+//! Reputations and bans can be persisted across restarts, see [`PeersetConfig::state_path`].
 
 mod peersstate;
+pub mod persistence;
 
 use futures::{channel::oneshot, prelude::*};
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
+use persistence::PersistedState;
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use serde_json::json;
 use std::{
 	collections::{HashMap, HashSet, VecDeque},
+	path::PathBuf,
 	pin::Pin,
 	task::{Context, Poll},
-	time::{Duration, Instant},
+	time::{Duration, Instant, SystemTime},
 };
 use wasm_timer::Delay;
 
@@ -55,6 +60,8 @@ const DISCONNECT_REPUTATION_CHANGE: i32 = -256;
 /// Amount of time between the moment we disconnect from a node and the moment we remove it from
 /// the list.
 const FORGET_AFTER: Duration = Duration::from_secs(3600);
+/// Interval between two writes of the reputations and bans to disk.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug)]
 enum Action {
@@ -66,6 +73,8 @@ enum Action {
 	AddToPeersSet(SetId, PeerId),
 	RemoveFromPeersSet(SetId, PeerId),
 	PeerReputation(PeerId, oneshot::Sender<i32>),
+	BanPeer(PeerId, Option<Duration>),
+	UnbanPeer(PeerId),
 }
 
 /// Identifier of a set in the peerset.
@@ -176,6 +185,29 @@ impl PeersetHandle {
 		// The channel can only be closed if the peerset no longer exists.
 		rx.await.map_err(|_| ())
 	}
+
+	/// Bans a peer for the given duration, or permanently if `None`.
+	///
+	/// The peer is disconnected from all sets, and isn't connected to again until it is unbanned.
+	pub fn ban_peer(&self, peer_id: PeerId, duration: Option<Duration>) {
+		let _ = self.tx.unbounded_send(Action::BanPeer(peer_id, duration));
+	}
+
+	/// Lifts the ban of a peer, and resets its reputation.
+	///
+	/// Has no effect if the peer isn't banned.
+	pub fn unban_peer(&self, peer_id: PeerId) {
+		let _ = self.tx.unbounded_send(Action::UnbanPeer(peer_id));
+	}
+}
+
+/// A peer banned with [`PeersetHandle::ban_peer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedPeer {
+	/// Identity of the peer.
+	pub peer_id: PeerId,
+	/// When the ban expires. The ban is permanent if `None`.
+	pub until: Option<SystemTime>,
 }
 
 /// Message that can be sent by the peer set manager (PSM).
@@ -218,6 +250,11 @@ impl From<u64> for IncomingIndex {
 pub struct PeersetConfig {
 	/// List of sets of nodes the peerset manages.
 	pub sets: Vec<SetConfig>,
+
+	/// File the reputations and bans are persisted to, and loaded from at startup.
+	///
+	/// Nothing is persisted if `None`.
+	pub state_path: Option<PathBuf>,
 }
 
 /// Configuration for a single set of nodes.
@@ -270,6 +307,13 @@ pub struct Peerset {
 	/// Next time to do a periodic call to `alloc_slots` with all sets. This is done once per
 	/// second, to match the period of the reputation updates.
 	next_periodic_alloc_slots: Delay,
+	/// Banned peers, with the time their ban expires at. Their reputation is kept at the minimum
+	/// for as long as they are banned.
+	bans: HashMap<PeerId, Option<SystemTime>>,
+	/// File the reputations and bans are persisted to.
+	state_path: Option<PathBuf>,
+	/// Next time to persist the reputations and bans.
+	next_persist: Delay,
 }
 
 impl Peerset {
@@ -297,9 +341,29 @@ impl Peerset {
 				created: now,
 				latest_time_update: now,
 				next_periodic_alloc_slots: Delay::new(Duration::new(0, 0)),
+				bans: HashMap::new(),
+				state_path: config.state_path,
+				next_persist: Delay::new(PERSIST_INTERVAL),
 			}
 		};
 
+		if let Some(path) = &peerset.state_path {
+			match PersistedState::load(path) {
+				Ok(mut state) => {
+					state.remove_expired_bans(SystemTime::now());
+					for (peer_id, reputation) in state.reputations {
+						peerset.data.peer_reputation(peer_id).set_reputation(reputation);
+					}
+					for (peer_id, until) in state.bans {
+						peerset.data.peer_reputation(peer_id).set_reputation(i32::MIN);
+						peerset.bans.insert(peer_id, until);
+					}
+				},
+				Err(e) =>
+					warn!(target: "peerset", "Failed to load peerset state from {:?}: {}", path, e),
+			}
+		}
+
 		for (set, set_config) in config.sets.into_iter().enumerate() {
 			for node in set_config.reserved_nodes {
 				peerset.data.add_no_slot_node(set, node);
@@ -469,6 +533,64 @@ impl Peerset {
 		let _ = pending_response.send(reputation.reputation());
 	}
 
+	fn on_ban_peer(&mut self, peer_id: PeerId, duration: Option<Duration>) {
+		self.bans.insert(peer_id, duration.map(|duration| SystemTime::now() + duration));
+		self.data.peer_reputation(peer_id).set_reputation(i32::MIN);
+		// Disconnects the peer.
+		self.on_report_peer(peer_id, ReputationChange::new_fatal("Banned"));
+	}
+
+	fn on_unban_peer(&mut self, peer_id: PeerId) {
+		if self.bans.remove(&peer_id).is_none() {
+			return
+		}
+
+		self.data.peer_reputation(peer_id).set_reputation(0);
+		for set_index in 0..self.data.num_sets() {
+			self.alloc_slots(SetId(set_index));
+		}
+	}
+
+	/// Lifts the expired bans, and keeps the reputation of the banned peers at the minimum.
+	fn update_bans(&mut self) {
+		let now = SystemTime::now();
+		let expired = self
+			.bans
+			.iter()
+			.filter(|(_, until)| until.map_or(false, |until| until <= now))
+			.map(|(peer_id, _)| *peer_id)
+			.collect::<Vec<_>>();
+		for peer_id in expired {
+			debug!(target: "peerset", "Ban of {} expired", peer_id);
+			self.bans.remove(&peer_id);
+			self.data.peer_reputation(peer_id).set_reputation(0);
+		}
+
+		for peer_id in self.bans.keys() {
+			self.data.peer_reputation(*peer_id).set_reputation(i32::MIN);
+		}
+	}
+
+	/// Writes the reputations and bans to [`Peerset::state_path`], if any.
+	fn persist(&mut self) {
+		let path = match &self.state_path {
+			Some(path) => path,
+			None => return,
+		};
+
+		let mut state = PersistedState { reputations: HashMap::new(), bans: self.bans.clone() };
+		for peer_id in self.data.peers().cloned().collect::<Vec<_>>() {
+			let reputation = self.data.peer_reputation(peer_id).reputation();
+			if reputation != 0 && !self.bans.contains_key(&peer_id) {
+				state.reputations.insert(peer_id, reputation);
+			}
+		}
+
+		if let Err(e) = state.save(path) {
+			warn!(target: "peerset", "Failed to persist peerset state to {:?}: {}", path, e);
+		}
+	}
+
 	/// Updates the value of `self.latest_time_update` and performs all the updates that happen
 	/// over time, such as reputation increases for staying connected.
 	fn update_time(&mut self) {
@@ -531,6 +653,10 @@ impl Peerset {
 				}
 			}
 		}
+
+		if secs_diff != 0 {
+			self.update_bans();
+		}
 	}
 
 	/// Try to fill available out slots with nodes for the given set.
@@ -711,10 +837,20 @@ impl Peerset {
 					"reserved_only": self.reserved_nodes[set_index].1,
 				})
 			}).collect::<Vec<_>>(),
+			"banned": self.bans.keys().map(|peer_id| peer_id.to_base58()).collect::<HashSet<_>>(),
 			"message_queue": self.message_queue.len(),
 		})
 	}
 
+	/// Returns the list of banned peers.
+	pub fn banned_peers(&mut self) -> Vec<BannedPeer> {
+		self.update_bans();
+		self.bans
+			.iter()
+			.map(|(peer_id, until)| BannedPeer { peer_id: *peer_id, until: *until })
+			.collect()
+	}
+
 	/// Returns the number of peers that we have discovered.
 	pub fn num_discovered_peers(&self) -> usize {
 		self.data.peers().len()
@@ -739,6 +875,11 @@ impl Stream for Peerset {
 				}
 			}
 
+			if let Poll::Ready(_) = Future::poll(Pin::new(&mut self.next_persist), cx) {
+				self.next_persist = Delay::new(PERSIST_INTERVAL);
+				self.persist();
+			}
+
 			let action = match Stream::poll_next(Pin::new(&mut self.rx), cx) {
 				Poll::Pending => return Poll::Pending,
 				Poll::Ready(Some(event)) => event,
@@ -761,11 +902,19 @@ impl Stream for Peerset {
 					self.on_remove_from_peers_set(sets_name, peer_id),
 				Action::PeerReputation(peer_id, pending_response) =>
 					self.on_peer_reputation(peer_id, pending_response),
+				Action::BanPeer(peer_id, duration) => self.on_ban_peer(peer_id, duration),
+				Action::UnbanPeer(peer_id) => self.on_unban_peer(peer_id),
 			}
 		}
 	}
 }
 
+impl Drop for Peerset {
+	fn drop(&mut self) {
+		self.persist();
+	}
+}
+
 /// Reason for calling [`Peerset::dropped`].
 pub enum DropReason {
 	/// Substream or connection has been closed for an unknown reason.
@@ -780,8 +929,8 @@ pub enum DropReason {
 #[cfg(test)]
 mod tests {
 	use super::{
-		IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig, SetId,
-		BANNED_THRESHOLD,
+		persistence, IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig,
+		SetId, BANNED_THRESHOLD,
 	};
 	use futures::prelude::*;
 	use libp2p::PeerId;
@@ -815,6 +964,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			state_path: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -848,6 +998,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -879,6 +1030,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			state_path: None,
 		};
 
 		let (mut peerset, _) = Peerset::from_config(config);
@@ -900,6 +1052,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -926,6 +1079,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -969,6 +1123,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			state_path: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -999,4 +1154,79 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
+
+	#[test]
+	fn test_peerset_ban_and_unban() {
+		let (mut peerset, handle) = Peerset::from_config(PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				bootnodes: vec![],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			state_path: None,
+		});
+
+		let peer_id = PeerId::random();
+		handle.ban_peer(peer_id, None);
+
+		let fut = futures::future::poll_fn(move |cx| {
+			// We need one polling for the message to be processed.
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+
+			// Unlike a low reputation, the ban doesn't wear off.
+			thread::sleep(Duration::from_millis(1500));
+			peerset.incoming(SetId::from(0), peer_id, IncomingIndex(1));
+			if let Poll::Ready(msg) = Stream::poll_next(Pin::new(&mut peerset), cx) {
+				assert_eq!(msg.unwrap(), Message::Reject(IncomingIndex(1)));
+			} else {
+				panic!()
+			}
+
+			// Once unbanned, the peerset connects to it again.
+			handle.unban_peer(peer_id);
+			if let Poll::Ready(msg) = Stream::poll_next(Pin::new(&mut peerset), cx) {
+				assert_eq!(msg.unwrap(), Message::Connect { set_id: SetId::from(0), peer_id });
+			} else {
+				panic!()
+			}
+
+			Poll::Ready(())
+		});
+
+		futures::executor::block_on(fut);
+	}
+
+	#[test]
+	fn test_peerset_persists_bans() {
+		let dir = tempfile::tempdir().unwrap();
+		let config = || PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				bootnodes: vec![],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			state_path: Some(dir.path().join(persistence::STATE_FILE_NAME)),
+		};
+		let (banned, expired) = (PeerId::random(), PeerId::random());
+
+		let (mut peerset, handle) = Peerset::from_config(config());
+		handle.ban_peer(banned, Some(Duration::from_secs(3600)));
+		handle.ban_peer(expired, Some(Duration::from_secs(0)));
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			Poll::Ready(())
+		}));
+		drop(peerset);
+
+		let (mut peerset, _handle) = Peerset::from_config(config());
+		let banned_peers = peerset.banned_peers();
+		assert_eq!(banned_peers.len(), 1);
+		assert_eq!(banned_peers[0].peer_id, banned);
+		peerset.incoming(SetId::from(0), banned, IncomingIndex(1));
+		assert_messages(peerset, vec![Message::Reject(IncomingIndex(1))]);
+	}
 }
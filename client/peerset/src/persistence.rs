@@ -0,0 +1,194 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistence of the reputations and bans of the peerset across restarts.
+//!
+//! The state is stored as a JSON file, so that it can be inspected and edited by operators.
+
+use libp2p::PeerId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs, io,
+	path::Path,
+	str::FromStr,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the file the state is persisted to, within the network configuration directory.
+pub const STATE_FILE_NAME: &str = "peerset.json";
+
+/// State of the peerset kept across restarts.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PersistedState {
+	/// Reputation of the peers.
+	pub reputations: HashMap<PeerId, i32>,
+	/// Banned peers, with the time their ban expires at. Bans without expiry are permanent.
+	pub bans: HashMap<PeerId, Option<SystemTime>>,
+}
+
+/// Format of the file.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredState {
+	/// Reputations by base58 encoded peer id.
+	#[serde(default)]
+	reputations: BTreeMap<String, i32>,
+	/// UNIX timestamps in seconds bans expire at, by base58 encoded peer id.
+	#[serde(default)]
+	bans: BTreeMap<String, Option<u64>>,
+}
+
+impl PersistedState {
+	/// Read the state from the file at `path`. Returns an empty state if the file doesn't exist.
+	///
+	/// Entries with invalid peer ids are skipped.
+	pub fn load(path: &Path) -> io::Result<Self> {
+		let stored: StoredState = match fs::read(path) {
+			Ok(data) => serde_json::from_slice(&data)
+				.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+			Err(e) => return Err(e),
+		};
+
+		let parse = |peer_id: &str| {
+			PeerId::from_str(peer_id)
+				.map_err(|_| warn!(target: "peerset", "Invalid peer id in {:?}: {}", path, peer_id))
+				.ok()
+		};
+		Ok(Self {
+			reputations: stored
+				.reputations
+				.iter()
+				.filter_map(|(peer_id, reputation)| Some((parse(peer_id)?, *reputation)))
+				.collect(),
+			bans: stored
+				.bans
+				.iter()
+				.filter_map(|(peer_id, until)| {
+					Some((
+						parse(peer_id)?,
+						until.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+					))
+				})
+				.collect(),
+		})
+	}
+
+	/// Write the state to the file at `path`, replacing the previous one.
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let stored = StoredState {
+			reputations: self
+				.reputations
+				.iter()
+				.map(|(peer_id, reputation)| (peer_id.to_base58(), *reputation))
+				.collect(),
+			bans: self
+				.bans
+				.iter()
+				.map(|(peer_id, until)| {
+					let secs = until.map(|until| {
+						until.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+					});
+					(peer_id.to_base58(), secs)
+				})
+				.collect(),
+		};
+		let data = serde_json::to_vec_pretty(&stored)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+		// Write to a temporary file first, so that a crash doesn't leave a truncated file behind.
+		let tmp_path = path.with_extension("tmp");
+		fs::write(&tmp_path, data)?;
+		fs::rename(tmp_path, path)
+	}
+
+	/// Remove the bans that expired at `now`.
+	pub fn remove_expired_bans(&mut self, now: SystemTime) {
+		self.bans.retain(|_, until| until.map_or(true, |until| until > now));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn state_is_saved_and_loaded() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join(STATE_FILE_NAME);
+
+		assert_eq!(PersistedState::load(&path).unwrap(), PersistedState::default());
+
+		let state = PersistedState {
+			reputations: vec![(PeerId::random(), -100), (PeerId::random(), 50)]
+				.into_iter()
+				.collect(),
+			bans: vec![
+				(PeerId::random(), None),
+				(PeerId::random(), Some(UNIX_EPOCH + Duration::from_secs(1_000_000))),
+			]
+			.into_iter()
+			.collect(),
+		};
+		state.save(&path).unwrap();
+
+		assert_eq!(PersistedState::load(&path).unwrap(), state);
+	}
+
+	#[test]
+	fn invalid_peer_ids_are_skipped() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join(STATE_FILE_NAME);
+		let peer_id = PeerId::random();
+		fs::write(
+			&path,
+			format!(r#"{{ "reputations": {{ "invalid": 1, "{}": 2 }} }}"#, peer_id.to_base58()),
+		)
+		.unwrap();
+
+		let state = PersistedState::load(&path).unwrap();
+
+		assert_eq!(state.reputations, vec![(peer_id, 2)].into_iter().collect());
+		assert!(state.bans.is_empty());
+	}
+
+	#[test]
+	fn expired_bans_are_removed() {
+		let now = SystemTime::now();
+		let (permanent, expired, active) = (PeerId::random(), PeerId::random(), PeerId::random());
+		let mut state = PersistedState {
+			reputations: Default::default(),
+			bans: vec![
+				(permanent, None),
+				(expired, Some(now - Duration::from_secs(1))),
+				(active, Some(now + Duration::from_secs(1))),
+			]
+			.into_iter()
+			.collect(),
+		};
+
+		state.remove_expired_bans(now);
+
+		assert_eq!(
+			state.bans.keys().collect::<std::collections::HashSet<_>>(),
+			[permanent, active].iter().collect()
+		);
+	}
+}
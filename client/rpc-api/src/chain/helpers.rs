@@ -0,0 +1,33 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate blockchain API helpers.
+
+use serde::{Deserialize, Serialize};
+
+/// Block together with the events it deposited, as sent by the block subscriptions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockWithEvents<SignedBlock> {
+	/// Header, extrinsics and justifications of the block.
+	#[serde(flatten)]
+	pub block: SignedBlock,
+	/// Events stored in `System::Events` at the block, decoded with the metadata of the runtime
+	/// of the block. `None` if the metadata doesn't describe a `System::Events` storage item.
+	pub events: Option<serde_json::Value>,
+}
@@ -19,6 +19,7 @@
 //! Substrate blockchain API.
 
 pub mod error;
+mod helpers;
 
 use self::error::{FutureResult, Result};
 use jsonrpc_core::Result as RpcResult;
@@ -26,7 +27,7 @@ use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use sp_rpc::{list::ListOrValue, number::NumberOrHex};
 
-pub use self::gen_client::Client as ChainClient;
+pub use self::{gen_client::Client as ChainClient, helpers::BlockWithEvents};
 
 /// Substrate blockchain API
 #[rpc]
@@ -110,4 +111,51 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 		metadata: Option<Self::Metadata>,
 		id: SubscriptionId,
 	) -> RpcResult<bool>;
+
+	/// New best block subscription, sending the full blocks together with their events.
+	#[pubsub(subscription = "chain_newBlock", subscribe, name = "chain_subscribeNewBlocks")]
+	fn subscribe_new_blocks(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock>>,
+	);
+
+	/// Unsubscribe from new best block subscription.
+	#[pubsub(subscription = "chain_newBlock", unsubscribe, name = "chain_unsubscribeNewBlocks")]
+	fn unsubscribe_new_blocks(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool>;
+
+	/// Finalized block subscription, sending the full blocks together with their events.
+	///
+	/// Every finalized block is sent, in order, starting with the block numbered `from`, or the
+	/// last finalized block if not given. A client can resume after a reconnect without missing
+	/// blocks by passing the number following the last block it received. The events are read
+	/// from the state of the blocks, so `from` must be within the state pruning window, and the
+	/// subscription ends with an error if a block is pruned before it is sent.
+	#[pubsub(
+		subscription = "chain_finalizedBlock",
+		subscribe,
+		name = "chain_subscribeFinalizedBlocks"
+	)]
+	fn subscribe_finalized_blocks(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock>>,
+		from: Option<NumberOrHex>,
+	);
+
+	/// Unsubscribe from finalized block subscription.
+	#[pubsub(
+		subscription = "chain_finalizedBlock",
+		unsubscribe,
+		name = "chain_unsubscribeFinalizedBlocks"
+	)]
+	fn unsubscribe_finalized_blocks(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool>;
 }
@@ -0,0 +1,98 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate indexed transaction API.
+
+pub mod error;
+
+use self::error::Result;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+
+pub use self::gen_client::Client as IndexedTransactionClient;
+
+/// Indexed transaction of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransactionInfo<Hash, Number> {
+	/// Blake2b-256 content hash of the indexed data.
+	pub hash: Hash,
+	/// Size of the indexed data in bytes.
+	pub size: u32,
+	/// Number of the finalized block at which the block's reference to the data is pruned.
+	///
+	/// The data is kept for longer if it is renewed in a later block. `None` if blocks are never
+	/// pruned.
+	pub pruned_at: Option<Number>,
+}
+
+/// Indexed transaction data, with how long it is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransactionData<Number> {
+	/// The data.
+	pub data: Bytes,
+	/// Number of the finalized block at which the data is pruned, unless it is renewed in a later
+	/// block.
+	///
+	/// `None` if blocks are never pruned, and for blocks of the UnixFS DAG of larger data, which
+	/// are kept as long as that data.
+	pub pruned_at: Option<Number>,
+	/// Whether the data is pinned by the node operator, and so kept after `pruned_at`.
+	pub pinned: bool,
+}
+
+/// Substrate indexed transaction RPC API
+#[rpc]
+pub trait IndexedTransactionApi<Hash, Number> {
+	/// Get indexed transaction data by its blake2b-256 content hash.
+	#[rpc(name = "indexedTransaction_getByHash")]
+	fn indexed_transaction(&self, hash: Hash) -> Result<Option<IndexedTransactionData<Number>>>;
+
+	/// Get indexed transaction data, or a block of its UnixFS DAG, by CID.
+	///
+	/// The CID may use any of the content hashes the data was indexed with.
+	#[rpc(name = "indexedTransaction_getByCid")]
+	fn indexed_transaction_by_cid(
+		&self,
+		cid: String,
+	) -> Result<Option<IndexedTransactionData<Number>>>;
+
+	/// Get indexed transactions of a block, including renewed ones. Best block by default.
+	///
+	/// Transactions whose data is no longer stored by the node are left out.
+	#[rpc(name = "indexedTransaction_getBlockTransactions")]
+	fn block_indexed_transactions(
+		&self,
+		hash: Option<Hash>,
+	) -> Result<Option<Vec<IndexedTransactionInfo<Hash, Number>>>>;
+
+	/// Pin indexed transaction data stored by the node, so that it is kept when the blocks that
+	/// stored or renewed it are pruned. Returns `false` if the data is pinned already.
+	#[rpc(name = "indexedTransaction_pin")]
+	fn pin(&self, hash: Hash) -> Result<bool>;
+
+	/// Unpin indexed transaction data. Returns `false` if the data is not pinned.
+	#[rpc(name = "indexedTransaction_unpin")]
+	fn unpin(&self, hash: Hash) -> Result<bool>;
+
+	/// Get content hashes of pinned indexed transaction data.
+	#[rpc(name = "indexedTransaction_pinned")]
+	fn pinned(&self) -> Result<Vec<Hash>>;
+}
@@ -17,7 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! RPC Metadata
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 use futures::channel::mpsc;
 use jsonrpc_pubsub::{PubSubMetadata, Session};
@@ -30,6 +30,7 @@ use jsonrpc_pubsub::{PubSubMetadata, Session};
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	remote_ip: Option<IpAddr>,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -42,7 +43,17 @@ impl PubSubMetadata for Metadata {
 impl Metadata {
 	/// Create new `Metadata` with session (Pub/Sub) support.
 	pub fn new(transport: mpsc::UnboundedSender<String>) -> Self {
-		Metadata { session: Some(Arc::new(Session::new(transport))) }
+		Metadata { session: Some(Arc::new(Session::new(transport))), remote_ip: None }
+	}
+
+	/// Attach the IP address of the remote client.
+	pub fn with_remote_ip(self, remote_ip: IpAddr) -> Self {
+		Metadata { remote_ip: Some(remote_ip), ..self }
+	}
+
+	/// IP address of the remote client, if known.
+	pub fn remote_ip(&self) -> Option<IpAddr> {
+		self.remote_ip
 	}
 
 	/// Create new `Metadata` for tests.
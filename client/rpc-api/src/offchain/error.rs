@@ -29,6 +29,17 @@ pub enum Error {
 	/// Unavailable storage kind error.
 	#[error("This storage kind is not available yet.")]
 	UnavailableStorageKind,
+	/// The offchain database can't be iterated.
+	#[error("The offchain database doesn't support iterating over keys.")]
+	IterationUnsupported,
+	/// Provided count exceeds maximum value.
+	#[error("count exceeds maximum value. value: {}, max: {}", .value, .max)]
+	InvalidCount {
+		/// Provided value
+		value: u32,
+		/// Maximum allowed value
+		max: u32,
+	},
 	/// Call to an unsafe RPC was denied.
 	#[error(transparent)]
 	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
@@ -45,6 +56,16 @@ impl From<Error> for rpc::Error {
 				message: "This storage kind is not available yet".into(),
 				data: None,
 			},
+			Error::IterationUnsupported => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: "The offchain database doesn't support iterating over keys".into(),
+				data: None,
+			},
+			Error::InvalidCount { .. } => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 3),
+				message: format!("{}", e),
+				data: None,
+			},
 			Error::UnsafeRpcCalled(e) => e.into(),
 		}
 	}
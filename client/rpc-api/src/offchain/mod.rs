@@ -22,10 +22,21 @@ pub mod error;
 
 use self::error::Result;
 use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
 use sp_core::{offchain::StorageKind, Bytes};
 
 pub use self::gen_client::Client as OffchainClient;
 
+/// Block that last set an offchain local storage value through offchain indexing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexingOrigin {
+	/// Number of the block.
+	pub block_number: u64,
+	/// Hash of the block.
+	pub block_hash: Bytes,
+}
+
 /// Substrate offchain RPC API
 #[rpc]
 pub trait OffchainApi {
@@ -36,4 +47,58 @@ pub trait OffchainApi {
 	/// Get offchain local storage under given key and prefix.
 	#[rpc(name = "offchain_localStorageGet")]
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<Option<Bytes>>;
+
+	/// Returns the keys of offchain local storage with given prefix, in key order.
+	///
+	/// Up to `count` keys are returned, starting after `start_key` if given.
+	#[rpc(name = "offchain_localStorageKeysPaged")]
+	fn local_storage_keys_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> Result<Vec<Bytes>>;
+
+	/// Returns the keys and values of offchain local storage with given prefix, in key order.
+	///
+	/// Up to `count` entries are returned, starting after `start_key` if given.
+	#[rpc(name = "offchain_localStoragePairsPaged")]
+	fn local_storage_pairs_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> Result<Vec<(Bytes, Bytes)>>;
+
+	/// Remove offchain local storage under given key.
+	#[rpc(name = "offchain_localStorageClear")]
+	fn clear_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<()>;
+
+	/// Remove offchain local storage under all keys with given prefix.
+	///
+	/// Returns the number of removed entries.
+	#[rpc(name = "offchain_localStorageClearPrefix")]
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> Result<u32>;
+
+	/// Returns the block that last set offchain local storage under given key through offchain
+	/// indexing.
+	#[rpc(name = "offchain_localStorageIndexedAt")]
+	fn local_storage_indexed_at(
+		&self,
+		kind: StorageKind,
+		key: Bytes,
+	) -> Result<Option<IndexingOrigin>>;
+
+	/// Export the raw entries of the offchain database, in key order.
+	///
+	/// Up to `count` entries are returned, starting after `start_key` if given. The entries can be
+	/// imported into another node with `offchain_importStorage`.
+	#[rpc(name = "offchain_exportStorage")]
+	fn export_storage(&self, count: u32, start_key: Option<Bytes>) -> Result<Vec<(Bytes, Bytes)>>;
+
+	/// Import raw entries exported with `offchain_exportStorage`, overwriting existing ones.
+	#[rpc(name = "offchain_importStorage")]
+	fn import_storage(&self, entries: Vec<(Bytes, Bytes)>) -> Result<()>;
 }
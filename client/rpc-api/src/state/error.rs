@@ -51,6 +51,9 @@ pub enum Error {
 		/// Maximum allowed value
 		max: u32,
 	},
+	/// Provided count is zero.
+	#[error("count must be greater than zero")]
+	ZeroCount,
 	/// Call to an unsafe RPC was denied.
 	#[error(transparent)]
 	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
@@ -67,7 +70,7 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: None,
 			},
-			Error::InvalidCount { .. } => rpc::Error {
+			Error::InvalidCount { .. } | Error::ZeroCount => rpc::Error {
 				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
 				message: format!("{}", e),
 				data: None,
@@ -19,7 +19,10 @@
 //! Substrate state API helpers.
 
 use serde::{Deserialize, Serialize};
-use sp_core::Bytes;
+use sp_core::{
+	storage::{StorageData, StorageKey},
+	Bytes,
+};
 
 /// ReadProof struct returned by the RPC
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -30,3 +33,49 @@ pub struct ReadProof<Hash> {
 	/// A proof used to prove that storage entries are included in the storage trie
 	pub proof: Vec<Bytes>,
 }
+
+/// Position to resume a paged storage query from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageQueryCursor<Hash> {
+	/// Block to resume from.
+	pub block: Hash,
+	/// Last key returned for `block`, the query resumes after it.
+	pub key: Option<StorageKey>,
+}
+
+/// Changes of the storage entries under a prefix at a single block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoragePrefixChanges<Hash> {
+	/// Block hash
+	pub block: Hash,
+	/// A list of changes, ordered by key
+	pub changes: Vec<(StorageKey, Option<StorageData>)>,
+	/// Proof of the changed entries at `block`, if requested
+	pub proof: Option<ReadProof<Hash>>,
+}
+
+/// A page of storage changes returned by `state_queryStoragePaged`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageChangesPage<Hash> {
+	/// Changes per block, in block order. Blocks without changes are omitted.
+	pub changes: Vec<StoragePrefixChanges<Hash>>,
+	/// Cursor to fetch the next page with, `None` if the end of the range was reached.
+	pub next: Option<StorageQueryCursor<Hash>>,
+}
+
+/// Storage keys whose values differ between two blocks, returned by `state_storageDiff`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiff {
+	/// Keys only present at the second block
+	pub inserted: Vec<StorageKey>,
+	/// Keys present at both blocks with different values
+	pub changed: Vec<StorageKey>,
+	/// Keys only present at the first block
+	pub removed: Vec<StorageKey>,
+	/// Key to pass as `start_key` to fetch the next page, `None` if all keys were returned
+	pub next: Option<StorageKey>,
+}
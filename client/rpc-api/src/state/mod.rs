@@ -31,7 +31,12 @@ use sp_core::{
 };
 use sp_version::RuntimeVersion;
 
-pub use self::{gen_client::Client as StateClient, helpers::ReadProof};
+pub use self::{
+	gen_client::Client as StateClient,
+	helpers::{
+		ReadProof, StorageChangesPage, StorageDiff, StoragePrefixChanges, StorageQueryCursor,
+	},
+};
 
 /// Substrate state API
 #[rpc]
@@ -102,6 +107,25 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Query historical changes of the storage entries under `prefix` between blocks `from` and
+	/// `to`, up to `count` changes per page.
+	///
+	/// Like `state_queryStorage`, the first block of the range reports the initial state of all
+	/// entries. Pass the returned `next` cursor to continue, keeping `to` fixed. Pages may hold
+	/// fewer than `count` changes, even none, when the number of blocks or keys compared for a
+	/// single page is reached: only a missing `next` marks the end of the range. If `with_proof`
+	/// is set, the changes of each block come with a read proof.
+	#[rpc(name = "state_queryStoragePaged")]
+	fn query_storage_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		from: Hash,
+		to: Option<Hash>,
+		cursor: Option<StorageQueryCursor<Hash>>,
+		with_proof: Option<bool>,
+	) -> FutureResult<StorageChangesPage<Hash>>;
+
 	/// Query storage entries (by key) starting at block hash given as the second parameter.
 	#[rpc(name = "state_queryStorageAt")]
 	fn query_storage_at(
@@ -110,6 +134,22 @@ pub trait StateApi<Hash> {
 		at: Option<Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Returns up to `count` storage keys with `prefix` that were inserted, changed or removed
+	/// between the states of blocks `from` and `to`. All keys are compared if no prefix is given.
+	///
+	/// Keys are returned in order, following `start_key` if given. Pass the returned `next` key
+	/// as `start_key` to fetch the next page. The content of child tries is not compared: a
+	/// changed child trie is reported as a change of the key holding its root.
+	#[rpc(name = "state_storageDiff")]
+	fn storage_diff(
+		&self,
+		from: Hash,
+		to: Hash,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff>;
+
 	/// Returns proof of storage entries at a specific block's state.
 	#[rpc(name = "state_getReadProof")]
 	fn read_proof(
@@ -71,6 +71,17 @@ pub struct PeerInfo<Hash, Number> {
 	pub best_number: Number,
 }
 
+/// Peer banned from connecting to the node.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPeer {
+	/// Peer ID
+	pub peer_id: String,
+	/// UNIX timestamp in seconds the ban expires at. Missing if the ban is permanent.
+	#[serde(default = "Default::default", skip_serializing_if = "Option::is_none")]
+	pub until: Option<u64>,
+}
+
 /// The role the node is running as
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum NodeRole {
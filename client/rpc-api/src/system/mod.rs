@@ -29,7 +29,7 @@ use self::error::Result as SystemResult;
 
 pub use self::{
 	gen_client::Client as SystemClient,
-	helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo},
+	helpers::{BannedPeer, Health, NodeRole, PeerInfo, SyncState, SystemInfo},
 };
 
 /// Substrate system RPC API
@@ -107,6 +107,25 @@ pub trait SystemApi<Hash, Number> {
 	#[rpc(name = "system_reservedPeers", returns = "Vec<String>")]
 	fn system_reserved_peers(&self) -> Receiver<Vec<String>>;
 
+	/// Returns the list of banned peers.
+	#[rpc(name = "system_bannedPeers", returns = "Vec<BannedPeer>")]
+	fn system_banned_peers(&self) -> Receiver<Vec<BannedPeer>>;
+
+	/// Bans a peer, disconnecting it and refusing any further connection with it. The string
+	/// should encode only the PeerId. The ban expires after `duration` seconds, or never if no
+	/// duration is given. Bans are persisted across restarts.
+	#[rpc(name = "system_banPeer", returns = "()")]
+	fn system_ban_peer(
+		&self,
+		peer_id: String,
+		duration: Option<u64>,
+	) -> BoxFuture<Result<(), jsonrpc_core::Error>>;
+
+	/// Lifts the ban of a peer and resets its reputation. The string should encode only the
+	/// PeerId.
+	#[rpc(name = "system_unbanPeer", returns = "()")]
+	fn system_unban_peer(&self, peer_id: String) -> BoxFuture<Result<(), jsonrpc_core::Error>>;
+
 	/// Returns the roles the node is running as.
 	#[rpc(name = "system_nodeRoles", returns = "Vec<NodeRole>")]
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>>;
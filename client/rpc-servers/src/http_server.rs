@@ -0,0 +1,238 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! HTTP RPC server attributing requests to the IP address of their client.
+//!
+//! The server of `jsonrpc-http-server` doesn't pass the address of the peer to the metadata
+//! extractor, so connections are accepted here and handed to its [`http::ServerHandler`].
+
+use crate::{forwarded_ip, hosts_filtering, map_cors, ClientMetadata, RpcHandler, MEGABYTE};
+use futures::channel::oneshot;
+use http::hyper::{
+	self,
+	server::conn::{AddrIncoming, AddrStream},
+	service::{make_service_fn, Service},
+	Body, Request,
+};
+use log::error;
+use std::{
+	convert::Infallible,
+	io,
+	net::{IpAddr, Ipv4Addr, SocketAddr},
+	sync::{mpsc, Arc},
+	task::{Context, Poll},
+};
+
+/// IP address of the peer a request was received from.
+#[derive(Clone, Copy)]
+struct PeerIp(IpAddr);
+
+/// Tags requests with the address of the peer before passing them on to `handler`.
+struct PeerService<H> {
+	peer: IpAddr,
+	handler: H,
+}
+
+impl<H: Service<Request<Body>>> Service<Request<Body>> for PeerService<H> {
+	type Response = H::Response;
+	type Error = H::Error;
+	type Future = H::Future;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.handler.poll_ready(cx)
+	}
+
+	fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+		request.extensions_mut().insert(PeerIp(self.peer));
+		self.handler.call(request)
+	}
+}
+
+/// HTTP RPC server, running until closed.
+pub struct HttpServer {
+	address: SocketAddr,
+	close: oneshot::Sender<()>,
+	done: mpsc::Receiver<()>,
+}
+
+impl HttpServer {
+	/// Address the server listens on.
+	pub fn address(&self) -> &SocketAddr {
+		&self.address
+	}
+
+	/// Stop accepting connections and wait for the server to shut down.
+	pub fn close(self) {
+		let _ = self.close.send(());
+		let _ = self.done.recv();
+	}
+}
+
+/// Start HTTP server listening on given address.
+///
+/// Requests are attributed to the IP address of the peer or, with `trust_proxy_headers`, to the
+/// client address reported by a reverse proxy.
+pub fn start_http<M: ClientMetadata + Default + Unpin>(
+	addr: &SocketAddr,
+	cors: Option<&Vec<String>>,
+	io: RpcHandler<M>,
+	maybe_max_payload_mb: Option<usize>,
+	trust_proxy_headers: bool,
+	tokio_handle: tokio::runtime::Handle,
+) -> io::Result<HttpServer> {
+	let max_request_body_size = maybe_max_payload_mb
+		.map(|mb| mb.saturating_mul(MEGABYTE))
+		.unwrap_or(crate::RPC_MAX_PAYLOAD_DEFAULT);
+
+	let meta_extractor = move |request: &Request<Body>| {
+		let proxied_ip = if trust_proxy_headers { forwarded_ip(request) } else { None };
+		let metadata = M::default();
+		match proxied_ip.or_else(|| request.extensions().get::<PeerIp>().map(|peer| peer.0)) {
+			Some(ip) => metadata.with_remote_ip(ip),
+			None => metadata,
+		}
+	};
+	let rpc = http::Rpc { handler: Arc::new(io.into()), extractor: Arc::new(meta_extractor) };
+
+	let mut incoming = {
+		let _guard = tokio_handle.enter();
+		AddrIncoming::bind(addr).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+	};
+	incoming.set_nodelay(true);
+	// Recover from accept errors (e.g. too many open files) instead of stopping the server.
+	incoming.set_sleep_on_errors(true);
+	let address = incoming.local_addr();
+
+	let cors_domains: Option<Vec<http::AccessControlAllowOrigin>> = map_cors(cors).into();
+	let allowed_hosts: Option<Vec<http::Host>> = hosts_filtering(cors.is_some()).into();
+	let allowed_hosts = allowed_hosts.map(|hosts| with_local_hosts(hosts, &address));
+	let request_middleware: Arc<dyn http::RequestMiddleware> =
+		Arc::new(|request: Request<Body>| http::RequestMiddlewareAction::Proceed {
+			should_continue_on_invalid_cors: false,
+			request,
+		});
+	let rest_api = if cors.is_some() { http::RestApi::Secure } else { http::RestApi::Unsecure };
+	let health_api = Some(("/health".to_owned(), "system_health".to_owned()));
+
+	let make_service = make_service_fn(move |connection: &AddrStream| {
+		let handler = http::ServerHandler::new(
+			rpc.downgrade(),
+			cors_domains.clone(),
+			None,
+			http::cors::AccessControlAllowHeaders::Any,
+			allowed_hosts.clone(),
+			request_middleware.clone(),
+			rest_api,
+			health_api.clone(),
+			max_request_body_size,
+			true,
+		);
+		let service = PeerService { peer: connection.remote_addr().ip(), handler };
+		async move { Ok::<_, Infallible>(service) }
+	});
+
+	let (close, shutdown) = oneshot::channel();
+	let (done_sender, done) = mpsc::channel();
+	let server = hyper::Server::builder(incoming)
+		.http1_keepalive(true)
+		.serve(make_service)
+		.with_graceful_shutdown(async {
+			let _ = shutdown.await;
+		});
+	tokio_handle.spawn(async move {
+		if let Err(err) = server.await {
+			error!("Error running HTTP RPC server: {:?}", err);
+		}
+		let _ = done_sender.send(());
+	});
+
+	Ok(HttpServer { address, close, done })
+}
+
+/// Add the addresses the server listening on `address` is reached at to the allowed `hosts`.
+fn with_local_hosts(mut hosts: Vec<http::Host>, address: &SocketAddr) -> Vec<http::Host> {
+	let address_string = address.to_string();
+	if address.ip() == IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
+		hosts.push(address_string.replace("0.0.0.0", "127.0.0.1").into());
+		hosts.push(address_string.replace("0.0.0.0", "localhost").into());
+	} else if address.ip() == IpAddr::V4(Ipv4Addr::LOCALHOST) {
+		hosts.push(address_string.replace("127.0.0.1", "localhost").into());
+	}
+	hosts.push(address_string.into());
+	hosts
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{rpc_handler, RpcMiddleware};
+	use jsonrpc_core::{MetaIoHandler, Params, Value};
+	use std::io::{Read, Write};
+
+	fn call(address: &SocketAddr, headers: &str) -> String {
+		let body = r#"{"jsonrpc":"2.0","method":"remote_ip","params":[],"id":1}"#;
+		let mut stream = std::net::TcpStream::connect(address).unwrap();
+		write!(
+			stream,
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+			Content-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+			address,
+			body.len(),
+			headers,
+			body,
+		)
+		.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	#[test]
+	fn attributes_requests_to_peer() {
+		let runtime = tokio::runtime::Runtime::new().unwrap();
+		let start = |trust_proxy_headers| {
+			let mut io = MetaIoHandler::<sc_rpc_api::Metadata>::default();
+			io.add_method_with_meta(
+				"remote_ip",
+				|_: Params, meta: sc_rpc_api::Metadata| async move {
+					Ok(Value::String(format!("{:?}", meta.remote_ip())))
+				},
+			);
+			start_http(
+				&"127.0.0.1:0".parse().unwrap(),
+				None,
+				rpc_handler(io, RpcMiddleware::new(None, Default::default(), "http")),
+				None,
+				trust_proxy_headers,
+				runtime.handle().clone(),
+			)
+			.unwrap()
+		};
+		let forwarded = "X-Forwarded-For: 10.0.0.1\r\n";
+
+		let server = start(false);
+		assert!(call(server.address(), "").contains("Some(127.0.0.1)"));
+		assert!(call(server.address(), forwarded).contains("Some(127.0.0.1)"));
+		server.close();
+
+		let server = start(true);
+		assert!(call(server.address(), "").contains("Some(127.0.0.1)"));
+		assert!(call(server.address(), forwarded).contains("Some(10.0.0.1)"));
+		server.close();
+	}
+}
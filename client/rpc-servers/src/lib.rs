@@ -20,12 +20,14 @@
 
 #![warn(missing_docs)]
 
+mod http_server;
+mod method_filter;
 mod middleware;
+mod rate_limit;
 
 use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
 use log::error;
 use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
-use pubsub::PubSubMetadata;
 use std::io;
 
 const MEGABYTE: usize = 1024 * 1024;
@@ -42,16 +44,31 @@ const WS_MAX_CONNECTIONS: usize = 100;
 /// The RPC IoHandler containing all requested APIs.
 pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
+pub use http_server::{start_http, HttpServer};
+pub use method_filter::{RpcMethodFilter, RpcMethodFilters};
 pub use middleware::{method_names, RpcMetrics, RpcMiddleware};
+pub use rate_limit::{
+	forwarded_ip, ClientMetadata, LimitExceeded, RpcClient, RpcRateLimiter, RpcRateLimits,
+	RATE_LIMIT_ERROR_CODE,
+};
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<M: PubSubMetadata>(
+///
+/// Only methods allowed by the method filter of `rpc_middleware` are exposed.
+pub fn rpc_handler<M: ClientMetadata>(
 	extension: impl IoHandlerExtension<M>,
 	rpc_middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
+	let mut all_methods = MetaIoHandler::<M>::default();
+	extension.augment(&mut all_methods);
+	let method_filter = rpc_middleware.method_filter().clone();
 	let io_handler = MetaIoHandler::with_middleware(rpc_middleware);
 	let mut io = pubsub::PubSubHandler::new(io_handler);
-	extension.augment(&mut io);
+	all_methods
+		.into_iter()
+		.filter(|(name, _)| method_filter.allows(name))
+		.collect::<Vec<_>>()
+		.augment(&mut io);
 
 	// add an endpoint to list all available methods.
 	let mut methods = io.iter().map(|x| x.0.clone()).collect::<Vec<String>>();
@@ -112,8 +129,6 @@ impl ServerMetrics {
 
 /// Type alias for ipc server
 pub type IpcServer = ipc::Server;
-/// Type alias for http server
-pub type HttpServer = http::Server;
 /// Type alias for ws server
 pub type WsServer = ws::Server;
 
@@ -127,31 +142,8 @@ impl ws::SessionStats for ServerMetrics {
 	}
 }
 
-/// Start HTTP server listening on given address.
-pub fn start_http<M: pubsub::PubSubMetadata + Default + Unpin>(
-	addr: &std::net::SocketAddr,
-	cors: Option<&Vec<String>>,
-	io: RpcHandler<M>,
-	maybe_max_payload_mb: Option<usize>,
-	tokio_handle: tokio::runtime::Handle,
-) -> io::Result<http::Server> {
-	let max_request_body_size = maybe_max_payload_mb
-		.map(|mb| mb.saturating_mul(MEGABYTE))
-		.unwrap_or(RPC_MAX_PAYLOAD_DEFAULT);
-
-	http::ServerBuilder::new(io)
-		.threads(1)
-		.event_loop_executor(tokio_handle)
-		.health_api(("/health", "system_health"))
-		.allowed_hosts(hosts_filtering(cors.is_some()))
-		.rest_api(if cors.is_some() { http::RestApi::Secure } else { http::RestApi::Unsecure })
-		.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-		.max_request_body_size(max_request_body_size)
-		.start_http(addr)
-}
-
 /// Start IPC server listening on given path.
-pub fn start_ipc<M: pubsub::PubSubMetadata + Default>(
+pub fn start_ipc<M: ClientMetadata + Default>(
 	addr: &str,
 	io: RpcHandler<M>,
 	server_metrics: ServerMetrics,
@@ -167,9 +159,7 @@ pub fn start_ipc<M: pubsub::PubSubMetadata + Default>(
 }
 
 /// Start WS server listening on given address.
-pub fn start_ws<
-	M: pubsub::PubSubMetadata + From<futures::channel::mpsc::UnboundedSender<String>>,
->(
+pub fn start_ws<M: ClientMetadata + From<futures::channel::mpsc::UnboundedSender<String>>>(
 	addr: &std::net::SocketAddr,
 	max_connections: Option<usize>,
 	cors: Option<&Vec<String>>,
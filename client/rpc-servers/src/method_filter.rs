@@ -0,0 +1,174 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Filtering of the RPC methods exposed by a server.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// Patterns of RPC methods to expose.
+///
+/// Patterns may contain `*` matching any sequence of characters and `?` matching any single
+/// character, e.g. `state_*`. A method is exposed if it matches an `allow` pattern, or `allow` is
+/// empty, and doesn't match any `deny` pattern. This is applied on top of the checks of unsafe
+/// methods.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcMethodFilter {
+	/// Patterns of methods to expose. All methods are exposed if empty.
+	pub allow: Vec<String>,
+	/// Patterns of methods not to expose.
+	pub deny: Vec<String>,
+}
+
+impl RpcMethodFilter {
+	/// Returns `true` if the filter doesn't hide any method.
+	pub fn is_empty(&self) -> bool {
+		self.allow.is_empty() && self.deny.is_empty()
+	}
+
+	/// Returns `true` if `method` is exposed.
+	pub fn allows(&self, method: &str) -> bool {
+		let matches = |pattern: &String| glob_match(pattern.as_bytes(), method.as_bytes());
+		(self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+	}
+
+	/// Check that every pattern matches one of `methods`, to catch mistyped method names.
+	pub fn validate(&self, methods: &HashSet<String>) -> Result<(), String> {
+		for pattern in self.allow.iter().chain(&self.deny) {
+			if !methods.iter().any(|method| glob_match(pattern.as_bytes(), method.as_bytes())) {
+				return Err(format!("RPC method pattern `{}` doesn't match any method", pattern))
+			}
+		}
+		Ok(())
+	}
+
+	/// Add the patterns of `other` to this filter.
+	pub fn extend(&mut self, other: &RpcMethodFilter) {
+		self.allow.extend(other.allow.iter().cloned());
+		self.deny.extend(other.deny.iter().cloned());
+	}
+}
+
+/// Methods to expose over each RPC interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcMethodFilters {
+	/// Methods exposed over HTTP.
+	pub http: RpcMethodFilter,
+	/// Methods exposed over WebSockets.
+	pub ws: RpcMethodFilter,
+	/// Methods exposed over IPC.
+	pub ipc: RpcMethodFilter,
+}
+
+impl RpcMethodFilters {
+	/// Check the filters of all interfaces, see [`RpcMethodFilter::validate`].
+	pub fn validate(&self, methods: &HashSet<String>) -> Result<(), String> {
+		self.http.validate(methods)?;
+		self.ws.validate(methods)?;
+		self.ipc.validate(methods)
+	}
+}
+
+/// Match `name` against a glob `pattern` supporting `*` and `?`.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+	let (mut p, mut n) = (0, 0);
+	// Position of the last `*` in the pattern and of the name it was matched at.
+	let mut backtrack = None;
+	while n < name.len() {
+		match pattern.get(p) {
+			Some(b'*') => {
+				backtrack = Some((p, n));
+				p += 1;
+			},
+			Some(&c) if c == b'?' || c == name[n] => {
+				p += 1;
+				n += 1;
+			},
+			_ => match backtrack {
+				// Let the last `*` match one more character.
+				Some((star, matched)) => {
+					backtrack = Some((star, matched + 1));
+					p = star + 1;
+					n = matched + 1;
+				},
+				None => return false,
+			},
+		}
+	}
+	pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn filter(allow: &[&str], deny: &[&str]) -> RpcMethodFilter {
+		RpcMethodFilter {
+			allow: allow.iter().map(|p| p.to_string()).collect(),
+			deny: deny.iter().map(|p| p.to_string()).collect(),
+		}
+	}
+
+	#[test]
+	fn glob_patterns() {
+		assert!(glob_match(b"state_getKeys", b"state_getKeys"));
+		assert!(!glob_match(b"state_getKeys", b"state_getKeysPaged"));
+		assert!(glob_match(b"state_*", b"state_getKeysPaged"));
+		assert!(glob_match(b"*_get*", b"state_getKeys"));
+		assert!(glob_match(b"*Keys*", b"state_getKeysPaged"));
+		assert!(!glob_match(b"*_get*", b"system_health"));
+		assert!(glob_match(b"chain_?ubscribe*", b"chain_subscribeNewHeads"));
+		assert!(!glob_match(b"chain_?ubscribe*", b"chain_unsubscribeNewHeads"));
+		assert!(glob_match(b"*", b""));
+		assert!(!glob_match(b"?", b""));
+	}
+
+	#[test]
+	fn allows_methods() {
+		assert!(filter(&[], &[]).allows("author_rotateKeys"));
+
+		let filter = filter(&["state_*", "system_*"], &["state_traceBlock", "system_add*"]);
+		assert!(filter.allows("state_getStorage"));
+		assert!(filter.allows("system_health"));
+		assert!(!filter.allows("state_traceBlock"));
+		assert!(!filter.allows("system_addReservedPeer"));
+		assert!(!filter.allows("author_rotateKeys"));
+	}
+
+	#[test]
+	fn validates_patterns() {
+		let methods = ["state_getStorage", "system_health"].iter().map(|m| m.to_string()).collect();
+
+		assert_eq!(filter(&["state_*"], &["system_health"]).validate(&methods), Ok(()));
+		assert!(filter(&["state_*"], &["system_helth"]).validate(&methods).is_err());
+	}
+
+	#[test]
+	fn parses_filters() {
+		let filters: RpcMethodFilters = serde_json::from_str(
+			r#"{ "http": { "deny": ["author_*"] }, "ws": { "allow": ["*"] } }"#,
+		)
+		.unwrap();
+
+		assert_eq!(filters.http, filter(&[], &["author_*"]));
+		assert_eq!(filters.ws, filter(&["*"], &[]));
+		assert!(filters.ipc.is_empty());
+	}
+}
@@ -20,15 +20,18 @@
 
 use std::collections::HashSet;
 
-use jsonrpc_core::{FutureOutput, FutureResponse, Metadata, Middleware as RequestMiddleware};
+use jsonrpc_core::{FutureOutput, FutureResponse, Middleware as RequestMiddleware};
 use prometheus_endpoint::{
 	register, CounterVec, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry, U64,
 };
 
 use futures::{future::Either, Future, FutureExt};
-use pubsub::PubSubMetadata;
 
-use crate::RpcHandler;
+use crate::{
+	method_filter::RpcMethodFilter,
+	rate_limit::{ClientMetadata, RpcRateLimiter, RATE_LIMIT_ERROR_CODE},
+	RpcHandler,
+};
 
 /// Metrics for RPC middleware
 #[derive(Debug, Clone)]
@@ -38,6 +41,7 @@ pub struct RpcMetrics {
 	calls_time: HistogramVec,
 	calls_started: CounterVec<U64>,
 	calls_finished: CounterVec<U64>,
+	calls_rate_limited: CounterVec<U64>,
 }
 
 impl RpcMetrics {
@@ -95,6 +99,16 @@ impl RpcMetrics {
 					)?,
 					r,
 				)?,
+				calls_rate_limited: register(
+					CounterVec::new(
+						Opts::new(
+							"substrate_rpc_calls_rate_limited",
+							"Number of RPC calls rejected for exceeding a rate limit",
+						),
+						&["protocol", "method", "limit"],
+					)?,
+					r,
+				)?,
 			}))
 		} else {
 			Ok(None)
@@ -106,7 +120,7 @@ impl RpcMetrics {
 pub fn method_names<F, M, E>(gen_handler: F) -> Result<HashSet<String>, E>
 where
 	F: FnOnce(RpcMiddleware) -> Result<RpcHandler<M>, E>,
-	M: PubSubMetadata,
+	M: ClientMetadata,
 {
 	let io = gen_handler(RpcMiddleware::new(None, HashSet::new(), "dummy"))?;
 	Ok(io.iter().map(|x| x.0.clone()).collect())
@@ -117,6 +131,8 @@ pub struct RpcMiddleware {
 	metrics: Option<RpcMetrics>,
 	known_rpc_method_names: HashSet<String>,
 	transport_label: String,
+	rate_limiter: Option<RpcRateLimiter>,
+	method_filter: RpcMethodFilter,
 }
 
 impl RpcMiddleware {
@@ -129,11 +145,34 @@ impl RpcMiddleware {
 		known_rpc_method_names: HashSet<String>,
 		transport_label: &str,
 	) -> Self {
-		RpcMiddleware { metrics, known_rpc_method_names, transport_label: transport_label.into() }
+		RpcMiddleware {
+			metrics,
+			known_rpc_method_names,
+			transport_label: transport_label.into(),
+			rate_limiter: None,
+			method_filter: Default::default(),
+		}
+	}
+
+	/// Reject calls exceeding the limits of `rate_limiter`.
+	pub fn with_rate_limiter(mut self, rate_limiter: RpcRateLimiter) -> Self {
+		self.rate_limiter = Some(rate_limiter);
+		self
+	}
+
+	/// Only expose the methods allowed by `method_filter` in handlers built with this middleware.
+	pub fn with_method_filter(mut self, method_filter: RpcMethodFilter) -> Self {
+		self.method_filter = method_filter;
+		self
+	}
+
+	/// Filter of the methods exposed in handlers built with this middleware.
+	pub fn method_filter(&self) -> &RpcMethodFilter {
+		&self.method_filter
 	}
 }
 
-impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
+impl<M: ClientMetadata> RequestMiddleware<M> for RpcMiddleware {
 	type Future = FutureResponse;
 	type CallFuture = FutureOutput;
 
@@ -186,6 +225,28 @@ impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
 				.with_label_values(&[transport_label.as_str(), name.as_str()])
 				.inc();
 		}
+		if let Some(ref rate_limiter) = self.rate_limiter {
+			if let Err(limit) = rate_limiter.check(meta.client(), &name) {
+				log::debug!(
+					target: "rpc_metrics",
+					"[{}] {} call exceeded {} rate limit",
+					transport_label,
+					name,
+					limit.label(),
+				);
+				if let Some(ref metrics) = metrics {
+					metrics
+						.calls_rate_limited
+						.with_label_values(&[
+							transport_label.as_str(),
+							name.as_str(),
+							limit.label(),
+						])
+						.inc();
+				}
+				return Either::Left(futures::future::ready(rate_limited(call)).boxed())
+			}
+		}
 		let r = next(call, meta);
 		Either::Left(
 			async move {
@@ -236,6 +297,21 @@ fn call_name<'a>(call: &'a jsonrpc_core::Call, known_methods: &HashSet<String>)
 	}
 }
 
+/// Error response to a call rejected for exceeding a rate limit.
+fn rate_limited(call: jsonrpc_core::Call) -> Option<jsonrpc_core::Output> {
+	let (id, jsonrpc) = match call {
+		jsonrpc_core::Call::MethodCall(call) => (call.id, call.jsonrpc),
+		jsonrpc_core::Call::Invalid { id } => (id, None),
+		jsonrpc_core::Call::Notification(_) => return None,
+	};
+	let error = jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(RATE_LIMIT_ERROR_CODE),
+		message: "RPC rate limit exceeded".into(),
+		data: None,
+	};
+	Some(jsonrpc_core::Output::from(Err(error), id, jsonrpc))
+}
+
 fn is_success(output: &Option<jsonrpc_core::Output>) -> bool {
 	match output {
 		Some(jsonrpc_core::Output::Success(..)) => true,
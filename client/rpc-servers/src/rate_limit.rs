@@ -0,0 +1,431 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Rate limiting of RPC calls.
+
+use pubsub::PubSubMetadata;
+use std::{
+	collections::{HashMap, HashSet},
+	net::IpAddr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// Error code of calls rejected for exceeding a rate limit.
+pub const RATE_LIMIT_ERROR_CODE: i64 = -32005;
+
+/// Default cost of calls to expensive methods. Calls to other methods cost 1.
+///
+/// Methods are named as in JSON-RPC requests, e.g. `state_getPairs` rather than `storage_pairs`.
+const DEFAULT_METHOD_COSTS: &[(&str, u32)] = &[
+	("state_queryStorage", 20),
+	("state_queryStorageAt", 10),
+	("state_queryStoragePaged", 20),
+	("state_storageDiff", 50),
+	("state_traceBlock", 50),
+	("state_getPairs", 20),
+	("state_getKeys", 10),
+	("childstate_getKeys", 10),
+];
+
+/// Number of tracked clients above which idle ones are forgotten.
+const MIN_CLEANUP_THRESHOLD: usize = 1024;
+
+/// Time after which the limits of an idle client are forgotten.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Rate limits applied to the RPC calls of each client.
+///
+/// Calls over HTTP are attributed to the IP address of the client. Calls over WebSockets are
+/// attributed to their connection, as the WebSocket server doesn't expose the address of the
+/// peer, so each connection gets its own limits. Calls over IPC are not limited.
+#[derive(Debug, Clone)]
+pub struct RpcRateLimits {
+	/// Total cost of calls a client may make per second. Unlimited if `None`.
+	pub client_cost: Option<u32>,
+	/// Number of calls per second a client may make to specific methods.
+	pub method_calls: HashMap<String, u32>,
+	/// Cost of calls to specific methods. Calls to other methods cost 1.
+	pub method_costs: HashMap<String, u32>,
+	/// Attribute HTTP requests to the client address in the `X-Real-IP` or `X-Forwarded-For`
+	/// headers set by a reverse proxy, rather than to the address of the peer.
+	pub trust_proxy_headers: bool,
+}
+
+impl Default for RpcRateLimits {
+	fn default() -> Self {
+		Self {
+			client_cost: None,
+			method_calls: HashMap::new(),
+			method_costs: DEFAULT_METHOD_COSTS
+				.iter()
+				.map(|(method, cost)| (method.to_string(), *cost))
+				.collect(),
+			trust_proxy_headers: false,
+		}
+	}
+}
+
+impl RpcRateLimits {
+	/// Returns `true` if any call may be rejected.
+	pub fn is_enabled(&self) -> bool {
+		self.client_cost.is_some() || !self.method_calls.is_empty()
+	}
+
+	/// Cost of a call to the given method.
+	pub fn cost(&self, method: &str) -> u32 {
+		self.method_costs.get(method).copied().unwrap_or(1)
+	}
+
+	/// Check that limits and costs set in addition to the defaults are for one of `methods`, to
+	/// catch mistyped method names.
+	pub fn validate(&self, methods: &HashSet<String>) -> Result<(), String> {
+		let is_default = |method: &String| DEFAULT_METHOD_COSTS.iter().any(|(m, _)| m == method);
+		let configured = self
+			.method_calls
+			.keys()
+			.chain(self.method_costs.keys().filter(|m| !is_default(m)));
+		for method in configured {
+			if !methods.contains(method) {
+				return Err(format!("RPC method `{}` of a rate limit doesn't exist", method))
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Client of RPC calls that limits are tracked for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RpcClient {
+	/// Client with a known IP address.
+	Ip(IpAddr),
+	/// Client connected over a persistent session, identified by the session.
+	Session(usize),
+	/// Client that can't be told apart from other such clients.
+	Anonymous,
+}
+
+/// RPC metadata identifying the client of a call.
+pub trait ClientMetadata: PubSubMetadata {
+	/// IP address of the client, if known.
+	fn remote_ip(&self) -> Option<IpAddr>;
+
+	/// Attach the IP address of the client.
+	fn with_remote_ip(self, ip: IpAddr) -> Self;
+
+	/// Client the call is attributed to.
+	fn client(&self) -> RpcClient {
+		if let Some(ip) = self.remote_ip() {
+			RpcClient::Ip(ip)
+		} else if let Some(session) = self.session() {
+			RpcClient::Session(Arc::as_ptr(&session) as usize)
+		} else {
+			RpcClient::Anonymous
+		}
+	}
+}
+
+impl ClientMetadata for sc_rpc_api::Metadata {
+	fn remote_ip(&self) -> Option<IpAddr> {
+		sc_rpc_api::Metadata::remote_ip(self)
+	}
+
+	fn with_remote_ip(self, ip: IpAddr) -> Self {
+		sc_rpc_api::Metadata::with_remote_ip(self, ip)
+	}
+}
+
+/// IP address of the client of an HTTP request, as reported by a reverse proxy.
+///
+/// Only the last address of `X-Forwarded-For` is used, earlier ones are set by the client.
+pub fn forwarded_ip<B>(request: &http::hyper::Request<B>) -> Option<IpAddr> {
+	let headers = request.headers();
+	if let Some(ip) = headers.get("x-real-ip") {
+		return ip.to_str().ok()?.trim().parse().ok()
+	}
+	headers
+		.get_all("x-forwarded-for")
+		.iter()
+		.next_back()?
+		.to_str()
+		.ok()?
+		.rsplit(',')
+		.next()?
+		.trim()
+		.parse()
+		.ok()
+}
+
+/// Limit exceeded by a rejected call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+	/// Total cost of calls made by the client.
+	Client,
+	/// Number of calls to the method made by the client.
+	Method,
+}
+
+impl LimitExceeded {
+	/// Label of the limit in metrics.
+	pub fn label(&self) -> &'static str {
+		match self {
+			LimitExceeded::Client => "client",
+			LimitExceeded::Method => "method",
+		}
+	}
+}
+
+/// Token bucket refilled at `rate` tokens per second, holding at most `rate` tokens.
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl Bucket {
+	fn new(rate: u32, now: Instant) -> Self {
+		Self { tokens: rate as f64, updated: now }
+	}
+
+	fn refill(&mut self, rate: u32, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+		self.updated = now;
+	}
+
+	/// Whether `amount` tokens may be taken.
+	///
+	/// Amounts over the capacity of the bucket may be taken from a full bucket, leaving it in
+	/// debt.
+	fn allows(&self, rate: u32, amount: u32) -> bool {
+		self.tokens >= amount.min(rate) as f64
+	}
+
+	fn take(&mut self, amount: u32) {
+		self.tokens -= amount as f64;
+	}
+}
+
+#[derive(Default)]
+struct Buckets {
+	/// Buckets of clients (`None`) and of their calls to specific methods.
+	buckets: HashMap<(RpcClient, Option<String>), Bucket>,
+	cleanup_threshold: usize,
+}
+
+/// Enforces [`RpcRateLimits`], shared by all RPC servers.
+#[derive(Clone)]
+pub struct RpcRateLimiter {
+	limits: Arc<RpcRateLimits>,
+	buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RpcRateLimiter {
+	/// Create a new instance enforcing `limits`.
+	pub fn new(limits: RpcRateLimits) -> Self {
+		Self { limits: Arc::new(limits), buckets: Default::default() }
+	}
+
+	/// Account for a call of `client` to `method`, unless it exceeds a limit.
+	pub fn check(&self, client: RpcClient, method: &str) -> Result<(), LimitExceeded> {
+		self.check_at(client, method, Instant::now())
+	}
+
+	fn check_at(&self, client: RpcClient, method: &str, now: Instant) -> Result<(), LimitExceeded> {
+		let method_rate = self.limits.method_calls.get(method).copied();
+		let client_rate = self.limits.client_cost;
+		if method_rate.is_none() && client_rate.is_none() {
+			return Ok(())
+		}
+		let cost = self.limits.cost(method);
+
+		let mut buckets = self.buckets.lock().expect("Rate limiter lock is never poisoned; qed");
+		buckets.cleanup(now);
+		if let Some(rate) = method_rate {
+			let bucket = buckets
+				.buckets
+				.entry((client.clone(), Some(method.into())))
+				.or_insert_with(|| Bucket::new(rate, now));
+			bucket.refill(rate, now);
+			if !bucket.allows(rate, 1) {
+				return Err(LimitExceeded::Method)
+			}
+		}
+		if let Some(rate) = client_rate {
+			let bucket = buckets
+				.buckets
+				.entry((client.clone(), None))
+				.or_insert_with(|| Bucket::new(rate, now));
+			bucket.refill(rate, now);
+			if !bucket.allows(rate, cost) {
+				return Err(LimitExceeded::Client)
+			}
+			bucket.take(cost);
+		}
+		if method_rate.is_some() {
+			if let Some(bucket) = buckets.buckets.get_mut(&(client, Some(method.into()))) {
+				bucket.take(1);
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Buckets {
+	/// Forget idle clients once too many are tracked.
+	fn cleanup(&mut self, now: Instant) {
+		if self.buckets.len() < self.cleanup_threshold.max(MIN_CLEANUP_THRESHOLD) {
+			return
+		}
+		self.buckets
+			.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < IDLE_TIMEOUT);
+		self.cleanup_threshold = self.buckets.len() * 2;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn limiter(client_cost: Option<u32>, method_calls: &[(&str, u32)]) -> RpcRateLimiter {
+		RpcRateLimiter::new(RpcRateLimits {
+			client_cost,
+			method_calls: method_calls.iter().map(|(m, c)| (m.to_string(), *c)).collect(),
+			..Default::default()
+		})
+	}
+
+	#[test]
+	fn reads_forwarded_ip() {
+		let request = |headers: &[(&str, &str)]| {
+			let mut request = http::hyper::Request::builder();
+			for (name, value) in headers {
+				request = request.header(*name, *value);
+			}
+			request.body(()).unwrap()
+		};
+
+		assert_eq!(forwarded_ip(&request(&[])), None);
+		assert_eq!(
+			forwarded_ip(&request(&[("X-Forwarded-For", "10.0.0.1, 192.168.1.2")])),
+			Some([192, 168, 1, 2].into()),
+		);
+		assert_eq!(
+			forwarded_ip(&request(&[
+				("X-Forwarded-For", "10.0.0.1"),
+				("X-Real-IP", "192.168.1.3")
+			])),
+			Some([192, 168, 1, 3].into()),
+		);
+		assert_eq!(forwarded_ip(&request(&[("X-Forwarded-For", "unknown")])), None);
+	}
+
+	#[test]
+	fn validates_methods() {
+		let methods = ["state_getPairs", "system_health"].iter().map(|m| m.to_string()).collect();
+		let limits = |method_calls: &[(&str, u32)], method_costs: &[(&str, u32)]| {
+			let mut limits = RpcRateLimits::default();
+			limits
+				.method_calls
+				.extend(method_calls.iter().map(|(m, c)| (m.to_string(), *c)));
+			limits
+				.method_costs
+				.extend(method_costs.iter().map(|(m, c)| (m.to_string(), *c)));
+			limits
+		};
+
+		// Default costs of methods that aren't exposed are fine.
+		assert_eq!(limits(&[], &[]).validate(&methods), Ok(()));
+		assert_eq!(
+			limits(&[("system_health", 1)], &[("state_getPairs", 5)]).validate(&methods),
+			Ok(())
+		);
+		assert!(limits(&[], &[("storage_pairs", 5)]).validate(&methods).is_err());
+		assert!(limits(&[("storage_pairs", 5)], &[]).validate(&methods).is_err());
+	}
+
+	#[test]
+	fn unlimited_by_default() {
+		let limiter = RpcRateLimiter::new(Default::default());
+		let now = Instant::now();
+		for _ in 0..1000 {
+			assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_traceBlock", now), Ok(()));
+		}
+	}
+
+	#[test]
+	fn limits_client_cost() {
+		let limiter = limiter(Some(30), &[]);
+		let client = RpcClient::Ip([127, 0, 0, 1].into());
+		let other = RpcClient::Session(1);
+		let now = Instant::now();
+
+		assert_eq!(limiter.check_at(client.clone(), "state_queryStorage", now), Ok(()));
+		assert_eq!(
+			limiter.check_at(client.clone(), "state_getPairs", now),
+			Err(LimitExceeded::Client)
+		);
+		for _ in 0..10 {
+			assert_eq!(limiter.check_at(client.clone(), "system_health", now), Ok(()));
+		}
+		assert_eq!(
+			limiter.check_at(client.clone(), "system_health", now),
+			Err(LimitExceeded::Client)
+		);
+		// Other clients have their own limits.
+		assert_eq!(limiter.check_at(other, "state_getPairs", now), Ok(()));
+		// Limits recover over time.
+		let later = now + Duration::from_millis(100);
+		for _ in 0..3 {
+			assert_eq!(limiter.check_at(client.clone(), "system_health", later), Ok(()));
+		}
+		assert_eq!(limiter.check_at(client, "system_health", later), Err(LimitExceeded::Client));
+	}
+
+	#[test]
+	fn calls_costlier_than_limit_need_full_budget() {
+		let limiter = limiter(Some(10), &[]);
+		let now = Instant::now();
+
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_traceBlock", now), Ok(()));
+		// The call left the client in debt for 4 seconds.
+		let later = now + Duration::from_secs(4);
+		assert_eq!(
+			limiter.check_at(RpcClient::Anonymous, "system_health", later),
+			Err(LimitExceeded::Client),
+		);
+		let later = now + Duration::from_secs(5);
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_traceBlock", later), Ok(()));
+	}
+
+	#[test]
+	fn limits_method_calls() {
+		let limiter = limiter(Some(100), &[("state_getKeys", 2)]);
+		let now = Instant::now();
+
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_getKeys", now), Ok(()));
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_getKeys", now), Ok(()));
+		assert_eq!(
+			limiter.check_at(RpcClient::Anonymous, "state_getKeys", now),
+			Err(LimitExceeded::Method),
+		);
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "system_health", now), Ok(()));
+		// Rejected calls don't count against the client budget.
+		let later = now + Duration::from_millis(500);
+		assert_eq!(limiter.check_at(RpcClient::Anonymous, "state_getKeys", later), Ok(()));
+	}
+}
@@ -18,39 +18,69 @@
 
 //! Blockchain API backend for full nodes.
 
-use super::{client_err, error::FutureResult, ChainBackend};
-use futures::FutureExt;
-use jsonrpc_pubsub::manager::SubscriptionManager;
-use sc_client_api::{BlockBackend, BlockchainEvents};
+use super::{
+	client_err,
+	error::{Error, FutureResult},
+	events::EventsDecoder,
+	BlockWithEvents, ChainBackend,
+};
+use futures::{future, stream, FutureExt, StreamExt, TryStreamExt};
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents, StorageProvider};
+use sp_api::{Metadata, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys, StorageKey};
+use sp_rpc::number::NumberOrHex;
 use sp_runtime::{
 	generic::{BlockId, SignedBlock},
-	traits::Block as BlockT,
+	traits::{Block as BlockT, Header, NumberFor, One, Saturating},
 };
 use std::{marker::PhantomData, sync::Arc};
 
+/// Maximal number of already finalized blocks a finalized block subscription may start with.
+///
+/// Notifications are buffered without limit, so this bounds the memory used by one subscription.
+const MAX_FINALIZED_BLOCKS_REPLAY: u32 = 4096;
+
+/// Events decoder of the last runtime seen by the subscriptions, with the hash of its code.
+type DecoderCache<Block> =
+	Arc<Mutex<Option<(<Block as BlockT>::Hash, Option<Arc<EventsDecoder>>)>>>;
+
 /// Blockchain API backend for full nodes. Reads all the data from local database.
-pub struct FullChain<Block: BlockT, Client> {
+pub struct FullChain<BE, Block: BlockT, Client> {
 	/// Substrate client.
 	client: Arc<Client>,
 	/// Current subscriptions.
 	subscriptions: SubscriptionManager,
+	/// Decoder of the events sent by the subscriptions.
+	decoder: DecoderCache<Block>,
 	/// phantom member to pin the block type
-	_phantom: PhantomData<Block>,
+	_phantom: PhantomData<(BE, Block)>,
 }
 
-impl<Block: BlockT, Client> FullChain<Block, Client> {
+impl<BE, Block: BlockT, Client> FullChain<BE, Block, Client> {
 	/// Create new Chain API RPC handler.
 	pub fn new(client: Arc<Client>, subscriptions: SubscriptionManager) -> Self {
-		Self { client, subscriptions, _phantom: PhantomData }
+		Self { client, subscriptions, decoder: Default::default(), _phantom: PhantomData }
 	}
 }
 
-impl<Block, Client> ChainBackend<Client, Block> for FullChain<Block, Client>
+impl<BE, Block, Client> ChainBackend<Client, Block> for FullChain<BE, Block, Client>
 where
 	Block: BlockT + 'static,
 	Block::Header: Unpin,
-	Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block> + 'static,
+	BE: Backend<Block> + 'static,
+	Client: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, BE>
+		+ ProvideRuntimeApi<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: Metadata<Block>,
 {
 	fn client(&self) -> &Arc<Client> {
 		&self.client
@@ -69,4 +99,219 @@ where
 		let res = self.client.block(&BlockId::Hash(self.unwrap_or_best(hash))).map_err(client_err);
 		async move { res }.boxed()
 	}
+
+	fn subscribe_new_blocks(
+		&self,
+		_metadata: crate::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+	) {
+		let client = self.client.clone();
+		let decoder = self.decoder.clone();
+		self.subscriptions.add(subscriber, move |sink| {
+			let notifications = client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.map(|notification| notification.hash);
+			// send current best block right at the start.
+			let best_hash = client.info().best_hash;
+
+			stream::once(future::ready(best_hash))
+				.chain(notifications)
+				.map(move |hash| Ok::<_, ()>(block_with_events(&*client, &decoder, hash)))
+				.try_for_each(move |block| {
+					future::ready(
+						sink.notify(block)
+							.map_err(|e| warn!("Error sending notifications: {:?}", e)),
+					)
+				})
+				// we ignore the result (if sending failed we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn subscribe_finalized_blocks(
+		&self,
+		_metadata: crate::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+		from: Option<NumberOrHex>,
+	) {
+		// FIXME <2329>: Database seems to limit the block number to u32 for no reason
+		let from = match from.map(u32::try_from).transpose() {
+			Ok(from) => from.map(<NumberFor<Block>>::from),
+			Err(_) => {
+				let _ = subscriber.reject(
+					Error::Other(format!("`{:?}` > u32::MAX, the max block number is u32.", from))
+						.into(),
+				);
+				return
+			},
+		};
+
+		let finalized_number = self.client.info().finalized_number;
+		if let Some(from) = from {
+			if finalized_number.saturating_sub(from) > MAX_FINALIZED_BLOCKS_REPLAY.into() {
+				let _ = subscriber.reject(
+					Error::Other(format!(
+						"Block #{} is more than {} blocks behind the last finalized block #{}.",
+						from, MAX_FINALIZED_BLOCKS_REPLAY, finalized_number,
+					))
+					.into(),
+				);
+				return
+			}
+			// The events are read from the state, which is gone for pruned blocks.
+			if from <= finalized_number {
+				if let Err(e) = self.client.storage_hash(&BlockId::Number(from), &code_key()) {
+					let _ = subscriber.reject(
+						Error::Other(format!(
+							"State of block #{} is not available, it may have been pruned: {}",
+							from, e,
+						))
+						.into(),
+					);
+					return
+				}
+			}
+		}
+
+		let client = self.client.clone();
+		let decoder = self.decoder.clone();
+		self.subscriptions.add(subscriber, move |sink| {
+			// Subscribe before reading the last finalized block, so that no block finalized in
+			// between is missed.
+			let notifications = client
+				.finality_notification_stream()
+				.map(|notification| *notification.header.number());
+			let finalized_number = client.info().finalized_number;
+			let next = from.unwrap_or(finalized_number);
+
+			stream::once(future::ready(finalized_number))
+				.chain(notifications)
+				// Finality notifications may skip blocks finalized implicitly, fill the gaps.
+				.scan(next, |next, finalized_number| {
+					let mut numbers = Vec::new();
+					while *next <= finalized_number {
+						numbers.push(*next);
+						*next += One::one();
+					}
+					future::ready(Some(stream::iter(numbers)))
+				})
+				.flatten()
+				.map(move |number| finalized_block_with_events(&*client, &decoder, number))
+				// A missing block would be a gap, end the subscription after reporting it.
+				.scan(false, |failed, res| {
+					if *failed {
+						return future::ready(None)
+					}
+					*failed = res.is_err();
+					future::ready(Some(Ok::<_, ()>(res)))
+				})
+				.try_for_each(move |block| {
+					future::ready(
+						sink.notify(block)
+							.map_err(|e| warn!("Error sending notifications: {:?}", e)),
+					)
+				})
+				// we ignore the result (if sending failed we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+}
+
+/// Storage key of the runtime code.
+fn code_key() -> StorageKey {
+	StorageKey(well_known_keys::CODE.to_vec())
+}
+
+/// Get the events decoder of the runtime of the given block.
+///
+/// The decoder is rebuilt from the metadata of the runtime whenever its code changes. `None` if
+/// the metadata can't be read or doesn't describe the events.
+fn events_decoder<BE, Block, Client>(
+	client: &Client,
+	cache: &DecoderCache<Block>,
+	id: &BlockId<Block>,
+) -> Result<Option<Arc<EventsDecoder>>, rpc::Error>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: StorageProvider<Block, BE> + ProvideRuntimeApi<Block>,
+	Client::Api: Metadata<Block>,
+{
+	let code_hash = client.storage_hash(id, &code_key()).map_err(client_err)?;
+	let mut cache = cache.lock();
+	if let Some((hash, decoder)) = &*cache {
+		if code_hash.as_ref() == Some(hash) {
+			return Ok(decoder.clone())
+		}
+	}
+
+	let decoder = match client.runtime_api().metadata(id) {
+		Ok(metadata) => EventsDecoder::new(&metadata).unwrap_or_else(|e| {
+			debug!("Events of block {} can't be decoded: {}", id, e);
+			None
+		}),
+		Err(e) => {
+			debug!("Failed to get the metadata at block {}: {}", id, e);
+			None
+		},
+	}
+	.map(Arc::new);
+	if let Some(code_hash) = code_hash {
+		*cache = Some((code_hash, decoder.clone()));
+	}
+	Ok(decoder)
+}
+
+/// Get the block with the given hash together with its events.
+fn block_with_events<BE, Block, Client>(
+	client: &Client,
+	decoder: &DecoderCache<Block>,
+	hash: Block::Hash,
+) -> Result<BlockWithEvents<SignedBlock<Block>>, rpc::Error>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: BlockBackend<Block> + StorageProvider<Block, BE> + ProvideRuntimeApi<Block>,
+	Client::Api: Metadata<Block>,
+{
+	let id = BlockId::Hash(hash);
+	let block = client
+		.block(&id)
+		.map_err(client_err)?
+		.ok_or_else(|| Error::Other(format!("Block {} not found.", hash)))?;
+	let events = match events_decoder(client, decoder, &id)? {
+		Some(decoder) => {
+			let events = client.storage(&id, decoder.key()).map_err(client_err)?;
+			let events =
+				decoder.decode(events.as_ref().map(|events| &events.0[..])).map_err(|e| {
+					Error::Other(format!("Failed to decode the events of block {}: {}", hash, e))
+				})?;
+			Some(events)
+		},
+		None => None,
+	};
+	Ok(BlockWithEvents { block, events })
+}
+
+/// Get the finalized block with the given number together with its events.
+fn finalized_block_with_events<BE, Block, Client>(
+	client: &Client,
+	decoder: &DecoderCache<Block>,
+	number: NumberFor<Block>,
+) -> Result<BlockWithEvents<SignedBlock<Block>>, rpc::Error>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ StorageProvider<Block, BE>
+		+ ProvideRuntimeApi<Block>,
+	Client::Api: Metadata<Block>,
+{
+	let hash = client
+		.hash(number)
+		.map_err(client_err)?
+		.ok_or_else(|| Error::Other(format!("Block #{} not found.", number)))?;
+	block_with_events(client, decoder, hash)
 }
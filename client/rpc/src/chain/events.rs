@@ -0,0 +1,321 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Decoding of the `System::Events` storage item with the metadata of the runtime.
+
+use codec::{Compact, Decode};
+use frame_metadata::{v14::StorageEntryType, RuntimeMetadata, RuntimeMetadataPrefixed};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use serde_json::{Map, Value};
+use sp_core::{bytes::to_hex, hashing::twox_128, storage::StorageKey};
+
+/// Storage prefix of the pallet holding the events.
+const SYSTEM_PREFIX: &str = "System";
+
+/// Name of the storage item holding the events.
+const EVENTS_ENTRY: &str = "Events";
+
+/// Decoder of the events stored by a runtime, built from its metadata.
+pub struct EventsDecoder {
+	key: StorageKey,
+	ty: u32,
+	default: Vec<u8>,
+	types: PortableRegistry,
+}
+
+impl EventsDecoder {
+	/// Create a decoder from the SCALE encoded metadata of a runtime.
+	///
+	/// Returns `None` if the metadata doesn't describe a `System::Events` storage item.
+	pub fn new(metadata: &[u8]) -> Result<Option<Self>, String> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+			.map_err(|e| format!("Invalid metadata: {}", e))?;
+		let metadata = match metadata.1 {
+			RuntimeMetadata::V14(metadata) => metadata,
+			_ => return Err("Unsupported metadata version".into()),
+		};
+
+		let storage = metadata
+			.pallets
+			.iter()
+			.filter_map(|pallet| pallet.storage.as_ref())
+			.find(|storage| storage.prefix == SYSTEM_PREFIX);
+		let entry = storage
+			.and_then(|storage| storage.entries.iter().find(|entry| entry.name == EVENTS_ENTRY))
+			.and_then(|entry| match &entry.ty {
+				StorageEntryType::Plain(ty) => Some((ty.id(), entry.default.clone())),
+				StorageEntryType::Map { .. } => None,
+			});
+		Ok(entry.map(|(ty, default)| Self {
+			key: StorageKey(
+				[twox_128(SYSTEM_PREFIX.as_bytes()), twox_128(EVENTS_ENTRY.as_bytes())].concat(),
+			),
+			ty,
+			default,
+			types: metadata.types,
+		}))
+	}
+
+	/// Storage key of the events.
+	pub fn key(&self) -> &StorageKey {
+		&self.key
+	}
+
+	/// Decode the value of the events storage item, or its default value if it is not set.
+	///
+	/// Structs with named fields become objects, enum variants become their name or an object
+	/// with their name as only key, and byte arrays and large integers become strings.
+	pub fn decode(&self, value: Option<&[u8]>) -> Result<Value, String> {
+		let input = &mut value.unwrap_or(&self.default);
+		let events = self.decode_type(self.ty, input)?;
+		if !input.is_empty() {
+			return Err(format!("{} bytes left after decoding the events", input.len()))
+		}
+		Ok(events)
+	}
+
+	fn resolve(&self, id: u32) -> Result<&Type<PortableForm>, String> {
+		self.types
+			.resolve(id)
+			.ok_or_else(|| format!("Type {} is missing from the metadata", id))
+	}
+
+	fn decode_type(&self, id: u32, input: &mut &[u8]) -> Result<Value, String> {
+		match self.resolve(id)?.type_def() {
+			TypeDef::Composite(composite) => self.decode_fields(composite.fields(), input),
+			TypeDef::Variant(variant) => {
+				let index = decode::<u8>(input)?;
+				let variant = variant
+					.variants()
+					.iter()
+					.find(|variant| variant.index() == index)
+					.ok_or_else(|| format!("Unknown variant {} of type {}", index, id))?;
+				if variant.fields().is_empty() {
+					return Ok(Value::String(variant.name().clone()))
+				}
+				let mut object = Map::new();
+				object.insert(variant.name().clone(), self.decode_fields(variant.fields(), input)?);
+				Ok(Value::Object(object))
+			},
+			TypeDef::Sequence(sequence) => {
+				let len = decode::<Compact<u32>>(input)?.0;
+				self.decode_items(sequence.type_param().id(), len, input)
+			},
+			TypeDef::Array(array) => self.decode_items(array.type_param().id(), array.len(), input),
+			TypeDef::Tuple(tuple) => tuple
+				.fields()
+				.iter()
+				.map(|ty| self.decode_type(ty.id(), input))
+				.collect::<Result<_, _>>()
+				.map(Value::Array),
+			TypeDef::Primitive(primitive) => decode_primitive(primitive, input),
+			TypeDef::Compact(compact) => self.decode_compact(compact.type_param().id(), input),
+			TypeDef::BitSequence(_) => Err(format!("Bit sequence type {} is not supported", id)),
+		}
+	}
+
+	fn decode_fields(
+		&self,
+		fields: &[Field<PortableForm>],
+		input: &mut &[u8],
+	) -> Result<Value, String> {
+		match fields {
+			[] => Ok(Value::Null),
+			[field] if field.name().is_none() => self.decode_type(field.ty().id(), input),
+			_ if fields.iter().all(|field| field.name().is_some()) => fields
+				.iter()
+				.map(|field| {
+					let name = field.name().cloned().unwrap_or_default();
+					Ok((name, self.decode_type(field.ty().id(), input)?))
+				})
+				.collect::<Result<Map<_, _>, String>>()
+				.map(Value::Object),
+			_ => fields
+				.iter()
+				.map(|field| self.decode_type(field.ty().id(), input))
+				.collect::<Result<_, _>>()
+				.map(Value::Array),
+		}
+	}
+
+	fn decode_items(&self, id: u32, len: u32, input: &mut &[u8]) -> Result<Value, String> {
+		// Items take at least one byte, don't trust lengths exceeding the input.
+		if len as usize > input.len() {
+			return Err(format!("Length {} exceeds the {} bytes left", len, input.len()))
+		}
+		if let TypeDef::Primitive(TypeDefPrimitive::U8) = self.resolve(id)?.type_def() {
+			let (bytes, rest) = input.split_at(len as usize);
+			*input = rest;
+			return Ok(Value::String(to_hex(bytes, false)))
+		}
+		(0..len)
+			.map(|_| self.decode_type(id, input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array)
+	}
+
+	fn decode_compact(&self, id: u32, input: &mut &[u8]) -> Result<Value, String> {
+		match self.resolve(id)?.type_def() {
+			TypeDef::Primitive(TypeDefPrimitive::U8) => Ok(decode::<Compact<u8>>(input)?.0.into()),
+			TypeDef::Primitive(TypeDefPrimitive::U16) =>
+				Ok(decode::<Compact<u16>>(input)?.0.into()),
+			TypeDef::Primitive(TypeDefPrimitive::U32) =>
+				Ok(decode::<Compact<u32>>(input)?.0.into()),
+			TypeDef::Primitive(TypeDefPrimitive::U64) =>
+				Ok(decode::<Compact<u64>>(input)?.0.into()),
+			TypeDef::Primitive(TypeDefPrimitive::U128) =>
+				Ok(decode::<Compact<u128>>(input)?.0.to_string().into()),
+			// Compact wrappers of integers, such as `Perbill`.
+			TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+				let field = &composite.fields()[0];
+				let value = self.decode_compact(field.ty().id(), input)?;
+				Ok(match field.name() {
+					Some(name) => Value::Object(std::iter::once((name.clone(), value)).collect()),
+					None => value,
+				})
+			},
+			_ => Err(format!("Compact encoding of type {} is not supported", id)),
+		}
+	}
+}
+
+fn decode<T: Decode>(input: &mut &[u8]) -> Result<T, String> {
+	T::decode(input).map_err(|e| format!("Failed to decode events: {}", e))
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value, String> {
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => decode::<bool>(input)?.into(),
+		TypeDefPrimitive::Char => char::from_u32(decode::<u32>(input)?)
+			.ok_or_else(|| "Invalid char".to_string())?
+			.to_string()
+			.into(),
+		TypeDefPrimitive::Str => decode::<String>(input)?.into(),
+		TypeDefPrimitive::U8 => decode::<u8>(input)?.into(),
+		TypeDefPrimitive::U16 => decode::<u16>(input)?.into(),
+		TypeDefPrimitive::U32 => decode::<u32>(input)?.into(),
+		TypeDefPrimitive::U64 => decode::<u64>(input)?.into(),
+		TypeDefPrimitive::U128 => decode::<u128>(input)?.to_string().into(),
+		TypeDefPrimitive::I8 => decode::<i8>(input)?.into(),
+		TypeDefPrimitive::I16 => decode::<i16>(input)?.into(),
+		TypeDefPrimitive::I32 => decode::<i32>(input)?.into(),
+		TypeDefPrimitive::I64 => decode::<i64>(input)?.into(),
+		TypeDefPrimitive::I128 => decode::<i128>(input)?.to_string().into(),
+		// Little endian, like all SCALE integers.
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 =>
+			to_hex(&decode::<[u8; 32]>(input)?, false).into(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::v14::{
+		ExtrinsicMetadata, PalletMetadata, PalletStorageMetadata, RuntimeMetadataV14,
+		StorageEntryMetadata, StorageEntryModifier,
+	};
+	use scale_info::{meta_type, TypeInfo};
+	use serde_json::json;
+
+	#[derive(Encode, TypeInfo)]
+	enum Event {
+		Transfer { from: [u8; 4], amount: u128 },
+		Remark(Vec<u8>),
+		Ping,
+	}
+
+	#[derive(Encode, TypeInfo)]
+	struct EventRecord {
+		phase: (u8, Compact<u32>),
+		event: Event,
+		topics: Vec<[u8; 2]>,
+	}
+
+	fn metadata(prefix: &'static str) -> Vec<u8> {
+		let storage = PalletStorageMetadata {
+			prefix,
+			entries: vec![StorageEntryMetadata {
+				name: EVENTS_ENTRY,
+				modifier: StorageEntryModifier::Default,
+				ty: StorageEntryType::Plain(meta_type::<Vec<EventRecord>>()),
+				default: vec![0],
+				docs: vec![],
+			}],
+		};
+		let pallet = PalletMetadata {
+			name: "System",
+			storage: Some(storage),
+			calls: None,
+			event: None,
+			constants: vec![],
+			error: None,
+			index: 0,
+		};
+		let extrinsic =
+			ExtrinsicMetadata { ty: meta_type::<()>(), version: 4, signed_extensions: vec![] };
+		RuntimeMetadataPrefixed::from(RuntimeMetadataV14::new(
+			vec![pallet],
+			extrinsic,
+			meta_type::<()>(),
+		))
+		.encode()
+	}
+
+	#[test]
+	fn decodes_events() {
+		let decoder = EventsDecoder::new(&metadata(SYSTEM_PREFIX)).unwrap().unwrap();
+		assert_eq!(decoder.key().0, [twox_128(b"System"), twox_128(b"Events")].concat());
+
+		let events = vec![
+			EventRecord {
+				phase: (0, Compact(1)),
+				event: Event::Transfer { from: [1, 2, 3, 4], amount: u128::MAX },
+				topics: vec![[5, 6]],
+			},
+			EventRecord { phase: (1, Compact(2)), event: Event::Remark(vec![7]), topics: vec![] },
+			EventRecord { phase: (2, Compact(3)), event: Event::Ping, topics: vec![] },
+		];
+		assert_eq!(
+			decoder.decode(Some(&events.encode())).unwrap(),
+			json!([
+				{
+					"phase": [0, 1],
+					"event": {
+						"Transfer": { "from": "0x01020304", "amount": u128::MAX.to_string() },
+					},
+					"topics": ["0x0506"],
+				},
+				{ "phase": [1, 2], "event": { "Remark": "0x07" }, "topics": [] },
+				{ "phase": [2, 3], "event": "Ping", "topics": [] },
+			]),
+		);
+
+		let mut trailing = events.encode();
+		trailing.push(0);
+		assert!(decoder.decode(Some(&trailing)).is_err());
+		assert!(decoder.decode(Some(&[0xff])).is_err());
+		assert_eq!(decoder.decode(None).unwrap(), json!([]));
+	}
+
+	#[test]
+	fn requires_system_events() {
+		assert!(EventsDecoder::new(&metadata("Other")).unwrap().is_none());
+		assert!(EventsDecoder::new(&[1, 2, 3]).is_err());
+	}
+}
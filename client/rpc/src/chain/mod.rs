@@ -19,6 +19,7 @@
 //! Substrate blockchain API.
 
 mod chain_full;
+mod events;
 
 #[cfg(test)]
 mod tests;
@@ -32,7 +33,7 @@ use rpc::{
 use std::sync::Arc;
 
 use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
-use sc_client_api::BlockchainEvents;
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
 use sp_rpc::{list::ListOrValue, number::NumberOrHex};
 use sp_runtime::{
 	generic::{BlockId, SignedBlock},
@@ -43,6 +44,7 @@ use self::error::{Error, FutureResult, Result};
 
 use sc_client_api::BlockBackend;
 pub use sc_rpc_api::chain::*;
+use sp_api::{Metadata, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 
 /// Blockchain backend API
@@ -185,19 +187,61 @@ where
 	) -> RpcResult<bool> {
 		Ok(self.subscriptions().cancel(id))
 	}
+
+	/// New best block subscription
+	fn subscribe_new_blocks(
+		&self,
+		_metadata: crate::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+	);
+
+	/// Unsubscribe from new best block subscription.
+	fn unsubscribe_new_blocks(
+		&self,
+		_metadata: Option<crate::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions().cancel(id))
+	}
+
+	/// Finalized block subscription
+	fn subscribe_finalized_blocks(
+		&self,
+		_metadata: crate::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+		from: Option<NumberOrHex>,
+	);
+
+	/// Unsubscribe from finalized block subscription.
+	fn unsubscribe_finalized_blocks(
+		&self,
+		_metadata: Option<crate::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions().cancel(id))
+	}
 }
 
 /// Create new state API that works on full node.
-pub fn new_full<Block: BlockT, Client>(
+pub fn new_full<BE, Block: BlockT, Client>(
 	client: Arc<Client>,
 	subscriptions: SubscriptionManager,
 ) -> Chain<Block, Client>
 where
 	Block: BlockT + 'static,
 	Block::Header: Unpin,
-	Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block> + 'static,
+	BE: Backend<Block> + 'static,
+	Client: BlockBackend<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, BE>
+		+ ProvideRuntimeApi<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: Metadata<Block>,
 {
-	Chain { backend: Box::new(self::chain_full::FullChain::new(client, subscriptions)) }
+	Chain { backend: Box::new(self::chain_full::FullChain::<BE, _, _>::new(client, subscriptions)) }
 }
 
 /// Chain API with subscriptions support.
@@ -281,6 +325,39 @@ where
 	) -> RpcResult<bool> {
 		self.backend.unsubscribe_finalized_heads(metadata, id)
 	}
+
+	fn subscribe_new_blocks(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+	) {
+		self.backend.subscribe_new_blocks(metadata, subscriber)
+	}
+
+	fn unsubscribe_new_blocks(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		self.backend.unsubscribe_new_blocks(metadata, id)
+	}
+
+	fn subscribe_finalized_blocks(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<BlockWithEvents<SignedBlock<Block>>>,
+		from: Option<NumberOrHex>,
+	) {
+		self.backend.subscribe_finalized_blocks(metadata, subscriber, from)
+	}
+
+	fn unsubscribe_finalized_blocks(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		self.backend.unsubscribe_finalized_blocks(metadata, id)
+	}
 }
 
 /// Subscribe to new headers.
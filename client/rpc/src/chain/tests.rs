@@ -246,3 +246,72 @@ fn should_notify_about_finalized_block() {
 	executor::block_on((&mut transport).take(2).collect::<Vec<_>>());
 	assert!(executor::block_on(transport.next()).is_none());
 }
+
+#[test]
+fn should_notify_about_new_blocks() {
+	let (subscriber, id, mut transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
+
+		api.subscribe_new_blocks(Default::default(), subscriber);
+
+		// assert id assigned
+		assert!(matches!(executor::block_on(id), Ok(Ok(SubscriptionId::String(_)))));
+
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+	}
+
+	// Assert that the correct number of notifications have been sent.
+	let notifications = executor::block_on((&mut transport).take(2).collect::<Vec<_>>());
+	assert!(notifications.iter().all(|n| n.contains(r#""extrinsics":"#)));
+	assert!(executor::block_on(transport.next()).is_none());
+}
+
+#[test]
+fn should_notify_about_every_finalized_block() {
+	let (subscriber, id, mut transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
+
+		for _ in 0..2 {
+			let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+			executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		}
+		client.finalize_block(BlockId::number(1), None).unwrap();
+
+		// resume from genesis
+		api.subscribe_finalized_blocks(Default::default(), subscriber, Some(0u64.into()));
+
+		// assert id assigned
+		assert!(matches!(executor::block_on(id), Ok(Ok(SubscriptionId::String(_)))));
+
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		// implicitly finalizes block 2
+		client.finalize_block(BlockId::number(3), None).unwrap();
+	}
+
+	// Assert that blocks 0 to 3 have been sent in order.
+	let notifications = executor::block_on((&mut transport).take(4).collect::<Vec<_>>());
+	for (number, notification) in notifications.iter().enumerate() {
+		assert!(notification.contains(&format!(r#""number":"0x{:x}""#, number)));
+	}
+	assert!(executor::block_on(transport.next()).is_none());
+}
+
+#[test]
+fn should_reject_finalized_blocks_from_invalid_number() {
+	let (subscriber, id, _transport) = Subscriber::new_test("test");
+
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let api = new_full(client, SubscriptionManager::new(Arc::new(TaskExecutor)));
+
+	api.subscribe_finalized_blocks(Default::default(), subscriber, Some(u64::MAX.into()));
+
+	assert!(matches!(executor::block_on(id), Ok(Err(_))));
+}
@@ -0,0 +1,168 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate indexed transaction API.
+
+#[cfg(test)]
+mod tests;
+
+use self::error::{Error, Result};
+use cid::{multihash::Code, Cid};
+use sc_client_api::{Backend, BlockBackend};
+/// Re-export the API for backward compatibility.
+pub use sc_rpc_api::indexed_transaction::*;
+use sc_rpc_api::DenyUnsafe;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc};
+
+/// Indexed transaction API
+pub struct IndexedTransactions<Block, Client, BE> {
+	client: Arc<Client>,
+	backend: Arc<BE>,
+	/// Number of finalized blocks after which block bodies are pruned. `None` for archive nodes.
+	keep_blocks: Option<u32>,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, Client, BE> IndexedTransactions<Block, Client, BE> {
+	/// Create new instance of Indexed transaction API.
+	pub fn new(
+		client: Arc<Client>,
+		backend: Arc<BE>,
+		keep_blocks: Option<u32>,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		IndexedTransactions { client, backend, keep_blocks, deny_unsafe, _phantom: PhantomData }
+	}
+}
+
+impl<Block: BlockT, Client, BE> IndexedTransactions<Block, Client, BE> {
+	/// Number of the finalized block at which `block` is pruned.
+	fn pruned_at(&self, block: Option<NumberFor<Block>>) -> Option<NumberFor<Block>> {
+		Some(block? + self.keep_blocks?.into())
+	}
+}
+
+fn client_err(err: sp_blockchain::Error) -> Error {
+	Error::Client(Box::new(err))
+}
+
+/// Convert a 32-byte blake2b-256 digest to a block hash.
+fn to_hash<Block: BlockT>(digest: &[u8]) -> Block::Hash {
+	let mut hash = Block::Hash::default();
+	hash.as_mut().copy_from_slice(digest);
+	hash
+}
+
+impl<Block, Client, BE> IndexedTransactionApi<Block::Hash, NumberFor<Block>>
+	for IndexedTransactions<Block, Client, BE>
+where
+	Block: BlockT + 'static,
+	Client: BlockBackend<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	BE: Backend<Block> + 'static,
+{
+	fn indexed_transaction(
+		&self,
+		hash: Block::Hash,
+	) -> Result<Option<IndexedTransactionData<NumberFor<Block>>>> {
+		let data = match self.client.indexed_transaction(&hash).map_err(client_err)? {
+			Some(data) => data,
+			None => return Ok(None),
+		};
+		let block = self.client.indexed_transaction_block(&hash).map_err(client_err)?;
+		let pinned = self.backend.pinned_indexed_transactions().map_err(client_err)?;
+		Ok(Some(IndexedTransactionData {
+			data: data.into(),
+			pruned_at: self.pruned_at(block),
+			pinned: pinned.contains(&hash),
+		}))
+	}
+
+	fn indexed_transaction_by_cid(
+		&self,
+		cid: String,
+	) -> Result<Option<IndexedTransactionData<NumberFor<Block>>>> {
+		let cid = Cid::try_from(cid.as_str()).map_err(|e| Error::InvalidCid(e.to_string()))?;
+		let code = cid.hash().code();
+		let digest = cid.hash().digest();
+		if code == u64::from(Code::Blake2b256) {
+			if digest.len() != 32 {
+				return Err(Error::InvalidCid("blake2b-256 digest must be 32 bytes".into()))
+			}
+			let hash = to_hash::<Block>(digest);
+			if let Some(data) = self.indexed_transaction(hash)? {
+				return Ok(Some(data))
+			}
+			let block = self.client.indexed_dag_block(&hash).map_err(client_err)?;
+			return Ok(block.map(|data| IndexedTransactionData {
+				data: data.into(),
+				pruned_at: None,
+				pinned: false,
+			}))
+		}
+		match self.client.indexed_transaction_hash(code, digest).map_err(client_err)? {
+			Some(hash) => self.indexed_transaction(hash),
+			None => Ok(None),
+		}
+	}
+
+	fn block_indexed_transactions(
+		&self,
+		hash: Option<Block::Hash>,
+	) -> Result<Option<Vec<IndexedTransactionInfo<Block::Hash, NumberFor<Block>>>>> {
+		let hash = hash.unwrap_or_else(|| self.client.info().best_hash);
+		let number = match self.client.number(hash).map_err(client_err)? {
+			Some(number) => number,
+			None => return Ok(None),
+		};
+		let hashes =
+			match self.client.block_indexed_hashes(&BlockId::Hash(hash)).map_err(client_err)? {
+				Some(hashes) => hashes,
+				None => return Ok(None),
+			};
+		let pruned_at = self.pruned_at(Some(number));
+		let mut transactions = Vec::with_capacity(hashes.len());
+		// Data that is no longer stored is skipped rather than failing the whole listing.
+		for hash in hashes {
+			if let Some(size) = self.client.indexed_transaction_size(&hash).map_err(client_err)? {
+				transactions.push(IndexedTransactionInfo { hash, size: size as u32, pruned_at });
+			}
+		}
+		Ok(Some(transactions))
+	}
+
+	fn pin(&self, hash: Block::Hash) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+		self.backend.pin_indexed_transaction(&hash).map_err(client_err)
+	}
+
+	fn unpin(&self, hash: Block::Hash) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+		self.backend.unpin_indexed_transaction(&hash).map_err(client_err)
+	}
+
+	fn pinned(&self) -> Result<Vec<Block::Hash>> {
+		self.deny_unsafe.check_if_safe()?;
+		self.backend.pinned_indexed_transactions().map_err(client_err)
+	}
+}
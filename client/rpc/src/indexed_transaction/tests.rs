@@ -0,0 +1,85 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use cid::multihash::MultihashDigest;
+use futures::executor;
+use sc_block_builder::BlockBuilderProvider;
+use sp_consensus::BlockOrigin;
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block, Extrinsic},
+};
+
+#[test]
+fn should_return_indexed_transactions() {
+	let builder = TestClientBuilder::with_tx_storage(10);
+	let backend = builder.backend();
+	let mut client = Arc::new(builder.build());
+	let data = b"indexed data".to_vec();
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push(Extrinsic::Store(data.clone())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = block.hash();
+	executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+
+	let api = IndexedTransactions::new(client.clone(), backend, Some(10), DenyUnsafe::No);
+	let hash = to_hash::<Block>(&sp_core::hashing::blake2_256(&data));
+	let expected =
+		IndexedTransactionData { data: data.clone().into(), pruned_at: Some(11), pinned: false };
+	assert_eq!(api.indexed_transaction(hash).unwrap(), Some(expected.clone()));
+
+	let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(&data));
+	assert_eq!(api.indexed_transaction_by_cid(cid.to_string()).unwrap(), Some(expected));
+	assert_matches!(api.indexed_transaction_by_cid("not a cid".into()), Err(Error::InvalidCid(_)));
+
+	assert_eq!(
+		api.block_indexed_transactions(Some(block_hash)).unwrap(),
+		Some(vec![IndexedTransactionInfo { hash, size: data.len() as u32, pruned_at: Some(11) }]),
+	);
+	assert_eq!(api.block_indexed_transactions(None).unwrap().map(|txs| txs.len()), Some(1));
+}
+
+#[test]
+fn should_pin_indexed_transactions() {
+	let builder = TestClientBuilder::with_tx_storage(10);
+	let backend = builder.backend();
+	let mut client = Arc::new(builder.build());
+	let data = b"pinned data".to_vec();
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push(Extrinsic::Store(data.clone())).unwrap();
+	let block = builder.build().unwrap().block;
+	executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+	let hash = to_hash::<Block>(&sp_core::hashing::blake2_256(&data));
+
+	let api = IndexedTransactions::new(client.clone(), backend.clone(), None, DenyUnsafe::Yes);
+	assert_matches!(api.pin(hash), Err(Error::UnsafeRpcCalled(_)));
+
+	let api = IndexedTransactions::new(client.clone(), backend, None, DenyUnsafe::No);
+	assert_matches!(api.pin(hash), Ok(true));
+	assert_matches!(api.pin(hash), Ok(false));
+	assert_eq!(api.pinned().unwrap(), vec![hash]);
+	assert_matches!(api.indexed_transaction(hash), Ok(Some(ref x)) if x.pinned);
+	assert_matches!(api.pin(Default::default()), Err(Error::Client(_)));
+	assert_matches!(api.unpin(hash), Ok(true));
+	assert_matches!(api.unpin(hash), Ok(false));
+	assert!(api.pinned().unwrap().is_empty());
+	// The data is still referenced by the block.
+	assert_matches!(api.indexed_transaction(hash), Ok(Some(_)));
+}
@@ -22,16 +22,20 @@
 mod tests;
 
 use self::error::{Error, Result};
+use codec::Decode;
 use parking_lot::RwLock;
 /// Re-export the API for backward compatibility.
 pub use sc_rpc_api::offchain::*;
 use sc_rpc_api::DenyUnsafe;
 use sp_core::{
-	offchain::{OffchainStorage, StorageKind},
+	offchain::{OffchainIndexingOrigin, OffchainStorage, StorageKind, INDEXING_ORIGIN_PREFIX},
 	Bytes,
 };
 use std::sync::Arc;
 
+/// Maximum number of entries returned by the paged methods.
+const MAX_PAGE_SIZE: u32 = 1000;
+
 /// Offchain API
 #[derive(Debug)]
 pub struct Offchain<T: OffchainStorage> {
@@ -45,6 +49,58 @@ impl<T: OffchainStorage> Offchain<T> {
 	pub fn new(storage: T, deny_unsafe: DenyUnsafe) -> Self {
 		Offchain { storage: Arc::new(RwLock::new(storage)), deny_unsafe }
 	}
+
+	/// Up to `count` raw entries with keys starting with `prefix`, following `start_key`.
+	fn entries(
+		&self,
+		prefix: &[u8],
+		count: u32,
+		start_key: Option<&[u8]>,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+		if count > MAX_PAGE_SIZE {
+			return Err(Error::InvalidCount { value: count, max: MAX_PAGE_SIZE })
+		}
+		let storage = self.storage.read();
+		let entries = storage.iter_prefix(prefix).ok_or(Error::IterationUnsupported)?;
+		Ok(entries
+			.skip_while(|(key, _)| start_key.map_or(false, |start_key| &key[..] <= start_key))
+			.take(count as usize)
+			.collect())
+	}
+
+	/// Paged entries of the local storage of `kind` under keys starting with `prefix`.
+	fn local_entries(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> Result<Vec<(Bytes, Bytes)>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let storage_prefix = storage_prefix(kind)?;
+		let prefix = [storage_prefix, prefix.as_deref().unwrap_or_default()].concat();
+		let start_key = start_key.map(|key| [storage_prefix, &key[..]].concat());
+		let entries = self.entries(&prefix, count, start_key.as_deref())?;
+		Ok(entries
+			.into_iter()
+			.map(|(key, value)| (key[storage_prefix.len()..].to_vec().into(), value.into()))
+			.collect())
+	}
+
+	/// Remove the local storage entry under `key`, along with its offchain indexing origin.
+	fn remove(storage: &mut T, prefix: &[u8], key: &[u8]) {
+		storage.remove(prefix, key);
+		storage.remove(INDEXING_ORIGIN_PREFIX, &[prefix, key].concat());
+	}
+}
+
+/// Prefix of the local storage of `kind`.
+fn storage_prefix(kind: StorageKind) -> Result<&'static [u8]> {
+	match kind {
+		StorageKind::PERSISTENT => Ok(sp_offchain::STORAGE_PREFIX),
+		StorageKind::LOCAL => Err(Error::UnavailableStorageKind),
+	}
 }
 
 impl<T: OffchainStorage + 'static> OffchainApi for Offchain<T> {
@@ -70,4 +126,90 @@ impl<T: OffchainStorage + 'static> OffchainApi for Offchain<T> {
 		};
 		Ok(self.storage.read().get(prefix, &*key).map(Into::into))
 	}
+
+	/// Get the keys of offchain local storage with given prefix.
+	fn local_storage_keys_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> Result<Vec<Bytes>> {
+		let entries = self.local_entries(kind, prefix, count, start_key)?;
+		Ok(entries.into_iter().map(|(key, _)| key).collect())
+	}
+
+	/// Get the keys and values of offchain local storage with given prefix.
+	fn local_storage_pairs_paged(
+		&self,
+		kind: StorageKind,
+		prefix: Option<Bytes>,
+		count: u32,
+		start_key: Option<Bytes>,
+	) -> Result<Vec<(Bytes, Bytes)>> {
+		self.local_entries(kind, prefix, count, start_key)
+	}
+
+	/// Remove offchain local storage under given key.
+	fn clear_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<()> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let prefix = storage_prefix(kind)?;
+		Self::remove(&mut self.storage.write(), prefix, &key);
+		Ok(())
+	}
+
+	/// Remove offchain local storage under all keys with given prefix.
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> Result<u32> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let storage_prefix = storage_prefix(kind)?;
+		let mut storage = self.storage.write();
+		let keys: Vec<_> = storage
+			.iter_prefix(&[storage_prefix, &prefix[..]].concat())
+			.ok_or(Error::IterationUnsupported)?
+			.map(|(key, _)| key)
+			.collect();
+		for key in &keys {
+			Self::remove(&mut storage, storage_prefix, &key[storage_prefix.len()..]);
+		}
+		Ok(keys.len() as u32)
+	}
+
+	/// Get the block that last set offchain local storage under given key.
+	fn local_storage_indexed_at(
+		&self,
+		kind: StorageKind,
+		key: Bytes,
+	) -> Result<Option<IndexingOrigin>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let prefix = storage_prefix(kind)?;
+		let origin = self.storage.read().get(INDEXING_ORIGIN_PREFIX, &[prefix, &key[..]].concat());
+		Ok(origin
+			.and_then(|origin| OffchainIndexingOrigin::decode(&mut &origin[..]).ok())
+			.map(|origin| IndexingOrigin {
+				block_number: origin.block_number,
+				block_hash: origin.block_hash.into(),
+			}))
+	}
+
+	/// Export the raw entries of the offchain database.
+	fn export_storage(&self, count: u32, start_key: Option<Bytes>) -> Result<Vec<(Bytes, Bytes)>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let entries = self.entries(&[], count, start_key.as_deref())?;
+		Ok(entries.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+	}
+
+	/// Import raw entries of the offchain database.
+	fn import_storage(&self, entries: Vec<(Bytes, Bytes)>) -> Result<()> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let mut storage = self.storage.write();
+		for (key, value) in entries {
+			storage.set(&[], &key, &value);
+		}
+		Ok(())
+	}
 }
@@ -18,6 +18,7 @@
 
 use super::*;
 use assert_matches::assert_matches;
+use codec::Encode;
 use sp_core::{offchain::storage::InMemOffchainStorage, Bytes};
 
 #[test]
@@ -53,3 +54,103 @@ fn offchain_calls_considered_unsafe() {
 		Err(Error::UnsafeRpcCalled(_))
 	);
 }
+
+#[test]
+fn local_storage_keys_should_be_paged() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	for key in [&b"a1"[..], b"a2", b"a3", b"b1"] {
+		offchain
+			.set_local_storage(StorageKind::PERSISTENT, key.to_vec().into(), key.to_vec().into())
+			.unwrap();
+	}
+	let keys = |prefix: &[u8], count, start_key: Option<&[u8]>| {
+		offchain
+			.local_storage_keys_paged(
+				StorageKind::PERSISTENT,
+				Some(prefix.to_vec().into()),
+				count,
+				start_key.map(|key| key.to_vec().into()),
+			)
+			.unwrap()
+	};
+
+	assert_eq!(keys(b"a", 2, None), vec![Bytes(b"a1".to_vec()), Bytes(b"a2".to_vec())]);
+	assert_eq!(keys(b"a", 2, Some(b"a2")), vec![Bytes(b"a3".to_vec())]);
+	assert_eq!(keys(b"", 10, Some(b"a3")), vec![Bytes(b"b1".to_vec())]);
+	assert_matches!(
+		offchain.local_storage_pairs_paged(StorageKind::PERSISTENT, None, 1, None),
+		Ok(ref pairs) if *pairs == vec![(Bytes(b"a1".to_vec()), Bytes(b"a1".to_vec()))]
+	);
+	assert_matches!(
+		offchain.local_storage_keys_paged(StorageKind::PERSISTENT, None, 1001, None),
+		Err(Error::InvalidCount { value: 1001, max: 1000 })
+	);
+}
+
+#[test]
+fn local_storage_should_be_cleared() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	for key in [&b"a1"[..], b"a2", b"b1"] {
+		offchain
+			.set_local_storage(StorageKind::PERSISTENT, key.to_vec().into(), key.to_vec().into())
+			.unwrap();
+	}
+
+	assert_matches!(
+		offchain.clear_local_storage(StorageKind::PERSISTENT, Bytes(b"b1".to_vec())),
+		Ok(())
+	);
+	assert_matches!(
+		offchain.get_local_storage(StorageKind::PERSISTENT, Bytes(b"b1".to_vec())),
+		Ok(None)
+	);
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, Bytes(b"a".to_vec())),
+		Ok(2)
+	);
+	assert_matches!(offchain.local_storage_keys_paged(StorageKind::PERSISTENT, None, 10, None), Ok(ref keys) if keys.is_empty());
+}
+
+#[test]
+fn local_storage_indexing_origin_should_be_returned() {
+	let mut storage = InMemOffchainStorage::default();
+	let key = Bytes(b"offchain_storage".to_vec());
+	let origin = OffchainIndexingOrigin { block_number: 42, block_hash: vec![1; 32] };
+	storage.set(
+		INDEXING_ORIGIN_PREFIX,
+		&[sp_offchain::STORAGE_PREFIX, &key[..]].concat(),
+		&origin.encode(),
+	);
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+
+	assert_matches!(
+		offchain.local_storage_indexed_at(StorageKind::PERSISTENT, key.clone()),
+		Ok(Some(IndexingOrigin { block_number: 42, ref block_hash })) if *block_hash == Bytes(vec![1; 32])
+	);
+	offchain.clear_local_storage(StorageKind::PERSISTENT, key.clone()).unwrap();
+	assert_matches!(offchain.local_storage_indexed_at(StorageKind::PERSISTENT, key), Ok(None));
+}
+
+#[test]
+fn storage_should_be_exported_and_imported() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	for key in [&b"a"[..], b"b", b"c"] {
+		offchain
+			.set_local_storage(StorageKind::PERSISTENT, key.to_vec().into(), key.to_vec().into())
+			.unwrap();
+	}
+	let mut exported = offchain.export_storage(2, None).unwrap();
+	let last_key = exported.last().map(|(key, _)| key.clone());
+	exported.extend(offchain.export_storage(2, last_key).unwrap());
+	assert_eq!(exported.len(), 3);
+
+	let imported = Offchain::new(InMemOffchainStorage::default(), DenyUnsafe::No);
+	assert_matches!(imported.import_storage(exported), Ok(()));
+	assert_matches!(
+		imported.get_local_storage(StorageKind::PERSISTENT, Bytes(b"c".to_vec())),
+		Ok(Some(ref v)) if *v == Bytes(b"c".to_vec())
+	);
+}
@@ -48,6 +48,10 @@ use sp_blockchain::{HeaderBackend, HeaderMetadata};
 
 const STORAGE_KEYS_PAGED_MAX_COUNT: u32 = 1000;
 
+const QUERY_STORAGE_PAGED_MAX_COUNT: u32 = 1000;
+
+const STORAGE_DIFF_MAX_COUNT: u32 = 1000;
+
 /// State backend API.
 pub trait StateBackend<Block: BlockT, Client>: Send + Sync + 'static
 where
@@ -127,6 +131,19 @@ where
 		keys: Vec<StorageKey>,
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>>;
 
+	/// Query historical changes of the storage entries under a prefix, one page at a time.
+	///
+	/// NOTE The first block of the range reports the initial state of all entries.
+	fn query_storage_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		from: Block::Hash,
+		to: Option<Block::Hash>,
+		cursor: Option<StorageQueryCursor<Block::Hash>>,
+		with_proof: bool,
+	) -> FutureResult<StorageChangesPage<Block::Hash>>;
+
 	/// Query storage entries (by key) starting at block hash given as the second parameter.
 	fn query_storage_at(
 		&self,
@@ -134,6 +151,17 @@ where
 		at: Option<Block::Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>>;
 
+	/// Returns up to `count` storage keys with prefix, following `start_key`, that differ between
+	/// the states of two blocks.
+	fn storage_diff(
+		&self,
+		from: Block::Hash,
+		to: Block::Hash,
+		prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff>;
+
 	/// Returns proof of storage entries at a specific block's state.
 	fn read_proof(
 		&self,
@@ -310,6 +338,38 @@ where
 		self.backend.query_storage(from, to, keys)
 	}
 
+	fn query_storage_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		from: Block::Hash,
+		to: Option<Block::Hash>,
+		cursor: Option<StorageQueryCursor<Block::Hash>>,
+		with_proof: Option<bool>,
+	) -> FutureResult<StorageChangesPage<Block::Hash>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return async move { Err(err.into()) }.boxed()
+		}
+		if count == 0 {
+			return async move { Err(Error::ZeroCount) }.boxed()
+		}
+		if count > QUERY_STORAGE_PAGED_MAX_COUNT {
+			return async move {
+				Err(Error::InvalidCount { value: count, max: QUERY_STORAGE_PAGED_MAX_COUNT })
+			}
+			.boxed()
+		}
+
+		self.backend.query_storage_paged(
+			prefix,
+			count,
+			from,
+			to,
+			cursor,
+			with_proof.unwrap_or(false),
+		)
+	}
+
 	fn query_storage_at(
 		&self,
 		keys: Vec<StorageKey>,
@@ -318,6 +378,31 @@ where
 		self.backend.query_storage_at(keys, at)
 	}
 
+	fn storage_diff(
+		&self,
+		from: Block::Hash,
+		to: Block::Hash,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return async move { Err(err.into()) }.boxed()
+		}
+		if count == 0 {
+			return async move { Err(Error::ZeroCount) }.boxed()
+		}
+		if count > STORAGE_DIFF_MAX_COUNT {
+			return async move {
+				Err(Error::InvalidCount { value: count, max: STORAGE_DIFF_MAX_COUNT })
+			}
+			.boxed()
+		}
+
+		let prefix = prefix.unwrap_or_else(|| StorageKey(Vec::new()));
+		self.backend.storage_diff(from, to, prefix, count, start_key)
+	}
+
 	fn read_proof(
 		&self,
 		keys: Vec<StorageKey>,
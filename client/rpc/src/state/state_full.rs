@@ -28,7 +28,9 @@ use log::warn;
 use rpc::Result as RpcResult;
 use std::{collections::HashMap, sync::Arc};
 
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{
+	ReadProof, StorageChangesPage, StorageDiff, StoragePrefixChanges, StorageQueryCursor,
+};
 use sp_blockchain::{
 	CachedHeaderMetadata, Error as ClientError, HeaderBackend, HeaderMetadata,
 	Result as ClientResult,
@@ -55,6 +57,12 @@ use sc_client_api::{
 };
 use std::marker::PhantomData;
 
+/// Maximum number of blocks walked for a single page of state_queryStoragePaged.
+const QUERY_STORAGE_PAGED_MAX_BLOCKS: usize = 1000;
+
+/// Maximum number of storage keys compared for a single page of state_queryStoragePaged.
+const QUERY_STORAGE_PAGED_MAX_KEYS: usize = 10_000;
+
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
 	/// Hashes of all the blocks in the range.
@@ -174,6 +182,64 @@ where
 		}
 		Ok(())
 	}
+
+	/// Compares the entries under `prefix` at `block` with those at `parent`, returning up to
+	/// `limit` changed entries with keys after `start_key`.
+	///
+	/// Stops early once `limit` is reached or `keys_budget` is spent, returning the key to
+	/// resume after. All entries at `block` are reported if there is no `parent`.
+	fn prefix_changes(
+		&self,
+		parent: Option<Block::Hash>,
+		block: Block::Hash,
+		prefix: &StorageKey,
+		start_key: Option<&StorageKey>,
+		limit: usize,
+		keys_budget: &mut usize,
+	) -> Result<(Vec<(StorageKey, Option<StorageData>)>, Option<StorageKey>)> {
+		let keys = |hash| {
+			self.client
+				.storage_keys_iter(&BlockId::Hash(hash), Some(prefix), start_key)
+				.map(Iterator::peekable)
+				.map_err(client_err)
+		};
+		let mut current = keys(block)?;
+		let mut previous = parent.map(keys).transpose()?;
+
+		let mut changes = Vec::new();
+		let mut last_key = None;
+		loop {
+			let next_previous = previous.as_mut().and_then(|keys| keys.peek());
+			let key = match (current.peek(), next_previous) {
+				(None, None) => return Ok((changes, None)),
+				// Compared at least one key, so that paging makes progress.
+				_ if *keys_budget == 0 && last_key.is_some() => return Ok((changes, last_key)),
+				(Some(key), None) | (None, Some(key)) => key.clone(),
+				(Some(current), Some(previous)) => current.min(previous).clone(),
+			};
+			let in_current = current.next_if_eq(&key).is_some();
+			let in_previous = previous.as_mut().and_then(|keys| keys.next_if_eq(&key)).is_some();
+
+			let value = if in_current {
+				self.client.storage(&BlockId::Hash(block), &key).map_err(client_err)?
+			} else {
+				None
+			};
+			let changed = match parent {
+				Some(parent) if in_previous =>
+					self.client.storage(&BlockId::Hash(parent), &key).map_err(client_err)? != value,
+				_ => true,
+			};
+			if changed {
+				if changes.len() == limit {
+					return Ok((changes, last_key))
+				}
+				changes.push((key.clone(), value));
+			}
+			*keys_budget = keys_budget.saturating_sub(1);
+			last_key = Some(key);
+		}
+	}
 }
 
 impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Client>
@@ -357,6 +423,92 @@ where
 		async move { r }.boxed()
 	}
 
+	fn query_storage_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		from: Block::Hash,
+		to: Option<Block::Hash>,
+		cursor: Option<StorageQueryCursor<Block::Hash>>,
+		with_proof: bool,
+	) -> FutureResult<StorageChangesPage<Block::Hash>> {
+		let call_fn = move || {
+			let range = self.query_storage_range(from, to)?;
+			let (start, mut start_key) = match cursor {
+				Some(cursor) => {
+					let start =
+						range.hashes.iter().position(|hash| *hash == cursor.block).ok_or_else(
+							|| {
+								invalid_block::<Block>(
+									from,
+									to,
+									format!("cursor block {:?} is not in range", cursor.block),
+								)
+							},
+						)?;
+					(start, cursor.key)
+				},
+				None => (0, None),
+			};
+
+			let mut remaining = count as usize;
+			let mut keys_budget = QUERY_STORAGE_PAGED_MAX_KEYS;
+			let mut changes = Vec::new();
+			let mut next = None;
+			for (index, block) in range.hashes.iter().enumerate().skip(start) {
+				if remaining == 0 ||
+					keys_budget == 0 ||
+					index - start == QUERY_STORAGE_PAGED_MAX_BLOCKS
+				{
+					next = Some(StorageQueryCursor { block: *block, key: start_key.take() });
+					break
+				}
+
+				let parent = index.checked_sub(1).map(|parent| range.hashes[parent]);
+				let (block_changes, resume_key) = self.prefix_changes(
+					parent,
+					*block,
+					&prefix,
+					start_key.take().as_ref(),
+					remaining,
+					&mut keys_budget,
+				)?;
+				remaining -= block_changes.len();
+				if let Some(key) = resume_key {
+					next = Some(StorageQueryCursor { block: *block, key: Some(key) });
+				}
+				if !block_changes.is_empty() {
+					let proof = if with_proof {
+						let keys = block_changes.iter().map(|(key, _)| key.0.as_ref());
+						let proof = self
+							.client
+							.read_proof(&BlockId::Hash(*block), &mut keys.into_iter())
+							.map_err(client_err)?;
+						Some(ReadProof {
+							at: *block,
+							proof: proof.iter_nodes().map(|node| node.into()).collect(),
+						})
+					} else {
+						None
+					};
+					changes.push(StoragePrefixChanges {
+						block: *block,
+						changes: block_changes,
+						proof,
+					});
+				}
+				if next.is_some() {
+					break
+				}
+			}
+
+			Ok(StorageChangesPage { changes, next })
+		};
+
+		let r = call_fn();
+		async move { r }.boxed()
+	}
+
 	fn query_storage_at(
 		&self,
 		keys: Vec<StorageKey>,
@@ -366,6 +518,36 @@ where
 		self.query_storage(at, Some(at), keys)
 	}
 
+	fn storage_diff(
+		&self,
+		from: Block::Hash,
+		to: Block::Hash,
+		prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<StorageDiff> {
+		let r = self
+			.client
+			.storage_diff(
+				&BlockId::Hash(from),
+				&BlockId::Hash(to),
+				&prefix,
+				start_key.as_ref(),
+				count as usize,
+			)
+			.map(|diff| {
+				let keys = |keys: Vec<Vec<u8>>| keys.into_iter().map(StorageKey).collect();
+				StorageDiff {
+					inserted: keys(diff.inserted),
+					changed: keys(diff.changed),
+					removed: keys(diff.removed),
+					next: diff.next.map(StorageKey),
+				}
+			})
+			.map_err(client_err);
+		async move { r }.boxed()
+	}
+
 	fn read_proof(
 		&self,
 		block: Option<Block::Hash>,
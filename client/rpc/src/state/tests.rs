@@ -515,6 +515,208 @@ fn should_query_storage() {
 	run_tests(Arc::new(TestClientBuilder::new().build()));
 }
 
+#[test]
+fn should_query_storage_paged() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+		None,
+	);
+	let key = |k: &[u8]| StorageKey([&b"paged"[..], k].concat());
+	let data = |d: u8| Some(StorageData(vec![d]));
+
+	let mut add_block = |changes: Vec<(StorageKey, Option<StorageData>)>| {
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for (key, value) in changes {
+			builder.push_storage_change(key.0, value.map(|v| v.0)).unwrap();
+		}
+		let block = builder.build().unwrap().block;
+		let hash = block.header.hash();
+		executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		hash
+	};
+	let block1_hash =
+		add_block(vec![(key(b"1"), data(1)), (key(b"2"), data(2)), (key(b"3"), data(3))]);
+	let block2_hash =
+		add_block(vec![(key(b"1"), data(10)), (key(b"2"), None), (key(b"4"), data(4))]);
+	let genesis_hash = client.genesis_hash();
+	let prefix = StorageKey(b"paged".to_vec());
+
+	let block1_changes = vec![(key(b"1"), data(1)), (key(b"2"), data(2)), (key(b"3"), data(3))];
+	let block2_changes = vec![(key(b"1"), data(10)), (key(b"2"), None), (key(b"4"), data(4))];
+	let changes = |block, changes| StoragePrefixChanges { block, changes, proof: None };
+
+	// Whole range in a single page.
+	let result =
+		api.query_storage_paged(prefix.clone(), 10, genesis_hash, Some(block2_hash), None, None);
+	assert_eq!(
+		executor::block_on(result).unwrap(),
+		StorageChangesPage {
+			changes: vec![
+				changes(block1_hash, block1_changes.clone()),
+				changes(block2_hash, block2_changes.clone()),
+			],
+			next: None,
+		}
+	);
+
+	// Pages of two changes.
+	let result =
+		api.query_storage_paged(prefix.clone(), 2, genesis_hash, Some(block2_hash), None, None);
+	let page = executor::block_on(result).unwrap();
+	assert_eq!(page.changes, vec![changes(block1_hash, block1_changes[..2].to_vec())]);
+	assert_eq!(page.next, Some(StorageQueryCursor { block: block1_hash, key: Some(key(b"2")) }));
+
+	let result = api.query_storage_paged(
+		prefix.clone(),
+		2,
+		genesis_hash,
+		Some(block2_hash),
+		page.next,
+		None,
+	);
+	let page = executor::block_on(result).unwrap();
+	assert_eq!(
+		page.changes,
+		vec![
+			changes(block1_hash, block1_changes[2..].to_vec()),
+			changes(block2_hash, block2_changes[..1].to_vec()),
+		]
+	);
+	assert_eq!(page.next, Some(StorageQueryCursor { block: block2_hash, key: Some(key(b"1")) }));
+
+	let result = api.query_storage_paged(
+		prefix.clone(),
+		2,
+		genesis_hash,
+		Some(block2_hash),
+		page.next,
+		None,
+	);
+	let page = executor::block_on(result).unwrap();
+	assert_eq!(page.changes, vec![changes(block2_hash, block2_changes[1..].to_vec())]);
+	assert_eq!(page.next, None);
+
+	// Changes come with a read proof of the block.
+	let result = api.query_storage_paged(
+		prefix.clone(),
+		10,
+		block2_hash,
+		Some(block2_hash),
+		None,
+		Some(true),
+	);
+	let page = executor::block_on(result).unwrap();
+	assert_eq!(page.changes.len(), 1);
+	assert_matches!(&page.changes[0].proof, Some(proof) if proof.at == block2_hash && !proof.proof.is_empty());
+
+	// Cursor outside of the range.
+	let cursor = StorageQueryCursor { block: block2_hash, key: None };
+	let result = api.query_storage_paged(
+		prefix.clone(),
+		10,
+		genesis_hash,
+		Some(block1_hash),
+		Some(cursor),
+		None,
+	);
+	assert_matches!(executor::block_on(result), Err(Error::InvalidBlockRange { .. }));
+
+	// No changes per page.
+	let result = api.query_storage_paged(prefix.clone(), 0, genesis_hash, None, None, None);
+	assert_matches!(executor::block_on(result), Err(Error::ZeroCount));
+
+	// Too many changes per page.
+	let result = api.query_storage_paged(prefix, 1001, genesis_hash, None, None, None);
+	assert_matches!(
+		executor::block_on(result),
+		Err(Error::InvalidCount { value: 1001, max: 1000 })
+	);
+}
+
+#[test]
+fn should_return_storage_diff() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+		None,
+	);
+
+	let mut add_block = |changes: Vec<(&[u8], Option<&[u8]>)>| {
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for (key, value) in changes {
+			builder.push_storage_change(key.to_vec(), value.map(|v| v.to_vec())).unwrap();
+		}
+		let block = builder.build().unwrap().block;
+		let hash = block.header.hash();
+		executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		hash
+	};
+	let block1_hash = add_block(vec![(b"diff1", Some(b"1")), (b"diff2", Some(b"2"))]);
+	let block2_hash =
+		add_block(vec![(b"diff1", Some(b"10")), (b"diff2", None), (b"diff3", Some(b"3"))]);
+	let key = |k: &[u8]| StorageKey(k.to_vec());
+
+	let result = api.storage_diff(block1_hash, block2_hash, Some(key(b"diff")), 10, None);
+	assert_eq!(
+		executor::block_on(result).unwrap(),
+		StorageDiff {
+			inserted: vec![key(b"diff3")],
+			changed: vec![key(b"diff1")],
+			removed: vec![key(b"diff2")],
+			next: None,
+		}
+	);
+
+	let result = api.storage_diff(block2_hash, block1_hash, Some(key(b"diff2")), 10, None);
+	assert_eq!(
+		executor::block_on(result).unwrap(),
+		StorageDiff { inserted: vec![key(b"diff2")], ..Default::default() }
+	);
+
+	let result = api.storage_diff(block2_hash, block2_hash, None, 10, None);
+	assert_eq!(executor::block_on(result).unwrap(), StorageDiff::default());
+
+	// Pages.
+	let result = api.storage_diff(block1_hash, block2_hash, Some(key(b"diff")), 2, None);
+	assert_eq!(
+		executor::block_on(result).unwrap(),
+		StorageDiff {
+			changed: vec![key(b"diff1")],
+			removed: vec![key(b"diff2")],
+			next: Some(key(b"diff2")),
+			..Default::default()
+		}
+	);
+	let result =
+		api.storage_diff(block1_hash, block2_hash, Some(key(b"diff")), 2, Some(key(b"diff2")));
+	assert_eq!(
+		executor::block_on(result).unwrap(),
+		StorageDiff { inserted: vec![key(b"diff3")], ..Default::default() }
+	);
+
+	let result = api.storage_diff(block1_hash, block2_hash, None, 0, None);
+	assert_matches!(executor::block_on(result), Err(Error::ZeroCount));
+	let result = api.storage_diff(block1_hash, block2_hash, None, 1001, None);
+	assert_matches!(
+		executor::block_on(result),
+		Err(Error::InvalidCount { value: 1001, max: 1000 })
+	);
+
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::Yes,
+		None,
+	);
+	let result = api.storage_diff(block1_hash, block2_hash, None, 10, None);
+	assert_matches!(executor::block_on(result), Err(Error::UnsafeRpcCalled(_)));
+}
+
 #[test]
 fn should_return_runtime_version() {
 	let client = Arc::new(substrate_test_runtime_client::new());
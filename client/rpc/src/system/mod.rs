@@ -27,7 +27,7 @@ use sp_runtime::traits::{self, Header as HeaderT};
 
 pub use self::{
 	gen_client::Client as SystemClient,
-	helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo},
+	helpers::{BannedPeer, Health, NodeRole, PeerInfo, SyncState, SystemInfo},
 };
 pub use sc_rpc_api::system::*;
 
@@ -69,6 +69,12 @@ pub enum Request<B: traits::Block> {
 	NetworkRemoveReservedPeer(String, oneshot::Sender<Result<()>>),
 	/// Must return the list of reserved peers
 	NetworkReservedPeers(oneshot::Sender<Vec<String>>),
+	/// Must return the list of banned peers.
+	NetworkBannedPeers(oneshot::Sender<Vec<BannedPeer>>),
+	/// Must return any potential parse error.
+	NetworkBanPeer(String, Option<u64>, oneshot::Sender<Result<()>>),
+	/// Must return any potential parse error.
+	NetworkUnbanPeer(String, oneshot::Sender<Result<()>>),
 	/// Must return the node role.
 	NodeRoles(oneshot::Sender<Vec<NodeRole>>),
 	/// Must return the state of the node syncing.
@@ -184,6 +190,46 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		Receiver(rx)
 	}
 
+	fn system_banned_peers(&self) -> Receiver<Vec<BannedPeer>> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::NetworkBannedPeers(tx));
+		Receiver(rx)
+	}
+
+	fn system_ban_peer(
+		&self,
+		peer_id: String,
+		duration: Option<u64>,
+	) -> rpc::BoxFuture<rpc::Result<()>> {
+		bail_if_unsafe!(self.deny_unsafe);
+
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::NetworkBanPeer(peer_id, duration, tx));
+		async move {
+			match rx.await {
+				Ok(Ok(())) => Ok(()),
+				Ok(Err(e)) => Err(rpc::Error::from(e)),
+				Err(_) => Err(rpc::Error::internal_error()),
+			}
+		}
+		.boxed()
+	}
+
+	fn system_unban_peer(&self, peer_id: String) -> rpc::BoxFuture<rpc::Result<()>> {
+		bail_if_unsafe!(self.deny_unsafe);
+
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::NetworkUnbanPeer(peer_id, tx));
+		async move {
+			match rx.await {
+				Ok(Ok(())) => Ok(()),
+				Ok(Err(e)) => Err(rpc::Error::from(e)),
+				Err(_) => Err(rpc::Error::internal_error()),
+			}
+		}
+		.boxed()
+	}
+
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>> {
 		let (tx, rx) = oneshot::channel();
 		let _ = self.send_back.unbounded_send(Request::NodeRoles(tx));
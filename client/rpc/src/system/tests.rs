@@ -87,6 +87,7 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 							external_addresses: Default::default(),
 							connected_peers: Default::default(),
 							not_connected_peers: Default::default(),
+							bitswap_ledgers: Default::default(),
 							peerset: serde_json::Value::Null,
 						})
 						.unwrap(),
@@ -110,6 +111,20 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 					let _ = sender
 						.send(vec!["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string()]);
 				},
+				Request::NetworkBannedPeers(sender) => {
+					let _ = sender.send(vec![BannedPeer {
+						peer_id: "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string(),
+						until: Some(1_000_000),
+					}]);
+				},
+				Request::NetworkBanPeer(peer, _, sender) |
+				Request::NetworkUnbanPeer(peer, sender) => {
+					let _ = match peer.parse::<PeerId>() {
+						Ok(_) => sender.send(Ok(())),
+						Err(s) =>
+							sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
+					};
+				},
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				},
@@ -250,6 +265,7 @@ fn system_network_state() {
 			external_addresses: Default::default(),
 			connected_peers: Default::default(),
 			not_connected_peers: Default::default(),
+			bitswap_ledgers: Default::default(),
 			peerset: serde_json::Value::Null,
 		}
 	);
@@ -300,6 +316,34 @@ fn system_network_reserved_peers() {
 	);
 }
 
+#[test]
+fn system_network_banned_peers() {
+	assert_eq!(
+		wait_receiver(api(None).system_banned_peers()),
+		vec![BannedPeer {
+			peer_id: "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string(),
+			until: Some(1_000_000),
+		}]
+	);
+}
+
+#[test]
+fn system_network_ban_and_unban_peer() {
+	let good_peer_id = "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	let bad_peer_id =
+		"/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+
+	let good_fut = api(None).system_ban_peer(good_peer_id.into(), Some(60));
+	let bad_fut = api(None).system_ban_peer(bad_peer_id.into(), None);
+	assert_eq!(executor::block_on(good_fut), Ok(()));
+	assert!(executor::block_on(bad_fut).is_err());
+
+	let good_fut = api(None).system_unban_peer(good_peer_id.into());
+	let bad_fut = api(None).system_unban_peer(bad_peer_id.into());
+	assert_eq!(executor::block_on(good_fut), Ok(()));
+	assert!(executor::block_on(bad_fut).is_err());
+}
+
 #[test]
 fn test_add_reset_log_filter() {
 	const EXPECTED_BEFORE_ADD: &'static str = "EXPECTED_BEFORE_ADD";
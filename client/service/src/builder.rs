@@ -19,7 +19,7 @@
 use crate::{
 	build_network_future,
 	client::{Client, ClientConfig},
-	config::{Configuration, KeystoreConfig, PrometheusConfig, TransactionStorageMode},
+	config::{Configuration, KeepBlocks, KeystoreConfig, PrometheusConfig, TransactionStorageMode},
 	error::Error,
 	metrics::MetricsService,
 	start_rpc_servers, RpcHandlers, SpawnTaskHandle, TaskManager, TransactionPoolAdapter,
@@ -30,7 +30,7 @@ use log::info;
 use prometheus_endpoint::Registry;
 use sc_chain_spec::get_extension;
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
+	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, AuxStore, BadBlocks,
 	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider, UsageProvider,
 };
 use sc_client_db::{Backend, DatabaseSettings};
@@ -410,10 +410,21 @@ pub fn build_offchain_workers<TBl, TCl>(
 ) -> Option<Arc<sc_offchain::OffchainWorkers<TCl, TBl>>>
 where
 	TBl: BlockT,
-	TCl: Send + Sync + ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + 'static,
+	TCl: Send + Sync + ProvideRuntimeApi<TBl> + BlockBackend<TBl> + BlockchainEvents<TBl> + 'static,
 	<TCl as ProvideRuntimeApi<TBl>>::Api: sc_offchain::OffchainWorkerApi<TBl>,
 {
-	let offchain_workers = Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone())));
+	let options = sc_offchain::OffchainWorkerOptions {
+		max_concurrent_workers: config.offchain_worker.max_concurrent_workers,
+		run_timeout: config.offchain_worker.run_timeout,
+		skip_during_major_sync: config.offchain_worker.skip_during_major_sync,
+		http: config.offchain_worker.http.clone(),
+		..Default::default()
+	};
+	let offchain_workers = Some(Arc::new(sc_offchain::OffchainWorkers::new_with_options(
+		client.clone(),
+		options,
+		config.prometheus_registry(),
+	)));
 
 	// Inform the offchain worker about new imported blocks
 	if let Some(offchain) = offchain_workers.clone() {
@@ -541,6 +552,7 @@ where
 			&config,
 			task_manager.spawn_handle(),
 			client.clone(),
+			backend.clone(),
 			transaction_pool.clone(),
 			keystore.clone(),
 			&*rpc_extensions_builder,
@@ -639,6 +651,7 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 	config: &Configuration,
 	spawn_handle: SpawnTaskHandle,
 	client: Arc<TCl>,
+	backend: Arc<TBackend>,
 	transaction_pool: Arc<TExPool>,
 	keystore: SyncCryptoStorePtr,
 	rpc_extensions_builder: &(dyn RpcExtensionBuilder<Output = TRpc> + Send),
@@ -666,7 +679,7 @@ where
 	TBl::Hash: Unpin,
 	TBl::Header: Unpin,
 {
-	use sc_rpc::{author, chain, offchain, state, system};
+	use sc_rpc::{author, chain, indexed_transaction, offchain, state, system};
 
 	let system_info = sc_rpc::system::SystemInfo {
 		chain_name: config.chain_spec.name().into(),
@@ -691,6 +704,21 @@ where
 		(chain, state, child_state)
 	};
 
+	let maybe_indexed_transaction_rpc =
+		matches!(config.transaction_storage, TransactionStorageMode::StorageChain).then(|| {
+			let keep_blocks = match config.keep_blocks {
+				KeepBlocks::All => None,
+				KeepBlocks::Some(keep_blocks) => Some(keep_blocks),
+			};
+			let indexed_transactions = indexed_transaction::IndexedTransactions::new(
+				client.clone(),
+				backend,
+				keep_blocks,
+				deny_unsafe,
+			);
+			indexed_transaction::IndexedTransactionApi::to_delegate(indexed_transactions)
+		});
+
 	let author =
 		sc_rpc::author::Author::new(client, transaction_pool, subscriptions, keystore, deny_unsafe);
 	let system = system::System::new(system_info, system_rpc_tx, deny_unsafe);
@@ -706,6 +734,7 @@ where
 			state::ChildStateApi::to_delegate(child_state),
 			chain::ChainApi::to_delegate(chain),
 			maybe_offchain_rpc,
+			maybe_indexed_transaction_rpc,
 			author::AuthorApi::to_delegate(author),
 			system::SystemApi::to_delegate(system),
 			rpc_extensions_builder.build(deny_unsafe, task_executor)?,
@@ -754,6 +783,7 @@ where
 		+ ProofProvider<TBl>
 		+ HeaderBackend<TBl>
 		+ BlockchainEvents<TBl>
+		+ AuxStore
 		+ 'static,
 	TExPool: MaintainedTransactionPool<Block = TBl, Hash = <TBl as BlockT>::Hash> + 'static,
 	TImpQu: ImportQueue<TBl> + 'static,
@@ -0,0 +1,352 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Export and import of state snapshots.
+//!
+//! A snapshot starts with [`MAGIC`] and the format version, followed by the header and
+//! justifications of a finalized block, and by the state of that block as a sequence of compact
+//! range proofs, each one starting after the last key of the previous one. The proofs are the
+//! same as the ones exchanged by state sync, and are verified against the state root of the header
+//! when imported.
+
+use crate::error::Error;
+use codec::{Decode, Encode, Input, IoReader};
+use futures::future;
+use log::{info, warn};
+use sc_client_api::{BlockBackend, CompactProof, HeaderBackend, ProofProvider};
+use sc_consensus::{
+	import_queue::{BlockImportError, BlockImportStatus, ImportQueue, IncomingBlock, Link},
+	ImportedState,
+};
+use smallvec::SmallVec;
+use sp_consensus::BlockOrigin;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header, NumberFor, Zero},
+	Justifications,
+};
+use std::{
+	collections::HashMap,
+	io::{Read, Write},
+	sync::Arc,
+	task::Poll,
+};
+
+/// Bytes snapshots start with.
+pub const MAGIC: &[u8; 8] = b"snapshot";
+
+/// Version of the snapshot format.
+const VERSION: u32 = 1;
+
+/// Maximum size of each state proof of a snapshot.
+const PROOF_SIZE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Block a snapshot is taken at.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct SnapshotBlock<H> {
+	header: H,
+	justifications: Option<Justifications>,
+}
+
+fn write_block<H: Encode>(output: &mut impl Write, block: &SnapshotBlock<H>) -> Result<(), Error> {
+	output.write_all(MAGIC)?;
+	output.write_all(&VERSION.encode())?;
+	output.write_all(&block.encode())?;
+	Ok(())
+}
+
+fn read_block<H: Decode>(input: &mut impl Input) -> Result<SnapshotBlock<H>, Error> {
+	let decode_error = |e: codec::Error| Error::Other(format!("Error reading snapshot: {}", e));
+	let mut magic = [0; MAGIC.len()];
+	input.read(&mut magic).map_err(decode_error)?;
+	if &magic != MAGIC {
+		return Err("Input is not a snapshot".into())
+	}
+	let version = u32::decode(input).map_err(decode_error)?;
+	if version != VERSION {
+		return Err(format!("Unsupported snapshot version {}", version).into())
+	}
+	SnapshotBlock::decode(input).map_err(decode_error)
+}
+
+/// Export a snapshot of the state of `block` to `output`. If `block` is `None`, the last
+/// finalized block is used.
+///
+/// Only finalized blocks can be exported, as snapshots are imported as finalized.
+pub fn export_snapshot<B, C>(
+	client: Arc<C>,
+	block: Option<BlockId<B>>,
+	mut output: impl Write,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+{
+	let info = client.info();
+	let header = client.expect_header(block.unwrap_or(BlockId::Hash(info.finalized_hash)))?;
+	let (hash, number) = (header.hash(), *header.number());
+	if number > info.finalized_number || client.hash(number)? != Some(hash) {
+		return Err(format!("Block #{} ({}) is not finalized", number, hash).into())
+	}
+	let justifications = client.justifications(&BlockId::Hash(hash))?;
+	if justifications.is_none() {
+		warn!("Block #{} ({}) has no justification", number, hash);
+	}
+
+	info!("Exporting snapshot of block #{} ({})", number, hash);
+	let state_root = *header.state_root();
+	write_block(&mut output, &SnapshotBlock { header, justifications })?;
+
+	let mut start = SmallVec::<[Vec<u8>; 2]>::new();
+	let (mut proofs, mut size) = (0, 0);
+	loop {
+		let (proof, _) =
+			client.read_proof_collection(&BlockId::Hash(hash), &start, PROOF_SIZE_LIMIT)?;
+		let encoded = proof.encode();
+		// Verify the proof to find where the next one starts.
+		let (values, completed) = client.verify_range_proof(state_root, proof, &start)?;
+		output.write_all(&encoded)?;
+		proofs += 1;
+		size += encoded.len();
+		if completed == 0 {
+			break
+		}
+		if !values.update_last_key(completed, &mut start) {
+			return Err(format!("Error updating key cursor, depth: {}", completed).into())
+		}
+	}
+	output.flush()?;
+
+	info!("🎉 Exported snapshot with {} proofs ({} MiB)", proofs, size / (1024 * 1024));
+	Ok(())
+}
+
+/// Import the snapshot read from `input` into an empty database, through `import_queue`.
+///
+/// The state is verified against the state root of the header of the snapshot, and the hash of
+/// the header against `expected_hash`, which must come from a trusted source, as the justification
+/// of the snapshot is not checked. The block is imported as finalized, without its ancestors, as
+/// if it was downloaded with state sync.
+pub async fn import_snapshot<B, IQ, C>(
+	client: Arc<C>,
+	mut import_queue: IQ,
+	input: impl Read,
+	expected_hash: B::Hash,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + ProofProvider<B>,
+	IQ: ImportQueue<B>,
+{
+	let info = client.info();
+	if !info.best_number.is_zero() {
+		return Err(format!(
+			"Database is at block #{}, snapshots can only be imported into an empty database",
+			info.best_number,
+		)
+		.into())
+	}
+
+	let mut input = IoReader(input);
+	let SnapshotBlock { header, justifications } = read_block::<B::Header>(&mut input)?;
+	let (hash, number) = (header.hash(), *header.number());
+	if hash != expected_hash {
+		return Err(format!(
+			"Snapshot is taken at block #{} ({}), expected {}",
+			number, hash, expected_hash,
+		)
+		.into())
+	}
+
+	info!("Importing snapshot of block #{} ({})", number, hash);
+	let mut state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)> = HashMap::new();
+	let mut start = SmallVec::<[Vec<u8>; 2]>::new();
+	let mut size = 0;
+	loop {
+		let proof = CompactProof::decode(&mut input)
+			.map_err(|e| format!("Error reading snapshot proof: {}", e))?;
+		size += proof.encoded_size();
+		let (values, completed) = client
+			.verify_range_proof(*header.state_root(), proof, &start)
+			.map_err(|e| format!("Invalid snapshot proof: {}", e))?;
+		if completed != 0 && !values.update_last_key(completed, &mut start) {
+			return Err(format!("Error updating key cursor, depth: {}", completed).into())
+		}
+
+		for level in values.0 {
+			let is_top = level.state_root.is_empty();
+			let entry = state.entry(level.state_root).or_default();
+			let mut child_roots = Vec::new();
+			for (key, value) in level.key_values {
+				// Child trie roots are recalculated on import.
+				if is_top && well_known_keys::is_child_storage_key(&key) {
+					child_roots.push((value, key));
+				} else {
+					entry.0.push((key, value));
+				}
+			}
+			for (root, storage_key) in child_roots {
+				state.entry(root).or_default().1.push(storage_key);
+			}
+		}
+		if completed == 0 {
+			break
+		}
+	}
+	info!("Verified {} MiB of state, importing", size / (1024 * 1024));
+
+	import_queue.import_blocks(
+		BlockOrigin::File,
+		vec![IncomingBlock {
+			hash,
+			header: Some(header),
+			body: None,
+			indexed_body: None,
+			justifications,
+			origin: None,
+			allow_missing_state: true,
+			import_existing: true,
+			skip_execution: true,
+			state: Some(ImportedState { block: hash, state: state.into() }),
+		}],
+	);
+
+	struct WaitLink {
+		result: Option<Result<(), String>>,
+	}
+
+	impl<B: BlockT> Link<B> for WaitLink {
+		fn blocks_processed(
+			&mut self,
+			_imported: usize,
+			_num_expected_blocks: usize,
+			results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+		) {
+			for (result, hash) in results {
+				self.result = Some(result.map(drop).map_err(|e| {
+					format!("There was an error importing block with hash {:?}: {}", hash, e)
+				}));
+			}
+		}
+	}
+
+	let mut link = WaitLink { result: None };
+	future::poll_fn(|cx| {
+		import_queue.poll_actions(cx, &mut link);
+		match link.result.take() {
+			Some(result) => Poll::Ready(result),
+			None => Poll::Pending,
+		}
+	})
+	.await?;
+
+	info!("🎉 Imported snapshot of block #{} ({})", number, hash);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_block_builder::BlockBuilderProvider;
+	use sc_client_api::StorageProvider;
+	use sc_consensus::{BasicQueue, BlockImportParams, Verifier};
+	use sp_consensus::CacheKeyId;
+	use sp_core::{storage::StorageKey, testing::TaskExecutor};
+	use sp_runtime::testing::Header;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{Block, Hash},
+		TestClient,
+	};
+
+	struct PassThroughVerifier;
+
+	#[async_trait::async_trait]
+	impl Verifier<Block> for PassThroughVerifier {
+		async fn verify(
+			&mut self,
+			block: BlockImportParams<Block, ()>,
+		) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+			Ok((block, None))
+		}
+	}
+
+	/// Import `snapshot` into a new client.
+	fn import(snapshot: &[u8], expected_hash: Hash) -> (Arc<TestClient>, Result<(), Error>) {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let import_queue = BasicQueue::new(
+			PassThroughVerifier,
+			Box::new(client.clone()),
+			None,
+			&TaskExecutor::new(),
+			None,
+		);
+		let result =
+			block_on(import_snapshot(client.clone(), import_queue, snapshot, expected_hash));
+		(client, result)
+	}
+
+	#[test]
+	fn snapshot_block_is_written_and_read() {
+		let block = SnapshotBlock {
+			header: Header::new_from_number(42),
+			justifications: Some(Justifications::from((*b"FRNK", vec![1, 2, 3]))),
+		};
+		let mut output = Vec::new();
+		write_block(&mut output, &block).unwrap();
+
+		assert_eq!(read_block::<Header>(&mut output.as_slice()).unwrap(), block);
+
+		output[0] = b'x';
+		assert!(read_block::<Header>(&mut output.as_slice()).is_err());
+	}
+
+	#[test]
+	fn snapshot_is_exported_and_imported() {
+		let mut client = Arc::new(TestClientBuilder::new().build());
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		block_on(client.import(BlockOrigin::Own, block)).unwrap();
+		client.finalize_block(BlockId::Number(1), None).unwrap();
+		let hash = client.info().finalized_hash;
+
+		let mut snapshot = Vec::new();
+		export_snapshot(client.clone(), None, &mut snapshot).unwrap();
+
+		// The state doesn't match the state root of the header.
+		let mut tampered = snapshot.clone();
+		*tampered.last_mut().unwrap() ^= 1;
+		let (imported, result) = import(&tampered, hash);
+		assert!(result.unwrap_err().to_string().contains("Invalid snapshot proof"));
+		assert_eq!(imported.info().best_number, 0);
+
+		// The snapshot is not taken at the expected block.
+		let (imported, result) = import(&snapshot, client.info().genesis_hash);
+		assert!(result.is_err());
+		assert_eq!(imported.info().best_number, 0);
+
+		let (imported, result) = import(&snapshot, hash);
+		result.unwrap();
+		assert_eq!(imported.info().best_hash, hash);
+		let pairs = |client: &TestClient| {
+			client.storage_pairs(&BlockId::Hash(hash), &StorageKey(Vec::new())).unwrap()
+		};
+		assert_eq!(pairs(&imported), pairs(&client));
+	}
+}
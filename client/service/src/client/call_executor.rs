@@ -18,7 +18,11 @@
 
 use super::{client::ClientConfig, wasm_override::WasmOverride, wasm_substitutes::WasmSubstitutes};
 use codec::{Decode, Encode};
-use sc_client_api::{backend, call_executor::CallExecutor, HeaderBackend};
+use sc_client_api::{
+	backend,
+	call_executor::{CallExecutor, StorageAccess},
+	HeaderBackend,
+};
 use sc_executor::{RuntimeVersion, RuntimeVersionOf};
 use sp_api::{ProofRecorder, StorageTransactionCache};
 use sp_core::{
@@ -183,6 +187,51 @@ where
 		Ok(return_data.into_encoded())
 	}
 
+	fn call_with_storage_access(
+		&self,
+		at: &BlockId<Block>,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+		extensions: Option<Extensions>,
+	) -> sp_blockchain::Result<(Vec<u8>, StorageAccess)> {
+		let mut changes = OverlayedChanges::default();
+		let state = self.backend.state_at(*at)?;
+		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code =
+			state_runtime_code.runtime_code().map_err(sp_blockchain::Error::RuntimeCode)?;
+
+		let runtime_code = self.check_override(runtime_code, at)?;
+
+		let at_hash = self.backend.blockchain().block_hash_from_id(at)?.ok_or_else(|| {
+			sp_blockchain::Error::UnknownBlock(format!("Could not find block hash for {:?}", at))
+		})?;
+
+		// The runtime code was fetched above, so it isn't recorded.
+		let recording_state = sp_state_machine::ReadRecordingBackend::new(&state);
+		let return_data = StateMachine::new(
+			&recording_state,
+			&mut changes,
+			&self.executor,
+			method,
+			call_data,
+			extensions.unwrap_or_default(),
+			&runtime_code,
+			self.spawn_handle.clone(),
+		)
+		.set_parent_hash(at_hash)
+		.execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+			strategy.get_manager(),
+			None,
+		)?;
+
+		let access = StorageAccess {
+			read: recording_state.read_keys(),
+			written: changes.changes().map(|(key, _)| key.clone()).collect(),
+		};
+		Ok((return_data.into_encoded(), access))
+	}
+
 	fn contextual_call<
 		EM: Fn(
 			Result<NativeOrEncoded<R>, Self::Error>,
@@ -78,7 +78,7 @@ use sp_runtime::{
 use sp_state_machine::{
 	prove_child_read, prove_range_read_with_child_with_size, prove_read,
 	read_range_proof_check_with_child_on_proving_backend, Backend as StateBackend,
-	ChildStorageCollection, KeyValueStates, KeyValueStorageLevel, StorageCollection,
+	ChildStorageCollection, KeyValueStates, KeyValueStorageLevel, StorageCollection, TrieDiff,
 	MAX_NESTED_TRIE_DEPTH,
 };
 use sp_trie::{CompactProof, StorageProof};
@@ -1522,6 +1522,24 @@ where
 			.child_storage_hash(child_info, &key.0)
 			.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)))
 	}
+
+	fn storage_diff(
+		&self,
+		from: &BlockId<Block>,
+		to: &BlockId<Block>,
+		key_prefix: &StorageKey,
+		start_key: Option<&StorageKey>,
+		limit: usize,
+	) -> sp_blockchain::Result<TrieDiff> {
+		let from_state = self.state_at(from)?;
+		let to_state = self.state_at(to)?;
+		let not_trie = || sp_blockchain::Error::Backend("State is not backed by a trie".into());
+		let from_trie = from_state.as_trie_backend().ok_or_else(not_trie)?;
+		let to_root = to_state.as_trie_backend().ok_or_else(not_trie)?.root();
+		from_trie
+			.diff(to_root, &key_prefix.0, start_key.map(|key| &key.0[..]), limit)
+			.map_err(sp_blockchain::Error::Storage)
+	}
 }
 
 impl<B, E, Block, RA> HeaderMetadata<Block> for Client<B, E, Block, RA>
@@ -1996,12 +2014,42 @@ where
 		self.backend.blockchain().has_indexed_transaction(hash)
 	}
 
+	fn indexed_transaction_size(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<usize>> {
+		self.backend.blockchain().indexed_transaction_size(hash)
+	}
+
+	fn indexed_transaction_hash(
+		&self,
+		code: u64,
+		digest: &[u8],
+	) -> sp_blockchain::Result<Option<Block::Hash>> {
+		self.backend.blockchain().indexed_transaction_hash(code, digest)
+	}
+
+	fn indexed_dag_block(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.backend.blockchain().indexed_dag_block(hash)
+	}
+
+	fn indexed_transaction_block(
+		&self,
+		hash: &Block::Hash,
+	) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+		self.backend.blockchain().indexed_transaction_block(hash)
+	}
+
 	fn block_indexed_body(
 		&self,
 		id: &BlockId<Block>,
 	) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
 		self.backend.blockchain().block_indexed_body(*id)
 	}
+
+	fn block_indexed_hashes(
+		&self,
+		id: &BlockId<Block>,
+	) -> sp_blockchain::Result<Option<Vec<Block::Hash>>> {
+		self.backend.blockchain().block_indexed_hashes(*id)
+	}
 }
 
 impl<B, E, Block, RA> backend::AuxStore for Client<B, E, Block, RA>
@@ -2080,17 +2128,36 @@ where
 impl<BE, E, B, RA> sp_transaction_storage_proof::IndexedBody<B> for Client<BE, E, B, RA>
 where
 	BE: backend::Backend<B>,
-	E: CallExecutor<B>,
+	E: CallExecutor<B, Backend = BE> + Send + Sync,
 	B: BlockT,
+	RA: ConstructRuntimeApi<B, Self>,
+	RA::RuntimeApi: sp_transaction_storage_proof::TransactionStorageApi<B>,
 {
 	fn block_indexed_body(
 		&self,
 		number: NumberFor<B>,
+		at: &B::Hash,
 	) -> Result<Option<Vec<Vec<u8>>>, sp_transaction_storage_proof::Error> {
-		self.backend
-			.blockchain()
-			.block_indexed_body(BlockId::number(number))
-			.map_err(|e| sp_transaction_storage_proof::Error::Application(Box::new(e)))
+		use sp_transaction_storage_proof::{Error, TransactionStorageApi};
+
+		// Removed data is only dropped from the database once the removal is finalized, so the
+		// runtime decides which of the indexed data is still stored on top of `at`.
+		let hashes = self
+			.runtime_api()
+			.indexed_transactions(&BlockId::Hash(*at), number)
+			.map_err(|e| Error::Application(Box::new(e)))?;
+		let mut transactions = Vec::with_capacity(hashes.len());
+		for hash in hashes {
+			match self.backend.blockchain().indexed_transaction(&hash) {
+				Ok(Some(data)) => transactions.push(data),
+				Ok(None) =>
+					return Err(Error::Application(
+						format!("Missing indexed transaction {:?}", hash).into(),
+					)),
+				Err(e) => return Err(Error::Application(Box::new(e))),
+			}
+		}
+		Ok(Some(transactions))
 	}
 
 	fn number(
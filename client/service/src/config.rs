@@ -29,6 +29,11 @@ pub use sc_network::{
 	},
 	Multiaddr,
 };
+pub use sc_offchain::{
+	HttpClientIdentity as OffchainHttpClientIdentity, HttpConfig as OffchainHttpConfig,
+	HttpHostPattern as OffchainHttpHostPattern, HttpProxy as OffchainHttpProxy,
+};
+pub use sc_rpc_server::{RpcMethodFilter, RpcMethodFilters, RpcRateLimits};
 
 use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
@@ -39,6 +44,7 @@ use std::{
 	io, iter,
 	net::SocketAddr,
 	path::{Path, PathBuf},
+	time::Duration,
 };
 use tempfile::TempDir;
 
@@ -97,6 +103,10 @@ pub struct Configuration {
 	pub rpc_methods: RpcMethods,
 	/// Maximum payload of rpc request/responses.
 	pub rpc_max_payload: Option<usize>,
+	/// Rate limits of RPC calls over HTTP and WebSockets.
+	pub rpc_rate_limits: RpcRateLimits,
+	/// RPC methods to expose over each interface, on top of `rpc_methods`.
+	pub rpc_method_filters: RpcMethodFilters,
 	/// Maximum size of the output buffer capacity for websocket connections.
 	pub ws_max_out_buffer_capacity: Option<usize>,
 	/// Prometheus endpoint configuration. `None` if disabled.
@@ -175,6 +185,14 @@ pub struct OffchainWorkerConfig {
 	pub enabled: bool,
 	/// allow writes from the runtime to the offchain worker database.
 	pub indexing_enabled: bool,
+	/// Maximum number of offchain workers running at the same time, further blocks are skipped.
+	pub max_concurrent_workers: Option<usize>,
+	/// Wall-clock time an offchain worker run may take before its HTTP requests are dropped.
+	pub run_timeout: Option<Duration>,
+	/// Don't run offchain workers during major sync.
+	pub skip_during_major_sync: bool,
+	/// Configuration of the HTTP client of offchain workers.
+	pub http: OffchainHttpConfig,
 }
 
 /// Configuration of the Prometheus endpoint.
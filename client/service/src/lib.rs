@@ -34,7 +34,13 @@ mod client;
 mod metrics;
 mod task_manager;
 
-use std::{collections::HashMap, io, net::SocketAddr, pin::Pin};
+use std::{
+	collections::HashMap,
+	io,
+	net::SocketAddr,
+	pin::Pin,
+	time::{Duration, UNIX_EPOCH},
+};
 
 use codec::{Decode, Encode};
 use futures::{Future, FutureExt, StreamExt};
@@ -242,6 +248,41 @@ async fn build_network_future<
 
 						let _ = sender.send(reserved_peers);
 					}
+					sc_rpc::system::Request::NetworkBannedPeers(sender) => {
+						let banned_peers = network.banned_peers()
+							.into_iter()
+							.map(|banned| sc_rpc::system::BannedPeer {
+								peer_id: banned.peer_id.to_base58(),
+								until: banned.until.map(|until| {
+									until.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+								}),
+							})
+							.collect();
+
+						let _ = sender.send(banned_peers);
+					}
+					sc_rpc::system::Request::NetworkBanPeer(peer_id, duration, sender) => {
+						let _ = match peer_id.parse::<PeerId>() {
+							Ok(peer_id) => {
+								network.service().ban_peer(peer_id, duration.map(Duration::from_secs));
+								sender.send(Ok(()))
+							}
+							Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+								e.to_string(),
+							))),
+						};
+					}
+					sc_rpc::system::Request::NetworkUnbanPeer(peer_id, sender) => {
+						let _ = match peer_id.parse::<PeerId>() {
+							Ok(peer_id) => {
+								network.service().unban_peer(peer_id);
+								sender.send(Ok(()))
+							}
+							Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+								e.to_string(),
+							))),
+						};
+					}
 					sc_rpc::system::Request::NodeRoles(sender) => {
 						use sc_rpc::system::NodeRole;
 
@@ -279,8 +320,7 @@ mod waiting {
 	impl Drop for HttpServer {
 		fn drop(&mut self) {
 			if let Some(server) = self.0.take() {
-				server.close_handle().close();
-				server.wait();
+				server.close();
 			}
 		}
 	}
@@ -352,7 +392,26 @@ fn start_rpc_servers<
 	}
 
 	let rpc_method_names = sc_rpc_server::method_names(|m| gen_handler(sc_rpc::DenyUnsafe::No, m))?;
+	config.rpc_method_filters.validate(&rpc_method_names).map_err(Error::Other)?;
+	config.rpc_rate_limits.validate(&rpc_method_names).map_err(Error::Other)?;
+	let rate_limiter = config
+		.rpc_rate_limits
+		.is_enabled()
+		.then(|| sc_rpc_server::RpcRateLimiter::new(config.rpc_rate_limits.clone()));
+	let external_middleware = |transport_label, method_filter: &sc_rpc_server::RpcMethodFilter| {
+		let middleware = sc_rpc_server::RpcMiddleware::new(
+			rpc_metrics.clone(),
+			rpc_method_names.clone(),
+			transport_label,
+		)
+		.with_method_filter(method_filter.clone());
+		match rate_limiter {
+			Some(ref rate_limiter) => middleware.with_rate_limiter(rate_limiter.clone()),
+			None => middleware,
+		}
+	};
 	Ok(Box::new((
+		// IPC is only reachable locally, so its calls are not rate limited.
 		config
 			.rpc_ipc
 			.as_ref()
@@ -365,7 +424,8 @@ fn start_rpc_servers<
 							rpc_metrics.clone(),
 							rpc_method_names.clone(),
 							"ipc",
-						),
+						)
+						.with_method_filter(config.rpc_method_filters.ipc.clone()),
 					)?,
 					server_metrics.clone(),
 				)
@@ -378,13 +438,10 @@ fn start_rpc_servers<
 				config.rpc_cors.as_ref(),
 				gen_handler(
 					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(
-						rpc_metrics.clone(),
-						rpc_method_names.clone(),
-						"http",
-					),
+					external_middleware("http", &config.rpc_method_filters.http),
 				)?,
 				config.rpc_max_payload,
+				config.rpc_rate_limits.trust_proxy_headers,
 				config.tokio_handle.clone(),
 			)
 			.map_err(Error::from)
@@ -397,11 +454,7 @@ fn start_rpc_servers<
 				config.rpc_cors.as_ref(),
 				gen_handler(
 					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(
-						rpc_metrics.clone(),
-						rpc_method_names.clone(),
-						"ws",
-					),
+					external_middleware("ws", &config.rpc_method_filters.ws),
 				)?,
 				config.rpc_max_payload,
 				config.ws_max_out_buffer_capacity,
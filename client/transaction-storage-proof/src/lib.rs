@@ -0,0 +1,793 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage proof readiness for transaction storage block authors.
+//!
+//! Every block of a chain running `pallet-transaction-storage` must include a proof of a random
+//! chunk of the data indexed `StoragePeriod` blocks earlier. An author that doesn't have this
+//! data fails to author. The storage period is read from the runtime through
+//! [`TransactionStorageApi`].
+//!
+//! [`ProofReadiness`] checks ahead of time that the indexed data of the blocks that upcoming
+//! proofs are built from is available locally. Missing data is fetched from a list of
+//! [`IndexedDataSource`]s and kept in memory until it's no longer needed, up to a bound on its
+//! total size. The handle implements
+//! [`IndexedBody`], so it can be passed to
+//! [`new_data_provider`](sp_transaction_storage_proof::registration::new_data_provider) in place
+//! of the client to make use of fetched data.
+//!
+//! The checks are driven by [`run_readiness_worker`]. The result is available from
+//! [`ProofReadiness::status`], through the [`rpc`] module and as prometheus metrics.
+
+#![warn(missing_docs)]
+
+use futures::{FutureExt, StreamExt};
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Saturating, Zero},
+};
+use sp_transaction_storage_proof::{
+	Error, IndexedBody, TransactionStorageApi, DEFAULT_STORAGE_PERIOD,
+};
+use std::{collections::HashMap, sync::Arc};
+
+pub mod rpc;
+mod source;
+
+pub use source::{BitswapSource, DirectorySource, IndexedDataSource};
+
+const LOG_TARGET: &str = "storage-proof";
+
+/// Default number of upcoming blocks to check readiness for.
+pub const DEFAULT_LOOKAHEAD: u32 = 16;
+
+/// Default bound on the total size of fetched data kept in memory.
+pub const DEFAULT_MAX_FETCHED_BYTES: usize = 512 * 1024 * 1024;
+
+/// Readiness of the node to build upcoming storage proofs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessStatus<Number> {
+	/// Best block number at the time of the last check.
+	pub best_number: Number,
+	/// Range of blocks whose indexed data upcoming proofs are built from. `None` if no proofs
+	/// are required for the upcoming blocks.
+	pub checked: Option<(Number, Number)>,
+	/// Number of indexed transactions in the checked blocks.
+	pub transactions: u32,
+	/// Number of indexed transactions of the checked blocks that are not available.
+	pub missing: u32,
+	/// Number of fetched indexed transactions kept in memory.
+	pub fetched: u32,
+	/// Total size of fetched indexed transactions kept in memory, in bytes.
+	pub fetched_bytes: u64,
+	/// `true` if all data required for upcoming proofs is available.
+	pub ready: bool,
+}
+
+/// Range of blocks whose indexed data is needed to build proofs for the `lookahead` blocks
+/// following `best`, given the storage `period`.
+fn proof_range<N>(best: N, period: N, lookahead: u32) -> Option<(N, N)>
+where
+	N: Saturating + One + Zero + PartialOrd + From<u32> + Copy,
+{
+	let first = best.saturating_add(One::one()).saturating_sub(period);
+	let last = best.saturating_add(N::from(lookahead)).saturating_sub(period);
+	if last.is_zero() {
+		// Too early to collect proofs.
+		return None
+	}
+	Some((if first.is_zero() { One::one() } else { first }, last))
+}
+
+#[derive(Clone)]
+struct Metrics {
+	transactions: Gauge<U64>,
+	missing: Gauge<U64>,
+	ready: Gauge<U64>,
+	fetched: Counter<U64>,
+	fetch_failures: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			transactions: register(
+				Gauge::new(
+					"substrate_transaction_storage_proof_transactions",
+					"Number of indexed transactions required for upcoming storage proofs",
+				)?,
+				registry,
+			)?,
+			missing: register(
+				Gauge::new(
+					"substrate_transaction_storage_proof_missing_transactions",
+					"Number of indexed transactions required for upcoming storage proofs \
+					that are not available",
+				)?,
+				registry,
+			)?,
+			ready: register(
+				Gauge::new(
+					"substrate_transaction_storage_proof_ready",
+					"Whether all data required for upcoming storage proofs is available",
+				)?,
+				registry,
+			)?,
+			fetched: register(
+				Counter::new(
+					"substrate_transaction_storage_proof_fetched_total",
+					"Number of indexed transactions fetched from external sources",
+				)?,
+				registry,
+			)?,
+			fetch_failures: register(
+				Counter::new(
+					"substrate_transaction_storage_proof_fetch_failures_total",
+					"Number of failed attempts to fetch indexed transactions",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Fetched data kept in memory, bounded by its total size.
+struct Fetched<B: BlockT> {
+	/// Data by content hash, along with the number of the block that indexed it.
+	data: HashMap<B::Hash, (NumberFor<B>, Vec<u8>)>,
+	/// Total size of `data` in bytes.
+	bytes: usize,
+	max_bytes: usize,
+}
+
+impl<B: BlockT> Fetched<B> {
+	fn new(max_bytes: usize) -> Self {
+		Self { data: HashMap::new(), bytes: 0, max_bytes }
+	}
+
+	fn get(&self, hash: &B::Hash) -> Option<&Vec<u8>> {
+		self.data.get(hash).map(|(_, data)| data)
+	}
+
+	fn contains(&self, hash: &B::Hash) -> bool {
+		self.data.contains_key(hash)
+	}
+
+	/// Keep `data` indexed in block `number`. Data of later blocks is needed for later proofs, so
+	/// it is evicted to make room if needed. Returns `false` if the data doesn't fit.
+	fn insert(&mut self, hash: B::Hash, number: NumberFor<B>, data: Vec<u8>) -> bool {
+		if data.len() > self.max_bytes {
+			return false
+		}
+		self.remove(&hash);
+		while self.bytes + data.len() > self.max_bytes {
+			let latest = self
+				.data
+				.iter()
+				.max_by_key(|(_, (number, _))| *number)
+				.map(|(hash, (number, _))| (*hash, *number));
+			match latest {
+				Some((latest, latest_number)) if latest_number > number => self.remove(&latest),
+				_ => return false,
+			}
+		}
+		self.bytes += data.len();
+		self.data.insert(hash, (number, data));
+		true
+	}
+
+	fn remove(&mut self, hash: &B::Hash) {
+		if let Some((_, data)) = self.data.remove(hash) {
+			self.bytes -= data.len();
+		}
+	}
+
+	/// Drop data indexed before block `first`.
+	fn prune(&mut self, first: NumberFor<B>) {
+		let bytes = &mut self.bytes;
+		self.data.retain(|_, (number, data)| {
+			if *number < first {
+				*bytes -= data.len();
+				return false
+			}
+			true
+		});
+	}
+}
+
+struct State<B: BlockT> {
+	status: ReadinessStatus<NumberFor<B>>,
+	fetched: Fetched<B>,
+}
+
+/// Shared handle tracking the readiness of the node to build storage proofs.
+pub struct ProofReadiness<B: BlockT, C> {
+	client: Arc<C>,
+	lookahead: u32,
+	state: Arc<Mutex<State<B>>>,
+	metrics: Option<Metrics>,
+}
+
+impl<B: BlockT, C> Clone for ProofReadiness<B, C> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			lookahead: self.lookahead,
+			state: self.state.clone(),
+			metrics: self.metrics.clone(),
+		}
+	}
+}
+
+impl<B, C> ProofReadiness<B, C>
+where
+	B: BlockT,
+	C: BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+{
+	/// Create a new instance, checking `lookahead` blocks ahead of the best block and keeping up
+	/// to `max_fetched_bytes` of fetched data in memory.
+	pub fn new(
+		client: Arc<C>,
+		lookahead: u32,
+		max_fetched_bytes: usize,
+		metrics_registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		let metrics = metrics_registry.map(Metrics::register).transpose()?;
+		let status = ReadinessStatus {
+			best_number: client.info().best_number,
+			checked: None,
+			transactions: 0,
+			missing: 0,
+			fetched: 0,
+			fetched_bytes: 0,
+			ready: true,
+		};
+		Ok(Self {
+			client,
+			lookahead,
+			state: Arc::new(Mutex::new(State { status, fetched: Fetched::new(max_fetched_bytes) })),
+			metrics,
+		})
+	}
+
+	/// Result of the last check.
+	pub fn status(&self) -> ReadinessStatus<NumberFor<B>> {
+		self.state.lock().status.clone()
+	}
+}
+
+impl<B, C> ProofReadiness<B, C>
+where
+	B: BlockT,
+	C: BlockBackend<B> + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: TransactionStorageApi<B>,
+{
+	/// Check that the data required for proofs of the blocks following `best` is available,
+	/// fetching missing data from `sources`.
+	pub async fn check(&self, best: NumberFor<B>, sources: &[Box<dyn IndexedDataSource<B>>]) {
+		let range = proof_range(best, self.storage_period(best), self.lookahead);
+		let mut transactions = 0;
+		let mut missing = 0;
+
+		if let Some((first, last)) = range {
+			self.state.lock().fetched.prune(first);
+
+			let mut number = first;
+			while number <= last {
+				let hashes = match self.indexed_hashes(number, BlockId::Number(best)) {
+					Ok(hashes) => hashes,
+					Err(e) => {
+						log::debug!(
+							target: LOG_TARGET,
+							"Error reading indexed transactions of block #{}: {}",
+							number,
+							e,
+						);
+						Vec::new()
+					},
+				};
+				for hash in hashes {
+					transactions += 1;
+					if self.is_available(&hash) {
+						continue
+					}
+					match self.fetch(hash, sources).await {
+						Some(data) =>
+							if !self.state.lock().fetched.insert(hash, number, data) {
+								log::debug!(
+									target: LOG_TARGET,
+									"No room to keep fetched indexed transaction {:?}",
+									hash,
+								);
+								missing += 1;
+							},
+						None => missing += 1,
+					}
+				}
+				number += One::one();
+			}
+		}
+
+		let mut state = self.state.lock();
+		let fetched = state.fetched.data.len() as u32;
+		let fetched_bytes = state.fetched.bytes as u64;
+		state.status = ReadinessStatus {
+			best_number: best,
+			checked: range,
+			transactions,
+			missing,
+			fetched,
+			fetched_bytes,
+			ready: missing == 0,
+		};
+		if let Some(metrics) = &self.metrics {
+			metrics.transactions.set(transactions.into());
+			metrics.missing.set(missing.into());
+			metrics.ready.set((missing == 0).into());
+		}
+		if missing > 0 {
+			log::warn!(
+				target: LOG_TARGET,
+				"💾 {} indexed transactions required for upcoming storage proofs are missing",
+				missing,
+			);
+		}
+	}
+
+	/// Storage period in effect at block `best`. Falls back to [`DEFAULT_STORAGE_PERIOD`] if the
+	/// runtime doesn't provide it.
+	fn storage_period(&self, best: NumberFor<B>) -> NumberFor<B> {
+		self.client
+			.runtime_api()
+			.storage_period(&BlockId::Number(best))
+			.unwrap_or_else(|e| {
+				log::debug!(
+					target: LOG_TARGET,
+					"Error reading the storage period at block #{}: {}",
+					best,
+					e,
+				);
+				DEFAULT_STORAGE_PERIOD.into()
+			})
+	}
+
+	/// Content hashes of the data indexed in block `number` that is still stored as of block
+	/// `at`. Removed data is only dropped from the database once the removal is finalized, so
+	/// this follows the runtime rather than the database.
+	fn indexed_hashes(
+		&self,
+		number: NumberFor<B>,
+		at: BlockId<B>,
+	) -> Result<Vec<B::Hash>, sp_api::ApiError> {
+		self.client.runtime_api().indexed_transactions(&at, number)
+	}
+
+	fn is_available(&self, hash: &B::Hash) -> bool {
+		if self.state.lock().fetched.contains(hash) {
+			return true
+		}
+		self.client.has_indexed_transaction(hash).unwrap_or(false)
+	}
+
+	/// Fetch data with the given content hash from the first source that has it.
+	async fn fetch(
+		&self,
+		hash: B::Hash,
+		sources: &[Box<dyn IndexedDataSource<B>>],
+	) -> Option<Vec<u8>> {
+		for source in sources {
+			match source.fetch(hash).await {
+				Ok(data) if sp_core::hashing::blake2_256(&data)[..] == *hash.as_ref() => {
+					log::debug!(
+						target: LOG_TARGET,
+						"Fetched indexed transaction {:?} from {}",
+						hash,
+						source.name(),
+					);
+					if let Some(metrics) = &self.metrics {
+						metrics.fetched.inc();
+					}
+					return Some(data)
+				},
+				Ok(_) => log::debug!(
+					target: LOG_TARGET,
+					"Data of indexed transaction {:?} from {} doesn't match its hash",
+					hash,
+					source.name(),
+				),
+				Err(e) => log::debug!(
+					target: LOG_TARGET,
+					"Failed to fetch indexed transaction {:?} from {}: {}",
+					hash,
+					source.name(),
+					e,
+				),
+			}
+			if let Some(metrics) = &self.metrics {
+				metrics.fetch_failures.inc();
+			}
+		}
+		None
+	}
+}
+
+impl<B, C> IndexedBody<B> for ProofReadiness<B, C>
+where
+	B: BlockT,
+	C: BlockBackend<B> + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: TransactionStorageApi<B>,
+{
+	fn block_indexed_body(
+		&self,
+		number: NumberFor<B>,
+		at: &B::Hash,
+	) -> Result<Option<Vec<Vec<u8>>>, Error> {
+		let hashes = self
+			.indexed_hashes(number, BlockId::Hash(*at))
+			.map_err(|e| Error::Application(Box::new(e)))?;
+		let state = self.state.lock();
+		let mut transactions = Vec::with_capacity(hashes.len());
+		for hash in hashes {
+			if let Some(data) = state.fetched.get(&hash) {
+				transactions.push(data.clone());
+				continue
+			}
+			match self.client.indexed_transaction(&hash) {
+				Ok(Some(data)) => transactions.push(data),
+				Ok(None) =>
+					return Err(Error::Application(
+						format!("Missing indexed transaction {:?}", hash).into(),
+					)),
+				Err(e) => return Err(Error::Application(Box::new(e))),
+			}
+		}
+		Ok(Some(transactions))
+	}
+
+	fn number(&self, hash: B::Hash) -> Result<Option<NumberFor<B>>, Error> {
+		self.client.number(hash).map_err(|e| Error::Application(Box::new(e)))
+	}
+}
+
+/// Keep checking readiness as new best blocks are imported.
+pub async fn run_readiness_worker<B, C>(
+	readiness: ProofReadiness<B, C>,
+	sources: Vec<Box<dyn IndexedDataSource<B>>>,
+) where
+	B: BlockT,
+	C: BlockBackend<B>
+		+ HeaderBackend<B>
+		+ BlockchainEvents<B>
+		+ ProvideRuntimeApi<B>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: TransactionStorageApi<B>,
+{
+	let mut imports = readiness.client.import_notification_stream();
+	readiness.check(readiness.client.info().best_number, &sources).await;
+
+	while let Some(notification) = imports.next().await {
+		let mut best = notification.is_new_best.then(|| *notification.header.number());
+		// Skip blocks imported while the previous check was running.
+		while let Some(Some(notification)) = imports.next().now_or_never() {
+			if notification.is_new_best {
+				best = Some(*notification.header.number());
+			}
+		}
+		if let Some(best) = best {
+			readiness.check(best, &sources).await;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{executor::block_on, future::BoxFuture};
+	use sp_api::{ApiRef, ProvideRuntimeApi};
+	use sp_runtime::Justifications;
+	use substrate_test_runtime_client::runtime::{Block, Extrinsic, Hash};
+
+	const PERIOD: u64 = DEFAULT_STORAGE_PERIOD as u64;
+	const TEST_PERIOD: u64 = 10;
+
+	/// Client with a fixed best block whose runtime indexed `indexed` data, of which `stored`
+	/// is available locally.
+	#[derive(Default)]
+	struct TestClient {
+		best: u64,
+		indexed: HashMap<u64, Vec<Hash>>,
+		stored: HashMap<Hash, Vec<u8>>,
+	}
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = RuntimeApi;
+
+		fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api> {
+			RuntimeApi { indexed: self.indexed.clone() }.into()
+		}
+	}
+
+	struct RuntimeApi {
+		indexed: HashMap<u64, Vec<Hash>>,
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl TransactionStorageApi<Block> for RuntimeApi {
+			fn storage_period() -> NumberFor<Block> {
+				TEST_PERIOD
+			}
+
+			fn indexed_transactions(&self, block: NumberFor<Block>) -> Vec<Hash> {
+				self.indexed.get(&block).cloned().unwrap_or_default()
+			}
+		}
+	}
+
+	impl HeaderBackend<Block> for TestClient {
+		fn header(
+			&self,
+			_id: BlockId<Block>,
+		) -> sp_blockchain::Result<Option<<Block as BlockT>::Header>> {
+			Ok(None)
+		}
+
+		fn info(&self) -> sp_blockchain::Info<Block> {
+			sp_blockchain::Info {
+				best_hash: Default::default(),
+				best_number: self.best,
+				finalized_hash: Default::default(),
+				finalized_number: Zero::zero(),
+				genesis_hash: Default::default(),
+				number_leaves: Default::default(),
+				finalized_state: None,
+				block_gap: None,
+			}
+		}
+
+		fn status(&self, _id: BlockId<Block>) -> sp_blockchain::Result<sp_blockchain::BlockStatus> {
+			Ok(sp_blockchain::BlockStatus::Unknown)
+		}
+
+		fn number(&self, _hash: Hash) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+			Ok(None)
+		}
+
+		fn hash(&self, _number: NumberFor<Block>) -> sp_blockchain::Result<Option<Hash>> {
+			Ok(None)
+		}
+	}
+
+	impl BlockBackend<Block> for TestClient {
+		fn block_body(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Vec<Extrinsic>>> {
+			unimplemented!()
+		}
+
+		fn block_indexed_body(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
+			unimplemented!()
+		}
+
+		fn block_indexed_hashes(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Vec<Hash>>> {
+			unimplemented!()
+		}
+
+		fn block(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<sp_runtime::generic::SignedBlock<Block>>> {
+			unimplemented!()
+		}
+
+		fn block_status(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<sp_consensus::BlockStatus> {
+			unimplemented!()
+		}
+
+		fn justifications(
+			&self,
+			_id: &BlockId<Block>,
+		) -> sp_blockchain::Result<Option<Justifications>> {
+			unimplemented!()
+		}
+
+		fn block_hash(&self, _number: NumberFor<Block>) -> sp_blockchain::Result<Option<Hash>> {
+			unimplemented!()
+		}
+
+		fn indexed_transaction(&self, hash: &Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.stored.get(hash).cloned())
+		}
+
+		fn indexed_transaction_hash(
+			&self,
+			_code: u64,
+			_digest: &[u8],
+		) -> sp_blockchain::Result<Option<Hash>> {
+			unimplemented!()
+		}
+
+		fn indexed_dag_block(&self, _hash: &Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			unimplemented!()
+		}
+
+		fn indexed_transaction_block(
+			&self,
+			_hash: &Hash,
+		) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+			unimplemented!()
+		}
+	}
+
+	/// Source serving `data`, which may not match the requested hash.
+	#[derive(Clone, Default)]
+	struct TestSource {
+		data: Arc<Mutex<HashMap<Hash, Vec<u8>>>>,
+	}
+
+	impl IndexedDataSource<Block> for TestSource {
+		fn name(&self) -> &str {
+			"test"
+		}
+
+		fn fetch(&self, hash: Hash) -> BoxFuture<'static, Result<Vec<u8>, String>> {
+			let data = self.data.lock().get(&hash).cloned().ok_or_else(|| "Not found".to_string());
+			futures::future::ready(data).boxed()
+		}
+	}
+
+	fn hash(data: &[u8]) -> Hash {
+		sp_core::hashing::blake2_256(data).into()
+	}
+
+	#[test]
+	fn check_fetches_missing_data() {
+		let (stored, fetched, invalid) = (vec![1], vec![2; 10], vec![3; 10]);
+		let mut client = TestClient { best: TEST_PERIOD, ..Default::default() };
+		client.indexed.insert(1, vec![hash(&stored)]);
+		client.indexed.insert(2, vec![hash(&fetched), hash(&invalid)]);
+		client.stored.insert(hash(&stored), stored.clone());
+		let source = TestSource::default();
+		source.data.lock().insert(hash(&fetched), fetched.clone());
+		source.data.lock().insert(hash(&invalid), vec![4; 10]);
+		let sources: Vec<Box<dyn IndexedDataSource<Block>>> = vec![Box::new(source.clone())];
+		let readiness =
+			ProofReadiness::<Block, _>::new(Arc::new(client), 2, DEFAULT_MAX_FETCHED_BYTES, None)
+				.unwrap();
+
+		block_on(readiness.check(TEST_PERIOD, &sources));
+		assert_eq!(
+			readiness.status(),
+			ReadinessStatus {
+				best_number: TEST_PERIOD,
+				checked: Some((1, 2)),
+				transactions: 3,
+				missing: 1,
+				fetched: 1,
+				fetched_bytes: 10,
+				ready: false,
+			}
+		);
+		assert!(readiness.block_indexed_body(2, &Default::default()).is_err());
+
+		source.data.lock().insert(hash(&invalid), invalid.clone());
+		block_on(readiness.check(TEST_PERIOD, &sources));
+		let status = readiness.status();
+		assert_eq!((status.missing, status.fetched, status.fetched_bytes), (0, 2, 20));
+		assert!(status.ready);
+
+		// Proofs are built from both locally stored and fetched data.
+		assert_eq!(
+			readiness.block_indexed_body(1, &Default::default()).unwrap(),
+			Some(vec![stored])
+		);
+		assert_eq!(
+			readiness.block_indexed_body(2, &Default::default()).unwrap(),
+			Some(vec![fetched, invalid])
+		);
+
+		// Fetched data is dropped once no upcoming proof needs it.
+		block_on(readiness.check(TEST_PERIOD + 2, &sources));
+		let status = readiness.status();
+		assert_eq!((status.checked, status.fetched, status.fetched_bytes), (Some((3, 4)), 0, 0));
+	}
+
+	#[test]
+	fn check_keeps_fetched_data_within_bound() {
+		let (first, second) = (vec![1; 10], vec![2; 10]);
+		let mut client = TestClient { best: TEST_PERIOD, ..Default::default() };
+		client.indexed.insert(1, vec![hash(&first)]);
+		client.indexed.insert(2, vec![hash(&second)]);
+		let source = TestSource::default();
+		source.data.lock().insert(hash(&first), first.clone());
+		source.data.lock().insert(hash(&second), second);
+		let sources: Vec<Box<dyn IndexedDataSource<Block>>> = vec![Box::new(source)];
+		let readiness = ProofReadiness::<Block, _>::new(Arc::new(client), 2, 15, None).unwrap();
+
+		block_on(readiness.check(TEST_PERIOD, &sources));
+		let status = readiness.status();
+		assert_eq!((status.missing, status.fetched, status.fetched_bytes), (1, 1, 10));
+		assert_eq!(
+			readiness.block_indexed_body(1, &Default::default()).unwrap(),
+			Some(vec![first])
+		);
+	}
+
+	#[test]
+	fn fetched_data_evicts_later_blocks() {
+		let mut fetched = Fetched::<Block>::new(25);
+		assert!(!fetched.insert(hash(&[0]), 1, vec![0; 30]));
+		assert!(fetched.insert(hash(&[1]), 3, vec![1; 10]));
+		assert!(fetched.insert(hash(&[2]), 2, vec![2; 10]));
+		// Data needed for an earlier proof makes room by evicting data of later blocks.
+		assert!(fetched.insert(hash(&[3]), 1, vec![3; 10]));
+		assert!(!fetched.contains(&hash(&[1])));
+		assert_eq!(fetched.bytes, 20);
+		// Data of earlier blocks is never evicted for later ones.
+		assert!(!fetched.insert(hash(&[4]), 3, vec![4; 10]));
+		assert_eq!(fetched.bytes, 20);
+
+		fetched.prune(2);
+		assert!(!fetched.contains(&hash(&[3])));
+		assert!(fetched.contains(&hash(&[2])));
+		assert_eq!(fetched.bytes, 10);
+	}
+
+	#[test]
+	fn no_proofs_required_early() {
+		assert_eq!(proof_range::<u64>(0, PERIOD, DEFAULT_LOOKAHEAD), None);
+		let best = PERIOD - DEFAULT_LOOKAHEAD as u64;
+		assert_eq!(proof_range::<u64>(best, PERIOD, DEFAULT_LOOKAHEAD), None);
+	}
+
+	#[test]
+	fn range_starts_at_first_proof() {
+		let best = PERIOD - 2;
+		assert_eq!(proof_range::<u64>(best, PERIOD, 4), Some((1, 2)));
+	}
+
+	#[test]
+	fn range_covers_lookahead() {
+		let best = PERIOD + 100;
+		assert_eq!(proof_range::<u64>(best, PERIOD, 4), Some((101, 104)));
+	}
+
+	#[test]
+	fn range_follows_storage_period() {
+		assert_eq!(proof_range::<u64>(10, 20, 4), None);
+		assert_eq!(proof_range::<u64>(30, 20, 4), Some((11, 14)));
+	}
+}
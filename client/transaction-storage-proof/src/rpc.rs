@@ -0,0 +1,55 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC interface to the storage proof readiness status.
+
+use crate::{ProofReadiness, ReadinessStatus};
+use jsonrpc_derive::rpc;
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+/// Storage proof readiness RPC methods.
+#[rpc]
+pub trait ProofReadinessApi<Number> {
+	/// Returns the readiness of the node to build upcoming storage proofs.
+	#[rpc(name = "transactionStorage_proofReadiness", returns = "ReadinessStatus<Number>")]
+	fn proof_readiness(&self) -> jsonrpc_core::Result<ReadinessStatus<Number>>;
+}
+
+/// Implements the [`ProofReadinessApi`] RPC trait.
+pub struct ProofReadinessRpc<B: BlockT, C> {
+	readiness: ProofReadiness<B, C>,
+}
+
+impl<B: BlockT, C> ProofReadinessRpc<B, C> {
+	/// Create a new instance reporting the status of `readiness`.
+	pub fn new(readiness: ProofReadiness<B, C>) -> Self {
+		Self { readiness }
+	}
+}
+
+impl<B, C> ProofReadinessApi<NumberFor<B>> for ProofReadinessRpc<B, C>
+where
+	B: BlockT,
+	C: BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+{
+	fn proof_readiness(&self) -> jsonrpc_core::Result<ReadinessStatus<NumberFor<B>>> {
+		Ok(self.readiness.status())
+	}
+}
@@ -0,0 +1,170 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sources of indexed data missing locally.
+
+use futures::{
+	future::{self, BoxFuture, Either},
+	FutureExt,
+};
+use futures_timer::Delay;
+use hyper::{body::HttpBody, client::HttpConnector, Client, StatusCode};
+use hyper_rustls::HttpsConnector;
+use sc_network::{bitswap::Cid, ExHashT, NetworkService};
+use sp_runtime::traits::Block as BlockT;
+use sp_transaction_storage_proof::unixfs::{cid, RAW_CODEC};
+use std::{sync::Arc, time::Duration};
+
+/// Maximum size of data accepted from a directory.
+const MAX_FETCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// A source of indexed data.
+///
+/// Returned data is checked against the requested content hash by the caller.
+pub trait IndexedDataSource<B: BlockT>: Send + Sync {
+	/// Name of the source, used in logs.
+	fn name(&self) -> &str;
+
+	/// Fetch the indexed data with the given blake2b-256 content hash.
+	fn fetch(&self, hash: B::Hash) -> BoxFuture<'static, Result<Vec<u8>, String>>;
+}
+
+/// Raw CIDv1 of indexed data with the given blake2b-256 content hash.
+fn raw_cid<H: AsRef<[u8]>>(hash: &H) -> Result<Cid, String> {
+	let hash: [u8; 32] = hash
+		.as_ref()
+		.try_into()
+		.map_err(|_| "Content hash is not 32 bytes long".to_string())?;
+	Cid::try_from(cid(RAW_CODEC, &hash)).map_err(|e| e.to_string())
+}
+
+/// Fetches indexed data from peers over bitswap.
+///
+/// Requires the bitswap client to be enabled in the network configuration.
+pub struct BitswapSource<B: BlockT + 'static, H: ExHashT> {
+	network: Arc<NetworkService<B, H>>,
+	timeout: Duration,
+}
+
+impl<B: BlockT + 'static, H: ExHashT> BitswapSource<B, H> {
+	/// Create a new instance, giving up on a request after `timeout`.
+	pub fn new(network: Arc<NetworkService<B, H>>, timeout: Duration) -> Self {
+		Self { network, timeout }
+	}
+}
+
+impl<B: BlockT + 'static, H: ExHashT> IndexedDataSource<B> for BitswapSource<B, H> {
+	fn name(&self) -> &str {
+		"bitswap"
+	}
+
+	fn fetch(&self, hash: B::Hash) -> BoxFuture<'static, Result<Vec<u8>, String>> {
+		let network = self.network.clone();
+		let timeout = self.timeout;
+		async move {
+			let cid = raw_cid(&hash)?;
+			network.bitswap_request(cid, timeout).await.map_err(|e| e.to_string())
+		}
+		.boxed()
+	}
+}
+
+/// Fetches indexed data over HTTP(S) from an external directory.
+///
+/// The URL of the data is built from a template by replacing `{hash}` with the hex encoded
+/// content hash and `{cid}` with the raw CIDv1 of the data, e.g.
+/// `https://ipfs.example.com/ipfs/{cid}`.
+pub struct DirectorySource {
+	url: String,
+	client: Client<HttpsConnector<HttpConnector>>,
+	timeout: Duration,
+}
+
+impl DirectorySource {
+	/// Create a new instance with the given URL template, giving up on a request after
+	/// `timeout`.
+	pub fn new(url: String, timeout: Duration) -> Self {
+		let connector = HttpsConnector::with_native_roots();
+		Self { url, client: Client::builder().build(connector), timeout }
+	}
+
+	fn url<H: AsRef<[u8]>>(&self, hash: &H) -> Result<String, String> {
+		let mut url = self.url.replace("{hash}", &hex::encode(hash.as_ref()));
+		if url.contains("{cid}") {
+			url = url.replace("{cid}", &raw_cid(hash)?.to_string());
+		}
+		Ok(url)
+	}
+}
+
+impl<B: BlockT> IndexedDataSource<B> for DirectorySource {
+	fn name(&self) -> &str {
+		&self.url
+	}
+
+	fn fetch(&self, hash: B::Hash) -> BoxFuture<'static, Result<Vec<u8>, String>> {
+		let client = self.client.clone();
+		let url = self.url(&hash);
+		let request = async move {
+			let uri = url?.parse::<hyper::Uri>().map_err(|e| e.to_string())?;
+			let response = client.get(uri).await.map_err(|e| e.to_string())?;
+			if response.status() != StatusCode::OK {
+				return Err(format!("Unexpected response status {}", response.status()))
+			}
+			let mut body = response.into_body();
+			let mut data = Vec::new();
+			while let Some(chunk) = body.data().await {
+				let chunk = chunk.map_err(|e| e.to_string())?;
+				if data.len() + chunk.len() > MAX_FETCH_SIZE {
+					return Err("Response is too large".into())
+				}
+				data.extend_from_slice(&chunk);
+			}
+			Ok(data)
+		};
+		let timeout = Delay::new(self.timeout);
+		async move {
+			match future::select(Box::pin(request), timeout).await {
+				Either::Left((result, _)) => result,
+				Either::Right(_) => Err("Request timed out".into()),
+			}
+		}
+		.boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn directory_url_from_template() {
+		let hash = H256::repeat_byte(0xab);
+		let source = DirectorySource::new(
+			"https://example.com/{hash}?cid={cid}".into(),
+			Duration::from_secs(1),
+		);
+		let expected_cid = raw_cid(&hash).unwrap();
+		assert_eq!(expected_cid.codec(), RAW_CODEC);
+		assert_eq!(
+			source.url(&hash).unwrap(),
+			format!("https://example.com/{}?cid={}", hex::encode(hash), expected_cid),
+		);
+	}
+}
@@ -23,6 +23,23 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+use sp_runtime::{ApplyExtrinsicResult, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// The effects of dry running an extrinsic, see [`DryRunApi::dry_run`].
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct DryRunEffects<Balance> {
+	/// The result of applying the extrinsic.
+	pub result: ApplyExtrinsicResult,
+	/// SCALE encoded `Vec<EventRecord>` of the events deposited by the extrinsic.
+	pub events: Vec<u8>,
+	/// The weight of the extrinsic after dispatch.
+	pub weight: u64,
+	/// The fee charged for the extrinsic after dispatch, excluding the tip.
+	pub fee: Balance,
+}
+
 sp_api::decl_runtime_apis! {
 	/// The API to query account nonce (aka transaction index).
 	pub trait AccountNonceApi<AccountId, Index> where
@@ -32,4 +49,14 @@ sp_api::decl_runtime_apis! {
 		/// Get current account nonce of given `AccountId`.
 		fn account_nonce(account: AccountId) -> Index;
 	}
+
+	/// The API to dry run extrinsics.
+	pub trait DryRunApi<Balance> where
+		Balance: codec::Codec,
+	{
+		/// Apply the extrinsic on top of the current state and return its effects.
+		///
+		/// The changes to the state are meant to be discarded by the caller.
+		fn dry_run(extrinsic: Block::Extrinsic) -> DryRunEffects<Balance>;
+	}
 }
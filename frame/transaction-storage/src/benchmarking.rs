@@ -129,15 +129,23 @@ benchmarks! {
 		let l in 1 .. MaxTransactionSize::<T>::get();
 		let caller: T::AccountId = whitelisted_caller();
 		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		// The quota is checked against the default when the caller has no override.
+		DefaultQuota::<T>::put(u64::MAX);
 	}: _(RawOrigin::Signed(caller.clone()), vec![0u8; l as usize])
 	verify {
 		assert!(!BlockTransactions::<T>::get().is_empty());
-		assert_last_event::<T>(Event::Stored { index: 0 }.into());
+		let dag = Dag::build(&vec![0u8; l as usize], sp_io::hashing::blake2_256);
+		assert_last_event::<T>(Event::Stored {
+			index: 0,
+			cid: dag.root_cid(),
+			chunk_cids: dag.leaf_cids(),
+		}.into());
 	}
 
 	renew {
 		let caller: T::AccountId = whitelisted_caller();
 		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		DefaultQuota::<T>::put(u64::MAX);
 		TransactionStorage::<T>::store(
 			RawOrigin::Signed(caller.clone()).into(),
 			vec![0u8; MaxTransactionSize::<T>::get() as usize],
@@ -166,5 +174,99 @@ benchmarks! {
 		assert_last_event::<T>(Event::ProofChecked.into());
 	}
 
+	set_quota {
+		let who: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Root, who.clone(), Some(1024))
+	verify {
+		assert_eq!(Quotas::<T>::get(&who), Some(1024));
+	}
+
+	set_default_quota {
+	}: _(RawOrigin::Root, Some(1024))
+	verify {
+		assert_eq!(DefaultQuota::<T>::get(), Some(1024));
+	}
+
+	// Worst case: the first of `MaxBlockTransactions` transactions is removed, so all the others
+	// are shifted, and the subscription renewing it ends.
+	remove {
+		run_to_block::<T>(1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		for i in 0 .. MaxBlockTransactions::<T>::get() {
+			TransactionStorage::<T>::store(RawOrigin::Signed(caller.clone()).into(), i.encode())?;
+		}
+		run_to_block::<T>(2u32.into());
+		TransactionStorage::<T>::subscribe(
+			RawOrigin::Signed(caller.clone()).into(),
+			T::BlockNumber::one(),
+			0,
+			10,
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), T::BlockNumber::one(), 0)
+	verify {
+		assert_last_event::<T>(Event::Removed { block: T::BlockNumber::one(), index: 0 }.into());
+		assert!(Subscriptions::<T>::get(&BlakeTwo256::hash(&0u32.encode())).is_none());
+	}
+
+	subscribe {
+		run_to_block::<T>(1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		TransactionStorage::<T>::store(RawOrigin::Signed(caller.clone()).into(), vec![0u8; 1])?;
+		run_to_block::<T>(2u32.into());
+	}: _(RawOrigin::Signed(caller.clone()), T::BlockNumber::one(), 0, 10)
+	verify {
+		let content_hash = BlakeTwo256::hash(&[0u8]);
+		assert!(Subscriptions::<T>::get(&content_hash).is_some());
+	}
+
+	unsubscribe {
+		run_to_block::<T>(1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		TransactionStorage::<T>::store(RawOrigin::Signed(caller.clone()).into(), vec![0u8; 1])?;
+		run_to_block::<T>(2u32.into());
+		TransactionStorage::<T>::subscribe(
+			RawOrigin::Signed(caller.clone()).into(),
+			T::BlockNumber::one(),
+			0,
+			10,
+		)?;
+		let content_hash = BlakeTwo256::hash(&[0u8]);
+	}: _(RawOrigin::Signed(caller.clone()), content_hash)
+	verify {
+		assert!(Subscriptions::<T>::get(&content_hash).is_none());
+		assert!(RenewalQueue::<T>::get(StoragePeriod::<T>::get() + T::BlockNumber::one()).is_empty());
+	}
+
+	renew_subscriptions {
+		let n in 0 .. MaxBlockTransactions::<T>::get();
+		run_to_block::<T>(1u32.into());
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		DefaultQuota::<T>::put(u64::MAX);
+		for i in 0 .. n {
+			TransactionStorage::<T>::store(RawOrigin::Signed(caller.clone()).into(), i.encode())?;
+		}
+		run_to_block::<T>(2u32.into());
+		for i in 0 .. n {
+			TransactionStorage::<T>::subscribe(
+				RawOrigin::Signed(caller.clone()).into(),
+				T::BlockNumber::one(),
+				i,
+				10,
+			)?;
+		}
+		let due = StoragePeriod::<T>::get() + T::BlockNumber::one();
+		run_to_block::<T>(due - T::BlockNumber::one());
+		frame_system::Pallet::<T>::set_block_number(due);
+	}: {
+		crate::Pallet::<T>::on_initialize(due);
+	}
+	verify {
+		assert_eq!(BlockTransactions::<T>::decode_len().unwrap_or(0), n as usize);
+	}
+
 	impl_benchmark_test_suite!(TransactionStorage, crate::mock::new_test_ext(), crate::mock::Test);
 }
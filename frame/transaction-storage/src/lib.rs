@@ -31,12 +31,12 @@ mod tests;
 use codec::{Decode, Encode};
 use frame_support::{
 	dispatch::{Dispatchable, GetDispatchInfo},
-	traits::{Currency, OnUnbalanced, ReservableCurrency},
+	traits::{Currency, EnsureOrigin, OnUnbalanced, ReservableCurrency},
 };
-use sp_runtime::traits::{BlakeTwo256, Hash, One, Saturating, Zero};
+use sp_runtime::traits::{AtLeast32BitUnsigned, BlakeTwo256, Hash, One, Saturating, Zero};
 use sp_std::{prelude::*, result};
 use sp_transaction_storage_proof::{
-	encode_index, random_chunk, InherentError, TransactionStorageProof, CHUNK_SIZE,
+	encode_index, random_chunk, unixfs::Dag, InherentError, TransactionStorageProof, CHUNK_SIZE,
 	INHERENT_IDENTIFIER,
 };
 
@@ -70,6 +70,76 @@ pub struct TransactionInfo {
 	block_chunks: u32,
 }
 
+impl TransactionInfo {
+	/// Placeholder for removed data, keeping the indices of the following transactions in the
+	/// block.
+	fn tombstone(block_chunks: u32) -> Self {
+		TransactionInfo {
+			chunk_root: Default::default(),
+			content_hash: Default::default(),
+			size: 0,
+			block_chunks,
+		}
+	}
+
+	/// `true` if the data has been removed. Stored data is never empty.
+	fn is_removed(&self) -> bool {
+		self.size == 0
+	}
+}
+
+/// Automatic renewal subscription for stored data.
+#[derive(Encode, Decode, Clone, sp_runtime::RuntimeDebug, PartialEq, Eq, scale_info::TypeInfo)]
+pub struct Subscription<AccountId, Balance, BlockNumber> {
+	/// Account paying for the renewals.
+	pub owner: AccountId,
+	/// Block in which the data is renewed next.
+	pub due: BlockNumber,
+	/// Number of renewals left.
+	pub renewals: u32,
+	/// Funds reserved from `owner` for the remaining renewals.
+	pub reserved: Balance,
+}
+
+/// Parameters of a storage fee calculation.
+#[derive(Clone, sp_runtime::RuntimeDebug, PartialEq, Eq)]
+pub struct FeeParams<Balance> {
+	/// Size of the data in bytes.
+	pub size: u32,
+	/// `true` if previously stored data is renewed.
+	pub renewal: bool,
+	/// Number of transactions stored in the current block so far.
+	pub block_transactions: u32,
+	/// Maximum number of transactions stored in a block.
+	pub max_block_transactions: u32,
+	/// Configured fee per byte.
+	pub byte_fee: Balance,
+	/// Configured fee per transaction.
+	pub entry_fee: Balance,
+}
+
+/// Pricing of stored and renewed data.
+///
+/// Implementations reading storage should account for it in the weights of `store` and `renew`.
+pub trait StoragePricing<AccountId, Balance> {
+	/// Fee charged to `who` for storing or renewing data.
+	fn fee(who: &AccountId, params: &FeeParams<Balance>) -> Balance;
+}
+
+/// Charges `byte_fee` per byte plus `entry_fee` per transaction to everyone.
+pub struct FlatFee;
+
+impl<AccountId, Balance: AtLeast32BitUnsigned + Copy> StoragePricing<AccountId, Balance>
+	for FlatFee
+{
+	fn fee(_who: &AccountId, params: &FeeParams<Balance>) -> Balance {
+		params
+			.byte_fee
+			.saturating_mul(params.size.into())
+			.saturating_add(params.entry_fee)
+	}
+}
+
 fn num_chunks(bytes: u32) -> u32 {
 	((bytes as u64 + CHUNK_SIZE as u64 - 1) / CHUNK_SIZE as u64) as u32
 }
@@ -95,6 +165,18 @@ pub mod pallet {
 		type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
 		/// Weight information for extrinsics in this pallet.
 		type WeightInfo: WeightInfo;
+		/// Multihash codes of additional content hashes stored data is indexed under.
+		///
+		/// Data is always indexed by its blake2b-256 hash. See
+		/// `sp_transaction_storage_proof::multihash` for the supported codes.
+		#[pallet::constant]
+		type ContentHashCodes: Get<Vec<u64>>;
+		/// Pricing of stored and renewed data.
+		type Pricing: StoragePricing<Self::AccountId, BalanceOf<Self>>;
+		/// Origin that may remove any stored data, in addition to the account that stored or
+		/// renewed it. Data removed by this origin is taken down and can no longer be stored or
+		/// renewed.
+		type RemoveOrigin: EnsureOrigin<Self::Origin>;
 	}
 
 	#[pallet::error]
@@ -125,6 +207,25 @@ pub mod pallet {
 		TooManyTransactions,
 		/// Attempted to call `store` outside of block execution.
 		BadContext,
+		/// Storing the data would exceed the account quota for the current period.
+		QuotaExceeded,
+		/// Removed or subscribed transaction is not found.
+		TransactionNotFound,
+		/// Only the account that stored or renewed the data may remove it, and only the owner of
+		/// a subscription may cancel it.
+		NotOwner,
+		/// Subscription must renew the data at least once.
+		NoRenewals,
+		/// Transaction expires before it can be renewed automatically.
+		TransactionExpiring,
+		/// Another account is subscribed to the renewal of the data.
+		AlreadySubscribed,
+		/// Subscription is not found.
+		NotSubscribed,
+		/// Data has been taken down by `RemoveOrigin` and may not be stored or renewed.
+		TakenDown,
+		/// Block number does not fit into 32 bits.
+		BlockNumberOverflow,
 	}
 
 	#[pallet::pallet]
@@ -142,9 +243,13 @@ pub mod pallet {
 			if obsolete > Zero::zero() {
 				<Transactions<T>>::remove(obsolete);
 				<ChunkCount<T>>::remove(obsolete);
+				<TransactionOwners<T>>::remove(obsolete);
 			}
-			// 2 writes in `on_initialize` and 2 writes + 2 reads in `on_finalize`
-			T::DbWeight::get().reads_writes(2, 4)
+			let renewed = Self::renew_subscriptions(n);
+			// 3 writes in `on_initialize` and 3 writes + 3 reads in `on_finalize`
+			T::DbWeight::get()
+				.reads_writes(3, 6)
+				.saturating_add(T::WeightInfo::renew_subscriptions(renewed))
 		}
 
 		fn on_finalize(n: T::BlockNumber) {
@@ -160,10 +265,12 @@ pub mod pallet {
 			);
 			// Insert new transactions
 			let transactions = <BlockTransactions<T>>::take();
+			let owners = <BlockTransactionOwners<T>>::take();
 			let total_chunks = transactions.last().map_or(0, |t| t.block_chunks);
 			if total_chunks != 0 {
 				<ChunkCount<T>>::insert(n, total_chunks);
 				<Transactions<T>>::insert(n, transactions);
+				<TransactionOwners<T>>::insert(n, owners);
 			}
 		}
 	}
@@ -184,17 +291,33 @@ pub mod pallet {
 				Error::<T>::TransactionTooLarge
 			);
 			let sender = ensure_signed(origin)?;
-			Self::apply_fee(sender, data.len() as u32)?;
+			let content_hash = sp_io::hashing::blake2_256(&data);
+			ensure!(
+				!<TakenDown<T>>::contains_key(<BlakeTwo256 as Hash>::Output::from(content_hash)),
+				Error::<T>::TakenDown
+			);
+			let usage = Self::check_quota(&sender, data.len() as u32)?;
+			Self::apply_fee(&sender, data.len() as u32, false)?;
 
 			// Chunk data and compute storage root
 			let chunk_count = num_chunks(data.len() as u32);
 			let chunks = data.chunks(CHUNK_SIZE).map(|c| c.to_vec()).collect();
 			let root = sp_io::trie::blake2_256_ordered_root(chunks, sp_runtime::StateVersion::V1);
 
-			let content_hash = sp_io::hashing::blake2_256(&data);
+			let dag = Dag::build(&data, sp_io::hashing::blake2_256);
 			let extrinsic_index = <frame_system::Pallet<T>>::extrinsic_index()
 				.ok_or_else(|| Error::<T>::BadContext)?;
-			sp_io::transaction_index::index(extrinsic_index, data.len() as u32, content_hash);
+			let hash_codes = T::ContentHashCodes::get();
+			if hash_codes.is_empty() {
+				sp_io::transaction_index::index(extrinsic_index, data.len() as u32, content_hash);
+			} else {
+				sp_io::transaction_index::index_with_hashes(
+					extrinsic_index,
+					data.len() as u32,
+					content_hash,
+					hash_codes,
+				);
+			}
 
 			let mut index = 0;
 			<BlockTransactions<T>>::mutate(|transactions| {
@@ -211,14 +334,21 @@ pub mod pallet {
 				});
 				Ok(())
 			})?;
-			Self::deposit_event(Event::Stored { index });
+			<BlockTransactionOwners<T>>::append(&sender);
+			<QuotaUsage<T>>::insert(&sender, usage);
+			Self::deposit_event(Event::Stored {
+				index,
+				cid: dag.root_cid(),
+				chunk_cids: dag.leaf_cids(),
+			});
 			Ok(())
 		}
 
 		/// Renew previously stored data. Parameters are the block number that contains
 		/// previous `store` or `renew` call and transaction index within that block.
 		/// Transaction index is emitted in the `Stored` or `Renewed` event.
-		/// Applies same fees as `store`.
+		/// Applies same fees as `store`. Removed data and data taken down by `RemoveOrigin` can't
+		/// be renewed.
 		/// # <weight>
 		/// - Constant.
 		/// # </weight>
@@ -230,8 +360,13 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let sender = ensure_signed(origin)?;
 			let transactions = <Transactions<T>>::get(block).ok_or(Error::<T>::RenewedNotFound)?;
-			let info = transactions.get(index as usize).ok_or(Error::<T>::RenewedNotFound)?;
-			Self::apply_fee(sender, info.size)?;
+			let info = transactions
+				.get(index as usize)
+				.filter(|info| !info.is_removed())
+				.ok_or(Error::<T>::RenewedNotFound)?;
+			ensure!(!<TakenDown<T>>::contains_key(info.content_hash), Error::<T>::TakenDown);
+			let usage = Self::check_quota(&sender, info.size)?;
+			Self::apply_fee(&sender, info.size, true)?;
 
 			let extrinsic_index = <frame_system::Pallet<T>>::extrinsic_index().unwrap();
 			sp_io::transaction_index::renew(extrinsic_index, info.content_hash.into());
@@ -252,6 +387,8 @@ pub mod pallet {
 				});
 				Ok(())
 			})?;
+			<BlockTransactionOwners<T>>::append(&sender);
+			<QuotaUsage<T>>::insert(&sender, usage);
 			Self::deposit_event(Event::Renewed { index });
 			Ok(().into())
 		}
@@ -281,12 +418,9 @@ pub mod pallet {
 			let selected_chunk_index = random_chunk(parent_hash.as_ref(), total_chunks);
 			let (info, chunk_index) = match <Transactions<T>>::get(target_number) {
 				Some(infos) => {
-					let index = match infos
-						.binary_search_by_key(&selected_chunk_index, |info| info.block_chunks)
-					{
-						Ok(index) => index,
-						Err(index) => index,
-					};
+					// Skips removed transactions, which don't add any chunks.
+					let index =
+						infos.partition_point(|info| info.block_chunks <= selected_chunk_index);
 					let info =
 						infos.get(index).ok_or_else(|| Error::<T>::MissingStateData)?.clone();
 					let chunks = num_chunks(info.size);
@@ -309,17 +443,239 @@ pub mod pallet {
 			Self::deposit_event(Event::ProofChecked);
 			Ok(().into())
 		}
+
+		/// Set the number of bytes `who` may store or renew per storage period, overriding
+		/// `DefaultQuota`. `None` removes the override.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		/// # <weight>
+		/// - Constant.
+		/// # </weight>
+		#[pallet::weight(T::WeightInfo::set_quota())]
+		pub fn set_quota(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			quota: Option<u64>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			match quota {
+				Some(quota) => <Quotas<T>>::insert(&who, quota),
+				None => <Quotas<T>>::remove(&who),
+			}
+			Self::deposit_event(Event::QuotaSet { who, quota });
+			Ok(())
+		}
+
+		/// Set the number of bytes any account without an override in `Quotas` may store or
+		/// renew per storage period. `None` makes the quota unlimited.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		/// # <weight>
+		/// - Constant.
+		/// # </weight>
+		#[pallet::weight(T::WeightInfo::set_default_quota())]
+		pub fn set_default_quota(origin: OriginFor<T>, quota: Option<u64>) -> DispatchResult {
+			ensure_root(origin)?;
+			<DefaultQuota<T>>::set(quota);
+			Self::deposit_event(Event::DefaultQuotaSet { quota });
+			Ok(())
+		}
+
+		/// Remove data stored or renewed in `block` under `index` before its storage period
+		/// ends. The data can no longer be renewed or proven, and nodes drop it once the block
+		/// containing this call is finalized. Indices of the other transactions in `block` don't
+		/// change.
+		///
+		/// Copies of the data stored or renewed in other transactions are not affected, unless
+		/// the data is removed by `RemoveOrigin`. Such data is taken down: it can no longer be
+		/// stored or renewed by anyone, and existing copies expire at the end of their storage
+		/// period.
+		///
+		/// The automatic renewal of the removed copy, or of any copy of taken down data, ends
+		/// right away.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the account that stored or
+		/// renewed the data, or `RemoveOrigin`.
+		/// # <weight>
+		/// - Linear w.r.t the number of transactions in `block`, bounded by `MaxBlockTransactions`.
+		///   Charged for a full block.
+		/// # </weight>
+		#[pallet::weight(T::WeightInfo::remove())]
+		pub fn remove(origin: OriginFor<T>, block: T::BlockNumber, index: u32) -> DispatchResult {
+			let who = match T::RemoveOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+			let number: u32 = block.try_into().map_err(|_| Error::<T>::BlockNumberOverflow)?;
+			let mut transactions =
+				<Transactions<T>>::get(block).ok_or(Error::<T>::TransactionNotFound)?;
+			let position = index as usize;
+			ensure!(
+				transactions.get(position).map_or(false, |info| !info.is_removed()),
+				Error::<T>::TransactionNotFound
+			);
+			if let Some(who) = &who {
+				let owners = <TransactionOwners<T>>::get(block).unwrap_or_default();
+				ensure!(owners.get(position) == Some(who), Error::<T>::NotOwner);
+			}
+
+			let chunks = num_chunks(transactions[position].size);
+			let block_chunks = transactions[position].block_chunks - chunks;
+			let info = sp_std::mem::replace(
+				&mut transactions[position],
+				TransactionInfo::tombstone(block_chunks),
+			);
+			for info in transactions[position + 1..].iter_mut() {
+				info.block_chunks -= chunks;
+			}
+			if who.is_none() {
+				<TakenDown<T>>::insert(info.content_hash, ());
+			}
+			if let Some(subscription) = <Subscriptions<T>>::get(info.content_hash) {
+				// The subscription renews the copy in the block it was stored or renewed in last.
+				let renewed_block = subscription.due.saturating_sub(<StoragePeriod<T>>::get());
+				if who.is_none() {
+					let error = Some(Error::<T>::TakenDown.into());
+					Self::end_subscription(info.content_hash, subscription, error);
+				} else if renewed_block == block &&
+					!transactions.iter().any(|t| t.content_hash == info.content_hash)
+				{
+					let error = Some(Error::<T>::RenewedNotFound.into());
+					Self::end_subscription(info.content_hash, subscription, error);
+				}
+			}
+			match transactions.last() {
+				Some(last) if last.block_chunks > 0 => {
+					<ChunkCount<T>>::insert(block, last.block_chunks);
+					<Transactions<T>>::insert(block, transactions);
+				},
+				_ => {
+					<ChunkCount<T>>::remove(block);
+					<Transactions<T>>::remove(block);
+					<TransactionOwners<T>>::remove(block);
+				},
+			}
+
+			sp_io::transaction_index::remove(number, info.content_hash.into());
+			Self::deposit_event(Event::Removed { block, index });
+			Ok(())
+		}
+
+		/// Renew data stored or renewed in `block` under `index` automatically for `renewals`
+		/// more storage periods. Each renewal happens at the start of the last block the data is
+		/// kept for, and is charged the same fee as `renew`.
+		///
+		/// The fees for all renewals are reserved upfront at the current price, and whatever is
+		/// left is unreserved when the subscription ends. Renewals stop early if the reserved
+		/// funds no longer cover the fee, the quota of the owner is exceeded or the data is
+		/// removed or taken down. Subscribing again to the same data adds renewals to the
+		/// subscription.
+		/// # <weight>
+		/// - Constant.
+		/// # </weight>
+		#[pallet::weight(T::WeightInfo::subscribe())]
+		pub fn subscribe(
+			origin: OriginFor<T>,
+			block: T::BlockNumber,
+			index: u32,
+			renewals: u32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(renewals > 0, Error::<T>::NoRenewals);
+			let transactions =
+				<Transactions<T>>::get(block).ok_or(Error::<T>::TransactionNotFound)?;
+			let info = transactions
+				.get(index as usize)
+				.filter(|info| !info.is_removed())
+				.ok_or(Error::<T>::TransactionNotFound)?;
+			ensure!(!<TakenDown<T>>::contains_key(info.content_hash), Error::<T>::TakenDown);
+			let fee = Self::fee(&who, info.size, true)?.saturating_mul(renewals.into());
+			let (subscription, scheduled) = match <Subscriptions<T>>::get(&info.content_hash) {
+				Some(mut subscription) => {
+					ensure!(subscription.owner == who, Error::<T>::AlreadySubscribed);
+					subscription.renewals = subscription.renewals.saturating_add(renewals);
+					subscription.reserved = subscription.reserved.saturating_add(fee);
+					(subscription, true)
+				},
+				None => {
+					let due = block.saturating_add(<StoragePeriod<T>>::get());
+					ensure!(
+						due > <frame_system::Pallet<T>>::block_number(),
+						Error::<T>::TransactionExpiring
+					);
+					ensure!(
+						<RenewalQueue<T>>::decode_len(due).unwrap_or(0) <
+							MaxBlockTransactions::<T>::get() as usize,
+						Error::<T>::TooManyTransactions
+					);
+					(Subscription { owner: who.clone(), due, renewals, reserved: fee }, false)
+				},
+			};
+			T::Currency::reserve(&who, fee).map_err(|_| Error::<T>::InsufficientFunds)?;
+			if !scheduled {
+				<RenewalQueue<T>>::append(subscription.due, info.content_hash);
+			}
+			<Subscriptions<T>>::insert(info.content_hash, subscription);
+			Self::deposit_event(Event::Subscribed {
+				content_hash: info.content_hash,
+				owner: who,
+				renewals,
+			});
+			Ok(())
+		}
+
+		/// Cancel the automatic renewal of data with `content_hash` and unreserve the funds left
+		/// for it. Already renewed data is kept for the rest of its storage period.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the subscription.
+		/// # <weight>
+		/// - Constant.
+		/// # </weight>
+		#[pallet::weight(T::WeightInfo::unsubscribe())]
+		pub fn unsubscribe(
+			origin: OriginFor<T>,
+			content_hash: <BlakeTwo256 as Hash>::Output,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let subscription =
+				<Subscriptions<T>>::get(&content_hash).ok_or(Error::<T>::NotSubscribed)?;
+			ensure!(subscription.owner == who, Error::<T>::NotOwner);
+			Self::end_subscription(content_hash, subscription, None);
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		/// Stored data under specified index.
-		Stored { index: u32 },
+		/// Stored data under specified index. Data is available over IPFS as a UnixFS DAG with
+		/// root `cid`, made of raw leaf blocks `chunk_cids`.
+		Stored { index: u32, cid: Vec<u8>, chunk_cids: Vec<Vec<u8>> },
 		/// Renewed data under specified index.
 		Renewed { index: u32 },
 		/// Storage proof was successfully checked.
 		ProofChecked,
+		/// Quota of an account was changed.
+		QuotaSet { who: T::AccountId, quota: Option<u64> },
+		/// Default quota was changed.
+		DefaultQuotaSet { quota: Option<u64> },
+		/// Removed data stored in `block` under specified index.
+		Removed { block: T::BlockNumber, index: u32 },
+		/// `owner` subscribed to `renewals` automatic renewals of data.
+		Subscribed {
+			content_hash: <BlakeTwo256 as Hash>::Output,
+			owner: T::AccountId,
+			renewals: u32,
+		},
+		/// Renewed subscribed data under specified index.
+		AutoRenewed { content_hash: <BlakeTwo256 as Hash>::Output, index: u32 },
+		/// Automatic renewal of data ended, either because all renewals were made or the
+		/// subscription was cancelled, or due to `error`.
+		SubscriptionEnded {
+			content_hash: <BlakeTwo256 as Hash>::Output,
+			owner: T::AccountId,
+			error: Option<DispatchError>,
+		},
 	}
 
 	/// Collection of transaction metadata by block number.
@@ -328,6 +684,34 @@ pub mod pallet {
 	pub(super) type Transactions<T: Config> =
 		StorageMap<_, Blake2_128Concat, T::BlockNumber, Vec<TransactionInfo>, OptionQuery>;
 
+	/// Accounts that stored or renewed each transaction in `Transactions`, by block number.
+	#[pallet::storage]
+	pub(super) type TransactionOwners<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::BlockNumber, Vec<T::AccountId>, OptionQuery>;
+
+	/// Automatic renewal subscriptions by content hash of the renewed data.
+	#[pallet::storage]
+	#[pallet::getter(fn subscription)]
+	pub(super) type Subscriptions<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		<BlakeTwo256 as Hash>::Output,
+		Subscription<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	/// Content hashes of subscribed data to renew, by block number. Entries are removed when
+	/// their subscription ends. Holds at most `MaxBlockTransactions` entries per block, which
+	/// keeps room for the renewals in the block.
+	#[pallet::storage]
+	pub(super) type RenewalQueue<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::BlockNumber,
+		Vec<<BlakeTwo256 as Hash>::Output>,
+		ValueQuery,
+	>;
+
 	/// Count indexed chunks for each block.
 	#[pallet::storage]
 	pub(super) type ChunkCount<T: Config> =
@@ -353,9 +737,33 @@ pub mod pallet {
 	/// Maximum number of indexed transactions in the block.
 	pub(super) type MaxBlockTransactions<T: Config> = StorageValue<_, u32, ValueQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn default_quota)]
+	/// Number of bytes an account may store or renew per storage period, unless overridden in
+	/// `Quotas`. Unlimited if not set.
+	pub(super) type DefaultQuota<T: Config> = StorageValue<_, u64, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn quota)]
+	/// Per-account overrides of `DefaultQuota`.
+	pub(super) type Quotas<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u64, OptionQuery>;
+
+	/// Content hashes of data taken down by `RemoveOrigin`.
+	#[pallet::storage]
+	pub(super) type TakenDown<T: Config> =
+		StorageMap<_, Blake2_128Concat, <BlakeTwo256 as Hash>::Output, (), OptionQuery>;
+
+	/// Start of the current quota period of an account and the number of bytes stored or
+	/// renewed by it since.
+	#[pallet::storage]
+	pub(super) type QuotaUsage<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (T::BlockNumber, u64), OptionQuery>;
+
 	/// Storage period for data in blocks. Should match `sp_storage_proof::DEFAULT_STORAGE_PERIOD`
 	/// for block authoring.
 	#[pallet::storage]
+	#[pallet::getter(fn storage_period)]
 	pub(super) type StoragePeriod<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
 
 	// Intermediates
@@ -363,6 +771,10 @@ pub mod pallet {
 	pub(super) type BlockTransactions<T: Config> =
 		StorageValue<_, Vec<TransactionInfo>, ValueQuery>;
 
+	#[pallet::storage]
+	pub(super) type BlockTransactionOwners<T: Config> =
+		StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
 	/// Was the proof checked in this block?
 	#[pallet::storage]
 	pub(super) type ProofChecked<T: Config> = StorageValue<_, bool, ValueQuery>;
@@ -374,6 +786,7 @@ pub mod pallet {
 		pub storage_period: T::BlockNumber,
 		pub max_block_transactions: u32,
 		pub max_transaction_size: u32,
+		pub default_quota: Option<u64>,
 	}
 
 	#[cfg(feature = "std")]
@@ -385,6 +798,7 @@ pub mod pallet {
 				storage_period: sp_transaction_storage_proof::DEFAULT_STORAGE_PERIOD.into(),
 				max_block_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
 				max_transaction_size: DEFAULT_MAX_TRANSACTION_SIZE,
+				default_quota: None,
 			}
 		}
 	}
@@ -397,6 +811,7 @@ pub mod pallet {
 			<MaxTransactionSize<T>>::put(&self.max_transaction_size);
 			<MaxBlockTransactions<T>>::put(&self.max_block_transactions);
 			<StoragePeriod<T>>::put(&self.storage_period);
+			<DefaultQuota<T>>::set(self.default_quota);
 		}
 	}
 
@@ -426,14 +841,165 @@ pub mod pallet {
 	}
 
 	impl<T: Config> Pallet<T> {
-		fn apply_fee(sender: T::AccountId, size: u32) -> DispatchResult {
-			let byte_fee = ByteFee::<T>::get().ok_or(Error::<T>::NotConfigured)?;
-			let entry_fee = EntryFee::<T>::get().ok_or(Error::<T>::NotConfigured)?;
-			let fee = byte_fee.saturating_mul(size.into()).saturating_add(entry_fee);
-			ensure!(T::Currency::can_slash(&sender, fee), Error::<T>::InsufficientFunds);
-			let (credit, _) = T::Currency::slash(&sender, fee);
+		/// Content hashes of the data indexed in `block` that is still stored, in the order it
+		/// was indexed. The storage proof `StoragePeriod` blocks later is built from this data.
+		pub fn indexed_transactions(block: T::BlockNumber) -> Vec<<BlakeTwo256 as Hash>::Output> {
+			<Transactions<T>>::get(block)
+				.unwrap_or_default()
+				.into_iter()
+				.filter(|info| !info.is_removed())
+				.map(|info| info.content_hash)
+				.collect()
+		}
+
+		fn fee(
+			who: &T::AccountId,
+			size: u32,
+			renewal: bool,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let params = FeeParams {
+				size,
+				renewal,
+				block_transactions: BlockTransactions::<T>::decode_len().unwrap_or(0) as u32,
+				max_block_transactions: MaxBlockTransactions::<T>::get(),
+				byte_fee: ByteFee::<T>::get().ok_or(Error::<T>::NotConfigured)?,
+				entry_fee: EntryFee::<T>::get().ok_or(Error::<T>::NotConfigured)?,
+			};
+			Ok(T::Pricing::fee(who, &params))
+		}
+
+		fn apply_fee(sender: &T::AccountId, size: u32, renewal: bool) -> DispatchResult {
+			let fee = Self::fee(sender, size, renewal)?;
+			ensure!(T::Currency::can_slash(sender, fee), Error::<T>::InsufficientFunds);
+			let (credit, _) = T::Currency::slash(sender, fee);
 			T::FeeDestination::on_unbalanced(credit);
 			Ok(())
 		}
+
+		/// Check that `who` may store `size` more bytes in its current quota period. Returns the
+		/// updated usage to be recorded once the data is stored.
+		fn check_quota(
+			who: &T::AccountId,
+			size: u32,
+		) -> Result<(T::BlockNumber, u64), DispatchError> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let period = <StoragePeriod<T>>::get();
+			let (start, used) = match <QuotaUsage<T>>::get(who) {
+				Some((start, used)) if now < start.saturating_add(period) => (start, used),
+				_ => (now, 0),
+			};
+			let used = used.saturating_add(size as u64);
+			if let Some(quota) = <Quotas<T>>::get(who).or_else(<DefaultQuota<T>>::get) {
+				ensure!(used <= quota, Error::<T>::QuotaExceeded);
+			}
+			Ok((start, used))
+		}
+
+		/// Renew the subscribed data due in block `n`. Returns the number of processed queue
+		/// entries.
+		fn renew_subscriptions(n: T::BlockNumber) -> u32 {
+			let queue = <RenewalQueue<T>>::take(n);
+			if queue.is_empty() {
+				return 0
+			}
+			let period = <StoragePeriod<T>>::get();
+			let max_queued = MaxBlockTransactions::<T>::get() as usize;
+			let transactions = <Transactions<T>>::get(n.saturating_sub(period)).unwrap_or_default();
+			for content_hash in queue.iter() {
+				let mut subscription = match <Subscriptions<T>>::get(content_hash) {
+					Some(subscription) if subscription.due == n => subscription,
+					_ => continue,
+				};
+				let result =
+					match transactions.iter().find(|info| info.content_hash == *content_hash) {
+						Some(info) => Self::auto_renew(&mut subscription, info),
+						None => Err(Error::<T>::RenewedNotFound.into()),
+					};
+				match result {
+					Ok(index) => {
+						Self::deposit_event(Event::AutoRenewed {
+							content_hash: *content_hash,
+							index,
+						});
+						subscription.renewals -= 1;
+						if subscription.renewals == 0 {
+							Self::end_subscription(*content_hash, subscription, None);
+						} else {
+							subscription.due = n.saturating_add(period);
+							if <RenewalQueue<T>>::decode_len(subscription.due).unwrap_or(0) <
+								max_queued
+							{
+								<RenewalQueue<T>>::append(subscription.due, content_hash);
+								<Subscriptions<T>>::insert(content_hash, subscription);
+							} else {
+								// Only possible if `MaxBlockTransactions` was lowered after the
+								// renewals were queued.
+								Self::end_subscription(
+									*content_hash,
+									subscription,
+									Some(Error::<T>::TooManyTransactions.into()),
+								);
+							}
+						}
+					},
+					Err(error) => Self::end_subscription(*content_hash, subscription, Some(error)),
+				}
+			}
+			queue.len() as u32
+		}
+
+		/// Renew `info` in the current block, paying from the funds reserved for `subscription`.
+		/// Returns the index of the renewed data in the block.
+		fn auto_renew(
+			subscription: &mut Subscription<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+			info: &TransactionInfo,
+		) -> Result<u32, DispatchError> {
+			ensure!(!<TakenDown<T>>::contains_key(info.content_hash), Error::<T>::TakenDown);
+			let usage = Self::check_quota(&subscription.owner, info.size)?;
+			let fee = Self::fee(&subscription.owner, info.size, true)?;
+			ensure!(fee <= subscription.reserved, Error::<T>::InsufficientFunds);
+
+			// Renewals are queued only while the queue of the block is shorter than
+			// `MaxBlockTransactions` and run before any extrinsic, so the block always has room for
+			// them.
+			let index = <BlockTransactions<T>>::mutate(|transactions| {
+				let chunks = num_chunks(info.size);
+				let total_chunks = transactions.last().map_or(0, |t| t.block_chunks) + chunks;
+				transactions.push(TransactionInfo { block_chunks: total_chunks, ..info.clone() });
+				transactions.len() as u32 - 1
+			});
+			sp_io::transaction_index::block_renew(info.content_hash.into());
+
+			let (credit, _) = T::Currency::slash_reserved(&subscription.owner, fee);
+			T::FeeDestination::on_unbalanced(credit);
+			subscription.reserved = subscription.reserved.saturating_sub(fee);
+			<BlockTransactionOwners<T>>::append(&subscription.owner);
+			<QuotaUsage<T>>::insert(&subscription.owner, usage);
+			Ok(index)
+		}
+
+		/// End `subscription` of data with `content_hash`, unreserving the funds left for it and
+		/// freeing its slot in the renewal queue.
+		fn end_subscription(
+			content_hash: <BlakeTwo256 as Hash>::Output,
+			subscription: Subscription<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+			error: Option<DispatchError>,
+		) {
+			T::Currency::unreserve(&subscription.owner, subscription.reserved);
+			<Subscriptions<T>>::remove(content_hash);
+			<RenewalQueue<T>>::mutate_exists(subscription.due, |queue| {
+				if let Some(hashes) = queue {
+					hashes.retain(|hash| *hash != content_hash);
+					if hashes.is_empty() {
+						*queue = None;
+					}
+				}
+			});
+			Self::deposit_event(Event::SubscriptionEnded {
+				content_hash,
+				owner: subscription.owner,
+				error,
+			});
+		}
 	}
 }
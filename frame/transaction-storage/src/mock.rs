@@ -18,8 +18,11 @@
 //! Test environment for transaction-storage pallet.
 
 use crate as pallet_transaction_storage;
-use crate::TransactionStorageProof;
-use frame_support::traits::{ConstU16, ConstU32, ConstU64, OnFinalize, OnInitialize};
+use crate::{FeeParams, FlatFee, StoragePricing, TransactionStorageProof};
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64, OnFinalize, OnInitialize},
+};
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
@@ -90,6 +93,26 @@ impl pallet_transaction_storage::Config for Test {
 	type Currency = Balances;
 	type FeeDestination = ();
 	type WeightInfo = ();
+	type ContentHashCodes = ContentHashCodes;
+	type Pricing = TestPricing;
+	type RemoveOrigin = frame_system::EnsureRoot<u64>;
+}
+
+parameter_types! {
+	pub ContentHashCodes: Vec<u64> = vec![sp_transaction_storage_proof::multihash::SHA2_256];
+}
+
+/// Storage is free for account 4, other accounts pay the flat fee.
+pub struct TestPricing;
+
+impl StoragePricing<u64, u64> for TestPricing {
+	fn fee(who: &u64, params: &FeeParams<u64>) -> u64 {
+		if *who == 4 {
+			0
+		} else {
+			FlatFee::fee(who, params)
+		}
+	}
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
@@ -104,6 +127,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			entry_fee: 200,
 			max_block_transactions: crate::DEFAULT_MAX_BLOCK_TRANSACTIONS,
 			max_transaction_size: crate::DEFAULT_MAX_TRANSACTION_SIZE,
+			default_quota: None,
 		},
 	}
 	.build_storage()
@@ -19,9 +19,9 @@
 
 use super::{Pallet as TransactionStorage, *};
 use crate::mock::*;
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{assert_err, assert_noop, assert_ok};
 use frame_system::RawOrigin;
-use sp_transaction_storage_proof::registration::build_proof;
+use sp_transaction_storage_proof::{registration::build_proof, unixfs::DAG_CHUNK_SIZE};
 
 const MAX_DATA_SIZE: u32 = DEFAULT_MAX_TRANSACTION_SIZE;
 
@@ -146,3 +146,487 @@ fn renews_data() {
 		assert!(Transactions::<Test>::get(6).is_none());
 	});
 }
+
+#[test]
+fn emits_dag_cids() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		let data = vec![1u8; DAG_CHUNK_SIZE + 1];
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), data.clone()));
+		let dag = Dag::build(&data, sp_io::hashing::blake2_256);
+		assert_eq!(dag.leaves.len(), 2);
+		System::assert_last_event(
+			crate::Event::Stored { index: 0, cid: dag.root_cid(), chunk_cids: dag.leaf_cids() }
+				.into(),
+		);
+	});
+}
+
+#[test]
+fn applies_pricing() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(3).into(), vec![0u8; 2000]),
+			Error::<Test>::InsufficientFunds,
+		);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(4).into(), vec![0u8; 2000]));
+		assert_eq!(Balances::free_balance(4), 100);
+	});
+}
+
+#[test]
+fn enforces_quota() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		DefaultQuota::<Test>::put(3000);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_noop!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]),
+			Error::<Test>::QuotaExceeded,
+		);
+		run_to_block(2, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::renew(RawOrigin::Signed(1).into(), 1, 0),
+			Error::<Test>::QuotaExceeded,
+		);
+		// Overrides take precedence over the default quota.
+		assert_noop!(
+			TransactionStorage::<Test>::set_quota(RawOrigin::Signed(1).into(), 1, Some(4000)),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+		assert_ok!(TransactionStorage::<Test>::set_quota(RawOrigin::Root.into(), 1, Some(4000)));
+		System::assert_last_event(crate::Event::QuotaSet { who: 1, quota: Some(4000) }.into());
+		assert_ok!(TransactionStorage::<Test>::renew(RawOrigin::Signed(1).into(), 1, 0));
+		assert_eq!(QuotaUsage::<Test>::get(1), Some((1, 4000)));
+	});
+}
+
+#[test]
+fn quota_resets_every_period() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		DefaultQuota::<Test>::put(2000);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		run_to_block(10, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 1]),
+			Error::<Test>::QuotaExceeded,
+		);
+		run_to_block(11, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_eq!(QuotaUsage::<Test>::get(1), Some((11, 2000)));
+	});
+}
+
+#[test]
+fn removes_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(4).into(), vec![0u8; 1000]));
+		run_to_block(2, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::remove(RawOrigin::Signed(4).into(), 1, 0),
+			Error::<Test>::NotOwner,
+		);
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Signed(1).into(), 1, 0));
+		System::assert_last_event(crate::Event::Removed { block: 1, index: 0 }.into());
+		// The removed transaction is replaced by a tombstone, keeping the following indices.
+		let transactions = Transactions::<Test>::get(1).unwrap();
+		assert_eq!(transactions.len(), 2);
+		assert!(transactions[0].is_removed());
+		assert_eq!(transactions[1].size, 1000);
+		assert_eq!(transactions[1].block_chunks, num_chunks(1000));
+		assert_eq!(ChunkCount::<Test>::get(1), num_chunks(1000));
+		assert_eq!(TransactionOwners::<Test>::get(1), Some(vec![1, 4]));
+		assert_noop!(
+			TransactionStorage::<Test>::renew(RawOrigin::Signed(1).into(), 1, 0),
+			Error::<Test>::RenewedNotFound,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1),
+			Error::<Test>::TransactionNotFound,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::remove(RawOrigin::Signed(1).into(), 1, 0),
+			Error::<Test>::TransactionNotFound,
+		);
+		// Data removed by its owner may be stored again.
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::renew(RawOrigin::Signed(4).into(), 1, 1));
+		// `RemoveOrigin` may remove data stored by anyone.
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Root.into(), 1, 1));
+		assert_eq!(Transactions::<Test>::get(1), None);
+		assert_eq!(ChunkCount::<Test>::get(1), 0);
+		assert_eq!(TransactionOwners::<Test>::get(1), None);
+		assert_noop!(
+			TransactionStorage::<Test>::remove(RawOrigin::Root.into(), 1, 1),
+			Error::<Test>::TransactionNotFound,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::remove(RawOrigin::Root.into(), u32::MAX as u64 + 1, 0),
+			Error::<Test>::BlockNumberOverflow,
+		);
+		// No proof is required for the emptied block.
+		run_to_block(12, || None);
+	});
+}
+
+#[test]
+fn proves_data_following_removed_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![1u8; 1000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![2u8; 3000]));
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Signed(1).into(), 1, 1));
+		run_to_block(11, || None);
+		// Select the first chunk following the removed data.
+		let mut parent_hash = [0u8; 32];
+		parent_hash[7] = num_chunks(2000) as u8;
+		System::set_parent_hash(parent_hash.into());
+		// Proofs are built from the data left in the block.
+		let transactions = vec![vec![0u8; 2000], vec![2u8; 3000]];
+		let proof = build_proof(parent_hash.as_ref(), transactions).unwrap();
+		assert_ok!(TransactionStorage::<Test>::check_proof(Origin::none(), proof));
+	});
+}
+
+#[test]
+fn proves_data_right_after_removal() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		let data = vec![vec![0u8; 2000], vec![1u8; 1000]];
+		for data in &data {
+			assert_ok!(TransactionStorage::<Test>::store(
+				RawOrigin::Signed(1).into(),
+				data.clone()
+			));
+		}
+		run_to_block(10, || None);
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Signed(1).into(), 1, 0));
+		run_to_block(11, || None);
+		// Select the first chunk, which belonged to the removed data.
+		System::set_parent_hash(Default::default());
+		// The proof is built from the data still stored as of the parent block.
+		let transactions = TransactionStorage::<Test>::indexed_transactions(1)
+			.into_iter()
+			.map(|hash| data.iter().find(|data| BlakeTwo256::hash(data) == hash).unwrap().clone())
+			.collect::<Vec<_>>();
+		assert_eq!(transactions, vec![vec![1u8; 1000]]);
+		let proof = build_proof(&[0u8; 32], transactions).unwrap();
+		assert_ok!(TransactionStorage::<Test>::check_proof(Origin::none(), proof));
+		run_to_block(12, || None);
+	});
+}
+
+#[test]
+fn taken_down_data_is_not_stored_or_renewed() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(4).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::renew(RawOrigin::Signed(1).into(), 1, 0));
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 2));
+		run_to_block(3, || None);
+		let content_hash = Transactions::<Test>::get(2).unwrap()[0].content_hash;
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Root.into(), 1, 0));
+		System::assert_has_event(
+			crate::Event::SubscriptionEnded {
+				content_hash,
+				owner: 1,
+				error: Some(Error::<Test>::TakenDown.into()),
+			}
+			.into(),
+		);
+		assert_eq!(TransactionStorage::<Test>::subscription(content_hash), None);
+		assert!(RenewalQueue::<Test>::get(11).is_empty());
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// Copies stored or renewed by other accounts can't be renewed either.
+		assert_noop!(
+			TransactionStorage::<Test>::renew(RawOrigin::Signed(4).into(), 2, 0),
+			Error::<Test>::TakenDown,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(4).into(), 2, 1, 1),
+			Error::<Test>::TakenDown,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(4).into(), vec![0u8; 2000]),
+			Error::<Test>::TakenDown,
+		);
+		let proof_provider = || {
+			let block_num = <frame_system::Pallet<Test>>::block_number();
+			if block_num == 12 {
+				let parent_hash = <frame_system::Pallet<Test>>::parent_hash();
+				let transactions = vec![vec![0u8; 2000], vec![0u8; 2000]];
+				Some(build_proof(parent_hash.as_ref(), transactions).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block(11, proof_provider);
+		assert!(BlockTransactions::<Test>::get().is_empty());
+		run_to_block(13, proof_provider);
+	});
+}
+
+#[test]
+fn sets_default_quota() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::set_default_quota(RawOrigin::Signed(1).into(), Some(1000)),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+		assert_ok!(TransactionStorage::<Test>::set_default_quota(
+			RawOrigin::Root.into(),
+			Some(1000)
+		));
+		System::assert_last_event(crate::Event::DefaultQuotaSet { quota: Some(1000) }.into());
+		assert_noop!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]),
+			Error::<Test>::QuotaExceeded,
+		);
+		assert_ok!(TransactionStorage::<Test>::set_default_quota(RawOrigin::Root.into(), None));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+	});
+}
+
+#[test]
+fn auto_renews_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		let info = BlockTransactions::<Test>::get().last().unwrap().clone();
+		let content_hash = info.content_hash;
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 2));
+		System::assert_last_event(
+			crate::Event::Subscribed { content_hash, owner: 1, renewals: 2 }.into(),
+		);
+		assert_eq!(Balances::reserved_balance(1), 4200 * 2);
+		let proof_provider = || {
+			let block_num = <frame_system::Pallet<Test>>::block_number();
+			if block_num == 11 || block_num == 21 || block_num == 31 {
+				let parent_hash = <frame_system::Pallet<Test>>::parent_hash();
+				Some(build_proof(parent_hash.as_ref(), vec![vec![0u8; 2000]]).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block(11, proof_provider);
+		assert_eq!(BlockTransactions::<Test>::get(), vec![info.clone()]);
+		System::assert_last_event(crate::Event::AutoRenewed { content_hash, index: 0 }.into());
+		assert_eq!(Balances::reserved_balance(1), 4200);
+		run_to_block(21, proof_provider);
+		assert_eq!(BlockTransactions::<Test>::get(), vec![info.clone()]);
+		System::assert_last_event(
+			crate::Event::SubscriptionEnded { content_hash, owner: 1, error: None }.into(),
+		);
+		assert_eq!(Subscriptions::<Test>::get(content_hash), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 1_000_000_000 - 4200 * 3);
+		run_to_block(31, proof_provider);
+		assert!(Transactions::<Test>::get(21).is_some());
+		run_to_block(32, proof_provider);
+		assert!(Transactions::<Test>::get(21).is_none());
+	});
+}
+
+#[test]
+fn stops_renewing_when_funds_run_out() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		let content_hash = BlockTransactions::<Test>::get()[0].content_hash;
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 2));
+		// The price rises after the renewals are paid for.
+		ByteFee::<Test>::put(3);
+		let proof_provider = || {
+			let block_num = <frame_system::Pallet<Test>>::block_number();
+			if block_num == 11 {
+				let parent_hash = <frame_system::Pallet<Test>>::parent_hash();
+				Some(build_proof(parent_hash.as_ref(), vec![vec![0u8; 2000]]).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block(11, proof_provider);
+		assert_eq!(Balances::reserved_balance(1), 4200 * 2 - 6200);
+		run_to_block(21, proof_provider);
+		assert!(BlockTransactions::<Test>::get().is_empty());
+		System::assert_last_event(
+			crate::Event::SubscriptionEnded {
+				content_hash,
+				owner: 1,
+				error: Some(Error::<Test>::InsufficientFunds.into()),
+			}
+			.into(),
+		);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 1_000_000_000 - 4200 - 6200);
+	});
+}
+
+#[test]
+fn cancels_subscription() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![1u8; 2000]));
+		let transactions = BlockTransactions::<Test>::get();
+		let content_hash = transactions[0].content_hash;
+		run_to_block(2, || None);
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 0),
+			Error::<Test>::NoRenewals,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(2).into(), 1, 0, 1),
+			Error::<Test>::InsufficientFunds,
+		);
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1));
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1));
+		assert_eq!(TransactionStorage::<Test>::subscription(content_hash).unwrap().renewals, 2);
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(4).into(), 1, 0, 1),
+			Error::<Test>::AlreadySubscribed,
+		);
+		assert_noop!(
+			TransactionStorage::<Test>::unsubscribe(RawOrigin::Signed(4).into(), content_hash),
+			Error::<Test>::NotOwner,
+		);
+		assert_ok!(TransactionStorage::<Test>::unsubscribe(
+			RawOrigin::Signed(1).into(),
+			content_hash
+		));
+		System::assert_last_event(
+			crate::Event::SubscriptionEnded { content_hash, owner: 1, error: None }.into(),
+		);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_noop!(
+			TransactionStorage::<Test>::unsubscribe(RawOrigin::Signed(1).into(), content_hash),
+			Error::<Test>::NotSubscribed,
+		);
+
+		// Removed data is not renewed.
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 1, 1));
+		assert_ok!(TransactionStorage::<Test>::remove(RawOrigin::Signed(1).into(), 1, 1));
+		System::assert_has_event(
+			crate::Event::SubscriptionEnded {
+				content_hash: transactions[1].content_hash,
+				owner: 1,
+				error: Some(Error::<Test>::RenewedNotFound.into()),
+			}
+			.into(),
+		);
+		assert!(RenewalQueue::<Test>::get(11).is_empty());
+		assert_eq!(Balances::reserved_balance(1), 0);
+		let proof_provider = || {
+			let block_num = <frame_system::Pallet<Test>>::block_number();
+			if block_num == 11 {
+				let parent_hash = <frame_system::Pallet<Test>>::parent_hash();
+				Some(build_proof(parent_hash.as_ref(), vec![vec![0u8; 2000]]).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block(11, proof_provider);
+		assert!(BlockTransactions::<Test>::get().is_empty());
+		assert_noop!(
+			TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1),
+			Error::<Test>::TransactionExpiring,
+		);
+	});
+}
+
+#[test]
+fn auto_renewals_have_room_in_the_block() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![1u8; 2000]));
+		let transactions = BlockTransactions::<Test>::get();
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1));
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 1, 1));
+		// Both renewals were queued before the limit was lowered.
+		MaxBlockTransactions::<Test>::put(1);
+		run_to_block(11, || None);
+		assert_eq!(BlockTransactions::<Test>::get(), transactions);
+		for info in &transactions {
+			System::assert_has_event(
+				crate::Event::SubscriptionEnded {
+					content_hash: info.content_hash,
+					owner: 1,
+					error: None,
+				}
+				.into(),
+			);
+		}
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_err!(
+			TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![2u8; 2000]),
+			Error::<Test>::TooManyTransactions,
+		);
+	});
+}
+
+#[test]
+fn bounds_renewal_queue() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![1u8; 2000]));
+		let transactions = BlockTransactions::<Test>::get();
+		let (first, second) = (transactions[0].content_hash, transactions[1].content_hash);
+		run_to_block(2, || None);
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 2));
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 1, 2));
+		MaxBlockTransactions::<Test>::put(1);
+		run_to_block(11, || None);
+		// Both are renewed, but only one fits in the queue for the next renewal.
+		assert_eq!(BlockTransactions::<Test>::get(), transactions);
+		assert_eq!(RenewalQueue::<Test>::get(21), vec![first]);
+		assert_eq!(TransactionStorage::<Test>::subscription(first).unwrap().due, 21);
+		assert_eq!(TransactionStorage::<Test>::subscription(second), None);
+		System::assert_last_event(
+			crate::Event::SubscriptionEnded {
+				content_hash: second,
+				owner: 1,
+				error: Some(Error::<Test>::TooManyTransactions.into()),
+			}
+			.into(),
+		);
+		assert_eq!(Balances::reserved_balance(1), 4200);
+	});
+}
+
+#[test]
+fn cancelled_subscriptions_free_their_queue_slot() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1, || None);
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![0u8; 2000]));
+		assert_ok!(TransactionStorage::<Test>::store(RawOrigin::Signed(1).into(), vec![1u8; 2000]));
+		let content_hash = BlockTransactions::<Test>::get()[0].content_hash;
+		run_to_block(2, || None);
+		MaxBlockTransactions::<Test>::put(2);
+		for _ in 0..3 {
+			assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1));
+			assert_eq!(RenewalQueue::<Test>::get(11), vec![content_hash]);
+			assert_ok!(TransactionStorage::<Test>::unsubscribe(
+				RawOrigin::Signed(1).into(),
+				content_hash
+			));
+			assert!(RenewalQueue::<Test>::get(11).is_empty());
+		}
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 0, 1));
+		assert_ok!(TransactionStorage::<Test>::subscribe(RawOrigin::Signed(1).into(), 1, 1, 1));
+		assert_eq!(RenewalQueue::<Test>::decode_len(11), Some(2));
+	});
+}
@@ -36,6 +36,12 @@
 // --template=.maintain/frame-weight-template.hbs
 // --header=HEADER-APACHE2
 // --raw
+//
+// Not all of the weights below were generated by this command. Functions marked "Not
+// benchmarked" contain estimates: the weights of `set_quota`, `set_default_quota`, `remove`,
+// `subscribe`, `unsubscribe` and `renew_subscriptions`, and the storage accesses and DAG hashing
+// added to `store` and `renew` since the date above. Benchmarks for all of them are in
+// `benchmarking.rs`; regenerate this file with the command above to replace the estimates.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -49,34 +55,59 @@ pub trait WeightInfo {
 	fn store(l: u32, ) -> Weight;
 	fn renew() -> Weight;
 	fn check_proof_max() -> Weight;
+	fn set_quota() -> Weight;
+	fn set_default_quota() -> Weight;
+	fn remove() -> Weight;
+	fn subscribe() -> Weight;
+	fn unsubscribe() -> Weight;
+	fn renew_subscriptions(n: u32, ) -> Weight;
 }
 
 /// Weights for pallet_transaction_storage using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Not benchmarked: storage accesses other than MaxTransactionSize, ByteFee, EntryFee, the
+	// extrinsic index, BlockTransactions and MaxBlockTransactions are estimated.
 	// Storage: TransactionStorage MaxTransactionSize (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
 	// Storage: TransactionStorage ByteFee (r:1 w:0)
 	// Storage: TransactionStorage EntryFee (r:1 w:0)
 	// Storage: unknown [0x3a65787472696e7369635f696e646578] (r:1 w:0)
 	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
 	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
 	fn store(l: u32, ) -> Weight {
 		(0 as Weight)
 			// Standard Error: 0
-			.saturating_add((5_000 as Weight).saturating_mul(l as Weight))
-			.saturating_add(T::DbWeight::get().reads(6 as Weight))
-			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			// Not benchmarked: 2_000 per byte added for hashing the UnixFS DAG leaves.
+			.saturating_add((7_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads(13 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
 	}
+	// Not benchmarked: storage accesses other than Transactions, ByteFee, EntryFee, the extrinsic
+	// index, BlockTransactions and MaxBlockTransactions are estimated.
 	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
 	// Storage: TransactionStorage ByteFee (r:1 w:0)
 	// Storage: TransactionStorage EntryFee (r:1 w:0)
 	// Storage: unknown [0x3a65787472696e7369635f696e646578] (r:1 w:0)
 	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
 	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
 	fn renew() -> Weight {
 		(41_286_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(6 as Weight))
-			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads(13 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
 	}
 	// Storage: TransactionStorage ProofChecked (r:1 w:1)
 	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
@@ -88,33 +119,128 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(5 as Weight))
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Quotas (r:0 w:1)
+	fn set_quota() -> Weight {
+		(15_120_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage DefaultQuota (r:0 w:1)
+	fn set_default_quota() -> Weight {
+		(15_120_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Not benchmarked: estimated for a full block.
+	// Storage: TransactionStorage Transactions (r:1 w:1)
+	// Storage: TransactionStorage TransactionOwners (r:1 w:1)
+	// Storage: TransactionStorage ChunkCount (r:0 w:1)
+	// Storage: TransactionStorage TakenDown (r:0 w:1)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	fn remove() -> Weight {
+		(58_412_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: TransactionStorage ByteFee (r:1 w:0)
+	// Storage: TransactionStorage EntryFee (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn subscribe() -> Weight {
+		(62_708_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(11 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	fn unsubscribe() -> Weight {
+		(34_935_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
+	// Storage: TransactionStorage ByteFee (r:1 w:0)
+	// Storage: TransactionStorage EntryFee (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
+	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn renew_subscriptions(n: u32, ) -> Weight {
+		(3_412_000 as Weight)
+			.saturating_add((47_306_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes((6 as Weight).saturating_mul(n as Weight)))
+	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
+	// Not benchmarked: storage accesses other than MaxTransactionSize, ByteFee, EntryFee, the
+	// extrinsic index, BlockTransactions and MaxBlockTransactions are estimated.
 	// Storage: TransactionStorage MaxTransactionSize (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
 	// Storage: TransactionStorage ByteFee (r:1 w:0)
 	// Storage: TransactionStorage EntryFee (r:1 w:0)
 	// Storage: unknown [0x3a65787472696e7369635f696e646578] (r:1 w:0)
 	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
 	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
 	fn store(l: u32, ) -> Weight {
 		(0 as Weight)
 			// Standard Error: 0
-			.saturating_add((5_000 as Weight).saturating_mul(l as Weight))
-			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
-			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			// Not benchmarked: 2_000 per byte added for hashing the UnixFS DAG leaves.
+			.saturating_add((7_000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(RocksDbWeight::get().reads(13 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
 	}
+	// Not benchmarked: storage accesses other than Transactions, ByteFee, EntryFee, the extrinsic
+	// index, BlockTransactions and MaxBlockTransactions are estimated.
 	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
 	// Storage: TransactionStorage ByteFee (r:1 w:0)
 	// Storage: TransactionStorage EntryFee (r:1 w:0)
 	// Storage: unknown [0x3a65787472696e7369635f696e646578] (r:1 w:0)
 	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
 	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
 	fn renew() -> Weight {
 		(41_286_000 as Weight)
-			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
-			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().reads(13 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
 	}
 	// Storage: TransactionStorage ProofChecked (r:1 w:1)
 	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
@@ -126,4 +252,80 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
 			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
 	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Quotas (r:0 w:1)
+	fn set_quota() -> Weight {
+		(15_120_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage DefaultQuota (r:0 w:1)
+	fn set_default_quota() -> Weight {
+		(15_120_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Not benchmarked: estimated for a full block.
+	// Storage: TransactionStorage Transactions (r:1 w:1)
+	// Storage: TransactionStorage TransactionOwners (r:1 w:1)
+	// Storage: TransactionStorage ChunkCount (r:0 w:1)
+	// Storage: TransactionStorage TakenDown (r:0 w:1)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	fn remove() -> Weight {
+		(58_412_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(7 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: TransactionStorage ByteFee (r:1 w:0)
+	// Storage: TransactionStorage EntryFee (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn subscribe() -> Weight {
+		(62_708_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(11 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	fn unsubscribe() -> Weight {
+		(34_935_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	// Not benchmarked: estimated.
+	// Storage: TransactionStorage RenewalQueue (r:1 w:1)
+	// Storage: TransactionStorage StoragePeriod (r:1 w:0)
+	// Storage: TransactionStorage Transactions (r:1 w:0)
+	// Storage: TransactionStorage Subscriptions (r:1 w:1)
+	// Storage: TransactionStorage TakenDown (r:1 w:0)
+	// Storage: System Number (r:1 w:0)
+	// Storage: TransactionStorage QuotaUsage (r:1 w:1)
+	// Storage: TransactionStorage Quotas (r:1 w:0)
+	// Storage: TransactionStorage DefaultQuota (r:1 w:0)
+	// Storage: TransactionStorage ByteFee (r:1 w:0)
+	// Storage: TransactionStorage EntryFee (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactions (r:1 w:1)
+	// Storage: TransactionStorage MaxBlockTransactions (r:1 w:0)
+	// Storage: TransactionStorage BlockTransactionOwners (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn renew_subscriptions(n: u32, ) -> Weight {
+		(3_412_000 as Weight)
+			.saturating_add((47_306_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((6 as Weight).saturating_mul(n as Weight)))
+	}
 }
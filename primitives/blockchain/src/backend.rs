@@ -230,7 +230,40 @@ pub trait Backend<Block: BlockT>:
 		Ok(self.indexed_transaction(hash)?.is_some())
 	}
 
+	/// Get the size of an indexed transaction in bytes, without loading it if the backend allows.
+	fn indexed_transaction_size(&self, hash: &Block::Hash) -> Result<Option<usize>> {
+		Ok(self.indexed_transaction(hash)?.map(|data| data.len()))
+	}
+
+	/// Get the content hash of an indexed transaction by one of the additional content hashes it
+	/// was indexed with. `code` is the multihash code of `digest`.
+	fn indexed_transaction_hash(&self, _code: u64, _digest: &[u8]) -> Result<Option<Block::Hash>> {
+		Ok(None)
+	}
+
+	/// Get a block of the UnixFS DAG of an indexed transaction by its blake2b-256 hash.
+	///
+	/// Only transactions that do not fit into a single DAG leaf have such blocks: the dag-pb root
+	/// node and the raw leaves.
+	fn indexed_dag_block(&self, _hash: &Block::Hash) -> Result<Option<Vec<u8>>> {
+		Ok(None)
+	}
+
+	/// Get the number of the latest block that stored or renewed an indexed transaction.
+	///
+	/// The data is kept until that block is pruned, unless it is pinned.
+	fn indexed_transaction_block(&self, _hash: &Block::Hash) -> Result<Option<NumberFor<Block>>> {
+		Ok(None)
+	}
+
 	fn block_indexed_body(&self, id: BlockId<Block>) -> Result<Option<Vec<Vec<u8>>>>;
+
+	/// Get content hashes of all indexed transactions of a block, including renewed transactions.
+	///
+	/// Unlike `block_indexed_body`, this succeeds when some of the indexed data is missing.
+	fn block_indexed_hashes(&self, _id: BlockId<Block>) -> Result<Option<Vec<Block::Hash>>> {
+		Ok(None)
+	}
 }
 
 /// Blockchain info
@@ -36,6 +36,19 @@ pub mod testing;
 /// Persistent storage prefix used by the Offchain Worker API when creating a DB key.
 pub const STORAGE_PREFIX: &[u8] = b"storage";
 
+/// Prefix of the [`OffchainIndexingOrigin`] records, stored under the prefix and key of the
+/// value they describe.
+pub const INDEXING_ORIGIN_PREFIX: &[u8] = b"indexing_origin";
+
+/// Block that last set a value of the persistent storage through offchain indexing.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct OffchainIndexingOrigin {
+	/// Number of the block.
+	pub block_number: u64,
+	/// Hash of the block.
+	pub block_hash: Vec<u8>,
+}
+
 /// Offchain DB persistent (non-fork-aware) storage.
 pub trait OffchainStorage: Clone + Send + Sync {
 	/// Persist a value in storage under given key and prefix.
@@ -47,6 +60,17 @@ pub trait OffchainStorage: Clone + Send + Sync {
 	/// Retrieve a value from storage under given key and prefix.
 	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Iterate over the entries whose key, the concatenation of their prefix and key, starts with
+	/// `prefix`, in key order.
+	///
+	/// Returns `None` if the storage doesn't support iteration, which is the default.
+	fn iter_prefix<'a>(
+		&'a self,
+		_prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		None
+	}
+
 	/// Replace the value in storage if given old_value matches the current one.
 	///
 	/// Returns `true` if the value has been set and false otherwise.
@@ -140,6 +164,37 @@ impl From<HttpError> for u32 {
 	}
 }
 
+/// An error enum returned by IPFS methods.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode, PassByEnum)]
+#[repr(C)]
+pub enum IpfsError {
+	/// The content couldn't been fetched within a deadline.
+	DeadlineReached = 1_isize,
+	/// The CID is malformed or uses an unsupported hash function.
+	InvalidCid = 2_isize,
+	/// Neither the local node nor any of the peers asked has the content.
+	NotFound = 3_isize,
+}
+
+impl TryFrom<u32> for IpfsError {
+	type Error = ();
+
+	fn try_from(error: u32) -> Result<Self, Self::Error> {
+		match error {
+			e if e == IpfsError::DeadlineReached as u8 as u32 => Ok(IpfsError::DeadlineReached),
+			e if e == IpfsError::InvalidCid as u8 as u32 => Ok(IpfsError::InvalidCid),
+			e if e == IpfsError::NotFound as u8 as u32 => Ok(IpfsError::NotFound),
+			_ => Err(()),
+		}
+	}
+}
+
+impl From<IpfsError> for u32 {
+	fn from(c: IpfsError) -> Self {
+		c as u8 as u32
+	}
+}
+
 /// Status of the HTTP request
 #[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode, PassByCodec)]
 pub enum HttpRequestStatus {
@@ -279,6 +334,8 @@ bitflags::bitflags! {
 		const NODE_AUTHORIZATION = 0b0000_1000_0000;
 		/// Access time related functionality
 		const TIME = 0b0001_0000_0000;
+		/// Fetch content by CID from the local node and its bitswap peers.
+		const IPFS = 0b0010_0000_0000;
 	}
 }
 
@@ -433,6 +490,22 @@ pub trait Externalities: Send {
 	/// - `authorized_only`: if true, only the authorized nodes are allowed to connect,
 	/// otherwise unauthorized nodes can also be connected through other mechanism.
 	fn set_authorized_nodes(&mut self, nodes: Vec<OpaquePeerId>, authorized_only: bool);
+
+	/// Fetch the content with the given CID.
+	///
+	/// The content is looked up among the indexed transactions stored by the local node first,
+	/// then requested from connected peers over bitswap. Returned content always matches the
+	/// CID.
+	///
+	/// Passing `None` as a deadline blocks until the content is found or all peers have been
+	/// asked.
+	///
+	/// Returns an error if:
+	/// - The CID is malformed or uses an unsupported hash function.
+	/// - The deadline is reached.
+	/// - The content couldn't be found.
+	fn ipfs_fetch(&mut self, cid: &[u8], deadline: Option<Timestamp>)
+		-> Result<Vec<u8>, IpfsError>;
 }
 
 impl<T: Externalities + ?Sized> Externalities for Box<T> {
@@ -507,6 +580,14 @@ impl<T: Externalities + ?Sized> Externalities for Box<T> {
 	fn set_authorized_nodes(&mut self, nodes: Vec<OpaquePeerId>, authorized_only: bool) {
 		(&mut **self).set_authorized_nodes(nodes, authorized_only)
 	}
+
+	fn ipfs_fetch(
+		&mut self,
+		cid: &[u8],
+		deadline: Option<Timestamp>,
+	) -> Result<Vec<u8>, IpfsError> {
+		(&mut **self).ipfs_fetch(cid, deadline)
+	}
 }
 
 /// An `*Externalities` implementation with limited capabilities.
@@ -615,6 +696,15 @@ impl<T: Externalities> Externalities for LimitedExternalities<T> {
 		self.check(Capabilities::NODE_AUTHORIZATION, "set_authorized_nodes");
 		self.externalities.set_authorized_nodes(nodes, authorized_only)
 	}
+
+	fn ipfs_fetch(
+		&mut self,
+		cid: &[u8],
+		deadline: Option<Timestamp>,
+	) -> Result<Vec<u8>, IpfsError> {
+		self.check(Capabilities::IPFS, "ipfs_fetch");
+		self.externalities.ipfs_fetch(cid, deadline)
+	}
 }
 
 #[cfg(feature = "std")]
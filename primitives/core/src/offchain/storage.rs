@@ -63,6 +63,20 @@ impl OffchainStorage for InMemOffchainStorage {
 		self.storage.get(&key).cloned()
 	}
 
+	fn iter_prefix<'a>(
+		&'a self,
+		prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		let mut entries: Vec<_> = self
+			.storage
+			.iter()
+			.filter(|(key, _)| key.starts_with(prefix))
+			.map(|(key, value)| (key.clone(), value.clone()))
+			.collect();
+		entries.sort_unstable();
+		Some(Box::new(entries.into_iter()))
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -23,7 +23,7 @@
 use crate::{
 	offchain::{
 		self, storage::InMemOffchainStorage, HttpError, HttpRequestId as RequestId,
-		HttpRequestStatus as RequestStatus, OffchainOverlayedChange, OffchainStorage,
+		HttpRequestStatus as RequestStatus, IpfsError, OffchainOverlayedChange, OffchainStorage,
 		OpaqueNetworkState, StorageKind, Timestamp, TransactionPool,
 	},
 	OpaquePeerId,
@@ -106,6 +106,14 @@ impl OffchainStorage for TestPersistentOffchainDB {
 		self.persistent.read().get(prefix, key)
 	}
 
+	fn iter_prefix<'a>(
+		&'a self,
+		prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		let entries: Vec<_> = self.persistent.read().iter_prefix(prefix)?.collect();
+		Some(Box::new(entries.into_iter()))
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -134,6 +142,8 @@ pub struct OffchainState {
 	pub seed: [u8; 32],
 	/// A timestamp simulating the current time.
 	pub timestamp: Timestamp,
+	/// Content returned by `ipfs_fetch`, by CID.
+	pub ipfs_content: BTreeMap<Vec<u8>, Vec<u8>>,
 }
 
 impl OffchainState {
@@ -353,6 +363,14 @@ impl offchain::Externalities for TestOffchainExt {
 	fn set_authorized_nodes(&mut self, _nodes: Vec<OpaquePeerId>, _authorized_only: bool) {
 		unimplemented!()
 	}
+
+	fn ipfs_fetch(
+		&mut self,
+		cid: &[u8],
+		_deadline: Option<Timestamp>,
+	) -> Result<Vec<u8>, IpfsError> {
+		self.0.read().ipfs_content.get(cid).cloned().ok_or(IpfsError::NotFound)
+	}
 }
 
 impl offchain::DbExternalities for TestOffchainExt {
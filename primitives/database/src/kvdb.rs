@@ -115,4 +115,13 @@ impl<D: KeyValueDB, H: Clone + AsRef<[u8]>> Database<H> for DbAdapter<D> {
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
 		handle_err(self.0.has_key(col, key))
 	}
+
+	fn iter_prefix<'a>(
+		&'a self,
+		col: ColumnId,
+		prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		let iter = self.0.iter_with_prefix(col, prefix);
+		Some(Box::new(iter.map(|(key, value)| (key.into_vec(), value.into_vec()))))
+	}
 }
@@ -110,6 +110,17 @@ pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 	fn supports_ref_counting(&self) -> bool {
 		false
 	}
+
+	/// Iterate over the entries of column `col` whose keys start with `prefix`, in key order.
+	///
+	/// Returns `None` if the database doesn't support iteration, which is the default.
+	fn iter_prefix<'a>(
+		&'a self,
+		_col: ColumnId,
+		_prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		None
+	}
 }
 
 impl<H> std::fmt::Debug for dyn Database<H> {
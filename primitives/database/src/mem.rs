@@ -73,6 +73,23 @@ where
 		let s = self.0.read();
 		s.get(&col).and_then(|c| c.get(key).map(|(_, v)| v.clone()))
 	}
+
+	fn iter_prefix<'a>(
+		&'a self,
+		col: ColumnId,
+		prefix: &'a [u8],
+	) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>> {
+		let s = self.0.read();
+		let mut entries: Vec<_> = s
+			.get(&col)
+			.into_iter()
+			.flatten()
+			.filter(|(key, _)| key.starts_with(prefix))
+			.map(|(key, (_, value))| (key.clone(), value.clone()))
+			.collect();
+		entries.sort_unstable();
+		Some(Box::new(entries.into_iter()))
+	}
 }
 
 impl MemDb {
@@ -205,11 +205,33 @@ pub trait Externalities: ExtensionStore {
 		unimplemented!("storage_index_transaction");
 	}
 
+	/// Index specified transaction slice and store it. The slice is additionally indexed by
+	/// its hashes computed with the given multihash codes.
+	fn storage_index_transaction_with_hashes(
+		&mut self,
+		_index: u32,
+		_hash: &[u8],
+		_size: u32,
+		_hash_codes: &[u64],
+	) {
+		unimplemented!("storage_index_transaction_with_hashes");
+	}
+
 	/// Renew existing piece of transaction storage.
 	fn storage_renew_transaction_index(&mut self, _index: u32, _hash: &[u8]) {
 		unimplemented!("storage_renew_transaction_index");
 	}
 
+	/// Renew existing piece of transaction storage on behalf of the block.
+	fn storage_block_renew_transaction_index(&mut self, _hash: &[u8]) {
+		unimplemented!("storage_block_renew_transaction_index");
+	}
+
+	/// Remove a piece of transaction storage from the index of an earlier block.
+	fn storage_remove_transaction_index(&mut self, _block: u32, _hash: &[u8]) {
+		unimplemented!("storage_remove_transaction_index");
+	}
+
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 	/// Benchmarking related functionality and shouldn't be used anywhere else!
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
@@ -49,7 +49,8 @@ use sp_core::{
 	crypto::KeyTypeId,
 	ecdsa, ed25519,
 	offchain::{
-		HttpError, HttpRequestId, HttpRequestStatus, OpaqueNetworkState, StorageKind, Timestamp,
+		HttpError, HttpRequestId, HttpRequestStatus, IpfsError, OpaqueNetworkState, StorageKind,
+		Timestamp,
 	},
 	sr25519,
 	storage::StateVersion,
@@ -1040,10 +1041,34 @@ pub trait TransactionIndex {
 		self.storage_index_transaction(extrinsic, &context_hash, size);
 	}
 
+	/// Add transaction index that can additionally be looked up by the content hashes computed
+	/// with the given multihash codes. Codes not supported by the node are ignored.
+	fn index_with_hashes(
+		&mut self,
+		extrinsic: u32,
+		size: u32,
+		context_hash: [u8; 32],
+		hash_codes: Vec<u64>,
+	) {
+		self.storage_index_transaction_with_hashes(extrinsic, &context_hash, size, &hash_codes);
+	}
+
 	/// Conduct a 512-bit Keccak hash.
 	fn renew(&mut self, extrinsic: u32, context_hash: [u8; 32]) {
 		self.storage_renew_transaction_index(extrinsic, &context_hash);
 	}
+
+	/// Renew indexed data on behalf of the current block rather than one of its extrinsics.
+	/// Renewals are indexed before the data of the block extrinsics.
+	fn block_renew(&mut self, context_hash: [u8; 32]) {
+		self.storage_block_renew_transaction_index(&context_hash);
+	}
+
+	/// Remove data indexed in block number `block` from the index. The data is dropped by the
+	/// node once the current block is finalized.
+	fn remove(&mut self, block: u32, context_hash: [u8; 32]) {
+		self.storage_remove_transaction_index(block, &context_hash);
+	}
 }
 
 /// Interface that provides functions to access the Offchain DB.
@@ -1285,6 +1310,21 @@ pub trait Offchain {
 			.expect("set_authorized_nodes can be called only in the offchain worker context")
 			.set_authorized_nodes(nodes, authorized_only)
 	}
+
+	/// Fetch the content with the given CID from the local node or its bitswap peers.
+	///
+	/// Returned content always matches the CID.
+	/// Passing `None` as a deadline blocks until the content is found or all peers have been
+	/// asked.
+	fn ipfs_fetch(
+		&mut self,
+		cid: &[u8],
+		deadline: Option<Timestamp>,
+	) -> Result<Vec<u8>, IpfsError> {
+		self.extension::<OffchainWorkerExt>()
+			.expect("ipfs_fetch can be called only in the offchain worker context")
+			.ipfs_fetch(cid, deadline)
+	}
 }
 
 /// Wasm only interface that provides functions for calling into the allocator.
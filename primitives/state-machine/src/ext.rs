@@ -623,6 +623,32 @@ where
 			extrinsic: index,
 			hash: hash.to_vec(),
 			size,
+			hash_codes: Vec::new(),
+		});
+	}
+
+	fn storage_index_transaction_with_hashes(
+		&mut self,
+		index: u32,
+		hash: &[u8],
+		size: u32,
+		hash_codes: &[u64],
+	) {
+		trace!(
+			target: "state",
+			method = "IndexTransactionWithHashes",
+			ext_id = %HexDisplay::from(&self.id.to_le_bytes()),
+			%index,
+			tx_hash = %HexDisplay::from(&hash),
+			%size,
+			?hash_codes,
+		);
+
+		self.overlay.add_transaction_index(IndexOperation::Insert {
+			extrinsic: index,
+			hash: hash.to_vec(),
+			size,
+			hash_codes: hash_codes.to_vec(),
 		});
 	}
 
@@ -640,6 +666,33 @@ where
 			.add_transaction_index(IndexOperation::Renew { extrinsic: index, hash: hash.to_vec() });
 	}
 
+	/// Renew existing piece of data storage on behalf of the block.
+	fn storage_block_renew_transaction_index(&mut self, hash: &[u8]) {
+		trace!(
+			target: "state",
+			method = "BlockRenewTransactionIndex",
+			ext_id = %HexDisplay::from(&self.id.to_le_bytes()),
+			tx_hash = %HexDisplay::from(&hash),
+		);
+
+		self.overlay
+			.add_transaction_index(IndexOperation::BlockRenew { hash: hash.to_vec() });
+	}
+
+	/// Remove piece of data storage from the index of an earlier block.
+	fn storage_remove_transaction_index(&mut self, block: u32, hash: &[u8]) {
+		trace!(
+			target: "state",
+			method = "RemoveTransactionIndex",
+			ext_id = %HexDisplay::from(&self.id.to_le_bytes()),
+			%block,
+			tx_hash = %HexDisplay::from(&hash),
+		);
+
+		self.overlay
+			.add_transaction_index(IndexOperation::Remove { block, hash: hash.to_vec() });
+	}
+
 	fn storage_start_transaction(&mut self) {
 		self.overlay.start_transaction()
 	}
@@ -32,11 +32,15 @@ pub(crate) mod overlayed_changes;
 mod proving_backend;
 #[cfg(feature = "std")]
 mod read_only;
+#[cfg(feature = "std")]
+mod recording_backend;
 mod stats;
 #[cfg(feature = "std")]
 mod testing;
 mod trie_backend;
 mod trie_backend_essence;
+#[cfg(feature = "std")]
+mod trie_diff;
 
 #[cfg(feature = "std")]
 pub use std_reexport::*;
@@ -148,7 +152,9 @@ mod std_reexport {
 			create_proof_check_backend, ProofRecorder, ProvingBackend, ProvingBackendRecorder,
 		},
 		read_only::{InspectState, ReadOnlyExternalities},
+		recording_backend::ReadRecordingBackend,
 		testing::TestExternalities,
+		trie_diff::TrieDiff,
 	};
 	pub use sp_trie::{
 		trie_types::{TrieDBMutV0, TrieDBMutV1},
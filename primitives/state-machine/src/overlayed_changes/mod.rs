@@ -115,6 +115,8 @@ pub enum IndexOperation {
 		hash: Vec<u8>,
 		/// Indexed data size.
 		size: u32,
+		/// Multihash codes of additional content hashes the data is indexed under.
+		hash_codes: Vec<u64>,
 	},
 	/// Renew existing transaction storage.
 	Renew {
@@ -123,6 +125,19 @@ pub enum IndexOperation {
 		/// Referenced index hash.
 		hash: Vec<u8>,
 	},
+	/// Renew existing transaction storage on behalf of the block itself rather than one of its
+	/// extrinsics, e.g. from `on_initialize`.
+	BlockRenew {
+		/// Referenced index hash.
+		hash: Vec<u8>,
+	},
+	/// Remove transaction from the index of an earlier block.
+	Remove {
+		/// Number of the block that indexed the transaction.
+		block: u32,
+		/// Removed index hash.
+		hash: Vec<u8>,
+	},
 }
 
 /// A storage changes structure that can be generated by the data collected in [`OverlayedChanges`].
@@ -0,0 +1,268 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State machine backend recording the keys read from it.
+
+use crate::{Backend, StorageKey, StorageValue};
+use hash_db::Hasher;
+use parking_lot::Mutex;
+use sp_core::storage::{ChildInfo, StateVersion};
+use std::collections::BTreeSet;
+
+/// Backend recording the keys of the main trie read from the wrapped backend.
+///
+/// Reads served by the overlay never reach the backend and are not recorded. Keys of child tries
+/// are not recorded either.
+pub struct ReadRecordingBackend<'a, B> {
+	backend: &'a B,
+	read: Mutex<BTreeSet<StorageKey>>,
+}
+
+impl<'a, B> ReadRecordingBackend<'a, B> {
+	/// Wrap `backend`.
+	pub fn new(backend: &'a B) -> Self {
+		Self { backend, read: Default::default() }
+	}
+
+	/// Keys read so far, in order.
+	pub fn read_keys(&self) -> BTreeSet<StorageKey> {
+		self.read.lock().clone()
+	}
+
+	fn record(&self, key: &[u8]) {
+		self.read.lock().insert(key.to_vec());
+	}
+}
+
+impl<'a, B: std::fmt::Debug> std::fmt::Debug for ReadRecordingBackend<'a, B> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "ReadRecordingBackend({:?})", self.backend)
+	}
+}
+
+impl<'a, B, H> Backend<H> for ReadRecordingBackend<'a, B>
+where
+	B: Backend<H>,
+	H: Hasher,
+{
+	type Error = B::Error;
+	type Transaction = B::Transaction;
+	type TrieBackendStorage = B::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<StorageValue>, Self::Error> {
+		self.record(key);
+		self.backend.storage(key)
+	}
+
+	fn storage_hash(&self, key: &[u8]) -> Result<Option<H::Out>, Self::Error> {
+		self.record(key);
+		self.backend.storage_hash(key)
+	}
+
+	fn child_storage(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageValue>, Self::Error> {
+		self.backend.child_storage(child_info, key)
+	}
+
+	fn child_storage_hash(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<H::Out>, Self::Error> {
+		self.backend.child_storage_hash(child_info, key)
+	}
+
+	fn exists_storage(&self, key: &[u8]) -> Result<bool, Self::Error> {
+		self.record(key);
+		self.backend.exists_storage(key)
+	}
+
+	fn exists_child_storage(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<bool, Self::Error> {
+		self.backend.exists_child_storage(child_info, key)
+	}
+
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<StorageKey>, Self::Error> {
+		let next = self.backend.next_storage_key(key)?;
+		if let Some(next) = &next {
+			self.record(next);
+		}
+		Ok(next)
+	}
+
+	fn next_child_storage_key(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageKey>, Self::Error> {
+		self.backend.next_child_storage_key(child_info, key)
+	}
+
+	fn apply_to_key_values_while<F: FnMut(Vec<u8>, Vec<u8>) -> bool>(
+		&self,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		mut f: F,
+		allow_missing: bool,
+	) -> Result<bool, Self::Error> {
+		let record = child_info.is_none();
+		self.backend.apply_to_key_values_while(
+			child_info,
+			prefix,
+			start_at,
+			|key, value| {
+				if record {
+					self.record(&key);
+				}
+				f(key, value)
+			},
+			allow_missing,
+		)
+	}
+
+	fn apply_to_keys_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		mut f: F,
+	) {
+		let record = child_info.is_none();
+		self.backend.apply_to_keys_while(child_info, prefix, |key| {
+			if record {
+				self.record(key);
+			}
+			f(key)
+		})
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], mut f: F) {
+		self.backend.for_keys_with_prefix(prefix, |key| {
+			self.record(key);
+			f(key)
+		})
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], mut f: F) {
+		self.backend.for_key_values_with_prefix(prefix, |key, value| {
+			self.record(key);
+			f(key, value)
+		})
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
+		&self,
+		child_info: &ChildInfo,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.backend.for_child_keys_with_prefix(child_info, prefix, f)
+	}
+
+	fn storage_root<'b>(
+		&self,
+		delta: impl Iterator<Item = (&'b [u8], Option<&'b [u8]>)>,
+		state_version: StateVersion,
+	) -> (H::Out, Self::Transaction)
+	where
+		H::Out: Ord,
+	{
+		self.backend.storage_root(delta, state_version)
+	}
+
+	fn child_storage_root<'b>(
+		&self,
+		child_info: &ChildInfo,
+		delta: impl Iterator<Item = (&'b [u8], Option<&'b [u8]>)>,
+		state_version: StateVersion,
+	) -> (H::Out, bool, Self::Transaction)
+	where
+		H::Out: Ord,
+	{
+		self.backend.child_storage_root(child_info, delta, state_version)
+	}
+
+	fn pairs(&self) -> Vec<(StorageKey, StorageValue)> {
+		let pairs = self.backend.pairs();
+		pairs.iter().for_each(|(key, _)| self.record(key));
+		pairs
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Vec<StorageKey> {
+		let keys = self.backend.keys(prefix);
+		keys.iter().for_each(|key| self.record(key));
+		keys
+	}
+
+	fn child_keys(&self, child_info: &ChildInfo, prefix: &[u8]) -> Vec<StorageKey> {
+		self.backend.child_keys(child_info, prefix)
+	}
+
+	fn register_overlay_stats(&self, stats: &crate::stats::StateMachineStats) {
+		self.backend.register_overlay_stats(stats)
+	}
+
+	fn usage_info(&self) -> crate::stats::UsageInfo {
+		self.backend.usage_info()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Ext, InMemoryBackend, OverlayedChanges, StorageTransactionCache};
+	use sp_core::{map, storage::Storage};
+	use sp_externalities::Externalities;
+	use sp_runtime::traits::BlakeTwo256;
+
+	#[test]
+	fn records_keys_read_from_backend() {
+		let backend = InMemoryBackend::<BlakeTwo256>::from((
+			Storage {
+				top: map![
+					b"a".to_vec() => b"0".to_vec(),
+					b"b".to_vec() => b"1".to_vec(),
+					b"c".to_vec() => b"2".to_vec(),
+					b"d".to_vec() => b"3".to_vec()
+				],
+				children_default: Default::default(),
+			},
+			StateVersion::V1,
+		));
+		let recording = ReadRecordingBackend::new(&backend);
+		let mut overlay = OverlayedChanges::default();
+		let mut cache = StorageTransactionCache::default();
+		let mut ext = Ext::new(&mut overlay, &mut cache, &recording, None);
+
+		ext.set_storage(b"e".to_vec(), b"4".to_vec());
+		assert_eq!(ext.storage(b"a"), Some(b"0".to_vec()));
+		assert_eq!(ext.storage(b"e"), Some(b"4".to_vec()));
+		assert!(!ext.exists_storage(b"ab"));
+		assert_eq!(ext.next_storage_key(b"b"), Some(b"c".to_vec()));
+		drop(ext);
+
+		let read: BTreeSet<StorageKey> =
+			vec![b"a".to_vec(), b"ab".to_vec(), b"c".to_vec()].into_iter().collect();
+		assert_eq!(recording.read_keys(), read);
+	}
+}
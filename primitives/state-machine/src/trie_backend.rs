@@ -59,6 +59,21 @@ where
 	pub fn into_storage(self) -> S {
 		self.essence.into_storage()
 	}
+
+	/// Compare this trie with the trie with root `other` from the same storage, returning up to
+	/// `limit` keys starting with `prefix` and following `start_key` whose values differ.
+	///
+	/// Subtries shared by both tries are skipped. Child tries are only compared by their root.
+	#[cfg(feature = "std")]
+	pub fn diff(
+		&self,
+		other: &H::Out,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		limit: usize,
+	) -> Result<crate::TrieDiff, crate::DefaultError> {
+		crate::trie_diff::diff::<H>(&self.essence, self.root(), other, prefix, start_key, limit)
+	}
 }
 
 impl<S: TrieBackendStorage<H>, H: Hasher> sp_std::fmt::Debug for TrieBackend<S, H> {
@@ -0,0 +1,525 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of two tries stored in the same database.
+//!
+//! Both tries are walked from their roots in parallel. Nodes at the same position with the same
+//! encoding head identical subtries, which are skipped without being loaded.
+//!
+//! Child tries are not compared: a child trie whose content changed shows up as a change of the
+//! key holding its root in the top trie.
+
+use crate::{StorageKey, StorageValue};
+use codec::Encode;
+use hash_db::{HashDBRef, Hasher};
+use sp_trie::{trie_types::TrieDB, DBValue, NodeCodec, Trie};
+use std::cmp::Ordering;
+use trie_db::{
+	nibble_ops::NIBBLE_LENGTH,
+	node::{Node, NodeHandle, Value},
+	NibbleSlice, NibbleVec, NodeCodec as NodeCodecT,
+};
+
+type Result<T> = std::result::Result<T, crate::DefaultError>;
+
+/// Keys whose values differ between two tries, see [`crate::TrieBackend::diff`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrieDiff {
+	/// Keys only present in the second trie.
+	pub inserted: Vec<StorageKey>,
+	/// Keys present in both tries with different values.
+	pub changed: Vec<StorageKey>,
+	/// Keys only present in the first trie.
+	pub removed: Vec<StorageKey>,
+	/// Last key returned if the limit was reached, to continue the comparison after.
+	pub next: Option<StorageKey>,
+}
+
+/// A reference to a child node, as stored in its parent.
+#[derive(PartialEq)]
+enum Child<H> {
+	Hash(H),
+	Inline(Vec<u8>),
+}
+
+/// A reference to a value, as stored in its node.
+#[derive(PartialEq)]
+enum ValueRef {
+	Inline(Vec<u8>),
+	Hash(Vec<u8>),
+}
+
+/// The part of a trie under a node, with the first `skip` nibbles of the node's partial key
+/// already matched by the parent.
+struct SubTrie {
+	encoded: DBValue,
+	skip: usize,
+}
+
+/// A decoded [`SubTrie`].
+struct Decoded<H> {
+	partial: Vec<u8>,
+	value: Option<ValueRef>,
+	children: [Option<Child<H>>; NIBBLE_LENGTH],
+}
+
+/// Compare the tries with roots `from` and `to` in `db`, limited to the keys starting with
+/// `prefix` and following `start_key`.
+///
+/// Keys are returned in order, up to `limit` of them.
+pub(crate) fn diff<H: Hasher>(
+	db: &dyn HashDBRef<H, DBValue>,
+	from: &H::Out,
+	to: &H::Out,
+	prefix: &[u8],
+	start_key: Option<&[u8]>,
+	limit: usize,
+) -> Result<TrieDiff>
+where
+	H::Out: Encode,
+{
+	let mut walker = Walker {
+		db,
+		roots: (from, to),
+		prefix: NibbleSlice::new(prefix),
+		start_key,
+		limit,
+		count: 0,
+		result: TrieDiff::default(),
+	};
+	if from != to {
+		let path = NibbleVec::new();
+		let from_root = walker.load(&Child::Hash(*from), &path)?;
+		let to_root = walker.load(&Child::Hash(*to), &path)?;
+		walker.diff(Some(from_root), Some(to_root), &mut NibbleVec::new())?;
+	}
+	Ok(walker.result)
+}
+
+fn read_value<H: Hasher>(
+	db: &dyn HashDBRef<H, DBValue>,
+	root: &H::Out,
+	key: &[u8],
+) -> Result<Option<StorageValue>>
+where
+	H::Out: Encode,
+{
+	let trie = TrieDB::<H>::new(db, root).map_err(|e| format!("{}", e))?;
+	trie.get(key)
+		.map(|value| value.map(|value| value.to_vec()))
+		.map_err(|e| format!("{}", e))
+}
+
+struct Walker<'a, H: Hasher> {
+	db: &'a dyn HashDBRef<H, DBValue>,
+	roots: (&'a H::Out, &'a H::Out),
+	prefix: NibbleSlice<'a>,
+	start_key: Option<&'a [u8]>,
+	limit: usize,
+	count: usize,
+	result: TrieDiff,
+}
+
+impl<'a, H: Hasher> Walker<'a, H>
+where
+	H::Out: Encode,
+{
+	fn load(&self, child: &Child<H::Out>, path: &NibbleVec) -> Result<SubTrie> {
+		let encoded = match child {
+			Child::Hash(hash) => self
+				.db
+				.get(hash, path.as_prefix())
+				.ok_or_else(|| format!("Trie node {:?} is missing from the database", hash))?,
+			Child::Inline(encoded) => encoded.clone(),
+		};
+		Ok(SubTrie { encoded, skip: 0 })
+	}
+
+	fn decode(&self, sub: &SubTrie) -> Result<Decoded<H::Out>> {
+		let node = <NodeCodec<H> as NodeCodecT>::decode(&sub.encoded)
+			.map_err(|e| format!("Failed to decode trie node: {}", e))?;
+		let mut children: [Option<Child<H::Out>>; NIBBLE_LENGTH] = Default::default();
+		let (partial, value) = match node {
+			Node::Empty => (NibbleSlice::new(&[]), None),
+			Node::Leaf(partial, value) => (partial, Some(value)),
+			Node::NibbledBranch(partial, handles, value) => {
+				for (child, handle) in children.iter_mut().zip(handles.iter()) {
+					*child = match handle {
+						Some(NodeHandle::Hash(hash)) => {
+							let mut out = H::Out::default();
+							out.as_mut().copy_from_slice(hash);
+							Some(Child::Hash(out))
+						},
+						Some(NodeHandle::Inline(encoded)) => Some(Child::Inline(encoded.to_vec())),
+						None => None,
+					};
+				}
+				(partial, value)
+			},
+			Node::Extension(..) | Node::Branch(..) =>
+				return Err("Unexpected trie node kind".into()),
+		};
+		let partial = partial.mid(sub.skip).iter().collect();
+		let value = value.map(|value| match value {
+			Value::Inline(value) => ValueRef::Inline(value.to_vec()),
+			Value::Node(hash, _) => ValueRef::Hash(hash.to_vec()),
+		});
+		Ok(Decoded { partial, value, children })
+	}
+
+	/// Returns `true` if no key under `path` needs to be compared: keys don't start with the
+	/// prefix, all come before the start key, or the limit is reached.
+	fn skip(&self, path: &NibbleVec) -> bool {
+		let len = path.len().min(self.prefix.len());
+		if (0..len).any(|i| path.at(i) != self.prefix.at(i)) || self.count >= self.limit {
+			return true
+		}
+		let start = match self.start_key {
+			Some(start) => NibbleSlice::new(start),
+			None => return false,
+		};
+		for i in 0..path.len().min(start.len()) {
+			match path.at(i).cmp(&start.at(i)) {
+				Ordering::Less => return true,
+				Ordering::Greater => return false,
+				Ordering::Equal => {},
+			}
+		}
+		false
+	}
+
+	fn push_key(&mut self, path: &NibbleVec, in_from: bool, in_to: bool) -> Result<()> {
+		if path.len() < self.prefix.len() || self.skip(path) {
+			return Ok(())
+		}
+		let key = path.inner().to_vec();
+		if matches!(self.start_key, Some(start) if key.as_slice() <= start) {
+			return Ok(())
+		}
+		match (in_from, in_to) {
+			(false, _) => self.result.inserted.push(key.clone()),
+			(_, false) => self.result.removed.push(key.clone()),
+			_ => {
+				// The values may only differ in how they are stored.
+				let (from, to) = self.roots;
+				if read_value::<H>(self.db, from, &key)? == read_value::<H>(self.db, to, &key)? {
+					return Ok(())
+				}
+				self.result.changed.push(key.clone());
+			},
+		}
+		self.count += 1;
+		if self.count == self.limit {
+			self.result.next = Some(key);
+		}
+		Ok(())
+	}
+
+	/// Compare the subtries at `path`.
+	fn diff(
+		&mut self,
+		from: Option<SubTrie>,
+		to: Option<SubTrie>,
+		path: &mut NibbleVec,
+	) -> Result<()> {
+		if self.skip(path) {
+			return Ok(())
+		}
+		let (from, to) = match (from, to) {
+			(None, None) => return Ok(()),
+			(Some(from), None) => return self.collect(&from, path, true),
+			(None, Some(to)) => return self.collect(&to, path, false),
+			(Some(from), Some(to)) => (from, to),
+		};
+		if from.skip == to.skip && from.encoded == to.encoded {
+			return Ok(())
+		}
+
+		let from_node = self.decode(&from)?;
+		let to_node = self.decode(&to)?;
+		let common = from_node
+			.partial
+			.iter()
+			.zip(to_node.partial.iter())
+			.take_while(|(a, b)| a == b)
+			.count();
+		if common < from_node.partial.len() && common < to_node.partial.len() {
+			// The subtries diverge and have no keys in common, collect them in key order.
+			return if from_node.partial[common] < to_node.partial[common] {
+				self.collect(&from, path, true)?;
+				self.collect(&to, path, false)
+			} else {
+				self.collect(&to, path, false)?;
+				self.collect(&from, path, true)
+			}
+		}
+
+		let depth = path.len();
+		for nibble in &from_node.partial[..common] {
+			path.push(*nibble);
+		}
+		if from_node.partial.len() == to_node.partial.len() {
+			match (&from_node.value, &to_node.value) {
+				(Some(from_value), Some(to_value)) if from_value == to_value => {},
+				(from_value, to_value) =>
+					if from_value.is_some() || to_value.is_some() {
+						self.push_key(path, from_value.is_some(), to_value.is_some())?;
+					},
+			}
+			let children = from_node.children.iter().zip(to_node.children.iter());
+			for (nibble, (from_child, to_child)) in children.enumerate() {
+				// Shared subtries are skipped without being loaded.
+				if from_child != to_child {
+					path.push(nibble as u8);
+					if !self.skip(path) {
+						let from = from_child.as_ref().map(|c| self.load(c, path)).transpose()?;
+						let to = to_child.as_ref().map(|c| self.load(c, path)).transpose()?;
+						self.diff(from, to, path)?;
+					}
+					path.pop();
+				}
+			}
+		} else if from_node.partial.len() < to_node.partial.len() {
+			// All keys of `to` are under a single child of `from`.
+			if from_node.value.is_some() {
+				self.push_key(path, true, false)?;
+			}
+			let branch = to_node.partial[common];
+			let rest = SubTrie { encoded: to.encoded, skip: to.skip + common + 1 };
+			self.diff_children(&from_node, Some((branch, rest)), path, true)?;
+		} else {
+			if to_node.value.is_some() {
+				self.push_key(path, false, true)?;
+			}
+			let branch = from_node.partial[common];
+			let rest = SubTrie { encoded: from.encoded, skip: from.skip + common + 1 };
+			self.diff_children(&to_node, Some((branch, rest)), path, false)?;
+		}
+		path.drop_lasts(path.len() - depth);
+		Ok(())
+	}
+
+	/// Compare the children of `node` with `other`, a subtrie under a single child position.
+	///
+	/// `node_is_from` tells whether `node` belongs to the first trie.
+	fn diff_children(
+		&mut self,
+		node: &Decoded<H::Out>,
+		mut other: Option<(u8, SubTrie)>,
+		path: &mut NibbleVec,
+		node_is_from: bool,
+	) -> Result<()> {
+		for (nibble, child) in node.children.iter().enumerate() {
+			path.push(nibble as u8);
+			if !self.skip(path) {
+				let child = child.as_ref().map(|c| self.load(c, path)).transpose()?;
+				let other = match &other {
+					Some((branch, _)) if *branch as usize == nibble =>
+						other.take().map(|(_, sub)| sub),
+					_ => None,
+				};
+				if node_is_from {
+					self.diff(child, other, path)?;
+				} else {
+					self.diff(other, child, path)?;
+				}
+			}
+			path.pop();
+		}
+		Ok(())
+	}
+
+	/// Record all keys of the subtrie at `path` as only present in one trie.
+	fn collect(&mut self, sub: &SubTrie, path: &mut NibbleVec, in_from: bool) -> Result<()> {
+		if self.skip(path) {
+			return Ok(())
+		}
+		let node = self.decode(sub)?;
+		let depth = path.len();
+		for nibble in &node.partial {
+			path.push(*nibble);
+		}
+		if node.value.is_some() {
+			self.push_key(path, in_from, !in_from)?;
+		}
+		for (nibble, child) in node.children.iter().enumerate() {
+			if let Some(child) = child {
+				path.push(nibble as u8);
+				if !self.skip(path) {
+					let child = self.load(child, path)?;
+					self.collect(&child, path, in_from)?;
+				}
+				path.pop();
+			}
+		}
+		path.drop_lasts(path.len() - depth);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::TrieBackend;
+	use rand::{rngs::StdRng, Rng, SeedableRng};
+	use sp_core::H256;
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_trie::{
+		trie_types::{TrieDBMutV0, TrieDBMutV1},
+		PrefixedMemoryDB, TrieMut,
+	};
+	use std::collections::BTreeMap;
+
+	type Entries = BTreeMap<Vec<u8>, Vec<u8>>;
+
+	fn random_entries(rng: &mut StdRng, count: usize) -> Entries {
+		// Few distinct bytes, so that keys share prefixes.
+		const BYTES: [u8; 5] = [0x00, 0x01, 0x10, 0x11, 0xff];
+		(0..count)
+			.map(|_| {
+				let key = (0..rng.gen_range(1, 5)).map(|_| BYTES[rng.gen_range(0, 5)]).collect();
+				let value = vec![rng.gen(); rng.gen_range(1, 40)];
+				(key, value)
+			})
+			.collect()
+	}
+
+	fn insert_v0(db: &mut PrefixedMemoryDB<BlakeTwo256>, entries: &Entries) -> H256 {
+		let mut root = H256::default();
+		let mut trie = TrieDBMutV0::new(db, &mut root);
+		for (key, value) in entries {
+			trie.insert(key, value).unwrap();
+		}
+		drop(trie);
+		root
+	}
+
+	fn insert_v1(db: &mut PrefixedMemoryDB<BlakeTwo256>, entries: &Entries) -> H256 {
+		let mut root = H256::default();
+		let mut trie = TrieDBMutV1::new(db, &mut root);
+		for (key, value) in entries {
+			trie.insert(key, value).unwrap();
+		}
+		drop(trie);
+		root
+	}
+
+	fn expected(from: &Entries, to: &Entries, prefix: &[u8]) -> TrieDiff {
+		let mut diff = TrieDiff::default();
+		for (key, value) in from.iter().filter(|(key, _)| key.starts_with(prefix)) {
+			match to.get(key) {
+				None => diff.removed.push(key.clone()),
+				Some(to_value) if to_value != value => diff.changed.push(key.clone()),
+				Some(_) => {},
+			}
+		}
+		for key in to.keys().filter(|key| key.starts_with(prefix) && !from.contains_key(*key)) {
+			diff.inserted.push(key.clone());
+		}
+		diff
+	}
+
+	#[test]
+	fn diff_matches_entries() {
+		let mut rng = StdRng::seed_from_u64(0);
+		for _ in 0..50 {
+			let from = random_entries(&mut rng, 60);
+			let mut to = from.clone();
+			for (key, value) in random_entries(&mut rng, 10) {
+				to.insert(key, value);
+			}
+			for key in random_entries(&mut rng, 10).keys() {
+				to.remove(key);
+			}
+
+			let mut db = PrefixedMemoryDB::default();
+			let from_root = insert_v1(&mut db, &from);
+			let to_root = insert_v1(&mut db, &to);
+			let backend = TrieBackend::new(db, from_root);
+			for prefix in [&[][..], &[0x00], &[0x10, 0x11], &[0xff, 0x00, 0x01]] {
+				assert_eq!(
+					backend.diff(&to_root, prefix, None, usize::MAX).unwrap(),
+					expected(&from, &to, prefix)
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn diff_in_pages() {
+		let mut rng = StdRng::seed_from_u64(3);
+		for _ in 0..20 {
+			let from = random_entries(&mut rng, 60);
+			let mut to = random_entries(&mut rng, 60);
+			for (key, value) in from.iter().take(20) {
+				to.insert(key.clone(), value.clone());
+			}
+
+			let mut db = PrefixedMemoryDB::default();
+			let from_root = insert_v1(&mut db, &from);
+			let to_root = insert_v1(&mut db, &to);
+			let backend = TrieBackend::new(db, from_root);
+			for limit in [1, 3, 10] {
+				let mut pages = TrieDiff::default();
+				let mut start_key = None;
+				loop {
+					let page = backend.diff(&to_root, &[], start_key.as_deref(), limit).unwrap();
+					let keys = page.inserted.len() + page.changed.len() + page.removed.len();
+					assert!(keys <= limit);
+					pages.inserted.extend(page.inserted);
+					pages.changed.extend(page.changed);
+					pages.removed.extend(page.removed);
+					start_key = match page.next {
+						Some(next) => Some(next),
+						None => break,
+					};
+				}
+				assert_eq!(pages, expected(&from, &to, &[]));
+			}
+		}
+	}
+
+	#[test]
+	fn diff_ignores_value_encoding() {
+		let mut rng = StdRng::seed_from_u64(1);
+		let entries = random_entries(&mut rng, 60);
+
+		let mut db = PrefixedMemoryDB::default();
+		let from_root = insert_v0(&mut db, &entries);
+		let to_root = insert_v1(&mut db, &entries);
+		assert_ne!(from_root, to_root);
+
+		let backend = TrieBackend::new(db, from_root);
+		assert_eq!(backend.diff(&to_root, &[], None, usize::MAX).unwrap(), TrieDiff::default());
+	}
+
+	#[test]
+	fn diff_with_empty_trie() {
+		let mut rng = StdRng::seed_from_u64(2);
+		let entries = random_entries(&mut rng, 20);
+
+		let mut db = PrefixedMemoryDB::default();
+		let empty_root = insert_v1(&mut db, &Entries::new());
+		let root = insert_v1(&mut db, &entries);
+
+		let backend = TrieBackend::new(db, empty_root);
+		assert_eq!(
+			backend.diff(&root, &[], None, usize::MAX).unwrap(),
+			expected(&Entries::new(), &entries, &[])
+		);
+	}
+}
@@ -28,6 +28,8 @@ use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 pub use sp_inherents::Error;
 
+pub mod unixfs;
+
 /// The identifier for the proof inherent.
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"tx_proof";
 /// Storage period for data.
@@ -35,6 +37,31 @@ pub const DEFAULT_STORAGE_PERIOD: u32 = 100800;
 /// Proof trie value size.
 pub const CHUNK_SIZE: usize = 256;
 
+/// Multihash codes of content hashes that indexed transactions may be looked up by.
+pub mod multihash {
+	/// 256-bit SHA2 hash.
+	pub const SHA2_256: u64 = 0x12;
+	/// 256-bit Keccak hash.
+	pub const KECCAK_256: u64 = 0x1b;
+	/// 256-bit Blake3 hash.
+	pub const BLAKE3_256: u64 = 0x1e;
+	/// 256-bit Blake2b hash. All indexed transactions can be looked up by this hash.
+	pub const BLAKE2B_256: u64 = 0xb220;
+}
+
+sp_api::decl_runtime_apis! {
+	/// API to query the parameters of the transaction storage pallet.
+	pub trait TransactionStorageApi {
+		/// Number of blocks indexed data is kept for. Each block must prove the data indexed
+		/// this many blocks earlier.
+		fn storage_period() -> NumberFor<Block>;
+
+		/// Content hashes of the data indexed in `block` that is still stored, in the order it
+		/// was indexed. The storage proof `storage_period` blocks later is built from this data.
+		fn indexed_transactions(block: NumberFor<Block>) -> Vec<<Block as BlockT>::Hash>;
+	}
+}
+
 /// Errors that can occur while checking the storage proof.
 #[derive(Encode, sp_runtime::RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Decode))]
@@ -125,12 +152,17 @@ pub fn encode_index(input: u32) -> Vec<u8> {
 
 /// An interface to request indexed data from the client.
 pub trait IndexedBody<B: BlockT> {
-	/// Get all indexed transactions for a block,
-	/// including renewed transactions.
+	/// Get all indexed transactions for a block, including renewed transactions, that are still
+	/// stored as of block `at`. Data removed in `at` or its ancestors is skipped, even before the
+	/// removal is finalized.
 	///
 	/// Note that this will only fetch transactions
 	/// that are indexed by the runtime with `storage_index_transaction`.
-	fn block_indexed_body(&self, number: NumberFor<B>) -> Result<Option<Vec<Vec<u8>>>, Error>;
+	fn block_indexed_body(
+		&self,
+		number: NumberFor<B>,
+		at: &B::Hash,
+	) -> Result<Option<Vec<Vec<u8>>>, Error>;
 
 	/// Get block number for a block hash.
 	fn number(&self, hash: B::Hash) -> Result<Option<NumberFor<B>>, Error>;
@@ -163,7 +195,7 @@ pub mod registration {
 			return Ok(InherentDataProvider::new(None))
 		}
 
-		let proof = match client.block_indexed_body(number)? {
+		let proof = match client.block_indexed_body(number, parent)? {
 			Some(transactions) if !transactions.is_empty() =>
 				Some(build_proof(parent.as_ref(), transactions)?),
 			Some(_) | None => {
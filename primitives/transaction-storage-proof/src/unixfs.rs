@@ -0,0 +1,168 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! UnixFS DAG layout of indexed data.
+//!
+//! Data that fits into a single leaf is exposed as one raw block. Larger data is split into
+//! `DAG_CHUNK_SIZE` raw leaves, linked from a single dag-pb root node describing a UnixFS file.
+//! All blocks are identified by CIDv1 with a blake2b-256 multihash, so that the runtime and the
+//! client derive identical CIDs from the same data.
+
+use sp_std::{prelude::*, vec};
+
+/// Maximum size of a leaf block of the DAG.
+pub const DAG_CHUNK_SIZE: usize = 256 * 1024;
+/// Multicodec code of raw binary blocks.
+pub const RAW_CODEC: u64 = 0x55;
+/// Multicodec code of dag-pb blocks.
+pub const DAG_PB_CODEC: u64 = 0x70;
+
+/// UnixFS `File` data type.
+const UNIXFS_FILE: u64 = 2;
+
+/// A dag-pb node of the DAG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+	/// Blake2b-256 hash of `data`.
+	pub hash: [u8; 32],
+	/// Encoded dag-pb node.
+	pub data: Vec<u8>,
+}
+
+/// UnixFS DAG of a piece of data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dag {
+	/// Blake2b-256 hashes of the raw leaves, in data order.
+	pub leaves: Vec<[u8; 32]>,
+	/// Root node linking the leaves. `None` if the data fits into a single leaf, which is then
+	/// the root of the DAG.
+	pub root: Option<Node>,
+}
+
+impl Dag {
+	/// Build the DAG of `data`, using `hash` to compute blake2b-256 hashes of blocks.
+	pub fn build(data: &[u8], hash: impl Fn(&[u8]) -> [u8; 32]) -> Self {
+		if data.len() <= DAG_CHUNK_SIZE {
+			return Dag { leaves: vec![hash(data)], root: None }
+		}
+		let leaves: Vec<_> = data.chunks(DAG_CHUNK_SIZE).map(&hash).collect();
+		let sizes = data.chunks(DAG_CHUNK_SIZE).map(|chunk| chunk.len() as u64);
+
+		let mut unixfs = Vec::new();
+		put_field(&mut unixfs, 1, UNIXFS_FILE);
+		put_field(&mut unixfs, 3, data.len() as u64);
+		let mut node = Vec::new();
+		for (leaf, size) in leaves.iter().zip(sizes) {
+			put_field(&mut unixfs, 4, size);
+			let mut link = Vec::new();
+			put_bytes(&mut link, 1, &cid(RAW_CODEC, leaf));
+			put_bytes(&mut link, 2, &[]);
+			put_field(&mut link, 3, size);
+			put_bytes(&mut node, 2, &link);
+		}
+		put_bytes(&mut node, 1, &unixfs);
+
+		let root = Node { hash: hash(&node), data: node };
+		Dag { leaves, root: Some(root) }
+	}
+
+	/// CID of the root of the DAG.
+	pub fn root_cid(&self) -> Vec<u8> {
+		match &self.root {
+			Some(node) => cid(DAG_PB_CODEC, &node.hash),
+			None => cid(RAW_CODEC, &self.leaves[0]),
+		}
+	}
+
+	/// CIDs of the leaves of the DAG, in data order.
+	pub fn leaf_cids(&self) -> Vec<Vec<u8>> {
+		self.leaves.iter().map(|leaf| cid(RAW_CODEC, leaf)).collect()
+	}
+}
+
+/// Encode a CIDv1 of a block with the given `codec` and blake2b-256 `hash`.
+pub fn cid(codec: u64, hash: &[u8; 32]) -> Vec<u8> {
+	let mut cid = Vec::with_capacity(38);
+	put_varint(&mut cid, 1);
+	put_varint(&mut cid, codec);
+	put_varint(&mut cid, crate::multihash::BLAKE2B_256);
+	put_varint(&mut cid, hash.len() as u64);
+	cid.extend_from_slice(hash);
+	cid
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		out.push((value as u8 & 0x7f) | 0x80);
+		value >>= 7;
+	}
+	out.push(value as u8);
+}
+
+/// Append a protobuf varint field.
+fn put_field(out: &mut Vec<u8>, field: u64, value: u64) {
+	put_varint(out, field << 3);
+	put_varint(out, value);
+}
+
+/// Append a protobuf length-delimited field.
+fn put_bytes(out: &mut Vec<u8>, field: u64, value: &[u8]) {
+	put_varint(out, field << 3 | 2);
+	put_varint(out, value.len() as u64);
+	out.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cid::{multihash::Code, Cid};
+	use sp_core::hashing::blake2_256;
+
+	#[test]
+	fn small_data_is_single_raw_block() {
+		let data = vec![42u8; 1000];
+		let dag = Dag::build(&data, blake2_256);
+		assert_eq!(dag.root, None);
+		assert_eq!(dag.leaf_cids(), vec![dag.root_cid()]);
+
+		let cid = Cid::try_from(dag.root_cid()).unwrap();
+		assert_eq!(cid.codec(), RAW_CODEC);
+		assert_eq!(cid.hash().code(), u64::from(Code::Blake2b256));
+		assert_eq!(cid.hash().digest(), &blake2_256(&data)[..]);
+	}
+
+	#[test]
+	fn large_data_is_linked_from_dag_pb_root() {
+		let data: Vec<u8> = (0..DAG_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+		let dag = Dag::build(&data, blake2_256);
+		assert_eq!(dag.leaves.len(), 3);
+		assert_eq!(dag.leaves[2], blake2_256(&data[DAG_CHUNK_SIZE * 2..]));
+
+		let root = dag.root.clone().unwrap();
+		assert_eq!(root.hash, blake2_256(&root.data));
+		let cid = Cid::try_from(dag.root_cid()).unwrap();
+		assert_eq!(cid.codec(), DAG_PB_CODEC);
+		assert_eq!(cid.hash().digest(), &root.hash[..]);
+
+		for leaf in dag.leaf_cids() {
+			let leaf_cid = Cid::try_from(leaf.clone()).unwrap();
+			assert_eq!(leaf_cid.codec(), RAW_CODEC);
+			// Links are stored in the root node in order.
+			assert!(root.data.windows(leaf.len()).any(|w| w == &leaf[..]));
+		}
+	}
+}
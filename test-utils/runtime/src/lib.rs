@@ -931,6 +931,19 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl frame_system_rpc_runtime_api::DryRunApi<Block, u64> for Runtime {
+				fn dry_run(
+					extrinsic: <Block as BlockT>::Extrinsic,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					frame_system_rpc_runtime_api::DryRunEffects {
+						result: system::execute_transaction(extrinsic),
+						events: Vec::new(),
+						weight: 0,
+						fee: 0,
+					}
+				}
+			}
 		}
 	} else {
 		impl_runtime_apis! {
@@ -1162,6 +1175,19 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl frame_system_rpc_runtime_api::DryRunApi<Block, u64> for Runtime {
+				fn dry_run(
+					extrinsic: <Block as BlockT>::Extrinsic,
+				) -> frame_system_rpc_runtime_api::DryRunEffects<u64> {
+					frame_system_rpc_runtime_api::DryRunEffects {
+						result: system::execute_transaction(extrinsic),
+						events: Vec::new(),
+						weight: 0,
+						fee: 0,
+					}
+				}
+			}
 		}
 	}
 }
@@ -0,0 +1,216 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dry run of extrinsics reporting their effects.
+
+use codec::{Codec, Decode, Encode};
+use futures::FutureExt;
+use jsonrpc_core::{Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use sc_client_api::{CallExecutor, ExecutorProvider};
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::ApiExt;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{self, MaybeDisplay, MaybeFromStr},
+};
+
+use crate::{Error, FullSystem, FutureResult};
+use frame_system_rpc_runtime_api::{DryRunApi, DryRunEffects};
+
+/// Name of the runtime function called by [`DryRunApi::dry_run`].
+const DRY_RUN_METHOD: &str = "DryRunApi_dry_run";
+
+/// The effects of dry running an extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(bound(serialize = "Balance: std::fmt::Display"))]
+#[serde(bound(deserialize = "Balance: std::str::FromStr"))]
+pub struct DryRunDetails<Balance> {
+	/// SCALE encoded `ApplyExtrinsicResult`.
+	pub result: Bytes,
+	/// SCALE encoded `Vec<EventRecord>` of the events deposited by the extrinsic.
+	pub events: Bytes,
+	/// The weight of the extrinsic after dispatch.
+	pub weight: u64,
+	/// The fee charged for the extrinsic after dispatch, excluding the tip.
+	#[serde(with = "serde_balance")]
+	pub fee: Balance,
+	/// Storage keys read by the extrinsic from the state of the block.
+	pub storage_read: Vec<Bytes>,
+	/// Storage keys written or removed by the extrinsic.
+	pub storage_written: Vec<Bytes>,
+}
+
+/// System RPC methods relying on [`DryRunApi`].
+#[rpc]
+pub trait SystemDryRunApi<BlockHash, ResponseType> {
+	/// Dry run an extrinsic at a given block and return its effects.
+	///
+	/// Storage keys of child tries are not reported.
+	#[rpc(name = "system_dryRunWithDetails")]
+	fn dry_run_with_details(
+		&self,
+		extrinsic: Bytes,
+		at: Option<BlockHash>,
+	) -> FutureResult<ResponseType>;
+}
+
+impl<P, C, Block, Balance> SystemDryRunApi<<Block as traits::Block>::Hash, DryRunDetails<Balance>>
+	for FullSystem<P, C, Block>
+where
+	C: sp_api::ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C: ExecutorProvider<Block>,
+	C: Send + Sync + 'static,
+	C::Api: DryRunApi<Block, Balance>,
+	P: TransactionPool + 'static,
+	Block: traits::Block,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Send + 'static,
+{
+	fn dry_run_with_details(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> FutureResult<DryRunDetails<Balance>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return async move { Err(err.into()) }.boxed()
+		}
+
+		let dry_run = || {
+			let api = self.client.runtime_api();
+			let at = BlockId::<Block>::hash(at.unwrap_or_else(||
+				// If the block hash is not supplied assume the best block.
+				self.client.info().best_hash));
+
+			let uxt: <Block as traits::Block>::Extrinsic = Decode::decode(&mut &*extrinsic)
+				.map_err(|e| RpcError {
+					code: ErrorCode::ServerError(Error::DecodeError.into()),
+					message: "Unable to dry run extrinsic.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+
+			let runtime_error = |e: String| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to dry run extrinsic.".into(),
+				data: Some(e.into()),
+			};
+			let supported = api
+				.has_api::<dyn DryRunApi<Block, Balance>>(&at)
+				.map_err(|e| runtime_error(e.to_string()))?;
+			if !supported {
+				return Err(runtime_error("Dry run is not supported by the runtime.".into()))
+			}
+
+			let (output, access) = self
+				.client
+				.executor()
+				.call_with_storage_access(
+					&at,
+					DRY_RUN_METHOD,
+					&uxt.encode(),
+					self.client.execution_extensions().strategies().other,
+					None,
+				)
+				.map_err(|e| runtime_error(e.to_string()))?;
+			let effects = DryRunEffects::<Balance>::decode(&mut &output[..])
+				.map_err(|e| runtime_error(e.to_string()))?;
+
+			Ok(DryRunDetails {
+				result: effects.result.encode().into(),
+				events: effects.events.into(),
+				weight: effects.weight,
+				fee: effects.fee,
+				storage_read: access.read.into_iter().map(Into::into).collect(),
+				storage_written: access.written.into_iter().map(Into::into).collect(),
+			})
+		};
+
+		let res = dry_run();
+
+		async move { res }.boxed()
+	}
+}
+
+mod serde_balance {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer, T: std::fmt::Display>(
+		t: &T,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&t.to_string())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, T: std::str::FromStr>(
+		deserializer: D,
+	) -> Result<T, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse::<T>().map_err(|_| serde::de::Error::custom("Parse from string failed"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_rpc_api::DenyUnsafe;
+	use sc_transaction_pool::BasicPool;
+	use sp_core::hashing::blake2_256;
+	use sp_runtime::ApplyExtrinsicResult;
+	use std::sync::Arc;
+	use substrate_test_runtime_client::{
+		runtime::{system::balance_of_key, Transfer},
+		AccountKeyring,
+	};
+
+	#[test]
+	fn dry_run_with_details_reports_storage_access() {
+		sp_tracing::try_init_simple();
+
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool =
+			BasicPool::new_full(Default::default(), true.into(), None, spawner, client.clone());
+		let system = FullSystem::new(client, pool, DenyUnsafe::No);
+
+		let tx = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce: 0,
+		}
+		.into_signed_tx();
+		let details: DryRunDetails<u64> =
+			block_on(system.dry_run_with_details(tx.encode().into(), None)).unwrap();
+
+		let result: ApplyExtrinsicResult = Decode::decode(&mut &details.result[..]).unwrap();
+		assert_eq!(result, Ok(Ok(())));
+		let alice = Bytes(blake2_256(&balance_of_key(AccountKeyring::Alice.into())).to_vec());
+		let bob = Bytes(blake2_256(&balance_of_key(AccountKeyring::Bob.into())).to_vec());
+		assert!(details.storage_read.contains(&alice));
+		assert!(details.storage_read.contains(&bob));
+		assert!(details.storage_written.contains(&alice));
+		assert!(details.storage_written.contains(&bob));
+
+		// The changes of the dry run are discarded.
+		let again = block_on(system.dry_run_with_details(tx.encode().into(), None)).unwrap();
+		assert_eq!(again, details);
+	}
+}
@@ -30,8 +30,13 @@ use sp_blockchain::HeaderBackend;
 use sp_core::{hexdisplay::HexDisplay, Bytes};
 use sp_runtime::{generic::BlockId, traits};
 
-pub use self::gen_client::Client as SystemClient;
-pub use frame_system_rpc_runtime_api::AccountNonceApi;
+mod dry_run;
+
+pub use self::{
+	dry_run::{gen_client::Client as SystemDryRunClient, DryRunDetails, SystemDryRunApi},
+	gen_client::Client as SystemClient,
+};
+pub use frame_system_rpc_runtime_api::{AccountNonceApi, DryRunApi};
 
 /// Future that resolves to account nonce.
 type FutureResult<T> = jsonrpc_core::BoxFuture<Result<T, RpcError>>;